@@ -0,0 +1,257 @@
+use ababil_core::models::collection::Collection;
+use ababil_core::models::environment::Environment;
+use ababil_core::report::{self, ReportFormat};
+use ababil_core::runner::{self, RequestExecution, RunOptions, RunSummary};
+use ababil_core::scripts::reason_phrase;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Headless runner for Postman collections.
+#[derive(Parser)]
+#[command(name = "ababil", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run every request in a collection and evaluate its tests
+    Run(RunArgs),
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    /// Path to a Postman v2.1 collection file
+    collection: PathBuf,
+
+    /// Path to a Postman environment file
+    #[arg(short = 'e', long)]
+    environment: Option<PathBuf>,
+
+    /// CSV or JSON file with one set of variables per iteration
+    #[arg(short = 'd', long = "iteration-data")]
+    iteration_data: Option<PathBuf>,
+
+    /// Only run requests in this folder (may be repeated)
+    #[arg(long)]
+    folder: Vec<String>,
+
+    /// Number of iterations to run
+    #[arg(short = 'n', long = "iteration-count")]
+    iteration_count: Option<usize>,
+
+    /// Override an environment variable, as KEY=VALUE (may be repeated)
+    #[arg(long = "env-var", value_parser = parse_key_value)]
+    env_var: Vec<(String, String)>,
+
+    /// Comma-separated reporters to use: cli, junit, json, html
+    #[arg(short = 'r', long, value_delimiter = ',', default_value = "cli")]
+    reporters: Vec<String>,
+
+    /// Where to write the JUnit XML report
+    #[arg(long = "reporter-junit-export")]
+    junit_export: Option<PathBuf>,
+
+    /// Where to write the JSON report
+    #[arg(long = "reporter-json-export")]
+    json_export: Option<PathBuf>,
+
+    /// Where to write the HTML report
+    #[arg(long = "reporter-html-export")]
+    html_export: Option<PathBuf>,
+
+    /// Fail the run when a test could not be evaluated (--strict=false to
+    /// only report it)
+    #[arg(
+        long,
+        default_value_t = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    strict: bool,
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Run(args) => run(args),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run(args: RunArgs) -> Result<bool, Box<dyn std::error::Error>> {
+    let collection = Collection::from_json(&read_file(&args.collection)?)?;
+
+    let environment = match &args.environment {
+        Some(path) => Some(serde_json::from_str::<Environment>(&read_file(path)?)?),
+        None => None,
+    };
+
+    let iteration_data = match &args.iteration_data {
+        Some(path) => runner::parse_iteration_data(&read_file(path)?)?,
+        None => Vec::new(),
+    };
+
+    let mut exports = Vec::new();
+    for name in &args.reporters {
+        let (format, path, default_path) = match name.as_str() {
+            "cli" => continue,
+            "junit" => (ReportFormat::JUnit, &args.junit_export, "ababil-report.xml"),
+            "json" => (ReportFormat::Json, &args.json_export, "ababil-report.json"),
+            "html" => (ReportFormat::Html, &args.html_export, "ababil-report.html"),
+            other => return Err(format!("unknown reporter '{}'", other).into()),
+        };
+        let path = path.clone().unwrap_or_else(|| PathBuf::from(default_path));
+        exports.push((format, path));
+    }
+
+    let options = RunOptions {
+        environment,
+        iteration_data,
+        folders: args.folder,
+        iteration_count: args.iteration_count,
+        env_var_overrides: args.env_var,
+    };
+
+    let summary = runner::run_collection(&collection, &options)?;
+    if args.reporters.iter().any(|r| r == "cli") {
+        print_summary(&summary);
+    }
+
+    for (format, path) in exports {
+        let contents = report::render(&summary, format)?;
+        std::fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    let skipped = summary.skipped_assertions();
+    if args.strict && skipped > 0 {
+        eprintln!(
+            "{} test(s) could not be evaluated; rerun with --strict=false to ignore them",
+            skipped
+        );
+        return Ok(false);
+    }
+    Ok(summary.is_success())
+}
+
+fn read_file(path: &PathBuf) -> Result<String, Box<dyn std::error::Error>> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+fn print_summary(summary: &RunSummary) {
+    println!("{}", summary.collection_name);
+
+    for execution in &summary.executions {
+        if summary.iterations > 1 && is_first_in_iteration(summary, execution) {
+            println!("\nIteration {}/{}", execution.iteration + 1, summary.iterations);
+        }
+        print_execution(execution);
+    }
+
+    let total = summary.executions.len();
+    let failed_requests = summary.failed_requests();
+    let assertions = summary.total_assertions();
+    let failed_assertions = summary.failed_assertions();
+    let skipped_assertions = summary.skipped_assertions();
+
+    println!();
+    println!("{:<14}{:>10}{:>10}{:>10}", "", "executed", "failed", "skipped");
+    println!("{:<14}{:>10}{:>10}{:>10}", "iterations", summary.iterations, "", "");
+    println!("{:<14}{:>10}{:>10}{:>10}", "requests", total, failed_requests, "");
+    println!(
+        "{:<14}{:>10}{:>10}{:>10}",
+        "assertions", assertions, failed_assertions, skipped_assertions
+    );
+    println!("total run duration: {}ms", summary.duration_ms);
+
+    let mut failures = Vec::new();
+    for execution in &summary.executions {
+        let location = item_label(execution);
+        if let Some(error) = &execution.error {
+            failures.push((location.clone(), error.clone()));
+        }
+        for assertion in execution.assertions.iter().filter(|a| !a.passed && !a.skipped) {
+            failures.push((
+                format!("{} / {}", location, assertion.name),
+                assertion.error.clone().unwrap_or_else(|| "assertion failed".to_string()),
+            ));
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("\nFailures:");
+        for (index, (location, message)) in failures.iter().enumerate() {
+            println!("  {}. {}\n     {}", index + 1, location, message);
+        }
+    }
+}
+
+fn print_execution(execution: &RequestExecution) {
+    println!("\n→ {}", item_label(execution));
+
+    let method = execution.request.method.as_deref().unwrap_or("GET").to_uppercase();
+    let url = execution
+        .request
+        .url
+        .as_ref()
+        .and_then(|u| u.raw.clone())
+        .unwrap_or_default();
+
+    match (&execution.response, &execution.error) {
+        (Some(response), _) => println!(
+            "  {} {} [{} {}, {}ms]",
+            method,
+            url,
+            response.status_code,
+            reason_phrase(response.status_code),
+            response.duration_ms
+        ),
+        (None, Some(error)) => println!("  {} {} [errored]\n    {}", method, url, error),
+        (None, None) => println!("  {} {}", method, url),
+    }
+
+    for assertion in &execution.assertions {
+        if assertion.skipped {
+            let reason = assertion.error.as_deref().unwrap_or("unsupported");
+            println!("  - {} (not evaluated: {})", assertion.name, reason);
+        } else if assertion.passed {
+            println!("  ✓ {}", assertion.name);
+        } else {
+            println!("  ✗ {}", assertion.name);
+        }
+    }
+}
+
+fn item_label(execution: &RequestExecution) -> String {
+    let mut parts = execution.folder_path.clone();
+    parts.push(execution.name.clone());
+    parts.join(" / ")
+}
+
+fn is_first_in_iteration(summary: &RunSummary, execution: &RequestExecution) -> bool {
+    summary
+        .executions
+        .iter()
+        .find(|e| e.iteration == execution.iteration)
+        .is_some_and(|first| std::ptr::eq(first, execution))
+}
@@ -0,0 +1,541 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::collection::{Collection, CollectionInfo, CollectionItem};
+use crate::models::environment::Environment;
+use crate::models::request::{
+    Auth, Body, Description, Event, FormData, GraphQLBody, Header, QueryParam, Request, Script, Url,
+};
+use crate::models::variable::Variable;
+use serde_json::{json, Value};
+use std::fs;
+use std::os::raw::c_char;
+use std::path::Path;
+
+const METHODS: [&str; 9] = ["get", "post", "put", "delete", "patch", "options", "head", "connect", "trace"];
+
+/// One top-level `name { ... }` or `name [ ... ]` block of a `.bru` file,
+/// with its lines dedented.
+struct Block {
+    name: String,
+    lines: Vec<String>,
+}
+
+impl Block {
+    /// `key: value` lines; a leading `~` marks an entry as disabled.
+    fn pairs(&self) -> Vec<(String, String, bool)> {
+        self.lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                let key = key.trim();
+                let (key, disabled) = match key.strip_prefix('~') {
+                    Some(key) => (key, true),
+                    None => (key, false),
+                };
+                Some((key.to_string(), value.trim().to_string(), disabled))
+            })
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.pairs()
+            .into_iter()
+            .find(|(k, _, disabled)| k == key && !disabled)
+            .map(|(_, value, _)| value)
+    }
+
+    fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+/// Splits a `.bru` file into its blocks. Blocks open with `name {` (or `[`)
+/// and close with `}` (or `]`) at the start of a line; content is indented
+/// by two spaces.
+fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_end();
+        let (name, close) = if let Some(name) = trimmed.strip_suffix(" {") {
+            (name, "}")
+        } else if let Some(name) = trimmed.strip_suffix(" [") {
+            (name, "]")
+        } else {
+            continue;
+        };
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        let mut content = Vec::new();
+        for line in lines.by_ref() {
+            if line.trim_end() == close {
+                break;
+            }
+            content.push(line.strip_prefix("  ").unwrap_or(line).to_string());
+        }
+        blocks.push(Block {
+            name: name.trim().to_string(),
+            lines: content,
+        });
+    }
+    blocks
+}
+
+fn find<'a>(blocks: &'a [Block], name: &str) -> Option<&'a Block> {
+    blocks.iter().find(|block| block.name == name)
+}
+
+/// Reads a Bruno collection directory (the one holding `bruno.json`) into a
+/// collection, plus one environment per file in `environments/`.
+pub fn parse_bruno_collection(dir: &Path) -> Result<(Collection, Vec<Environment>), FfiError> {
+    let manifest_path = dir.join("bruno.json");
+    let manifest: Value = fs::read_to_string(&manifest_path)
+        .map_err(|e| read_error(&manifest_path, e))
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| FfiError::json("bruno.json", &e)))?;
+
+    let collection_blocks = fs::read_to_string(dir.join("collection.bru"))
+        .map(|text| parse_blocks(&text))
+        .unwrap_or_default();
+    let mut collection_headers = headers(&collection_blocks);
+    let auth = scope_auth(&collection_blocks, &mut collection_headers);
+    let variable: Vec<Variable> = find(&collection_blocks, "vars:pre-request")
+        .map(variables)
+        .unwrap_or_default();
+
+    let collection = Collection {
+        info: CollectionInfo {
+            name: manifest
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("Bruno import")
+                .to_string(),
+            description: find(&collection_blocks, "docs").map(Block::text).map(Description::from),
+            schema: Some("https://schema.getpostman.com/json/collection/v2.1.0/collection.json".to_string()),
+            postman_id: None,
+            exporter_id: None,
+            extra: Default::default(),
+        },
+        item: read_folder(dir, &collection_headers)?,
+        variable: if variable.is_empty() { None } else { Some(variable) },
+        event: None,
+        auth,
+        extra: Default::default(),
+    };
+
+    Ok((collection, read_environments(&dir.join("environments"))?))
+}
+
+/// Folders and requests in `dir`, ordered by their `meta.seq`. Headers from
+/// `collection.bru` and enclosing `folder.bru` files are added to each request.
+fn read_folder(dir: &Path, inherited_headers: &[Header]) -> Result<Vec<CollectionItem>, FfiError> {
+    let entries = fs::read_dir(dir).map_err(|e| read_error(dir, e))?;
+    let mut items: Vec<(f64, CollectionItem)> = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            if dir.join("bruno.json").exists() && matches!(file_name.as_str(), "environments" | "node_modules") {
+                continue;
+            }
+            let meta_blocks = fs::read_to_string(path.join("folder.bru"))
+                .map(|text| parse_blocks(&text))
+                .unwrap_or_default();
+            let meta = find(&meta_blocks, "meta");
+            let variable = find(&meta_blocks, "vars:pre-request").map(variables).unwrap_or_default();
+            let mut folder_headers = merge_headers(inherited_headers, headers(&meta_blocks));
+            let auth = scope_auth(&meta_blocks, &mut folder_headers);
+            items.push((
+                seq(meta),
+                CollectionItem {
+                    name: meta.and_then(|m| m.get("name")).unwrap_or(file_name),
+                    item: Some(read_folder(&path, &folder_headers)?),
+                    request: None,
+                    response: None,
+                    event: None,
+                    description: find(&meta_blocks, "docs").map(Block::text).map(Description::from),
+                    auth,
+                    variable: if variable.is_empty() { None } else { Some(variable) },
+                    extra: Default::default(),
+                },
+            ));
+        } else if file_name.ends_with(".bru") && !matches!(file_name.as_str(), "folder.bru" | "collection.bru") {
+            let text = fs::read_to_string(&path).map_err(|e| read_error(&path, e))?;
+            let blocks = parse_blocks(&text);
+            let name = file_name.trim_end_matches(".bru").to_string();
+            if let Some(item) = to_item(&blocks, name, inherited_headers) {
+                items.push((seq(find(&blocks, "meta")), item));
+            }
+        }
+    }
+
+    items.sort_by(|(a, a_item), (b, b_item)| a.total_cmp(b).then_with(|| a_item.name.cmp(&b_item.name)));
+    Ok(items.into_iter().map(|(_, item)| item).collect())
+}
+
+fn seq(meta: Option<&Block>) -> f64 {
+    meta.and_then(|m| m.get("seq"))
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(f64::MAX)
+}
+
+fn to_item(blocks: &[Block], file_name: String, inherited_headers: &[Header]) -> Option<CollectionItem> {
+    let meta = find(blocks, "meta");
+    let method_block = blocks.iter().find(|b| METHODS.contains(&b.name.as_str()))?;
+    let raw_url = method_block.get("url").unwrap_or_default();
+
+    let mut url = Url::parse(&raw_url);
+    if let Some(block) = find(blocks, "params:query") {
+        url.query = Some(
+            block
+                .pairs()
+                .into_iter()
+                .map(|(key, value, disabled)| QueryParam {
+                    key,
+                    value: Some(value),
+                    disabled: disabled.then_some(true),
+                    description: None,
+                    extra: Default::default(),
+                })
+                .collect(),
+        );
+    }
+    if let Some(block) = find(blocks, "params:path") {
+        url.variable = Some(variables(block));
+    }
+
+    let mut header = merge_headers(inherited_headers, headers(blocks));
+
+    let body_mode = method_block.get("body").unwrap_or_default();
+    let body = body(blocks, &body_mode, &mut header);
+
+    // `inherit` (or no mode) falls back to the collection's auth.
+    let auth_mode = method_block.get("auth").unwrap_or_default();
+    let auth = match auth_mode.as_str() {
+        "" | "inherit" => None,
+        mode => auth(blocks, mode),
+    };
+    if auth_mode == "apikey" {
+        if let Some((key, value, in_query)) = api_key(blocks) {
+            if in_query {
+                url.query.get_or_insert_with(Vec::new).push(QueryParam {
+                    key,
+                    value: Some(value),
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                });
+            } else {
+                header.push(Header {
+                    key,
+                    value,
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                });
+            }
+        }
+    }
+
+    let mut event = Vec::new();
+    if let Some(block) = find(blocks, "script:pre-request") {
+        event.push(script_event("prerequest", block));
+    }
+    for name in ["script:post-response", "tests"] {
+        if let Some(block) = find(blocks, name) {
+            event.push(script_event("test", block));
+        }
+    }
+
+    let variable = find(blocks, "vars:pre-request").map(variables).unwrap_or_default();
+    let description = find(blocks, "docs").map(Block::text);
+
+    Some(CollectionItem {
+        name: meta.and_then(|m| m.get("name")).unwrap_or(file_name),
+        item: None,
+        request: Some(Request {
+            method: Some(method_block.name.to_uppercase()),
+            header: if header.is_empty() { None } else { Some(header) },
+            body,
+            url: Some(url),
+            description: description.clone().map(Description::from),
+            auth,
+            options: None,
+            extra: Default::default(),
+        }),
+        response: None,
+        event: if event.is_empty() { None } else { Some(event) },
+        description: description.map(Description::from),
+        auth: None,
+        variable: if variable.is_empty() { None } else { Some(variable) },
+        extra: Default::default(),
+    })
+}
+
+fn headers(blocks: &[Block]) -> Vec<Header> {
+    find(blocks, "headers")
+        .map(|block| {
+            block
+                .pairs()
+                .into_iter()
+                .map(|(key, value, disabled)| Header {
+                    key,
+                    value,
+                    disabled: disabled.then_some(true),
+                    description: None,
+                    extra: Default::default(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `inherited` with `headers` added, replacing any with the same name.
+fn merge_headers(inherited: &[Header], headers: Vec<Header>) -> Vec<Header> {
+    let mut merged = inherited.to_vec();
+    for header in headers {
+        merged.retain(|existing| !existing.key.eq_ignore_ascii_case(&header.key));
+        merged.push(header);
+    }
+    merged
+}
+
+fn variables(block: &Block) -> Vec<Variable> {
+    block
+        .pairs()
+        .into_iter()
+        .map(|(key, value, disabled)| Variable {
+            key,
+            value,
+            var_type: Some("string".to_string()),
+            disabled: disabled.then_some(true),
+            extra: Default::default(),
+        })
+        .collect()
+}
+
+fn script_event(listen: &str, block: &Block) -> Event {
+    Event {
+        listen: Some(listen.to_string()),
+        script: Some(Script {
+            script_type: Some("text/javascript".to_string()),
+            exec: Some(block.lines.clone()),
+            src: None,
+            extra: Default::default(),
+        }),
+        extra: Default::default(),
+    }
+}
+
+fn body(blocks: &[Block], mode: &str, headers: &mut Vec<Header>) -> Option<Body> {
+    let mut body = Body::empty();
+    let content_type = match mode {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "sparql" => "application/sparql-query",
+        _ => "text/plain",
+    };
+
+    match mode {
+        "json" | "text" | "xml" | "sparql" => {
+            body.mode = Some("raw".to_string());
+            body.raw = Some(find(blocks, &format!("body:{}", mode))?.text());
+            if !headers.iter().any(|h| h.key.eq_ignore_ascii_case("content-type")) {
+                headers.push(Header {
+                    key: "Content-Type".to_string(),
+                    value: content_type.to_string(),
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                });
+            }
+        }
+        "formUrlEncoded" => {
+            body.mode = Some("urlencoded".to_string());
+            body.urlencoded = Some(form_fields(find(blocks, "body:form-urlencoded")?));
+        }
+        "multipartForm" => {
+            body.mode = Some("formdata".to_string());
+            body.formdata = Some(form_fields(find(blocks, "body:multipart-form")?));
+        }
+        "graphql" => {
+            body.mode = Some("graphql".to_string());
+            body.graphql = Some(GraphQLBody {
+                query: find(blocks, "body:graphql").map(Block::text),
+                variables: find(blocks, "body:graphql:vars").map(Block::text),
+                extra: Default::default(),
+            });
+        }
+        _ => return None,
+    }
+    Some(body)
+}
+
+/// Form fields; `@file(path)` values are file uploads (only the first of
+/// several `|`-separated paths is kept).
+fn form_fields(block: &Block) -> Vec<FormData> {
+    block
+        .pairs()
+        .into_iter()
+        .map(|(key, value, disabled)| {
+            let file = value
+                .strip_prefix("@file(")
+                .and_then(|v| v.strip_suffix(')'))
+                .map(|paths| paths.split('|').next().unwrap_or("").to_string());
+            FormData {
+                key,
+                data_type: Some(if file.is_some() { "file" } else { "text" }.to_string()),
+                value: Some(file.unwrap_or(value)),
+                disabled: disabled.then_some(true),
+                description: None,
+                extra: Default::default(),
+            }
+        })
+        .collect()
+}
+
+/// Maps an `auth:<mode>` block to `Auth`; see `api_key` for `apikey`.
+fn auth(blocks: &[Block], mode: &str) -> Option<Auth> {
+    let mut auth = Auth::new(mode);
+    // An API key is sent as a plain header or parameter (see `api_key`), so
+    // the request must not inherit another scheme on top of it.
+    if mode == "none" || mode == "apikey" {
+        auth.auth_type = Some("noauth".to_string());
+        return Some(auth);
+    }
+
+    let block = find(blocks, &format!("auth:{}", mode))?;
+    let values = variables(block);
+    match mode {
+        "bearer" => auth.bearer = Some(values),
+        "basic" => auth.basic = Some(values),
+        "digest" => auth.digest = Some(values),
+        "awsv4" => auth.awsv4 = Some(values),
+        "ntlm" => auth.ntlm = Some(values),
+        "oauth2" => {
+            let values = values
+                .into_iter()
+                .map(|mut v| {
+                    v.key = match v.key.as_str() {
+                        "access_token_url" => "accessTokenUrl".to_string(),
+                        "authorization_url" => "authUrl".to_string(),
+                        "client_id" => "clientId".to_string(),
+                        "client_secret" => "clientSecret".to_string(),
+                        _ => v.key,
+                    };
+                    v
+                })
+                .collect();
+            auth.oauth2 = Some(values);
+        }
+        _ => return None,
+    }
+    Some(auth)
+}
+
+/// The auth set in `collection.bru` or `folder.bru`; `inherit` (or no mode)
+/// leaves it to the enclosing scope. A header API key is added to `headers`,
+/// which are passed down to every request in scope.
+fn scope_auth(blocks: &[Block], headers: &mut Vec<Header>) -> Option<Auth> {
+    let mode = find(blocks, "auth").and_then(|block| block.get("mode"))?;
+    if mode == "inherit" {
+        return None;
+    }
+    if mode == "apikey" {
+        if let Some((key, value, false)) = api_key(blocks) {
+            headers.push(Header {
+                key,
+                value,
+                disabled: None,
+                description: None,
+                extra: Default::default(),
+            });
+        }
+    }
+    auth(blocks, &mode)
+}
+
+/// `auth:apikey` as `(key, value, in_query)`; it has no `Auth` type, so it is
+/// sent as a header or query parameter instead.
+fn api_key(blocks: &[Block]) -> Option<(String, String, bool)> {
+    let block = find(blocks, "auth:apikey")?;
+    Some((
+        block.get("key")?,
+        block.get("value").unwrap_or_default(),
+        block.get("placement").as_deref() == Some("queryparams"),
+    ))
+}
+
+fn read_environments(dir: &Path) -> Result<Vec<Environment>, FfiError> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bru"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let text = fs::read_to_string(&path).map_err(|e| read_error(&path, e))?;
+            let blocks = parse_blocks(&text);
+            let mut values = find(&blocks, "vars").map(variables).unwrap_or_default();
+
+            // Secret values live in the user's app data, not in the file.
+            for line in find(&blocks, "vars:secret").into_iter().flat_map(|b| b.lines.iter()) {
+                let name = line.trim().trim_end_matches(',');
+                let (name, disabled) = match name.strip_prefix('~') {
+                    Some(name) => (name, Some(true)),
+                    None => (name, None),
+                };
+                if !name.is_empty() {
+                    values.push(Variable {
+                        key: name.to_string(),
+                        value: String::new(),
+                        var_type: Some("secret".to_string()),
+                        disabled,
+                        extra: Default::default(),
+                    });
+                }
+            }
+
+            Ok(Environment {
+                id: None,
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                values: Some(values),
+                postman_variable_scope: Some("environment".to_string()),
+                postman_exported_at: None,
+                postman_exported_using: None,
+                extra: Default::default(),
+            })
+        })
+        .collect()
+}
+
+fn read_error(path: &Path, e: std::io::Error) -> FfiError {
+    FfiError::new(ErrorCategory::Io, "read_failed", format!("Cannot read {}: {}", path.display(), e))
+        .with_details(json!({ "path": path.display().to_string() }))
+}
+
+/// Import a Bruno collection directory (containing `bruno.json`). Returns
+/// `{"collection": {...}, "environments": [...]}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn bruno_to_collection_json(dir_path: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::arg(dir_path, "dir_path") }.and_then(|dir| {
+        let (collection, environments) = parse_bruno_collection(Path::new(dir))?;
+        Ok(json!({ "collection": collection, "environments": environments }))
+    }))
+}
@@ -0,0 +1,587 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::request::Request;
+use crate::models::url::form_encode;
+use std::fmt::Write;
+use std::os::raw::c_char;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Curl,
+    Fetch,
+    Axios,
+    PythonRequests,
+    GoNetHttp,
+    RustReqwest,
+}
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "curl" | "shell" => Some(Target::Curl),
+            "fetch" | "javascript" | "js" => Some(Target::Fetch),
+            "axios" | "node" | "nodejs" => Some(Target::Axios),
+            "python" | "requests" | "python-requests" => Some(Target::PythonRequests),
+            "go" | "golang" => Some(Target::GoNetHttp),
+            "rust" | "reqwest" => Some(Target::RustReqwest),
+            _ => None,
+        }
+    }
+}
+
+/// One `formdata` field.
+struct Part {
+    key: String,
+    value: String,
+    is_file: bool,
+}
+
+/// The request body in the shape every generator works from.
+enum SnippetBody {
+    None,
+    Text(String),
+    UrlEncoded(Vec<(String, String)>),
+    Multipart(Vec<Part>),
+    /// Path of a file to send as the whole body.
+    File(String),
+}
+
+struct Prepared {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: SnippetBody,
+}
+
+fn prepare(request: &Request) -> Result<Prepared, Box<dyn std::error::Error>> {
+    let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
+    let url = crate::build_url_from_struct(request.url.as_ref())?;
+
+    let mut headers: Vec<(String, String)> = request
+        .header
+        .iter()
+        .flatten()
+        .filter(|h| !h.disabled.unwrap_or(false))
+        .map(|h| (h.key.clone(), h.value.clone()))
+        .collect();
+    if let Some(auth) = &request.auth {
+        crate::apply_auth(auth, &mut headers)?;
+    }
+
+    let body = match request.body.as_ref() {
+        None => SnippetBody::None,
+        Some(body) => match body.mode.as_deref().unwrap_or("raw") {
+            "urlencoded" => SnippetBody::UrlEncoded(
+                body.urlencoded
+                    .iter()
+                    .flatten()
+                    .filter(|f| !f.disabled.unwrap_or(false))
+                    .map(|f| (f.key.clone(), f.value.clone().unwrap_or_default()))
+                    .collect(),
+            ),
+            "formdata" => SnippetBody::Multipart(
+                body.formdata
+                    .iter()
+                    .flatten()
+                    .filter(|f| !f.disabled.unwrap_or(false))
+                    .map(|f| Part {
+                        key: f.key.clone(),
+                        value: f.value.clone().unwrap_or_default(),
+                        is_file: f.data_type.as_deref() == Some("file"),
+                    })
+                    .collect(),
+            ),
+            "file" => match body.file.as_ref().and_then(|f| f.src.clone()) {
+                Some(src) => SnippetBody::File(src),
+                None => SnippetBody::None,
+            },
+            "graphql" => {
+                if !has_header(&headers, "content-type") {
+                    headers.push(("Content-Type".to_string(), "application/json".to_string()));
+                }
+                match crate::build_body_from_struct(Some(body))? {
+                    Some(json) => SnippetBody::Text(json),
+                    None => SnippetBody::None,
+                }
+            }
+            _ => match &body.raw {
+                Some(raw) if !raw.is_empty() => SnippetBody::Text(raw.clone()),
+                _ => SnippetBody::None,
+            },
+        },
+    };
+
+    Ok(Prepared {
+        method,
+        url,
+        headers,
+        body,
+    })
+}
+
+fn has_header(headers: &[(String, String)], name: &str) -> bool {
+    headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(name))
+}
+
+/// Renders `request` as a runnable snippet for `target`. The request should
+/// already have its variables resolved.
+pub fn generate(request: &Request, target: Target) -> Result<String, Box<dyn std::error::Error>> {
+    let prepared = prepare(request)?;
+    Ok(match target {
+        Target::Curl => curl(&prepared),
+        Target::Fetch => fetch(&prepared),
+        Target::Axios => axios(&prepared),
+        Target::PythonRequests => python(&prepared),
+        Target::GoNetHttp => go(&prepared),
+        Target::RustReqwest => rust(&prepared),
+    })
+}
+
+/// Quotes for a POSIX shell.
+fn sh(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// A double-quoted string literal that is valid in JavaScript, Python and Go.
+/// U+2028 and U+2029 are escaped too, since they end a line in JavaScript.
+fn quoted(s: &str) -> String {
+    serde_json::to_string(s)
+        .unwrap_or_else(|_| "\"\"".to_string())
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+/// A double-quoted value for `curl --form`, which reads `\"` and `\\` as
+/// escapes inside quotes.
+fn curl_form_quoted(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A Rust string literal.
+fn rust_str(s: &str) -> String {
+    format!("{:?}", s)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+fn curl(p: &Prepared) -> String {
+    let method = if p.method.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+        p.method.clone()
+    } else {
+        sh(&p.method)
+    };
+    let mut out = format!("curl --location --request {} {}", method, sh(&p.url));
+    for (key, value) in &p.headers {
+        let _ = write!(out, " \\\n--header {}", sh(&format!("{}: {}", key, value)));
+    }
+    match &p.body {
+        SnippetBody::None => {}
+        SnippetBody::Text(text) => {
+            let _ = write!(out, " \\\n--data-raw {}", sh(text));
+        }
+        SnippetBody::UrlEncoded(pairs) => {
+            for (key, value) in pairs {
+                // curl encodes only the value; the name is sent as given.
+                let _ = write!(out, " \\\n--data-urlencode {}", sh(&format!("{}={}", form_encode(key), value)));
+            }
+        }
+        SnippetBody::Multipart(parts) => {
+            for part in parts {
+                let field = if part.is_file {
+                    format!("{}=@{}", part.key, curl_form_quoted(&part.value))
+                } else {
+                    format!("{}={}", part.key, curl_form_quoted(&part.value))
+                };
+                let _ = write!(out, " \\\n--form {}", sh(&field));
+            }
+        }
+        SnippetBody::File(path) => {
+            let _ = write!(out, " \\\n--data-binary {}", sh(&format!("@{}", path)));
+        }
+    }
+    out
+}
+
+fn fetch(p: &Prepared) -> String {
+    let mut out = String::new();
+    let is_multipart = matches!(p.body, SnippetBody::Multipart(_));
+
+    if !p.headers.is_empty() {
+        out.push_str("const myHeaders = new Headers();\n");
+        for (key, value) in &p.headers {
+            // The browser sets the multipart boundary itself.
+            if is_multipart && key.eq_ignore_ascii_case("content-type") {
+                continue;
+            }
+            let _ = writeln!(out, "myHeaders.append({}, {});", quoted(key), quoted(value));
+        }
+        out.push('\n');
+    }
+
+    let body_var = match &p.body {
+        SnippetBody::None => None,
+        SnippetBody::Text(text) => {
+            let _ = writeln!(out, "const raw = {};\n", quoted(text));
+            Some("raw")
+        }
+        SnippetBody::UrlEncoded(pairs) => {
+            out.push_str("const urlencoded = new URLSearchParams();\n");
+            for (key, value) in pairs {
+                let _ = writeln!(out, "urlencoded.append({}, {});", quoted(key), quoted(value));
+            }
+            out.push('\n');
+            Some("urlencoded")
+        }
+        SnippetBody::Multipart(parts) => {
+            out.push_str("const formdata = new FormData();\n");
+            for part in parts {
+                if part.is_file {
+                    let _ = writeln!(
+                        out,
+                        "formdata.append({}, fileInput.files[0], {});",
+                        quoted(&part.key),
+                        quoted(file_name(&part.value))
+                    );
+                } else {
+                    let _ = writeln!(out, "formdata.append({}, {});", quoted(&part.key), quoted(&part.value));
+                }
+            }
+            out.push('\n');
+            Some("formdata")
+        }
+        SnippetBody::File(path) => {
+            let _ = writeln!(out, "const file = fileInput.files[0]; // {}\n", quoted(path));
+            Some("file")
+        }
+    };
+
+    out.push_str("const requestOptions = {\n");
+    let _ = writeln!(out, "  method: {},", quoted(&p.method));
+    if !p.headers.is_empty() {
+        out.push_str("  headers: myHeaders,\n");
+    }
+    if let Some(body_var) = body_var {
+        let _ = writeln!(out, "  body: {},", body_var);
+    }
+    out.push_str("  redirect: \"follow\"\n};\n\n");
+    let _ = writeln!(out, "fetch({}, requestOptions)", quoted(&p.url));
+    out.push_str("  .then((response) => response.text())\n");
+    out.push_str("  .then((result) => console.log(result))\n");
+    out.push_str("  .catch((error) => console.error(error));\n");
+    out
+}
+
+fn axios(p: &Prepared) -> String {
+    let mut out = String::from("const axios = require('axios');\n");
+    let mut extra_headers = None;
+
+    let data = match &p.body {
+        SnippetBody::None => None,
+        SnippetBody::Text(text) => {
+            let _ = writeln!(out, "\nlet data = {};", quoted(text));
+            Some("data")
+        }
+        SnippetBody::UrlEncoded(pairs) => {
+            out.push_str("\nlet data = new URLSearchParams();\n");
+            for (key, value) in pairs {
+                let _ = writeln!(out, "data.append({}, {});", quoted(key), quoted(value));
+            }
+            Some("data")
+        }
+        SnippetBody::Multipart(parts) => {
+            out.insert_str(0, "const FormData = require('form-data');\nconst fs = require('fs');\n");
+            out.push_str("\nlet data = new FormData();\n");
+            for part in parts {
+                if part.is_file {
+                    let _ = writeln!(
+                        out,
+                        "data.append({}, fs.createReadStream({}));",
+                        quoted(&part.key),
+                        quoted(&part.value)
+                    );
+                } else {
+                    let _ = writeln!(out, "data.append({}, {});", quoted(&part.key), quoted(&part.value));
+                }
+            }
+            extra_headers = Some("...data.getHeaders()");
+            Some("data")
+        }
+        SnippetBody::File(path) => {
+            out.insert_str(0, "const fs = require('fs');\n");
+            let _ = writeln!(out, "\nlet data = fs.createReadStream({});", quoted(path));
+            Some("data")
+        }
+    };
+
+    out.push_str("\nlet config = {\n");
+    let _ = writeln!(out, "  method: {},", quoted(&p.method.to_lowercase()));
+    out.push_str("  maxBodyLength: Infinity,\n");
+    let _ = writeln!(out, "  url: {},", quoted(&p.url));
+    if !p.headers.is_empty() || extra_headers.is_some() {
+        out.push_str("  headers: {\n");
+        for (key, value) in &p.headers {
+            if extra_headers.is_some() && key.eq_ignore_ascii_case("content-type") {
+                continue;
+            }
+            let _ = writeln!(out, "    {}: {},", quoted(key), quoted(value));
+        }
+        if let Some(extra) = extra_headers {
+            let _ = writeln!(out, "    {}", extra);
+        }
+        out.push_str("  },\n");
+    }
+    if let Some(data) = data {
+        let _ = writeln!(out, "  data: {}", data);
+    }
+    out.push_str("};\n\n");
+    out.push_str("axios.request(config)\n");
+    out.push_str("  .then((response) => {\n    console.log(JSON.stringify(response.data));\n  })\n");
+    out.push_str("  .catch((error) => {\n    console.log(error);\n  });\n");
+    out
+}
+
+fn python(p: &Prepared) -> String {
+    let mut out = String::from("import requests\n\n");
+    let _ = writeln!(out, "url = {}\n", quoted(&p.url));
+
+    let mut args = vec!["headers=headers".to_string()];
+    match &p.body {
+        SnippetBody::None => {
+            out.push_str("payload = {}\n");
+            args.push("data=payload".to_string());
+        }
+        SnippetBody::Text(text) => {
+            let _ = writeln!(out, "payload = {}", quoted(text));
+            args.push("data=payload".to_string());
+        }
+        SnippetBody::UrlEncoded(pairs) => {
+            out.push_str("payload = {\n");
+            for (key, value) in pairs {
+                let _ = writeln!(out, "    {}: {},", quoted(key), quoted(value));
+            }
+            out.push_str("}\n");
+            args.push("data=payload".to_string());
+        }
+        SnippetBody::Multipart(parts) => {
+            out.push_str("payload = {\n");
+            for part in parts.iter().filter(|p| !p.is_file) {
+                let _ = writeln!(out, "    {}: {},", quoted(&part.key), quoted(&part.value));
+            }
+            out.push_str("}\nfiles = [\n");
+            for part in parts.iter().filter(|p| p.is_file) {
+                let _ = writeln!(
+                    out,
+                    "    ({}, ({}, open({}, \"rb\"), \"application/octet-stream\")),",
+                    quoted(&part.key),
+                    quoted(file_name(&part.value)),
+                    quoted(&part.value)
+                );
+            }
+            out.push_str("]\n");
+            args.push("data=payload".to_string());
+            args.push("files=files".to_string());
+        }
+        SnippetBody::File(path) => {
+            let _ = writeln!(out, "payload = open({}, \"rb\")", quoted(path));
+            args.push("data=payload".to_string());
+        }
+    }
+
+    out.push_str("headers = {\n");
+    for (key, value) in &p.headers {
+        if matches!(p.body, SnippetBody::Multipart(_)) && key.eq_ignore_ascii_case("content-type") {
+            continue;
+        }
+        let _ = writeln!(out, "    {}: {},", quoted(key), quoted(value));
+    }
+    out.push_str("}\n\n");
+
+    let _ = writeln!(
+        out,
+        "response = requests.request({}, url, {})\n",
+        quoted(&p.method),
+        args.join(", ")
+    );
+    out.push_str("print(response.text)\n");
+    out
+}
+
+fn go(p: &Prepared) -> String {
+    let mut imports = vec!["fmt", "io", "net/http"];
+    let mut setup = String::new();
+    let mut payload = "nil";
+    let mut content_type = None;
+
+    match &p.body {
+        SnippetBody::None => {}
+        SnippetBody::Text(text) => {
+            imports.push("strings");
+            let _ = writeln!(setup, "\tpayload := strings.NewReader({})\n", quoted(text));
+            payload = "payload";
+        }
+        SnippetBody::UrlEncoded(pairs) => {
+            imports.push("net/url");
+            imports.push("strings");
+            setup.push_str("\tform := url.Values{}\n");
+            for (key, value) in pairs {
+                let _ = writeln!(setup, "\tform.Add({}, {})", quoted(key), quoted(value));
+            }
+            setup.push_str("\tpayload := strings.NewReader(form.Encode())\n\n");
+            payload = "payload";
+        }
+        SnippetBody::Multipart(parts) => {
+            imports.extend(["bytes", "mime/multipart"]);
+            if parts.iter().any(|p| p.is_file) {
+                imports.extend(["os", "path/filepath"]);
+            }
+            setup.push_str("\tpayload := &bytes.Buffer{}\n\twriter := multipart.NewWriter(payload)\n");
+            for part in parts {
+                if part.is_file {
+                    let _ = writeln!(
+                        setup,
+                        "\t{{\n\t\tfile, err := os.Open({path})\n\t\tif err != nil {{\n\t\t\tfmt.Println(err)\n\t\t\treturn\n\t\t}}\n\t\tdefer file.Close()\n\t\tpart, err := writer.CreateFormFile({key}, filepath.Base({path}))\n\t\tif err != nil {{\n\t\t\tfmt.Println(err)\n\t\t\treturn\n\t\t}}\n\t\tif _, err = io.Copy(part, file); err != nil {{\n\t\t\tfmt.Println(err)\n\t\t\treturn\n\t\t}}\n\t}}",
+                        path = quoted(&part.value),
+                        key = quoted(&part.key)
+                    );
+                } else {
+                    let _ = writeln!(setup, "\t_ = writer.WriteField({}, {})", quoted(&part.key), quoted(&part.value));
+                }
+            }
+            setup.push_str("\tif err := writer.Close(); err != nil {\n\t\tfmt.Println(err)\n\t\treturn\n\t}\n\n");
+            payload = "payload";
+            content_type = Some("writer.FormDataContentType()");
+        }
+        SnippetBody::File(path) => {
+            imports.push("os");
+            let _ = writeln!(
+                setup,
+                "\tpayload, err := os.Open({})\n\tif err != nil {{\n\t\tfmt.Println(err)\n\t\treturn\n\t}}\n\tdefer payload.Close()\n",
+                quoted(path)
+            );
+            payload = "payload";
+        }
+    }
+
+    imports.sort();
+    imports.dedup();
+
+    let mut out = String::from("package main\n\nimport (\n");
+    for import in imports {
+        let _ = writeln!(out, "\t\"{}\"", import);
+    }
+    out.push_str(")\n\nfunc main() {\n");
+    // Not `url`, which would shadow the net/url package.
+    let _ = writeln!(out, "\trequestURL := {}", quoted(&p.url));
+    let _ = writeln!(out, "\tmethod := {}\n", quoted(&p.method));
+    out.push_str(&setup);
+    out.push_str("\tclient := &http.Client{}\n");
+    let _ = writeln!(out, "\treq, err := http.NewRequest(method, requestURL, {})", payload);
+    out.push_str("\tif err != nil {\n\t\tfmt.Println(err)\n\t\treturn\n\t}\n");
+    for (key, value) in &p.headers {
+        if content_type.is_some() && key.eq_ignore_ascii_case("content-type") {
+            continue;
+        }
+        let _ = writeln!(out, "\treq.Header.Add({}, {})", quoted(key), quoted(value));
+    }
+    if let Some(content_type) = content_type {
+        let _ = writeln!(out, "\treq.Header.Set(\"Content-Type\", {})", content_type);
+    }
+    out.push_str("\n\tres, err := client.Do(req)\n\tif err != nil {\n\t\tfmt.Println(err)\n\t\treturn\n\t}\n\tdefer res.Body.Close()\n\n");
+    out.push_str("\tbody, err := io.ReadAll(res.Body)\n\tif err != nil {\n\t\tfmt.Println(err)\n\t\treturn\n\t}\n\tfmt.Println(string(body))\n}\n");
+    out
+}
+
+fn rust(p: &Prepared) -> String {
+    let mut out = String::from("#[tokio::main]\nasync fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    out.push_str("    let client = reqwest::Client::builder().build()?;\n\n");
+
+    let is_multipart = matches!(p.body, SnippetBody::Multipart(_));
+    let headers: Vec<&(String, String)> = p
+        .headers
+        .iter()
+        .filter(|(k, _)| !(is_multipart && k.eq_ignore_ascii_case("content-type")))
+        .collect();
+    if !headers.is_empty() {
+        out.push_str("    let mut headers = reqwest::header::HeaderMap::new();\n");
+        for (key, value) in &headers {
+            let _ = writeln!(out, "    headers.insert({}, {}.parse()?);", rust_str(key), rust_str(value));
+        }
+        out.push('\n');
+    }
+
+    let body_call = match &p.body {
+        SnippetBody::None => String::new(),
+        SnippetBody::Text(text) => {
+            let _ = writeln!(out, "    let data = {};\n", rust_str(text));
+            "\n        .body(data)".to_string()
+        }
+        SnippetBody::UrlEncoded(pairs) => {
+            out.push_str("    let params = [\n");
+            for (key, value) in pairs {
+                let _ = writeln!(out, "        ({}, {}),", rust_str(key), rust_str(value));
+            }
+            out.push_str("    ];\n\n");
+            "\n        .form(&params)".to_string()
+        }
+        SnippetBody::Multipart(parts) => {
+            out.push_str("    let form = reqwest::multipart::Form::new()");
+            for part in parts {
+                if part.is_file {
+                    let _ = write!(
+                        out,
+                        "\n        .part({}, reqwest::multipart::Part::bytes(std::fs::read({})?).file_name({}))",
+                        rust_str(&part.key),
+                        rust_str(&part.value),
+                        rust_str(file_name(&part.value))
+                    );
+                } else {
+                    let _ = write!(out, "\n        .text({}, {})", rust_str(&part.key), rust_str(&part.value));
+                }
+            }
+            out.push_str(";\n\n");
+            "\n        .multipart(form)".to_string()
+        }
+        SnippetBody::File(path) => {
+            let _ = writeln!(out, "    let data = std::fs::read({})?;\n", rust_str(path));
+            "\n        .body(data)".to_string()
+        }
+    };
+
+    let _ = write!(
+        out,
+        "    let request = client.request(reqwest::Method::from_bytes({}.as_bytes())?, {})",
+        rust_str(&p.method),
+        rust_str(&p.url)
+    );
+    if !headers.is_empty() {
+        out.push_str("\n        .headers(headers)");
+    }
+    out.push_str(&body_call);
+    out.push_str(";\n\n");
+    out.push_str("    let response = request.send().await?;\n");
+    out.push_str("    let body = response.text().await?;\n\n");
+    out.push_str("    println!(\"{}\", body);\n\n    Ok(())\n}\n");
+    out
+}
+
+/// Generate a code snippet for a resolved request. `target` is one of
+/// `curl`, `fetch`, `axios`, `python`, `go` or `rust`. Returns the snippet
+/// as plain text.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn generate_code_snippet(request_json: *const c_char, target: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let request: Request = unsafe { ffi::json_arg(request_json, "request") }?;
+        let target_name = unsafe { ffi::arg(target, "target") }?;
+        let target = Target::from_name(target_name).ok_or_else(|| {
+            FfiError::new(
+                ErrorCategory::Unsupported,
+                "unknown_target",
+                format!("Unknown code generation target: {}", target_name),
+            )
+        })?;
+        Ok(generate(&request, target)?)
+    })();
+    ffi::respond(result)
+}
@@ -0,0 +1,599 @@
+use crate::ffi::{self, FfiError};
+use crate::models::request::{Auth, Body, FileBody, FormData, Header, Request, Url};
+use crate::models::url::{form_decode, form_encode};
+use crate::models::variable::Variable;
+use base64::Engine;
+use std::collections::VecDeque;
+use std::os::raw::c_char;
+
+/// Options that consume the following argument but do not affect the
+/// request we build (output files, timeouts, proxies, ...).
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-o", "--output", "-m", "--max-time", "--connect-timeout", "--retry", "--retry-delay",
+    "--retry-max-time", "-w", "--write-out", "-x", "--proxy", "-U", "--proxy-user", "-c",
+    "--cookie-jar", "-T", "--upload-file", "--cacert", "--capath", "-E", "--cert", "--key",
+    "--resolve", "--limit-rate", "-r", "--range", "--max-redirs", "-z", "--time-cond",
+    "--interface", "--dns-servers", "-D", "--dump-header", "-K", "--config", "-C", "--continue-at",
+    "-P", "--ftp-port", "-Q", "--quote", "-t", "--telnet-option", "-y", "--speed-time", "-Y",
+    "--speed-limit", "--cert-type", "--key-type", "--pass", "--ciphers", "--connect-to",
+    "--proxy-header", "--noproxy", "--preproxy", "--socks4", "--socks4a", "--socks5",
+    "--socks5-hostname", "--unix-socket", "--abstract-unix-socket", "--local-port",
+    "--keepalive-time", "--expect100-timeout", "--netrc-file", "--pinnedpubkey", "--tls-max",
+    "--trace", "--trace-ascii", "--stderr", "--proxy-cacert", "--proxy-cert", "--proxy-key",
+    "--crlfile", "--egd-file", "--random-file", "--happy-eyeballs-timeout-ms", "--max-filesize",
+    "--output-dir", "--parallel-max", "--retry-max-time", "--variable", "--request-target",
+];
+
+/// Options that take no argument and do not affect the request we build.
+/// Any `--no-...` form is accepted too.
+const IGNORED_FLAGS: &[&str] = &[
+    "--compressed", "--insecure", "--location", "--location-trusted", "--silent", "--show-error",
+    "--verbose", "--include", "--fail", "--fail-with-body", "--fail-early", "--http0.9", "--http1.0",
+    "--http1.1", "--http2", "--http2-prior-knowledge", "--http3", "--http3-only", "--globoff",
+    "--tcp-nodelay", "--tcp-fastopen", "--tlsv1", "--tlsv1.0", "--tlsv1.1", "--tlsv1.2", "--tlsv1.3",
+    "--sslv2", "--sslv3", "--ssl", "--ssl-reqd", "--ssl-no-revoke", "--ssl-allow-beast", "--ipv4",
+    "--ipv6", "--post301", "--post302", "--post303", "--remote-name", "--remote-name-all",
+    "--remote-header-name", "--remote-time", "--create-dirs", "--raw", "--path-as-is", "--anyauth",
+    "--basic", "--negotiate", "--progress-bar", "--styled-output", "--proxy-insecure",
+    "--doh-insecure", "--false-start", "--junk-session-cookies", "--list-only", "--append", "--crlf",
+    "--ftp-pasv", "--ftp-ssl", "--ignore-content-length", "--netrc", "--netrc-optional",
+    "--retry-connrefused", "--retry-all-errors", "--manual", "--help", "--version", "--xattr",
+    "--suppress-connect-headers", "--ca-native", "--proxy-ca-native", "--disable",
+    "--disallow-username-in-url", "--tr-encoding", "--parallel", "--parallel-immediate",
+    "--proxytunnel", "--proxy-basic", "--proxy-digest", "--proxy-ntlm", "--proxy-anyauth",
+    "--proxy-negotiate", "--sasl-ir", "--cert-status", "--compressed-ssh", "--mptcp",
+];
+
+/// Short options that take no argument and do not affect the request.
+const IGNORED_SHORT_FLAGS: &str = "0123456afgijJkLlMnNOpqRsSvVZ#";
+
+/// A `-d`-style argument: literal data, or `@file` whose contents curl
+/// would send (the file is not read).
+enum Data {
+    Text(String),
+    File(String),
+}
+
+#[derive(Default)]
+struct ParsedCurl {
+    method: Option<String>,
+    url: Option<String>,
+    headers: Vec<(String, String)>,
+    data: Vec<Data>,
+    form: Vec<FormData>,
+    user: Option<String>,
+    /// `digest` or `ntlm` when `-u` should use that scheme instead of basic.
+    auth_scheme: Option<&'static str>,
+    bearer: Option<String>,
+    cookies: Vec<String>,
+    /// `--url-query` parts, already encoded.
+    url_query: Vec<String>,
+    get: bool,
+    head: bool,
+}
+
+/// Parses a `curl` command line, as copied from browser devtools or docs,
+/// into a request.
+///
+/// `-d`-style data becomes an `urlencoded` body when it is form-encoded and a
+/// `raw` body otherwise; `-F` becomes `formdata`. `-u` and `Authorization:
+/// Basic/Bearer` headers become `Auth`. Transport-only flags such as
+/// `--compressed`, `-k` and `-L` are accepted and ignored.
+pub fn parse_curl(command: &str) -> Result<Request, String> {
+    let mut args: VecDeque<String> = tokenize(command)?.into();
+
+    match args.pop_front() {
+        Some(first) if first == "curl" || first.ends_with("/curl") || first.ends_with("curl.exe") => {}
+        Some(first) => return Err(format!("Expected a curl command, found '{}'", first)),
+        None => return Err("Empty command".to_string()),
+    }
+
+    let mut parsed = ParsedCurl::default();
+
+    while let Some(arg) = args.pop_front() {
+        // `--opt=value` form for long options.
+        let (flag, inline) = match arg.strip_prefix("--").and_then(|rest| rest.split_once('=')) {
+            Some((name, value)) => (format!("--{}", name), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+
+        let mut value = |name: &str| -> Result<String, String> {
+            match &inline {
+                Some(v) => Ok(v.clone()),
+                None => args.pop_front().ok_or_else(|| format!("Missing value for {}", name)),
+            }
+        };
+
+        match flag.as_str() {
+            "-X" | "--request" => parsed.method = Some(value(&flag)?.to_uppercase()),
+            "-H" | "--header" => {
+                let header = value(&flag)?;
+                if let Some((key, val)) = header.split_once(':') {
+                    parsed.headers.push((key.trim().to_string(), val.trim().to_string()));
+                }
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                let data = value(&flag)?;
+                if let Some(path) = data.strip_prefix('@') {
+                    parsed.data.push(Data::File(path.to_string()));
+                } else if flag == "--data-binary" {
+                    parsed.data.push(Data::Text(data));
+                } else {
+                    // curl strips newlines from non-binary data.
+                    parsed.data.push(Data::Text(data.replace(['\r', '\n'], "")));
+                }
+            }
+            "--data-raw" => parsed.data.push(Data::Text(value(&flag)?)),
+            "--data-urlencode" => {
+                let data = value(&flag)?;
+                parsed.data.push(match data.strip_prefix('@') {
+                    Some(path) => Data::File(path.to_string()),
+                    None => Data::Text(encode_data_urlencode(&data)),
+                });
+            }
+            "--url-query" => {
+                let query = value(&flag)?;
+                if query.trim_start_matches('+').starts_with('@') {
+                    return Err(format!("--url-query {} reads a file, which is not supported", query));
+                }
+                // A leading `+` means the part is already encoded.
+                parsed.url_query.push(match query.strip_prefix('+') {
+                    Some(encoded) => encoded.to_string(),
+                    None => encode_data_urlencode(&query),
+                });
+            }
+            "--json" => {
+                let data = value(&flag)?;
+                parsed.data.push(match data.strip_prefix('@') {
+                    Some(path) => Data::File(path.to_string()),
+                    None => Data::Text(data),
+                });
+                parsed.headers.push(("Content-Type".to_string(), "application/json".to_string()));
+                parsed.headers.push(("Accept".to_string(), "application/json".to_string()));
+            }
+            "-F" | "--form" | "--form-string" => {
+                let field = value(&flag)?;
+                let (key, val) = field.split_once('=').unwrap_or((field.as_str(), ""));
+                let is_file = flag != "--form-string" && val.starts_with('@');
+                let val = if is_file {
+                    // Drop `;type=...` and similar modifiers after the path.
+                    val[1..].split(';').next().unwrap_or("").to_string()
+                } else {
+                    val.to_string()
+                };
+                parsed.form.push(FormData {
+                    key: key.to_string(),
+                    value: Some(val),
+                    data_type: Some(if is_file { "file" } else { "text" }.to_string()),
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                });
+            }
+            "-u" | "--user" => parsed.user = Some(value(&flag)?),
+            "--digest" => parsed.auth_scheme = Some("digest"),
+            "--ntlm" => parsed.auth_scheme = Some("ntlm"),
+            "--oauth2-bearer" => parsed.bearer = Some(value(&flag)?),
+            "-b" | "--cookie" => {
+                // Without `=` the argument names a cookie file, which is not read.
+                let cookie = value(&flag)?;
+                if cookie.contains('=') {
+                    parsed.cookies.push(cookie);
+                }
+            }
+            "-A" | "--user-agent" => parsed.headers.push(("User-Agent".to_string(), value(&flag)?)),
+            "-e" | "--referer" => parsed.headers.push(("Referer".to_string(), value(&flag)?)),
+            "--url" => parsed.url = Some(value(&flag)?),
+            "-G" | "--get" => parsed.get = true,
+            "-I" | "--head" => parsed.head = true,
+            f if IGNORED_WITH_VALUE.contains(&f) => {
+                value(f)?;
+            }
+            f if IGNORED_FLAGS.contains(&f) || f.starts_with("--no-") => {}
+            f if f.starts_with("--") => {
+                // Whether it takes a value is unknown, so its argument could be
+                // mistaken for the URL.
+                return Err(format!("Unsupported curl option '{}'", f));
+            }
+            f if f.starts_with('-') && f.chars().count() > 2 => {
+                for expanded in expand_short_cluster(f).into_iter().rev() {
+                    args.push_front(expanded);
+                }
+            }
+            f if f.starts_with('-') && f.len() == 2 => {
+                if !IGNORED_SHORT_FLAGS.contains(&f[1..]) {
+                    return Err(format!("Unsupported curl option '{}'", f));
+                }
+            }
+            _ => {
+                if parsed.url.is_none() {
+                    parsed.url = Some(arg);
+                }
+            }
+        }
+    }
+
+    build_request(parsed)
+}
+
+/// Short options that take a value. Used to split clusters such as `-sSL`
+/// or attached values such as `-XPOST`.
+fn short_takes_value(c: char) -> bool {
+    matches!(c, 'X' | 'H' | 'd' | 'F' | 'u' | 'b' | 'A' | 'e')
+        || IGNORED_WITH_VALUE.contains(&format!("-{}", c).as_str())
+}
+
+/// Expands `-sSLXPOST` into `-s -S -L -X POST`.
+fn expand_short_cluster(arg: &str) -> Vec<String> {
+    let mut expanded = Vec::new();
+    let chars: Vec<char> = arg[1..].chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        expanded.push(format!("-{}", c));
+        if short_takes_value(*c) {
+            let attached: String = chars[i + 1..].iter().collect();
+            if !attached.is_empty() {
+                expanded.push(attached);
+            }
+            break;
+        }
+    }
+    expanded
+}
+
+fn build_request(parsed: ParsedCurl) -> Result<Request, String> {
+    let raw_url = parsed.url.ok_or("No URL found in curl command")?;
+    let raw_url = if raw_url.contains("://") || raw_url.starts_with("{{") {
+        raw_url
+    } else {
+        format!("http://{}", raw_url)
+    };
+
+    let mut headers = parsed.headers;
+    if !parsed.cookies.is_empty() {
+        headers.push(("Cookie".to_string(), parsed.cookies.join("; ")));
+    }
+
+    let mut auth = parsed.user.as_deref().map(|user| {
+        let (username, password) = user.split_once(':').unwrap_or((user, ""));
+        match parsed.auth_scheme {
+            Some(scheme) => credentials_auth(scheme, username, password),
+            None => basic_auth(username, password),
+        }
+    });
+    if let Some(token) = &parsed.bearer {
+        auth = Some(bearer_auth(token));
+    }
+
+    // Lift Authorization headers into Auth so they can be edited as such.
+    if auth.is_none() {
+        if let Some(index) = headers.iter().position(|(k, _)| k.eq_ignore_ascii_case("authorization")) {
+            auth = auth_from_header(&headers[index].1);
+            if auth.is_some() {
+                headers.remove(index);
+            }
+        }
+    }
+
+    let content_type = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.to_ascii_lowercase());
+
+    // The file's contents would be sent, so `@file` can only stand alone.
+    let files: Vec<&str> = parsed
+        .data
+        .iter()
+        .filter_map(|data| match data {
+            Data::File(path) => Some(path.as_str()),
+            Data::Text(_) => None,
+        })
+        .collect();
+    if !files.is_empty() && (parsed.data.len() > 1 || parsed.get) {
+        let what = if parsed.get { "-G" } else { "other data" };
+        return Err(format!("Cannot combine @{} with {}; the file is not read", files.join(", @"), what));
+    }
+    let texts: Vec<&str> = parsed
+        .data
+        .iter()
+        .filter_map(|data| match data {
+            Data::Text(text) => Some(text.as_str()),
+            Data::File(_) => None,
+        })
+        .collect();
+
+    let mut query = parsed.url_query.clone();
+    let has_body = !parsed.data.is_empty() || !parsed.form.is_empty();
+    let mut body = None;
+
+    if parsed.get && !texts.is_empty() {
+        // -G appends the data to the query string instead of sending a body.
+        query.extend(texts.iter().map(|text| text.to_string()));
+    } else if !parsed.form.is_empty() {
+        body = Some(Body {
+            mode: Some("formdata".to_string()),
+            formdata: Some(parsed.form),
+            ..Body::empty()
+        });
+    } else if let Some(path) = files.first() {
+        body = Some(Body {
+            mode: Some("file".to_string()),
+            file: Some(FileBody {
+                src: Some(path.to_string()),
+                extra: Default::default(),
+            }),
+            ..Body::empty()
+        });
+    } else if !texts.is_empty() {
+        let joined = texts.join("&");
+        let is_form = match &content_type {
+            Some(ct) => ct.contains("application/x-www-form-urlencoded"),
+            None => looks_form_encoded(&joined),
+        };
+        body = Some(if is_form {
+            Body {
+                mode: Some("urlencoded".to_string()),
+                urlencoded: Some(
+                    joined
+                        .split('&')
+                        .filter(|pair| !pair.is_empty())
+                        .map(|pair| {
+                            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                            FormData {
+                                key: form_decode(key),
+                                value: Some(form_decode(value)),
+                                data_type: Some("text".to_string()),
+                                disabled: None,
+                                description: None,
+                                extra: Default::default(),
+                            }
+                        })
+                        .collect(),
+                ),
+                ..Body::empty()
+            }
+        } else {
+            Body {
+                mode: Some("raw".to_string()),
+                raw: Some(joined),
+                ..Body::empty()
+            }
+        });
+    }
+
+    let url = if query.is_empty() {
+        Url::parse(&raw_url)
+    } else {
+        let separator = if raw_url.contains('?') { "&" } else { "?" };
+        Url::parse(&format!("{}{}{}", raw_url, separator, query.join("&")))
+    };
+
+    let method = match parsed.method {
+        Some(method) => method,
+        None if parsed.head => "HEAD".to_string(),
+        None if has_body && !parsed.get => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+
+    Ok(Request {
+        method: Some(method),
+        header: if headers.is_empty() {
+            None
+        } else {
+            Some(
+                headers
+                    .into_iter()
+                    .map(|(key, value)| Header {
+                        key,
+                        value,
+                        disabled: None,
+                        description: None,
+                        extra: Default::default(),
+                    })
+                    .collect(),
+            )
+        },
+        body,
+        url: Some(url),
+        description: None,
+        auth,
+        options: None,
+        extra: Default::default(),
+    })
+}
+
+/// Implements curl's `--data-urlencode` forms `content`, `=content` and
+/// `name=content`. `@file` is handled by the caller; `name@file` is not
+/// supported, so text such as an email address is encoded as content.
+fn encode_data_urlencode(value: &str) -> String {
+    if let Some(content) = value.strip_prefix('=') {
+        return form_encode(content);
+    }
+    if let Some((name, content)) = value.split_once('=') {
+        return format!("{}={}", name, form_encode(content));
+    }
+    form_encode(value)
+}
+
+fn looks_form_encoded(data: &str) -> bool {
+    let trimmed = data.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') || trimmed.starts_with('<') {
+        return false;
+    }
+    data.split('&').all(|pair| match pair.split_once('=') {
+        Some((key, _)) => !key.is_empty() && !key.contains(char::is_whitespace),
+        None => false,
+    })
+}
+
+/// Converts an `Authorization` header value with a bearer token or base64
+/// basic credentials into `Auth`.
+pub(crate) fn auth_from_header(value: &str) -> Option<Auth> {
+    if let Some(token) = strip_prefix_ignore_case(value, "Bearer ") {
+        return Some(bearer_auth(token.trim()));
+    }
+
+    let encoded = strip_prefix_ignore_case(value, "Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())?;
+    let (username, password) = decoded.split_once(':').unwrap_or((&decoded, ""));
+    Some(basic_auth(username, password))
+}
+
+pub(crate) fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    if value.len() >= prefix.len() && value.is_char_boundary(prefix.len()) && value[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        Some(&value[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn variable(key: &str, value: &str) -> Variable {
+    Variable {
+        key: key.to_string(),
+        value: value.to_string(),
+        var_type: Some("string".to_string()),
+        disabled: None,
+        extra: Default::default(),
+    }
+}
+
+pub(crate) fn basic_auth(username: &str, password: &str) -> Auth {
+    credentials_auth("basic", username, password)
+}
+
+/// `basic`, `digest` or `ntlm` auth with a username and password.
+fn credentials_auth(auth_type: &str, username: &str, password: &str) -> Auth {
+    let credentials = Some(vec![variable("username", username), variable("password", password)]);
+    let mut auth = Auth::new(auth_type);
+    match auth_type {
+        "digest" => auth.digest = credentials,
+        "ntlm" => auth.ntlm = credentials,
+        _ => auth.basic = credentials,
+    }
+    auth
+}
+
+fn bearer_auth(token: &str) -> Auth {
+    Auth {
+        bearer: Some(vec![variable("token", token)]),
+        ..Auth::new("bearer")
+    }
+}
+
+/// Splits a POSIX shell command line into words, handling single and double
+/// quotes, `$'...'` ANSI-C strings, backslash escapes and line
+/// continuations (`\` or `^` at the end of a line).
+fn tokenize(command: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') => {
+                    chars.next_if_eq(&'\n');
+                }
+                Some(next) => {
+                    current.push(next);
+                    in_token = true;
+                }
+                None => {}
+            },
+            '^' if matches!(chars.peek(), Some('\n') | Some('\r')) => {
+                // Windows cmd line continuation from "Copy as cURL (cmd)".
+                while matches!(chars.peek(), Some('\n') | Some('\r')) {
+                    chars.next();
+                }
+            }
+            '\'' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => current.push(ch),
+                        None => return Err("Unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some('r') => current.push('\r'),
+                            Some('0') => current.push('\0'),
+                            Some('x') => {
+                                let hex: String = (0..2).filter_map(|_| chars.next_if(|c| c.is_ascii_hexdigit())).collect();
+                                if let Some(ch) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                    current.push(ch);
+                                }
+                            }
+                            Some('u') => {
+                                let hex: String = (0..4).filter_map(|_| chars.next_if(|c| c.is_ascii_hexdigit())).collect();
+                                if let Some(ch) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                    current.push(ch);
+                                }
+                            }
+                            Some(ch) => current.push(ch),
+                            None => return Err("Unterminated $'...' string".to_string()),
+                        },
+                        Some(ch) => current.push(ch),
+                        None => return Err("Unterminated $'...' string".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(ch @ ('"' | '\\' | '$' | '`')) => current.push(ch),
+                            Some('\n') => {}
+                            Some(ch) => {
+                                current.push('\\');
+                                current.push(ch);
+                            }
+                            None => return Err("Unterminated double quote".to_string()),
+                        },
+                        Some(ch) => current.push(ch),
+                        None => return Err("Unterminated double quote".to_string()),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Parse a curl command line into a Request
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn parse_curl_command(command: *const c_char) -> *mut c_char {
+    ffi::respond(
+        unsafe { ffi::arg(command, "command") }.and_then(|command| parse_curl(command).map_err(|e| FfiError::parse("curl command", e))),
+    )
+}
@@ -0,0 +1,43 @@
+//! Undoes a response's `Content-Encoding`. reqwest is built without its own
+//! decompression so the encoded size can be reported and decoding can be
+//! turned off per request (`ababilOptions.decompress`).
+
+use crate::ffi::{ErrorCategory, FfiError};
+use std::io::Read;
+
+/// Sent with every request that does not set its own `Accept-Encoding`.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+/// Decodes `body` according to a `Content-Encoding` header value. Encodings
+/// are listed in the order they were applied, so they are undone in reverse.
+pub fn decode(body: &[u8], content_encoding: &str) -> Result<Vec<u8>, FfiError> {
+    let mut body = body.to_vec();
+    for coding in content_encoding.rsplit(',').map(|c| c.trim().to_ascii_lowercase()) {
+        body = match coding.as_str() {
+            "" | "identity" => body,
+            "gzip" | "x-gzip" => read_all(flate2::read::MultiGzDecoder::new(&body[..]), &coding)?,
+            // `deflate` is meant to be zlib-wrapped, but some servers send a
+            // bare deflate stream.
+            "deflate" => read_all(flate2::read::ZlibDecoder::new(&body[..]), &coding)
+                .or_else(|_| read_all(flate2::read::DeflateDecoder::new(&body[..]), &coding))?,
+            "br" => read_all(brotli_decompressor::Decompressor::new(&body[..], 4096), &coding)?,
+            "zstd" => zstd::stream::decode_all(&body[..]).map_err(|e| decode_error(&coding, e))?,
+            other => {
+                let message = format!("Unsupported Content-Encoding '{}'", other);
+                return Err(FfiError::new(ErrorCategory::Unsupported, "unsupported_encoding", message));
+            }
+        };
+    }
+    Ok(body)
+}
+
+fn read_all(mut reader: impl Read, coding: &str) -> Result<Vec<u8>, FfiError> {
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).map_err(|e| decode_error(coding, e))?;
+    Ok(out)
+}
+
+fn decode_error(coding: &str, e: std::io::Error) -> FfiError {
+    let message = format!("Cannot decode {} response body: {}", coding, e);
+    FfiError::new(ErrorCategory::Network, "decompression_failed", message)
+}
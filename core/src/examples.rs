@@ -0,0 +1,164 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::collection::{Collection, CollectionItem, Cookie, Response};
+use crate::models::request::{Header, Request};
+use crate::scripts::reason_phrase;
+use crate::HttpResponse;
+use std::os::raw::c_char;
+
+/// Builds a saved example from an executed request and its response.
+/// Without a `name` the example is named after the status, e.g. "200 OK".
+pub fn response_to_example(name: Option<&str>, request: &Request, response: &HttpResponse) -> Response {
+    let status = reason_phrase(response.status_code).to_string();
+    let name = match name {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => format!("{} {}", response.status_code, status).trim().to_string(),
+    };
+
+    let header: Vec<Header> = response
+        .headers
+        .iter()
+        .map(|(key, value)| Header {
+            key: key.clone(),
+            value: value.clone(),
+            disabled: None,
+            description: None,
+            extra: Default::default(),
+        })
+        .collect();
+
+    let cookie: Vec<Cookie> = response
+        .headers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("set-cookie"))
+        .filter_map(|(_, value)| parse_set_cookie(value))
+        .collect();
+
+    let content_type = response
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.as_str());
+
+    Response {
+        name: Some(name),
+        original_request: Some(request.clone()),
+        status: if status.is_empty() { None } else { Some(status) },
+        code: Some(response.status_code),
+        postman_previewlanguage: Some(preview_language(content_type).to_string()),
+        header: Some(header),
+        cookie: Some(cookie),
+        body: Some(response.body.clone()),
+        response_time: Some(response.duration_ms.to_string()),
+        timings: None,
+        extra: Default::default(),
+    }
+}
+
+/// Appends `example` to the request item found by following `item_path`
+/// (folder names then the request name) from the collection root.
+pub fn attach_example(
+    collection: &mut Collection,
+    item_path: &[String],
+    example: Response,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let item = find_item_mut(&mut collection.item, item_path).ok_or_else(|| {
+        let message = format!("No item at path '{}'", item_path.join(" / "));
+        FfiError::new(ErrorCategory::Argument, "item_not_found", message)
+    })?;
+
+    if item.request.is_none() {
+        let message = format!("'{}' is a folder, not a request", item.name);
+        return Err(FfiError::new(ErrorCategory::Argument, "not_a_request", message).into());
+    }
+
+    item.response.get_or_insert_with(Vec::new).push(example);
+    Ok(())
+}
+
+fn find_item_mut<'a>(items: &'a mut [CollectionItem], path: &[String]) -> Option<&'a mut CollectionItem> {
+    let (first, rest) = path.split_first()?;
+    let item = items.iter_mut().find(|item| &item.name == first)?;
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        find_item_mut(item.item.as_mut()?, rest)
+    }
+}
+
+/// Maps a Content-Type to the `_postman_previewlanguage` values Postman uses.
+fn preview_language(content_type: Option<&str>) -> &'static str {
+    let content_type = content_type.unwrap_or("").to_ascii_lowercase();
+    if content_type.contains("json") {
+        "json"
+    } else if content_type.contains("html") {
+        "html"
+    } else if content_type.contains("xml") {
+        "xml"
+    } else if content_type.contains("javascript") {
+        "javascript"
+    } else {
+        "text"
+    }
+}
+
+pub(crate) fn parse_set_cookie(header: &str) -> Option<Cookie> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+
+    let mut cookie = Cookie {
+        name: Some(name.trim().to_string()),
+        value: Some(value.trim().to_string()),
+        domain: None,
+        path: None,
+        expires: None,
+        http_only: Some(false),
+        secure: Some(false),
+        extra: Default::default(),
+    };
+
+    for attribute in parts {
+        let (key, value) = match attribute.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (attribute.trim(), None),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "domain" => cookie.domain = value,
+            "path" => cookie.path = value,
+            "expires" => cookie.expires = value,
+            "httponly" => cookie.http_only = Some(true),
+            "secure" => cookie.secure = Some(true),
+            _ => {}
+        }
+    }
+
+    Some(cookie)
+}
+
+/// Save an executed response as an example on a collection item.
+///
+/// `item_path_json` is a JSON array of names from the collection root to the
+/// request, e.g. `["Users", "Get user"]`. `name` may be null. Returns the
+/// updated collection JSON.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn save_response_as_example(
+    collection_json: *const c_char,
+    item_path_json: *const c_char,
+    request_json: *const c_char,
+    response_json: *const c_char,
+    name: *const c_char,
+) -> *mut c_char {
+    let result = (|| {
+        let mut collection = Collection::from_json(unsafe { ffi::arg(collection_json, "collection") }?)
+            .map_err(|e| FfiError::json("collection", &e))?;
+        let item_path: Vec<String> = unsafe { ffi::json_arg(item_path_json, "item_path") }?;
+        let request: Request = unsafe { ffi::json_arg(request_json, "request") }?;
+        let response: HttpResponse = unsafe { ffi::json_arg(response_json, "response") }?;
+        let name = unsafe { ffi::optional_arg(name, "name") }?;
+
+        let example = response_to_example(name, &request, &response);
+        attach_example(&mut collection, &item_path, example)?;
+        Ok(collection)
+    })();
+    ffi::respond(result)
+}
@@ -0,0 +1,204 @@
+//! The result envelope shared by every exported function. Success is
+//! `{"ok": <result>}`; failure is
+//! `{"error": {"code": "...", "category": "...", "message": "...", "details": {...}}}`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+
+/// What kind of failure an error is, so the UI can pick how to present it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// A missing or malformed argument, or a request that cannot be built.
+    Argument,
+    /// Input text (JSON, YAML, an import format) could not be parsed.
+    Parse,
+    /// The request's auth cannot be applied: an unsupported type or missing
+    /// credentials such as an OAuth 2.0 access token.
+    Auth,
+    Dns,
+    Connect,
+    Tls,
+    Timeout,
+    Redirect,
+    /// Any other failure while sending a request or reading its response.
+    Network,
+    /// Reading or writing files, or the history database.
+    Io,
+    Unsupported,
+    Internal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfiError {
+    /// Stable, machine-readable identifier such as `dns_resolution_failed`.
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+    /// Extra context, such as the URL and cause chain of a network error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Box<Value>>,
+}
+
+impl FfiError {
+    pub fn new(category: ErrorCategory, code: &str, message: impl Into<String>) -> Self {
+        FfiError {
+            code: code.to_string(),
+            category,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(Box::new(details));
+        self
+    }
+
+    /// Invalid JSON input, or JSON that does not match the expected type.
+    /// `what` names the argument, e.g. "collection".
+    pub fn json(what: &str, e: &serde_json::Error) -> Self {
+        let code = if e.is_data() { "invalid_structure" } else { "invalid_json" };
+        FfiError::new(ErrorCategory::Parse, code, format!("Invalid {}: {}", what, e))
+            .with_details(json!({ "line": e.line(), "column": e.column() }))
+    }
+
+    pub fn parse(what: &str, message: impl fmt::Display) -> Self {
+        FfiError::new(ErrorCategory::Parse, "invalid_input", format!("Invalid {}: {}", what, message))
+    }
+
+    pub fn internal(message: impl fmt::Display) -> Self {
+        FfiError::new(ErrorCategory::Internal, "internal_error", message.to_string())
+    }
+
+    /// Classifies an error from sending a request. `FfiError`s raised while
+    /// building the request pass through unchanged; other errors are sorted
+    /// into DNS, TLS, connection, timeout and redirect failures by reqwest's
+    /// flags and their cause chain.
+    pub fn from_request_error(e: &(dyn Error + 'static), url: Option<&str>) -> Self {
+        if let Some(error) = e.downcast_ref::<FfiError>() {
+            return error.clone();
+        }
+
+        let causes = cause_chain(e);
+        let text = causes.join(": ").to_lowercase();
+        let (category, code) = match e.downcast_ref::<reqwest::Error>() {
+            Some(error) if error.is_timeout() => (ErrorCategory::Timeout, "timeout"),
+            Some(error) if error.is_redirect() => (ErrorCategory::Redirect, "too_many_redirects"),
+            Some(error) if error.is_builder() => (ErrorCategory::Argument, "invalid_request"),
+            _ if is_dns_failure(&text) => (ErrorCategory::Dns, "dns_resolution_failed"),
+            _ if text.contains("certificate") => (ErrorCategory::Tls, "invalid_certificate"),
+            _ if text.contains("tls") || text.contains("handshake") => (ErrorCategory::Tls, "tls_handshake_failed"),
+            _ if text.contains("connection refused") => (ErrorCategory::Connect, "connection_refused"),
+            _ if text.contains("connection reset") => (ErrorCategory::Connect, "connection_reset"),
+            Some(error) if error.is_connect() => (ErrorCategory::Connect, "connection_failed"),
+            Some(error) if error.is_body() || error.is_decode() => (ErrorCategory::Network, "response_read_failed"),
+            Some(_) => (ErrorCategory::Network, "request_failed"),
+            None => (ErrorCategory::Internal, "internal_error"),
+        };
+
+        let mut details = json!({ "causes": causes });
+        if let Some(url) = url {
+            details["url"] = json!(url);
+        }
+        FfiError::new(category, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<Box<dyn Error>> for FfiError {
+    fn from(e: Box<dyn Error>) -> Self {
+        if let Some(error) = e.downcast_ref::<FfiError>() {
+            return error.clone();
+        }
+        if let Some(error) = e.downcast_ref::<serde_json::Error>() {
+            return FfiError::json("input", error);
+        }
+        if let Some(error) = e.downcast_ref::<std::io::Error>() {
+            return FfiError::new(ErrorCategory::Io, "io_error", error.to_string());
+        }
+        if let Some(error) = e.downcast_ref::<rusqlite::Error>() {
+            return FfiError::new(ErrorCategory::Io, "database_error", error.to_string());
+        }
+        FfiError::internal(e)
+    }
+}
+
+impl From<rusqlite::Error> for FfiError {
+    fn from(e: rusqlite::Error) -> Self {
+        FfiError::new(ErrorCategory::Io, "database_error", e.to_string())
+    }
+}
+
+impl fmt::Display for FfiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for FfiError {}
+
+fn cause_chain(e: &(dyn Error + 'static)) -> Vec<String> {
+    let mut causes = vec![e.to_string()];
+    let mut source = e.source();
+    while let Some(cause) = source {
+        causes.push(cause.to_string());
+        source = cause.source();
+    }
+    causes
+}
+
+fn is_dns_failure(text: &str) -> bool {
+    ["dns error", "failed to lookup address", "name or service not known", "no such host", "nodename nor servname"]
+        .iter()
+        .any(|pattern| text.contains(pattern))
+}
+
+/// Reads a required string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that stays valid
+/// and unchanged for `'a`, as a C caller's argument does for the call.
+pub(crate) unsafe fn arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    optional_arg(ptr, name)?.ok_or_else(|| {
+        FfiError::new(ErrorCategory::Argument, "null_argument", format!("'{}' must not be null", name))
+    })
+}
+
+/// Reads a string argument that may be null.
+///
+/// # Safety
+///
+/// As for [`arg`].
+pub(crate) unsafe fn optional_arg<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>, FfiError> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr).to_str().map(Some).map_err(|_| {
+        FfiError::new(ErrorCategory::Argument, "invalid_utf8", format!("'{}' is not valid UTF-8", name))
+    })
+}
+
+/// Parses a JSON argument into `T`.
+///
+/// # Safety
+///
+/// As for [`arg`].
+pub(crate) unsafe fn json_arg<T: serde::de::DeserializeOwned>(ptr: *const c_char, name: &str) -> Result<T, FfiError> {
+    serde_json::from_str(arg(ptr, name)?).map_err(|e| FfiError::json(name, &e))
+}
+
+/// Wraps a result in the envelope and hands it to the caller, who must
+/// release it with `free_string`. Never returns null.
+pub fn respond<T: Serialize>(result: Result<T, FfiError>) -> *mut c_char {
+    let envelope = match result.and_then(|value| serde_json::to_value(value).map_err(FfiError::internal)) {
+        Ok(value) => json!({ "ok": value }),
+        Err(error) => json!({ "error": error }),
+    };
+    // serde_json escapes NUL, so the text never contains an interior 0 byte.
+    CString::new(envelope.to_string()).unwrap().into_raw()
+}
@@ -0,0 +1,1080 @@
+//! GraphQL schemas, fetched by introspection and cached per endpoint URL, and
+//! validation of queries and their variables against them.
+
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::request::Request;
+use crate::PreparedRequest;
+use graphql_parser::query as ast;
+use graphql_parser::Pos;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::os::raw::c_char;
+use std::sync::{Arc, Mutex};
+
+static SCHEMAS: Mutex<Option<HashMap<String, Arc<Schema>>>> = Mutex::new(None);
+
+/// The standard introspection query, as sent by GraphiQL.
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType { kind name }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+/// `__schema` from an introspection result. Serializes back to the same
+/// shape, so editors can build a client schema from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub query_type: Option<NamedType>,
+    #[serde(default)]
+    pub mutation_type: Option<NamedType>,
+    #[serde(default)]
+    pub subscription_type: Option<NamedType>,
+    pub types: Vec<Type>,
+    #[serde(default)]
+    pub directives: Vec<Directive>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedType {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TypeKind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+    List,
+    NonNull,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Type {
+    pub kind: TypeKind,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub fields: Option<Vec<Field>>,
+    #[serde(default)]
+    pub input_fields: Option<Vec<InputValue>>,
+    #[serde(default)]
+    pub interfaces: Option<Vec<TypeRef>>,
+    #[serde(default)]
+    pub enum_values: Option<Vec<EnumValue>>,
+    #[serde(default)]
+    pub possible_types: Option<Vec<TypeRef>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Field {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub args: Vec<InputValue>,
+    #[serde(rename = "type")]
+    pub field_type: TypeRef,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    #[serde(default)]
+    pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputValue {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub value_type: TypeRef,
+    /// A GraphQL literal, e.g. `"10"` or `"ASC"`.
+    #[serde(default)]
+    pub default_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumValue {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    #[serde(default)]
+    pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Directive {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub locations: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<InputValue>,
+}
+
+/// A reference to a type, wrapped in any number of `LIST` and `NON_NULL`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeRef {
+    pub kind: TypeKind,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub of_type: Option<Box<TypeRef>>,
+}
+
+impl TypeRef {
+    /// The named type inside any wrappers.
+    pub fn named(&self) -> &str {
+        match (&self.name, &self.of_type) {
+            (Some(name), _) => name,
+            (None, Some(inner)) => inner.named(),
+            (None, None) => "",
+        }
+    }
+
+    fn inner(&self) -> &TypeRef {
+        self.of_type.as_deref().unwrap_or(self)
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            TypeKind::NonNull => write!(f, "{}!", self.inner()),
+            TypeKind::List => write!(f, "[{}]", self.inner()),
+            _ => f.write_str(self.named()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A validation error, in the format of GraphQL response errors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryError {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryReport {
+    pub valid: bool,
+    pub errors: Vec<QueryError>,
+}
+
+/// Sends the introspection query to the request's URL, with its headers and
+/// auth, and caches the schema for that URL.
+pub fn introspect(request: &Request) -> Result<Arc<Schema>, FfiError> {
+    let mut prepared = PreparedRequest::new(request).map_err(FfiError::from)?;
+    prepared.method = "POST".to_string();
+    prepared.body = Some(json!({ "query": INTROSPECTION_QUERY }).to_string());
+    prepared
+        .headers
+        .retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+    prepared
+        .headers
+        .push(("Content-Type".to_string(), "application/json".to_string()));
+    let url = prepared.url.clone();
+
+    let runtime = tokio::runtime::Runtime::new().map_err(FfiError::internal)?;
+    let (status, text) = runtime.block_on(async {
+        let client = reqwest::Client::new();
+        let send = async {
+            let response = prepared.into_builder(&client)?.send().await?;
+            let status = response.status().as_u16();
+            Ok::<_, Box<dyn std::error::Error>>((status, response.text().await?))
+        };
+        send.await
+            .map_err(|e| FfiError::from_request_error(e.as_ref(), Some(&url)))
+    })?;
+
+    let failed = |message: String| {
+        FfiError::new(ErrorCategory::Network, "introspection_failed", message)
+            .with_details(json!({ "url": url, "status_code": status }))
+    };
+    let mut result: Value = serde_json::from_str(&text).map_err(|_| {
+        failed(format!(
+            "Introspection returned a non-JSON response (status {})",
+            status
+        ))
+    })?;
+    let schema = match result.pointer_mut("/data/__schema").map(Value::take) {
+        Some(schema) if !schema.is_null() => schema,
+        _ => {
+            let message = result
+                .pointer("/errors/0/message")
+                .and_then(Value::as_str)
+                .map(|message| format!("Introspection failed: {}", message))
+                .unwrap_or_else(|| format!("Introspection returned no schema (status {})", status));
+            return Err(failed(message));
+        }
+    };
+    let schema: Arc<Schema> = Arc::new(serde_json::from_value(schema).map_err(|e| FfiError::json("schema", &e))?);
+
+    let mut schemas = SCHEMAS
+        .lock()
+        .map_err(|_| FfiError::internal("Schema cache is poisoned"))?;
+    schemas
+        .get_or_insert_with(HashMap::new)
+        .insert(url, Arc::clone(&schema));
+    Ok(schema)
+}
+
+/// The schema cached for an endpoint URL by `introspect`.
+pub fn cached_schema(url: &str) -> Option<Arc<Schema>> {
+    SCHEMAS.lock().ok()?.as_ref()?.get(url).cloned()
+}
+
+/// Rejects a GraphQL request whose query or variables do not match the
+/// schema cached for its URL. Requests to URLs without a cached schema, and
+/// requests with `validateGraphql: false` in their options, are not checked.
+pub(crate) fn check_before_sending(request: &Request, url: &str) -> Result<(), FfiError> {
+    if request.options.as_ref().and_then(|options| options.validate_graphql) == Some(false) {
+        return Ok(());
+    }
+    let graphql = match request.body.as_ref() {
+        Some(body) if body.mode.as_deref() == Some("graphql") => body.graphql.as_ref(),
+        _ => None,
+    };
+    let (graphql, schema) = match (graphql, cached_schema(url)) {
+        (Some(graphql), Some(schema)) => (graphql, schema),
+        _ => return Ok(()),
+    };
+
+    let operation_name = graphql.extra.get("operationName").and_then(Value::as_str);
+    let report = validate(
+        &schema,
+        graphql.query.as_deref().unwrap_or(""),
+        graphql.variables.as_deref(),
+        operation_name,
+    );
+    match report.errors.first() {
+        None => Ok(()),
+        Some(first) => {
+            let message = format!("GraphQL request does not match the schema: {}", first.message);
+            Err(FfiError::new(ErrorCategory::Argument, "invalid_graphql", message)
+                .with_details(json!({ "errors": report.errors })))
+        }
+    }
+}
+
+/// Validates a query document, and `variables` (JSON text) against the
+/// variables of the operation that will run: `operation_name`, or the only
+/// operation.
+pub fn validate(schema: &Schema, query: &str, variables: Option<&str>, operation_name: Option<&str>) -> QueryReport {
+    let errors = match ast::parse_query::<String>(query) {
+        Ok(document) => {
+            let mut validator = Validator::new(schema);
+            validator.document(&document);
+            validator.variable_values(&document, variables, operation_name);
+            validator.errors
+        }
+        Err(e) => vec![parse_error(&e.to_string())],
+    };
+    QueryReport {
+        valid: errors.is_empty(),
+        errors,
+    }
+}
+
+/// Turns graphql-parser's "query parse error: Parse error at 2:3\n..." into
+/// a message and a location.
+fn parse_error(text: &str) -> QueryError {
+    let text = text.trim_start_matches("query parse error: ");
+    let (head, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let location = head.rsplit_once(" at ").and_then(|(_, position)| {
+        let (line, column) = position.split_once(':')?;
+        Some(Location {
+            line: line.trim().parse().ok()?,
+            column: column.trim().parse().ok()?,
+        })
+    });
+    let detail = rest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    QueryError {
+        message: match detail.is_empty() {
+            true => format!("Syntax Error: {}", head),
+            false => format!("Syntax Error: {}", detail),
+        },
+        locations: location.into_iter().collect(),
+    }
+}
+
+/// An operation's parts, whatever its kind.
+struct Operation<'d> {
+    kind: &'static str,
+    name: Option<&'d str>,
+    position: Pos,
+    variables: &'d [ast::VariableDefinition<'d, String>],
+    directives: &'d [ast::Directive<'d, String>],
+    selections: &'d ast::SelectionSet<'d, String>,
+}
+
+fn operations<'d>(document: &'d ast::Document<'d, String>) -> Vec<Operation<'d>> {
+    use ast::OperationDefinition as Op;
+    document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            ast::Definition::Operation(operation) => Some(operation),
+            ast::Definition::Fragment(_) => None,
+        })
+        .map(|operation| match operation {
+            Op::SelectionSet(selections) => Operation {
+                kind: "query",
+                name: None,
+                position: selections.span.0,
+                variables: &[],
+                directives: &[],
+                selections,
+            },
+            Op::Query(q) => Operation {
+                kind: "query",
+                name: q.name.as_deref(),
+                position: q.position,
+                variables: &q.variable_definitions,
+                directives: &q.directives,
+                selections: &q.selection_set,
+            },
+            Op::Mutation(m) => Operation {
+                kind: "mutation",
+                name: m.name.as_deref(),
+                position: m.position,
+                variables: &m.variable_definitions,
+                directives: &m.directives,
+                selections: &m.selection_set,
+            },
+            Op::Subscription(s) => Operation {
+                kind: "subscription",
+                name: s.name.as_deref(),
+                position: s.position,
+                variables: &s.variable_definitions,
+                directives: &s.directives,
+                selections: &s.selection_set,
+            },
+        })
+        .collect()
+}
+
+struct Validator<'s, 'd> {
+    schema: &'s Schema,
+    types: HashMap<&'s str, &'s Type>,
+    fragments: HashMap<&'d str, &'d ast::FragmentDefinition<'d, String>>,
+    /// Variables of the operation being checked.
+    variables: HashMap<&'d str, &'d ast::VariableDefinition<'d, String>>,
+    /// Fragments already checked for the current operation.
+    visited: HashSet<&'d str>,
+    used_fragments: HashSet<&'d str>,
+    errors: Vec<QueryError>,
+}
+
+impl<'s, 'd> Validator<'s, 'd> {
+    fn new(schema: &'s Schema) -> Self {
+        Validator {
+            schema,
+            types: schema
+                .types
+                .iter()
+                .filter_map(|t| Some((t.name.as_deref()?, t)))
+                .collect(),
+            fragments: HashMap::new(),
+            variables: HashMap::new(),
+            visited: HashSet::new(),
+            used_fragments: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, message: String, position: Option<Pos>) {
+        let error = QueryError {
+            message,
+            locations: position
+                .map(|p| Location {
+                    line: p.line,
+                    column: p.column,
+                })
+                .into_iter()
+                .collect(),
+        };
+        // Fragments are checked wherever they are spread; report once.
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    fn document(&mut self, document: &'d ast::Document<'d, String>) {
+        for definition in &document.definitions {
+            if let ast::Definition::Fragment(fragment) = definition {
+                if self.fragments.insert(&fragment.name, fragment).is_some() {
+                    self.error(
+                        format!("There can be only one fragment named \"{}\".", fragment.name),
+                        Some(fragment.position),
+                    );
+                }
+            }
+        }
+
+        let operations = operations(document);
+        let mut names = HashSet::new();
+        for operation in &operations {
+            match operation.name {
+                None if operations.len() > 1 => self.error(
+                    "This anonymous operation must be the only defined operation.".to_string(),
+                    Some(operation.position),
+                ),
+                Some(name) if !names.insert(name) => self.error(
+                    format!("There can be only one operation named \"{}\".", name),
+                    Some(operation.position),
+                ),
+                _ => {}
+            }
+            self.operation(operation);
+        }
+
+        let mut unused: Vec<(&str, Pos)> = self
+            .fragments
+            .values()
+            .filter(|f| !self.used_fragments.contains(f.name.as_str()))
+            .map(|f| (f.name.as_str(), f.position))
+            .collect();
+        unused.sort_by_key(|(_, position)| (position.line, position.column));
+        for (name, position) in unused {
+            self.error(format!("Fragment \"{}\" is never used.", name), Some(position));
+        }
+    }
+
+    fn operation(&mut self, operation: &Operation<'d>) {
+        self.variables.clear();
+        self.visited.clear();
+        for variable in operation.variables {
+            if self.variables.insert(&variable.name, variable).is_some() {
+                self.error(
+                    format!("There can be only one variable named \"${}\".", variable.name),
+                    Some(variable.position),
+                );
+            }
+            let named = named_type(&variable.var_type);
+            match self.types.get(named).map(|t| t.kind) {
+                None => self.error(format!("Unknown type \"{}\".", named), Some(variable.position)),
+                Some(TypeKind::Scalar | TypeKind::Enum | TypeKind::InputObject) => {}
+                Some(_) => self.error(
+                    format!(
+                        "Variable \"${}\" cannot be non-input type \"{}\".",
+                        variable.name, variable.var_type
+                    ),
+                    Some(variable.position),
+                ),
+            }
+        }
+
+        let root = match operation.kind {
+            "mutation" => self.schema.mutation_type.as_ref(),
+            "subscription" => self.schema.subscription_type.as_ref(),
+            _ => self.schema.query_type.as_ref(),
+        };
+        let root = match root.and_then(|root| self.types.get(root.name.as_str())) {
+            Some(root) => *root,
+            None => {
+                let message = format!("Schema is not configured to execute {} operation.", operation.kind);
+                return self.error(message, Some(operation.position));
+            }
+        };
+        self.directives(operation.directives);
+        self.selections(root, operation.selections);
+    }
+
+    fn selections(&mut self, parent: &'s Type, selections: &'d ast::SelectionSet<'d, String>) {
+        for selection in &selections.items {
+            match selection {
+                ast::Selection::Field(field) => self.field(parent, field),
+                ast::Selection::FragmentSpread(spread) => {
+                    self.directives(&spread.directives);
+                    let name = spread.fragment_name.as_str();
+                    self.used_fragments.insert(name);
+                    let fragment = match self.fragments.get(name) {
+                        Some(fragment) => *fragment,
+                        None => {
+                            self.error(format!("Unknown fragment \"{}\".", name), Some(spread.position));
+                            continue;
+                        }
+                    };
+                    if self.visited.insert(name) {
+                        self.directives(&fragment.directives);
+                        let ast::TypeCondition::On(condition) = &fragment.type_condition;
+                        if let Some(condition) = self.condition(condition, fragment.position) {
+                            self.selections(condition, &fragment.selection_set);
+                        }
+                    }
+                }
+                ast::Selection::InlineFragment(inline) => {
+                    self.directives(&inline.directives);
+                    let condition = match &inline.type_condition {
+                        Some(ast::TypeCondition::On(condition)) => self.condition(condition, inline.position),
+                        None => Some(parent),
+                    };
+                    if let Some(condition) = condition {
+                        self.selections(condition, &inline.selection_set);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The type a fragment applies to, which must be an object, interface or union.
+    fn condition(&mut self, name: &str, position: Pos) -> Option<&'s Type> {
+        match self.types.get(name) {
+            Some(t) if matches!(t.kind, TypeKind::Object | TypeKind::Interface | TypeKind::Union) => Some(*t),
+            Some(_) => {
+                self.error(
+                    format!("Fragment cannot condition on non composite type \"{}\".", name),
+                    Some(position),
+                );
+                None
+            }
+            None => {
+                self.error(format!("Unknown type \"{}\".", name), Some(position));
+                None
+            }
+        }
+    }
+
+    fn field(&mut self, parent: &'s Type, field: &'d ast::Field<'d, String>) {
+        self.directives(&field.directives);
+        let parent_name = parent.name.as_deref().unwrap_or("");
+        let is_query_root = self
+            .schema
+            .query_type
+            .as_ref()
+            .is_some_and(|root| root.name == parent_name);
+        // Meta-fields; the introspection types are not checked.
+        if field.name == "__typename" || (is_query_root && (field.name == "__schema" || field.name == "__type")) {
+            return;
+        }
+
+        let definition = match parent.fields.iter().flatten().find(|f| f.name == field.name) {
+            Some(definition) => definition,
+            None => {
+                let message = format!("Cannot query field \"{}\" on type \"{}\".", field.name, parent_name);
+                return self.error(message, Some(field.position));
+            }
+        };
+        let owner = format!("field \"{}.{}\"", parent_name, field.name);
+        self.arguments(&definition.args, &field.arguments, &owner, field.position);
+
+        let named = definition.field_type.named();
+        let field_type = match self.types.get(named) {
+            Some(field_type) => *field_type,
+            None => return,
+        };
+        let has_selections = !field.selection_set.items.is_empty();
+        match field_type.kind {
+            TypeKind::Object | TypeKind::Interface | TypeKind::Union if has_selections => {
+                self.selections(field_type, &field.selection_set)
+            }
+            TypeKind::Object | TypeKind::Interface | TypeKind::Union => self.error(
+                format!(
+                    "Field \"{}\" of type \"{}\" must have a selection of subfields. Did you mean \"{} {{ ... }}\"?",
+                    field.name, definition.field_type, field.name
+                ),
+                Some(field.position),
+            ),
+            _ if has_selections => self.error(
+                format!(
+                    "Field \"{}\" must not have a selection since type \"{}\" has no subfields.",
+                    field.name, definition.field_type
+                ),
+                Some(field.position),
+            ),
+            _ => {}
+        }
+    }
+
+    fn directives(&mut self, directives: &'d [ast::Directive<'d, String>]) {
+        // Introspection results without directives cannot be checked.
+        if self.schema.directives.is_empty() {
+            return;
+        }
+        for directive in directives {
+            match self.schema.directives.iter().find(|d| d.name == directive.name) {
+                Some(definition) => {
+                    let owner = format!("directive \"@{}\"", directive.name);
+                    self.arguments(&definition.args, &directive.arguments, &owner, directive.position);
+                }
+                None => self.error(
+                    format!("Unknown directive \"@{}\".", directive.name),
+                    Some(directive.position),
+                ),
+            }
+        }
+    }
+
+    fn arguments(
+        &mut self,
+        definitions: &'s [InputValue],
+        given: &'d [(String, ast::Value<'d, String>)],
+        owner: &str,
+        position: Pos,
+    ) {
+        for (name, value) in given {
+            match definitions.iter().find(|d| &d.name == name) {
+                Some(definition) => self.literal(
+                    value,
+                    &definition.value_type,
+                    definition.default_value.is_some(),
+                    position,
+                ),
+                None => self.error(format!("Unknown argument \"{}\" on {}.", name, owner), Some(position)),
+            }
+        }
+        for definition in definitions {
+            let required = definition.value_type.kind == TypeKind::NonNull && definition.default_value.is_none();
+            if required && !given.iter().any(|(name, _)| name == &definition.name) {
+                let mut owner = owner.to_string();
+                if let Some(first) = owner.get_mut(..1) {
+                    first.make_ascii_uppercase();
+                }
+                let message = format!(
+                    "{} argument \"{}\" of type \"{}\" is required, but it was not provided.",
+                    owner, definition.name, definition.value_type
+                );
+                self.error(message, Some(position));
+            }
+        }
+    }
+
+    /// Checks a literal in the query against the type expected where it is
+    /// used. `has_default` is whether that location has a default value.
+    fn literal(&mut self, value: &'d ast::Value<'d, String>, expected: &'s TypeRef, has_default: bool, position: Pos) {
+        if let ast::Value::Variable(name) = value {
+            let variable = match self.variables.get(name.as_str()) {
+                Some(variable) => *variable,
+                None => return self.error(format!("Variable \"${}\" is not defined.", name), Some(position)),
+            };
+            let nullable_with_default =
+                has_default || variable.default_value.as_ref().is_some_and(|v| v != &ast::Value::Null);
+            let fits = match (expected.kind, &variable.var_type) {
+                (TypeKind::NonNull, var_type)
+                    if !matches!(var_type, ast::Type::NonNullType(_)) && nullable_with_default =>
+                {
+                    fits(var_type, expected.inner())
+                }
+                (_, var_type) => fits(var_type, expected),
+            };
+            if !fits {
+                let message = format!(
+                    "Variable \"${}\" of type \"{}\" used in position expecting type \"{}\".",
+                    name, variable.var_type, expected
+                );
+                self.error(message, Some(position));
+            }
+            return;
+        }
+
+        let mismatch =
+            |found: &ast::Value<String>| format!("Expected value of type \"{}\", found {}.", expected, found);
+        match (expected.kind, value) {
+            (TypeKind::NonNull, ast::Value::Null) => self.error(mismatch(value), Some(position)),
+            (TypeKind::NonNull, _) => self.literal(value, expected.inner(), false, position),
+            (_, ast::Value::Null) => {}
+            (TypeKind::List, ast::Value::List(items)) => {
+                for item in items {
+                    self.literal(item, expected.inner(), false, position);
+                }
+            }
+            // A single value is accepted where a list is expected.
+            (TypeKind::List, _) => self.literal(value, expected.inner(), false, position),
+            _ => {
+                let named = match self.types.get(expected.named()) {
+                    Some(named) => *named,
+                    None => return,
+                };
+                match (named.kind, value) {
+                    (TypeKind::Scalar, _) => {
+                        if !scalar_literal_fits(expected.named(), value) {
+                            self.error(mismatch(value), Some(position));
+                        }
+                    }
+                    (TypeKind::Enum, ast::Value::Enum(name)) => {
+                        if !named.enum_values.iter().flatten().any(|v| &v.name == name) {
+                            let message =
+                                format!("Value \"{}\" does not exist in \"{}\" enum.", name, expected.named());
+                            self.error(message, Some(position));
+                        }
+                    }
+                    (TypeKind::InputObject, ast::Value::Object(fields)) => {
+                        let definitions = named.input_fields.as_deref().unwrap_or(&[]);
+                        for (key, field_value) in fields {
+                            match definitions.iter().find(|d| &d.name == key) {
+                                Some(definition) => self.literal(
+                                    field_value,
+                                    &definition.value_type,
+                                    definition.default_value.is_some(),
+                                    position,
+                                ),
+                                None => {
+                                    let message =
+                                        format!("Field \"{}\" is not defined by type \"{}\".", key, expected.named());
+                                    self.error(message, Some(position));
+                                }
+                            }
+                        }
+                        for definition in definitions {
+                            let required =
+                                definition.value_type.kind == TypeKind::NonNull && definition.default_value.is_none();
+                            if required && !fields.contains_key(&definition.name) {
+                                let message = format!(
+                                    "Field \"{}.{}\" of required type \"{}\" was not provided.",
+                                    expected.named(),
+                                    definition.name,
+                                    definition.value_type
+                                );
+                                self.error(message, Some(position));
+                            }
+                        }
+                    }
+                    _ => self.error(mismatch(value), Some(position)),
+                }
+            }
+        }
+    }
+
+    /// Checks the request's JSON variables against the variables the
+    /// operation declares.
+    fn variable_values(
+        &mut self,
+        document: &'d ast::Document<'d, String>,
+        variables: Option<&str>,
+        operation_name: Option<&str>,
+    ) {
+        let operations = operations(document);
+        let operation = match operation_name {
+            Some(name) => match operations.iter().find(|o| o.name == Some(name)) {
+                Some(operation) => operation,
+                None => return self.error(format!("Unknown operation named \"{}\".", name), None),
+            },
+            None if operations.len() == 1 => &operations[0],
+            None => return,
+        };
+
+        let values = match variables.map(str::trim).filter(|text| !text.is_empty()) {
+            None => Map::new(),
+            Some(text) => match serde_json::from_str::<Value>(text) {
+                Ok(Value::Object(values)) => values,
+                Ok(Value::Null) => Map::new(),
+                Ok(_) => return self.error("Variables must be a JSON object.".to_string(), None),
+                Err(e) => return self.error(format!("Variables are not valid JSON: {}", e), None),
+            },
+        };
+
+        for variable in operation.variables {
+            let name = variable.name.as_str();
+            match values.get(name) {
+                None if variable.default_value.is_none() && matches!(variable.var_type, ast::Type::NonNullType(_)) => {
+                    let message = format!(
+                        "Variable \"${}\" of required type \"{}\" was not provided.",
+                        name, variable.var_type
+                    );
+                    self.error(message, Some(variable.position));
+                }
+                None => {}
+                Some(value) => {
+                    let mut problems = Vec::new();
+                    self.json_value(value, &variable.var_type, name, &mut problems);
+                    for (path, problem) in problems {
+                        let at = match path == name {
+                            true => String::new(),
+                            false => format!(" at \"{}\"", path),
+                        };
+                        let message = format!("Variable \"${}\" got invalid value {}{}; {}", name, value, at, problem);
+                        self.error(message, Some(variable.position));
+                    }
+                }
+            }
+        }
+    }
+
+    fn json_value<'t>(
+        &self,
+        value: &Value,
+        expected: &ast::Type<'t, String>,
+        path: &str,
+        problems: &mut Vec<(String, String)>,
+    ) {
+        match (expected, value) {
+            (ast::Type::NonNullType(_), Value::Null) => problems.push((
+                path.to_string(),
+                format!("Expected non-nullable type \"{}\" not to be null.", expected),
+            )),
+            (ast::Type::NonNullType(inner), _) => self.json_value(value, inner, path, problems),
+            (_, Value::Null) => {}
+            (ast::Type::ListType(inner), Value::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.json_value(item, inner, &format!("{}[{}]", path, index), problems);
+                }
+            }
+            (ast::Type::ListType(inner), _) => self.json_value(value, inner, path, problems),
+            (ast::Type::NamedType(name), _) => {
+                let named = match self.types.get(name.as_str()) {
+                    Some(named) => *named,
+                    None => return,
+                };
+                match named.kind {
+                    TypeKind::Scalar if !scalar_json_fits(name, value) => {
+                        problems.push((path.to_string(), format!("Expected type \"{}\".", name)))
+                    }
+                    TypeKind::Enum => {
+                        let known = value
+                            .as_str()
+                            .is_some_and(|v| named.enum_values.iter().flatten().any(|e| e.name == v));
+                        if !known {
+                            problems.push((
+                                path.to_string(),
+                                format!("Value {} does not exist in \"{}\" enum.", value, name),
+                            ));
+                        }
+                    }
+                    TypeKind::InputObject => {
+                        let fields = match value.as_object() {
+                            Some(fields) => fields,
+                            None => {
+                                return problems
+                                    .push((path.to_string(), format!("Expected type \"{}\" to be an object.", name)))
+                            }
+                        };
+                        let definitions = named.input_fields.as_deref().unwrap_or(&[]);
+                        for key in fields.keys() {
+                            if !definitions.iter().any(|d| &d.name == key) {
+                                problems.push((
+                                    path.to_string(),
+                                    format!("Field \"{}\" is not defined by type \"{}\".", key, name),
+                                ));
+                            }
+                        }
+                        for definition in definitions {
+                            let field_path = format!("{}.{}", path, definition.name);
+                            match fields.get(&definition.name) {
+                                Some(field) => {
+                                    if let Some(field_type) = parse_type(&definition.value_type) {
+                                        self.json_value(field, &field_type, &field_path, problems);
+                                    }
+                                }
+                                None if definition.value_type.kind == TypeKind::NonNull
+                                    && definition.default_value.is_none() =>
+                                {
+                                    problems.push((
+                                        path.to_string(),
+                                        format!(
+                                            "Field \"{}\" of required type \"{}\" was not provided.",
+                                            definition.name, definition.value_type
+                                        ),
+                                    ));
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn named_type<'a>(t: &'a ast::Type<String>) -> &'a str {
+    match t {
+        ast::Type::NamedType(name) => name,
+        ast::Type::ListType(inner) | ast::Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+/// An introspected type reference as a query AST type.
+fn parse_type(t: &TypeRef) -> Option<ast::Type<'static, String>> {
+    Some(match t.kind {
+        TypeKind::NonNull => ast::Type::NonNullType(Box::new(parse_type(t.of_type.as_deref()?)?)),
+        TypeKind::List => ast::Type::ListType(Box::new(parse_type(t.of_type.as_deref()?)?)),
+        _ => ast::Type::NamedType(t.name.clone()?),
+    })
+}
+
+/// Whether a variable of type `variable` can be used where `expected` is.
+fn fits(variable: &ast::Type<String>, expected: &TypeRef) -> bool {
+    match (variable, expected.kind) {
+        (ast::Type::NonNullType(inner), TypeKind::NonNull) => fits(inner, expected.inner()),
+        (_, TypeKind::NonNull) => false,
+        (ast::Type::NonNullType(inner), _) => fits(inner, expected),
+        (ast::Type::ListType(inner), TypeKind::List) => fits(inner, expected.inner()),
+        (_, TypeKind::List) | (ast::Type::ListType(_), _) => false,
+        (ast::Type::NamedType(name), _) => name == expected.named(),
+    }
+}
+
+fn scalar_literal_fits(scalar: &str, value: &ast::Value<String>) -> bool {
+    match (scalar, value) {
+        ("Int", ast::Value::Int(n)) => n.as_i64().is_some_and(|n| i32::try_from(n).is_ok()),
+        ("Float", ast::Value::Int(_) | ast::Value::Float(_)) => true,
+        ("String", ast::Value::String(_)) => true,
+        ("Boolean", ast::Value::Boolean(_)) => true,
+        ("ID", ast::Value::String(_) | ast::Value::Int(_)) => true,
+        ("Int" | "Float" | "String" | "Boolean" | "ID", _) => false,
+        // Custom scalars define their own literals.
+        _ => true,
+    }
+}
+
+fn scalar_json_fits(scalar: &str, value: &Value) -> bool {
+    let integer = || match value {
+        Value::Number(n) => n.as_i64().map(|n| i32::try_from(n).is_ok()).unwrap_or_else(|| {
+            n.as_f64()
+                .is_some_and(|f| f.fract() == 0.0 && f.abs() <= i32::MAX as f64)
+        }),
+        _ => false,
+    };
+    match scalar {
+        "Int" => integer(),
+        "Float" => value.is_number(),
+        "String" => value.is_string(),
+        "Boolean" => value.is_boolean(),
+        "ID" => value.is_string() || integer(),
+        _ => true,
+    }
+}
+
+fn schema_json(schema: &Schema) -> Value {
+    json!({ "__schema": schema })
+}
+
+/// Run the introspection query against the request's URL, with its headers
+/// and auth, and cache the schema for that URL. Returns `{"__schema": ...}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn graphql_introspect(request_json: *const c_char) -> *mut c_char {
+    let result = unsafe { ffi::json_arg::<Request>(request_json, "request") }.and_then(|request| introspect(&request));
+    ffi::respond(result.map(|schema| schema_json(&schema)))
+}
+
+/// The schema cached for an endpoint URL, as `{"__schema": ...}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn graphql_schema(url: *const c_char) -> *mut c_char {
+    let result = unsafe { ffi::arg(url, "url") }.and_then(|url| {
+        cached_schema(url).ok_or_else(|| {
+            let message = format!("No schema is cached for '{}'; call graphql_introspect first", url);
+            FfiError::new(ErrorCategory::Argument, "schema_not_cached", message)
+        })
+    });
+    ffi::respond(result.map(|schema| schema_json(&schema)))
+}
+
+/// Validate a request's GraphQL query and variables against the schema for
+/// its URL, introspecting first if none is cached. Returns a `QueryReport`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn graphql_validate(request_json: *const c_char) -> *mut c_char {
+    let result = unsafe { ffi::json_arg::<Request>(request_json, "request") }.and_then(|request| {
+        let url = PreparedRequest::new(&request).map_err(FfiError::from)?.url;
+        let schema = match cached_schema(&url) {
+            Some(schema) => schema,
+            None => introspect(&request)?,
+        };
+        let graphql = request.body.as_ref().and_then(|body| body.graphql.as_ref());
+        let query = graphql.and_then(|g| g.query.as_deref()).unwrap_or("");
+        let variables = graphql.and_then(|g| g.variables.as_deref());
+        let operation_name = graphql
+            .and_then(|g| g.extra.get("operationName"))
+            .and_then(Value::as_str);
+        Ok(validate(&schema, query, variables, operation_name))
+    });
+    ffi::respond(result)
+}
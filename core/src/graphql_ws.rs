@@ -0,0 +1,564 @@
+//! GraphQL subscriptions over WebSocket, with the `graphql-transport-ws`
+//! protocol or the legacy `subscriptions-transport-ws` one (subprotocol
+//! `graphql-ws`). Each subscription has its own connection and thread; its
+//! results are delivered to a callback or queued for polling.
+
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::request::Request;
+use crate::websocket;
+use crate::PreparedRequest;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+static SUBSCRIPTIONS: Mutex<Option<HashMap<u32, Subscription>>> = Mutex::new(None);
+
+/// The operation ID used on the wire; each connection carries one operation.
+const OPERATION_ID: &str = "1";
+
+/// How long to wait for the server to answer our close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Receives each message of subscription `id` as JSON. The string is only
+/// valid for the duration of the call. Called from the subscription's own
+/// thread.
+pub type SubscriptionCallback = extern "C" fn(id: u32, message_json: *const c_char);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SubscriptionProtocol {
+    /// The `graphql-ws` library's protocol.
+    #[serde(rename = "graphql-transport-ws")]
+    GraphqlTransportWs,
+    /// Apollo's legacy `subscriptions-transport-ws` protocol.
+    #[serde(rename = "graphql-ws")]
+    GraphqlWs,
+}
+
+impl SubscriptionProtocol {
+    fn name(self) -> &'static str {
+        match self {
+            SubscriptionProtocol::GraphqlTransportWs => "graphql-transport-ws",
+            SubscriptionProtocol::GraphqlWs => "graphql-ws",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "graphql-transport-ws" => Some(SubscriptionProtocol::GraphqlTransportWs),
+            "graphql-ws" => Some(SubscriptionProtocol::GraphqlWs),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionOptions {
+    /// Offer only this protocol. By default both are offered, with
+    /// `graphql-transport-ws` first, and the server's choice is used.
+    pub protocol: Option<SubscriptionProtocol>,
+    /// The `connection_init` payload, which servers commonly use for auth,
+    /// e.g. `{"Authorization": "Bearer ..."}`.
+    pub connection_params: Option<Value>,
+    /// Give up if the server has not sent `connection_ack` by then.
+    pub ack_timeout_ms: u64,
+}
+
+impl Default for SubscriptionOptions {
+    fn default() -> Self {
+        SubscriptionOptions {
+            protocol: None,
+            connection_params: None,
+            ack_timeout_ms: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubscriptionMessage {
+    /// The server accepted the connection; the subscription has been sent.
+    Ack {
+        protocol: SubscriptionProtocol,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+    /// One execution result, with `data` and/or `errors`.
+    Next {
+        payload: Value,
+    },
+    Error {
+        error: FfiError,
+    },
+    /// The server ended the subscription.
+    Complete,
+    /// Always the last message, with the close frame that ended the
+    /// connection, sent by either side.
+    Closed {
+        code: Option<u16>,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionState {
+    Connecting,
+    Active,
+    Closing,
+    Closed,
+}
+
+struct Shared {
+    state: SubscriptionState,
+    queue: VecDeque<SubscriptionMessage>,
+    callback: Option<SubscriptionCallback>,
+}
+
+struct Connection {
+    id: u32,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Connection {
+    fn set_state(&self, state: SubscriptionState) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.state = state;
+        }
+    }
+
+    fn deliver(&self, message: SubscriptionMessage) {
+        let callback = match self.shared.lock() {
+            Ok(mut shared) => match shared.callback {
+                Some(callback) => callback,
+                None => {
+                    shared.queue.push_back(message);
+                    return;
+                }
+            },
+            Err(_) => return,
+        };
+        if let Ok(text) = serde_json::to_string(&message) {
+            if let Ok(text) = CString::new(text) {
+                callback(self.id, text.as_ptr());
+            }
+        }
+    }
+
+    fn error(&self, error: FfiError) {
+        self.deliver(SubscriptionMessage::Error { error });
+    }
+}
+
+pub struct Subscription {
+    shared: Arc<Mutex<Shared>>,
+    stop: watch::Sender<bool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Subscription {
+    /// Checks the request's GraphQL body, then connects and subscribes on a
+    /// new thread.
+    pub fn start(
+        id: u32,
+        request: &Request,
+        options: SubscriptionOptions,
+        callback: Option<SubscriptionCallback>,
+    ) -> Result<Self, FfiError> {
+        let (handshake, operation) = prepare(request, &options)?;
+        let mut subscription = Subscription::new(callback);
+        subscription.spawn(id, handshake, options, operation);
+        Ok(subscription)
+    }
+
+    /// A subscription that has not connected yet; `spawn` starts it.
+    fn new(callback: Option<SubscriptionCallback>) -> Self {
+        let shared = Arc::new(Mutex::new(Shared {
+            state: SubscriptionState::Connecting,
+            queue: VecDeque::new(),
+            callback,
+        }));
+        let (stop, _) = watch::channel(false);
+        Subscription {
+            shared,
+            stop,
+            thread: None,
+        }
+    }
+
+    /// Spawns the thread that runs the subscription. Kept apart from `new` so
+    /// the registry holds the subscription before its first message is
+    /// delivered.
+    fn spawn(
+        &mut self,
+        id: u32,
+        handshake: tungstenite::handshake::client::Request,
+        options: SubscriptionOptions,
+        operation: Value,
+    ) {
+        let stopped = self.stop.subscribe();
+        let connection = Connection {
+            id,
+            shared: Arc::clone(&self.shared),
+        };
+        self.thread = Some(std::thread::spawn(move || {
+            let (code, reason) = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime.block_on(run(&connection, handshake, &options, operation, stopped)),
+                Err(e) => {
+                    connection.error(FfiError::internal(e));
+                    (None, String::new())
+                }
+            };
+            connection.set_state(SubscriptionState::Closed);
+            connection.deliver(SubscriptionMessage::Closed { code, reason });
+        }));
+    }
+
+    /// Takes the messages received since the last poll.
+    pub fn poll(&self) -> (SubscriptionState, Vec<SubscriptionMessage>) {
+        match self.shared.lock() {
+            Ok(mut shared) => (shared.state, shared.queue.drain(..).collect()),
+            Err(_) => (SubscriptionState::Closed, Vec::new()),
+        }
+    }
+
+    /// Ends the subscription, closes the connection and waits for it to end.
+    pub fn stop(mut self) {
+        let _ = self.stop.send(true);
+        if let Some(thread) = self.thread.take() {
+            // From a callback this runs on the subscription's own thread,
+            // which stops by itself once the callback returns.
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+/// The handshake and subscribe payload for a request, checked before
+/// anything is sent.
+fn prepare(
+    request: &Request,
+    options: &SubscriptionOptions,
+) -> Result<(tungstenite::handshake::client::Request, Value), FfiError> {
+    let operation = operation_payload(request)?;
+    let url = PreparedRequest::new(request).map_err(FfiError::from)?.url;
+    crate::graphql::check_before_sending(request, &url)?;
+    let protocols: Vec<String> = match options.protocol {
+        Some(protocol) => vec![protocol.name().to_string()],
+        None => vec!["graphql-transport-ws".to_string(), "graphql-ws".to_string()],
+    };
+    let handshake = websocket::handshake_request(request, &protocols)?;
+    Ok((handshake, operation))
+}
+
+/// The `subscribe` (or legacy `start`) payload for the request's GraphQL body.
+fn operation_payload(request: &Request) -> Result<Value, FfiError> {
+    let graphql = request.body.as_ref().and_then(|body| body.graphql.as_ref());
+    let query = match graphql.and_then(|g| g.query.as_deref()) {
+        Some(query) if !query.trim().is_empty() => query,
+        _ => {
+            let message = "The request has no GraphQL query to subscribe with";
+            return Err(FfiError::new(ErrorCategory::Argument, "missing_query", message));
+        }
+    };
+
+    let mut payload = Map::new();
+    payload.insert("query".to_string(), json!(query));
+    if let Some(variables) = graphql
+        .and_then(|g| g.variables.as_deref())
+        .filter(|v| !v.trim().is_empty())
+    {
+        let variables: Value = serde_json::from_str(variables).map_err(|e| FfiError::json("variables", &e))?;
+        payload.insert("variables".to_string(), variables);
+    }
+    if let Some(name) = graphql
+        .and_then(|g| g.extra.get("operationName"))
+        .filter(|name| name.is_string())
+    {
+        payload.insert("operationName".to_string(), name.clone());
+    }
+    Ok(Value::Object(payload))
+}
+
+/// What woke up the session loop.
+enum Event {
+    Text(String),
+    Closed(Option<u16>, String),
+    Stop,
+    Deadline,
+    Ended,
+    Failed(tungstenite::Error),
+}
+
+async fn run(
+    connection: &Connection,
+    handshake: tungstenite::handshake::client::Request,
+    options: &SubscriptionOptions,
+    operation: Value,
+    mut stopped: watch::Receiver<bool>,
+) -> (Option<u16>, String) {
+    let url = handshake.uri().to_string();
+    let (mut socket, response) = match tokio_tungstenite::connect_async(handshake).await {
+        Ok(connected) => connected,
+        Err(e) => {
+            connection.error(websocket::classify(e, &url));
+            return (None, String::new());
+        }
+    };
+    let accepted = response
+        .headers()
+        .get("sec-websocket-protocol")
+        .and_then(|value| value.to_str().ok())
+        .and_then(SubscriptionProtocol::from_name);
+    let protocol = accepted
+        .or(options.protocol)
+        .unwrap_or(SubscriptionProtocol::GraphqlTransportWs);
+
+    let mut init = json!({ "type": "connection_init" });
+    if let Some(params) = &options.connection_params {
+        init["payload"] = params.clone();
+    }
+    let mut outgoing = vec![Message::Text(init.to_string())];
+    let mut acked = false;
+    let mut closing = false;
+    let mut deadline = Instant::now() + Duration::from_millis(options.ack_timeout_ms);
+    // The close frame that ended the connection, sent by either side.
+    let mut close: Option<(Option<u16>, String)> = None;
+
+    loop {
+        for message in outgoing.drain(..) {
+            let is_close = matches!(message, Message::Close(_));
+            if let Message::Close(Some(frame)) = &message {
+                close.get_or_insert((Some(u16::from(frame.code)), frame.reason.to_string()));
+            }
+            match socket.send(message).await {
+                Ok(()) => {}
+                Err(tungstenite::Error::AlreadyClosed | tungstenite::Error::ConnectionClosed) => {
+                    return close.unwrap_or_default()
+                }
+                Err(e) => {
+                    connection.error(websocket::classify(e, &url));
+                    return close.unwrap_or_default();
+                }
+            }
+            if is_close {
+                closing = true;
+                deadline = Instant::now() + CLOSE_TIMEOUT;
+                connection.set_state(SubscriptionState::Closing);
+            }
+        }
+
+        let waiting = !acked || closing;
+        let event = tokio::select! {
+            incoming = socket.next() => match incoming {
+                Some(Ok(Message::Text(text))) => Event::Text(text),
+                Some(Ok(Message::Close(frame))) => {
+                    let code = frame.as_ref().map(|f| u16::from(f.code));
+                    Event::Closed(code, frame.map(|f| f.reason.into_owned()).unwrap_or_default())
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(tungstenite::Error::ConnectionClosed)) | None => Event::Ended,
+                Some(Err(e)) => Event::Failed(e),
+            },
+            _ = stopped.changed(), if !closing => Event::Stop,
+            _ = tokio::time::sleep_until(deadline), if waiting => Event::Deadline,
+        };
+
+        match event {
+            Event::Text(text) => {
+                let message: Value = match serde_json::from_str(&text) {
+                    Ok(message @ Value::Object(_)) => message,
+                    _ => {
+                        let message = "The server sent a message that is not a JSON object";
+                        connection.error(FfiError::new(ErrorCategory::Network, "protocol_error", message));
+                        outgoing.push(close_message(4400, "Invalid message received"));
+                        continue;
+                    }
+                };
+                let payload = message.get("payload").cloned();
+                match (message["type"].as_str().unwrap_or(""), protocol) {
+                    ("connection_ack", _) if !acked => {
+                        acked = true;
+                        connection.set_state(SubscriptionState::Active);
+                        connection.deliver(SubscriptionMessage::Ack { protocol, payload });
+                        let start = match protocol {
+                            SubscriptionProtocol::GraphqlTransportWs => "subscribe",
+                            SubscriptionProtocol::GraphqlWs => "start",
+                        };
+                        let subscribe = json!({ "id": OPERATION_ID, "type": start, "payload": operation });
+                        outgoing.push(Message::Text(subscribe.to_string()));
+                    }
+                    ("ping", SubscriptionProtocol::GraphqlTransportWs) => {
+                        outgoing.push(Message::Text(json!({ "type": "pong" }).to_string()));
+                    }
+                    ("next", SubscriptionProtocol::GraphqlTransportWs) | ("data", SubscriptionProtocol::GraphqlWs) => {
+                        connection.deliver(SubscriptionMessage::Next {
+                            payload: payload.unwrap_or(Value::Null),
+                        });
+                    }
+                    ("error", _) if acked => {
+                        let errors = payload.unwrap_or(Value::Null);
+                        let message = errors
+                            .pointer("/0/message")
+                            .or_else(|| errors.pointer("/message"))
+                            .and_then(Value::as_str)
+                            .map(|message| format!("Subscription failed: {}", message))
+                            .unwrap_or_else(|| "Subscription failed".to_string());
+                        connection.error(
+                            FfiError::new(ErrorCategory::Network, "subscription_error", message)
+                                .with_details(json!({ "errors": errors })),
+                        );
+                        outgoing.push(close_message(1000, "Normal Closure"));
+                    }
+                    ("connection_error", SubscriptionProtocol::GraphqlWs) | ("error", _) => {
+                        connection.error(rejected(json!({ "payload": payload })));
+                        outgoing.push(close_message(1000, "Normal Closure"));
+                    }
+                    ("complete", _) => {
+                        connection.deliver(SubscriptionMessage::Complete);
+                        outgoing.push(close_message(1000, "Normal Closure"));
+                    }
+                    // Keep-alives (`pong`, legacy `ka`) and anything unknown.
+                    _ => {}
+                }
+            }
+            Event::Closed(code, reason) => {
+                if !acked && !closing {
+                    connection.error(rejected(json!({ "code": code, "reason": reason })));
+                }
+                close.get_or_insert((code, reason));
+                connection.set_state(SubscriptionState::Closing);
+            }
+            Event::Stop => {
+                if acked {
+                    let stop = match protocol {
+                        SubscriptionProtocol::GraphqlTransportWs => "complete",
+                        SubscriptionProtocol::GraphqlWs => "stop",
+                    };
+                    outgoing.push(Message::Text(json!({ "id": OPERATION_ID, "type": stop }).to_string()));
+                }
+                outgoing.push(close_message(1000, "Normal Closure"));
+            }
+            Event::Deadline if closing => return close.unwrap_or_default(),
+            Event::Deadline => {
+                let message = format!(
+                    "The server did not acknowledge the connection within {} ms",
+                    options.ack_timeout_ms
+                );
+                connection.error(FfiError::new(ErrorCategory::Timeout, "connection_ack_timeout", message));
+                outgoing.push(close_message(4408, "Connection initialisation timeout"));
+            }
+            // A server that drops the connection once we have closed it is
+            // done with us; the reset is not worth reporting.
+            Event::Ended | Event::Failed(_) if closing => return close.unwrap_or_default(),
+            Event::Ended => return close.unwrap_or_default(),
+            Event::Failed(e) => {
+                connection.error(websocket::classify(e, &url));
+                return close.unwrap_or_default();
+            }
+        }
+    }
+}
+
+fn close_message(code: u16, reason: &'static str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code: CloseCode::from(code),
+        reason: Cow::Borrowed(reason),
+    }))
+}
+
+fn rejected(details: Value) -> FfiError {
+    let message = "The server rejected the connection; check the connection_params";
+    FfiError::new(ErrorCategory::Network, "connection_rejected", message).with_details(details)
+}
+
+fn not_found(id: u32) -> FfiError {
+    FfiError::new(
+        ErrorCategory::Argument,
+        "subscription_not_found",
+        format!("No subscription with id {}", id),
+    )
+}
+
+/// Run a request's GraphQL body as a subscription over WebSocket. The
+/// request's URL (`ws`, `wss`, or `http(s)` which is mapped to them), headers
+/// and auth are used for the handshake. If a schema is cached for the URL the
+/// query is validated first.
+///
+/// `options_json` may be null; see `SubscriptionOptions`. If `callback` is
+/// not null, every message is passed to it as it arrives; otherwise messages
+/// are queued for `graphql_subscription_poll`. Returns `{"id": 1}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn graphql_subscribe(
+    request_json: *const c_char,
+    options_json: *const c_char,
+    callback: Option<SubscriptionCallback>,
+) -> *mut c_char {
+    let result = (|| {
+        let request: Request = unsafe { ffi::json_arg(request_json, "request") }?;
+        let options: SubscriptionOptions = match unsafe { ffi::optional_arg(options_json, "options") }? {
+            Some(json) => serde_json::from_str(json).map_err(|e| FfiError::json("options", &e))?,
+            None => SubscriptionOptions::default(),
+        };
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let (handshake, operation) = prepare(&request, &options)?;
+
+        let mut subscriptions = SUBSCRIPTIONS
+            .lock()
+            .map_err(|_| FfiError::internal("Subscription registry is poisoned"))?;
+        let subscriptions = subscriptions.get_or_insert_with(HashMap::new);
+        let subscription = subscriptions.entry(id).or_insert(Subscription::new(callback));
+        subscription.spawn(id, handshake, options, operation);
+        Ok(json!({ "id": id }))
+    })();
+    ffi::respond(result)
+}
+
+/// Take the messages a subscription received since the last call. Returns
+/// `{"state": "active", "messages": [...]}`.
+#[no_mangle]
+pub extern "C" fn graphql_subscription_poll(id: u32) -> *mut c_char {
+    let subscriptions = SUBSCRIPTIONS.lock();
+    let result = match subscriptions.as_ref().ok().and_then(|s| s.as_ref()?.get(&id)) {
+        Some(subscription) => {
+            let (state, messages) = subscription.poll();
+            Ok(json!({ "state": state, "messages": messages }))
+        }
+        None => Err(not_found(id)),
+    };
+    ffi::respond(result)
+}
+
+/// Stop a subscription, close its connection and release it. Returns `true`.
+/// This may be called from the subscription's own callback.
+#[no_mangle]
+pub extern "C" fn graphql_unsubscribe(id: u32) -> *mut c_char {
+    let subscription = SUBSCRIPTIONS
+        .lock()
+        .ok()
+        .and_then(|mut subscriptions| subscriptions.as_mut().and_then(|s| s.remove(&id)));
+
+    ffi::respond(match subscription {
+        Some(subscription) => {
+            subscription.stop();
+            Ok(true)
+        }
+        None => Err(not_found(id)),
+    })
+}
@@ -0,0 +1,487 @@
+//! gRPC calls with messages written as JSON. Services and message types are
+//! loaded from `.proto` files, compiled at runtime, or fetched from the
+//! server with gRPC server reflection, so no generated code is needed.
+
+use crate::ffi::{self, ErrorCategory, FfiError};
+use base64::Engine;
+use prost::Message as _;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor, SerializeOptions};
+use prost_types::FileDescriptorProto;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, BinaryMetadataKey, BinaryMetadataValue, MetadataMap};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Status};
+use tonic_reflection::pb::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::ServerReflectionRequest;
+
+/// A gRPC call. `grpc_describe` takes the same shape and only reads `url`,
+/// `metadata` and the proto source.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcCall {
+    /// `host:port` or `http://host:port`; `https://` connects with TLS.
+    pub url: String,
+    /// `package.Service/Method`.
+    pub method: String,
+    /// `.proto` files defining the service. When empty, the server's
+    /// reflection service is asked instead.
+    pub proto_files: Vec<String>,
+    /// Directories `import`s are resolved against. Defaults to the
+    /// directories of `proto_files`.
+    pub include_paths: Vec<String>,
+    /// Request metadata. Values of `-bin` keys are base64.
+    pub metadata: Vec<(String, String)>,
+    /// Request messages. Unary and server-streaming calls take one, and send
+    /// an empty message when none is given.
+    pub messages: Vec<Value>,
+    pub timeout_ms: Option<u64>,
+    /// Include fields that have their default value in response messages.
+    pub emit_defaults: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcStatus {
+    pub code: i32,
+    /// Canonical name, e.g. `NOT_FOUND`.
+    pub name: String,
+    pub message: String,
+}
+
+/// The outcome of a call. A non-OK status is a normal response, like an HTTP
+/// error status; only failures to connect or to build the call are errors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcResponse {
+    pub status: GrpcStatus,
+    pub headers: Vec<(String, String)>,
+    pub messages: Vec<Value>,
+    pub trailers: Vec<(String, String)>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcService {
+    pub name: String,
+    pub methods: Vec<GrpcMethod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcMethod {
+    pub name: String,
+    /// Value for `GrpcCall::method`.
+    pub path: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub input_type: String,
+    pub output_type: String,
+    /// The input message with every scalar field at its default, as a
+    /// starting point for writing requests.
+    pub input_template: Value,
+}
+
+/// Encodes `DynamicMessage`s, and decodes them as `decode_as`.
+#[derive(Debug, Clone)]
+pub struct DynamicCodec {
+    decode_as: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    pub fn new(decode_as: MessageDescriptor) -> Self {
+        DynamicCodec { decode_as }
+    }
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        item.encode(dst).map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<DynamicMessage>, Status> {
+        DynamicMessage::decode(self.decode_as.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(format!("Cannot decode {}: {}", self.decode_as.full_name(), e)))
+    }
+}
+
+pub fn call(call: &GrpcCall) -> Result<GrpcResponse, FfiError> {
+    let runtime = tokio::runtime::Runtime::new().map_err(FfiError::internal)?;
+    runtime.block_on(async {
+        let timeout = call.timeout_ms.map(Duration::from_millis);
+        let channel = connect(&call.url, timeout).await?;
+        let pool = load_pool(call, Some(&channel)).await?;
+        let method = find_method(&pool, &call.method)?;
+        let messages = build_messages(&method, &call.messages)?;
+
+        let mut request = tonic::Request::new(futures_util::stream::iter(messages));
+        append_metadata(request.metadata_mut(), &call.metadata)?;
+        if let Some(timeout) = timeout {
+            request.set_timeout(timeout);
+        }
+        let path = PathAndQuery::try_from(method_path(&method)).map_err(FfiError::internal)?;
+
+        let start = Instant::now();
+        let mut client = tonic::client::Grpc::new(channel);
+        client.ready().await.map_err(|e| connect_error(&e, &call.url))?;
+        // Every call type is the same on the wire: a stream of messages each
+        // way. Streaming is used for all of them so headers, every message
+        // and trailers are kept.
+        let result = client.streaming(request, path, DynamicCodec::new(method.output())).await;
+
+        let options = SerializeOptions::new().skip_default_fields(!call.emit_defaults);
+        let mut response = GrpcResponse {
+            status: status(Code::Ok, ""),
+            headers: Vec::new(),
+            messages: Vec::new(),
+            trailers: Vec::new(),
+            duration_ms: 0,
+        };
+        match result {
+            Ok(streaming) => {
+                response.headers = metadata_pairs(streaming.metadata());
+                let mut stream = streaming.into_inner();
+                loop {
+                    match stream.message().await {
+                        Ok(Some(message)) => response.messages.push(to_json(&message, &options)?),
+                        Ok(None) => {
+                            if let Ok(Some(trailers)) = stream.trailers().await {
+                                response.trailers = metadata_pairs(&trailers);
+                            }
+                            break;
+                        }
+                        Err(error) => {
+                            response.status = status(error.code(), error.message());
+                            response.trailers = metadata_pairs(error.metadata());
+                            break;
+                        }
+                    }
+                }
+            }
+            // The server answered with trailers only.
+            Err(error) => {
+                response.status = status(error.code(), error.message());
+                response.trailers = metadata_pairs(error.metadata());
+            }
+        }
+        response.duration_ms = start.elapsed().as_millis() as u64;
+        Ok(response)
+    })
+}
+
+pub fn describe(call: &GrpcCall) -> Result<Vec<GrpcService>, FfiError> {
+    let runtime = tokio::runtime::Runtime::new().map_err(FfiError::internal)?;
+    let pool = runtime.block_on(async {
+        match call.proto_files.is_empty() {
+            true => {
+                let channel = connect(&call.url, call.timeout_ms.map(Duration::from_millis)).await?;
+                load_pool(call, Some(&channel)).await
+            }
+            false => load_pool(call, None).await,
+        }
+    })?;
+
+    let options = SerializeOptions::new().skip_default_fields(false);
+    pool.services()
+        .filter(|service| !service.full_name().starts_with("grpc.reflection."))
+        .map(|service| {
+            let methods = service
+                .methods()
+                .map(|method| {
+                    Ok(GrpcMethod {
+                        name: method.name().to_string(),
+                        path: method_path(&method).trim_start_matches('/').to_string(),
+                        client_streaming: method.is_client_streaming(),
+                        server_streaming: method.is_server_streaming(),
+                        input_type: method.input().full_name().to_string(),
+                        output_type: method.output().full_name().to_string(),
+                        input_template: to_json(&DynamicMessage::new(method.input()), &options)?,
+                    })
+                })
+                .collect::<Result<_, FfiError>>()?;
+            Ok(GrpcService {
+                name: service.full_name().to_string(),
+                methods,
+            })
+        })
+        .collect()
+}
+
+async fn connect(url: &str, timeout: Option<Duration>) -> Result<Channel, FfiError> {
+    let url = match url.contains("://") {
+        true => url.to_string(),
+        false => format!("http://{}", url),
+    };
+    let invalid = |e: &dyn std::fmt::Display| {
+        let message = format!("Invalid gRPC server URL '{}': {}", url, e);
+        FfiError::new(ErrorCategory::Argument, "invalid_url", message)
+    };
+    let mut endpoint = Endpoint::from_shared(url.clone()).map_err(|e| invalid(&e))?;
+    if url.starts_with("https://") {
+        endpoint = endpoint.tls_config(ClientTlsConfig::new()).map_err(|e| invalid(&e))?;
+    }
+    if let Some(timeout) = timeout {
+        endpoint = endpoint.connect_timeout(timeout).timeout(timeout);
+    }
+    endpoint.connect().await.map_err(|e| connect_error(&e, &url))
+}
+
+fn connect_error(e: &tonic::transport::Error, url: &str) -> FfiError {
+    match FfiError::from_request_error(e, Some(url)) {
+        error if error.category == ErrorCategory::Internal => FfiError {
+            category: ErrorCategory::Connect,
+            code: "connection_failed".to_string(),
+            ..error
+        },
+        error => error,
+    }
+}
+
+async fn load_pool(call: &GrpcCall, channel: Option<&Channel>) -> Result<DescriptorPool, FfiError> {
+    match channel {
+        Some(channel) if call.proto_files.is_empty() => reflect(channel.clone(), &call.metadata).await,
+        _ => compile(&call.proto_files, &call.include_paths),
+    }
+}
+
+fn compile(files: &[String], include_paths: &[String]) -> Result<DescriptorPool, FfiError> {
+    let includes: Vec<PathBuf> = match include_paths.is_empty() {
+        true => files
+            .iter()
+            .map(|file| match Path::new(file).parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect(),
+        false => include_paths.iter().map(PathBuf::from).collect(),
+    };
+    let invalid = |e: protox::Error| FfiError::new(ErrorCategory::Parse, "invalid_proto", e.to_string());
+
+    let mut compiler = protox::Compiler::new(includes).map_err(invalid)?;
+    compiler.include_imports(true).open_files(files).map_err(invalid)?;
+    Ok(compiler.descriptor_pool())
+}
+
+/// Fetches the files defining every service the server lists, and the files
+/// they import, with gRPC server reflection.
+async fn reflect(channel: Channel, metadata: &[(String, String)]) -> Result<DescriptorPool, FfiError> {
+    let mut client = ServerReflectionClient::new(channel);
+    let services = match ask(&mut client, metadata, MessageRequest::ListServices(String::new())).await? {
+        MessageResponse::ListServicesResponse(list) => list.service.into_iter().map(|s| s.name),
+        _ => return Err(reflection_error("Unexpected reply to ListServices")),
+    };
+
+    let mut pending: VecDeque<MessageRequest> = services
+        .filter(|name| !name.starts_with("grpc.reflection."))
+        .map(MessageRequest::FileContainingSymbol)
+        .collect();
+    let mut requested = HashSet::new();
+    let mut files = BTreeMap::new();
+    while let Some(request) = pending.pop_front() {
+        let protos = match ask(&mut client, metadata, request.clone()).await {
+            Ok(MessageResponse::FileDescriptorResponse(response)) => response
+                .file_descriptor_proto
+                .iter()
+                .map(|bytes| FileDescriptorProto::decode(&bytes[..]))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| reflection_error(format!("Invalid file descriptor: {}", e)))?,
+            // Servers often leave out the well-known types; they are built in.
+            Err(_) if matches!(&request, MessageRequest::FileByFilename(name) if name.starts_with("google/protobuf/")) => {
+                continue;
+            }
+            Ok(_) => return Err(reflection_error("Unexpected reply to a file request")),
+            Err(error) => return Err(error),
+        };
+        for proto in protos {
+            for dependency in &proto.dependency {
+                if !files.contains_key(dependency) && requested.insert(dependency.clone()) {
+                    pending.push_back(MessageRequest::FileByFilename(dependency.clone()));
+                }
+            }
+            files.insert(proto.name().to_string(), proto);
+        }
+    }
+
+    let mut pool = DescriptorPool::new();
+    let missing: Vec<String> = requested.into_iter().filter(|name| !files.contains_key(name)).collect();
+    if !missing.is_empty() {
+        let mut compiler = protox::Compiler::new(Vec::<PathBuf>::new()).map_err(|e| reflection_error(e.to_string()))?;
+        compiler.include_imports(true).open_files(missing).map_err(|e| reflection_error(e.to_string()))?;
+        pool.add_file_descriptor_protos(compiler.file_descriptor_set().file)
+            .map_err(|e| reflection_error(e.to_string()))?;
+    }
+    pool.add_file_descriptor_protos(files.into_values())
+        .map_err(|e| reflection_error(format!("Invalid file descriptor: {}", e)))?;
+    Ok(pool)
+}
+
+async fn ask(
+    client: &mut ServerReflectionClient<Channel>,
+    metadata: &[(String, String)],
+    request: MessageRequest,
+) -> Result<MessageResponse, FfiError> {
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    };
+    let mut request = tonic::Request::new(futures_util::stream::iter([request]));
+    append_metadata(request.metadata_mut(), metadata)?;
+
+    let status_error = |status: Status| match status.code() {
+        Code::Unimplemented => FfiError::new(
+            ErrorCategory::Unsupported,
+            "reflection_unavailable",
+            "The server does not support reflection; pass proto_files instead",
+        ),
+        code => reflection_error(format!("{}: {}", status_name(code), status.message())),
+    };
+    let mut stream = client.server_reflection_info(request).await.map_err(status_error)?.into_inner();
+    match stream.message().await.map_err(status_error)?.and_then(|r| r.message_response) {
+        Some(MessageResponse::ErrorResponse(error)) => Err(reflection_error(error.error_message)),
+        Some(response) => Ok(response),
+        None => Err(reflection_error("Empty reflection response")),
+    }
+}
+
+fn reflection_error(message: impl Into<String>) -> FfiError {
+    FfiError::new(ErrorCategory::Network, "reflection_failed", message)
+}
+
+/// Accepts `package.Service/Method`, with or without a leading `/`, and
+/// `package.Service.Method`.
+fn find_method(pool: &DescriptorPool, method: &str) -> Result<MethodDescriptor, FfiError> {
+    let path = method.trim_start_matches('/');
+    path.rsplit_once('/')
+        .or_else(|| path.rsplit_once('.'))
+        .and_then(|(service, name)| pool.get_service_by_name(service)?.methods().find(|m| m.name() == name))
+        .ok_or_else(|| {
+            let message = format!("No method '{}' in the loaded services", method);
+            FfiError::new(ErrorCategory::Argument, "method_not_found", message)
+        })
+}
+
+fn method_path(method: &MethodDescriptor) -> String {
+    format!("/{}/{}", method.parent_service().full_name(), method.name())
+}
+
+fn build_messages(method: &MethodDescriptor, messages: &[Value]) -> Result<Vec<DynamicMessage>, FfiError> {
+    let input = method.input();
+    if !method.is_client_streaming() && messages.len() > 1 {
+        let message = format!("{} takes a single request message", method.full_name());
+        return Err(FfiError::new(ErrorCategory::Argument, "too_many_messages", message));
+    }
+    let empty = [Value::Object(Default::default())];
+    let messages = match messages.is_empty() && !method.is_client_streaming() {
+        true => &empty[..],
+        false => messages,
+    };
+    messages
+        .iter()
+        .enumerate()
+        .map(|(index, json)| {
+            DynamicMessage::deserialize(input.clone(), json).map_err(|e| {
+                let message = format!("Message {} is not a valid {}: {}", index, input.full_name(), e);
+                FfiError::new(ErrorCategory::Argument, "invalid_message", message)
+            })
+        })
+        .collect()
+}
+
+fn to_json(message: &DynamicMessage, options: &SerializeOptions) -> Result<Value, FfiError> {
+    message
+        .serialize_with_options(serde_json::value::Serializer, options)
+        .map_err(FfiError::internal)
+}
+
+fn append_metadata(map: &mut MetadataMap, metadata: &[(String, String)]) -> Result<(), FfiError> {
+    for (key, value) in metadata {
+        let invalid = || FfiError::new(ErrorCategory::Argument, "invalid_metadata", format!("Invalid metadata '{}'", key));
+        let key = key.to_ascii_lowercase();
+        if key.ends_with("-bin") {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(value).map_err(|_| invalid())?;
+            let key = BinaryMetadataKey::from_bytes(key.as_bytes()).map_err(|_| invalid())?;
+            map.append_bin(key, BinaryMetadataValue::from_bytes(&bytes));
+        } else {
+            let key = AsciiMetadataKey::from_bytes(key.as_bytes()).map_err(|_| invalid())?;
+            map.append(key, AsciiMetadataValue::try_from(value.as_str()).map_err(|_| invalid())?);
+        }
+    }
+    Ok(())
+}
+
+fn metadata_pairs(metadata: &MetadataMap) -> Vec<(String, String)> {
+    metadata
+        .clone()
+        .into_headers()
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+fn status(code: Code, message: &str) -> GrpcStatus {
+    GrpcStatus {
+        code: code as i32,
+        name: status_name(code),
+        message: message.to_string(),
+    }
+}
+
+/// `DeadlineExceeded` -> `DEADLINE_EXCEEDED`.
+fn status_name(code: Code) -> String {
+    let mut name = String::new();
+    for (index, c) in format!("{:?}", code).chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+/// Make a gRPC call. Returns a `GrpcResponse`; a non-OK gRPC status is
+/// reported in `status`, not as an error.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn grpc_call(call_json: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::json_arg::<GrpcCall>(call_json, "call") }.and_then(|c| call(&c)))
+}
+
+/// List the services and methods of a server (by reflection) or of
+/// `proto_files`. Returns `{"services": [...]}` with `GrpcService`s.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn grpc_describe(call_json: *const c_char) -> *mut c_char {
+    let result = unsafe { ffi::json_arg::<GrpcCall>(call_json, "call") }.and_then(|c| describe(&c));
+    ffi::respond(result.map(|services| serde_json::json!({ "services": services })))
+}
@@ -0,0 +1,670 @@
+use crate::examples::{parse_set_cookie, response_to_example};
+use crate::ffi::{self, FfiError};
+use crate::history::{HistoryEntry, HistoryQuery};
+use crate::models::collection::{Collection, CollectionInfo, CollectionItem};
+use crate::models::request::{Body, Description, FormData, Header, Request, Url};
+use crate::report::format_timestamp;
+use crate::runner::RunSummary;
+use crate::scripts::reason_phrase;
+use crate::HttpResponse;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+/// HAR 1.2 (<http://www.softwareishard.com/blog/har-12-spec/>). Fields that
+/// browsers commonly omit are defaulted so real-world exports load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarLog {
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub creator: HarCreator,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<HarPage>,
+    #[serde(default)]
+    pub entries: Vec<HarEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPage {
+    #[serde(default)]
+    pub started_date_time: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pageref: Option<String>,
+    #[serde(default)]
+    pub started_date_time: String,
+    #[serde(default)]
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    #[serde(default)]
+    pub cache: serde_json::Value,
+    #[serde(default)]
+    pub timings: HarTimings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    pub server_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default = "default_http_version")]
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<HarCookie>,
+    #[serde(default)]
+    pub headers: Vec<HarNameValue>,
+    #[serde(default)]
+    pub query_string: Vec<HarNameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    #[serde(default = "unknown_size")]
+    pub headers_size: i64,
+    #[serde(default = "unknown_size")]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    #[serde(default)]
+    pub status: u16,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default = "default_http_version")]
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<HarCookie>,
+    #[serde(default)]
+    pub headers: Vec<HarNameValue>,
+    #[serde(default)]
+    pub content: HarContent,
+    #[serde(default, rename = "redirectURL")]
+    pub redirect_url: String,
+    #[serde(default = "unknown_size")]
+    pub headers_size: i64,
+    #[serde(default = "unknown_size")]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarNameValue {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarCookie {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<HarParam>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarParam {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    #[serde(default)]
+    pub size: i64,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// `base64` when `text` holds binary content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+/// Phase durations in milliseconds; -1 means not applicable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarTimings {
+    #[serde(default = "unknown_timing")]
+    pub blocked: f64,
+    #[serde(default = "unknown_timing")]
+    pub dns: f64,
+    #[serde(default = "unknown_timing")]
+    pub connect: f64,
+    #[serde(default)]
+    pub send: f64,
+    #[serde(default)]
+    pub wait: f64,
+    #[serde(default)]
+    pub receive: f64,
+    #[serde(default = "unknown_timing")]
+    pub ssl: f64,
+}
+
+impl Default for HarTimings {
+    fn default() -> Self {
+        HarTimings {
+            blocked: -1.0,
+            dns: -1.0,
+            connect: -1.0,
+            send: 0.0,
+            wait: 0.0,
+            receive: 0.0,
+            ssl: -1.0,
+        }
+    }
+}
+
+fn default_version() -> String {
+    "1.2".to_string()
+}
+
+fn default_http_version() -> String {
+    "HTTP/1.1".to_string()
+}
+
+fn unknown_size() -> i64 {
+    -1
+}
+
+fn unknown_timing() -> f64 {
+    -1.0
+}
+
+/// Headers that describe the connection rather than the request and should
+/// not be replayed (HTTP/2 pseudo-headers, hop-by-hop headers).
+fn is_replayable_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    !(name.starts_with(':') || name == "content-length" || name == "connection" || name == "host")
+}
+
+/// Converts a HAR log into a collection with one request per entry. With
+/// `keep_responses`, each recorded response is saved as the item's example.
+pub fn har_to_collection(har: &Har, keep_responses: bool) -> Collection {
+    let name = har
+        .log
+        .pages
+        .first()
+        .map(|page| page.title.clone())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "HAR import".to_string());
+
+    let item = har
+        .log
+        .entries
+        .iter()
+        .map(|entry| {
+            let request = har_request_to_request(&entry.request);
+            let url = request.url.as_ref();
+            let path = url
+                .and_then(|u| u.path.as_ref())
+                .map(|p| format!("/{}", p.join("/")))
+                .unwrap_or_else(|| "/".to_string());
+
+            let response = if keep_responses && entry.response.status != 0 {
+                let http_response = har_response_to_http_response(&entry.response, entry.time);
+                Some(vec![response_to_example(
+                    None,
+                    &request,
+                    &http_response,
+                )])
+            } else {
+                None
+            };
+
+            CollectionItem {
+                name: format!("{} {}", entry.request.method.to_uppercase(), path),
+                item: None,
+                request: Some(request),
+                response,
+                event: None,
+                description: entry.comment.clone().map(Description::from),
+                auth: None,
+                variable: None,
+                extra: Default::default(),
+            }
+        })
+        .collect();
+
+    Collection {
+        info: CollectionInfo {
+            name,
+            description: har.log.comment.clone().map(Description::from),
+            schema: Some("https://schema.getpostman.com/json/collection/v2.1.0/collection.json".to_string()),
+            postman_id: None,
+            exporter_id: None,
+            extra: Default::default(),
+        },
+        item,
+        variable: None,
+        event: None,
+        auth: None,
+        extra: Default::default(),
+    }
+}
+
+fn har_request_to_request(har: &HarRequest) -> Request {
+    let headers: Vec<Header> = har
+        .headers
+        .iter()
+        .filter(|h| is_replayable_header(&h.name))
+        .map(|h| Header {
+            key: h.name.clone(),
+            value: h.value.clone(),
+            disabled: None,
+            description: None,
+            extra: Default::default(),
+        })
+        .collect();
+
+    let body = har.post_data.as_ref().map(|post| {
+        let mime = post.mime_type.to_ascii_lowercase();
+        let fields = |params: &[HarParam]| -> Vec<FormData> {
+            params
+                .iter()
+                .map(|p| FormData {
+                    key: p.name.clone(),
+                    value: p.file_name.clone().or_else(|| p.value.clone()),
+                    data_type: Some(if p.file_name.is_some() { "file" } else { "text" }.to_string()),
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                })
+                .collect()
+        };
+
+        if mime.contains("application/x-www-form-urlencoded") && !post.params.is_empty() {
+            Body {
+                mode: Some("urlencoded".to_string()),
+                urlencoded: Some(fields(&post.params)),
+                ..Body::empty()
+            }
+        } else if mime.contains("multipart/form-data") && !post.params.is_empty() {
+            Body {
+                mode: Some("formdata".to_string()),
+                formdata: Some(fields(&post.params)),
+                ..Body::empty()
+            }
+        } else {
+            Body {
+                mode: Some("raw".to_string()),
+                raw: post.text.clone(),
+                ..Body::empty()
+            }
+        }
+    });
+
+    Request {
+        method: Some(har.method.to_uppercase()),
+        header: if headers.is_empty() { None } else { Some(headers) },
+        body,
+        url: Some(Url::parse(&har.url)),
+        description: None,
+        auth: None,
+        options: None,
+        extra: Default::default(),
+    }
+}
+
+fn har_response_to_http_response(har: &HarResponse, time: f64) -> HttpResponse {
+    let (body, body_base64) = match (&har.content.text, har.content.encoding.as_deref()) {
+        (Some(text), Some("base64")) => match base64::engine::general_purpose::STANDARD.decode(text) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(decoded) => (decoded, None),
+                Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), Some(text.clone())),
+            },
+            Err(_) => (text.clone(), None),
+        },
+        (Some(text), _) => (text.clone(), None),
+        (None, _) => (String::new(), None),
+    };
+    let decoded_size = if har.content.size > 0 { har.content.size as u64 } else { body.len() as u64 };
+
+    HttpResponse {
+        status_code: har.status,
+        headers: har.headers.iter().map(|h| (h.name.clone(), h.value.clone())).collect(),
+        body,
+        duration_ms: time.max(0.0) as u64,
+        encoded_size: if har.body_size >= 0 { har.body_size as u64 } else { decoded_size },
+        decoded_size,
+        body_base64,
+        warning: None,
+    }
+}
+
+/// Builds a HAR entry for one executed request. Only the total duration is
+/// known, so it is reported as `wait`.
+pub fn entry_from_execution(
+    started_at_ms: u64,
+    request: &Request,
+    response: Option<&HttpResponse>,
+    error: Option<&str>,
+) -> HarEntry {
+    let url = crate::build_url_from_struct(request.url.as_ref()).unwrap_or_default();
+    let mut headers: Vec<(String, String)> = request
+        .header
+        .iter()
+        .flatten()
+        .filter(|h| !h.disabled.unwrap_or(false))
+        .map(|h| (h.key.clone(), h.value.clone()))
+        .collect();
+    if let Some(auth) = &request.auth {
+        let _ = crate::apply_auth(auth, &mut headers);
+    }
+
+    let request_cookies = headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("cookie"))
+        .flat_map(|(_, v)| v.split(';'))
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| HarCookie {
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+            path: None,
+            domain: None,
+            expires: None,
+            http_only: None,
+            secure: None,
+        })
+        .collect();
+
+    let query_string = request
+        .url
+        .as_ref()
+        .and_then(|u| u.query.as_ref())
+        .map(|query| {
+            query
+                .iter()
+                .filter(|q| !q.disabled.unwrap_or(false))
+                .map(|q| HarNameValue {
+                    name: q.key.clone(),
+                    value: q.value.clone().unwrap_or_default(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let post_data = request.body.as_ref().and_then(|body| post_data_for(body, &headers));
+    let body_size = post_data
+        .as_ref()
+        .and_then(|p| p.text.as_ref())
+        .map_or(0, |text| text.len() as i64);
+
+    let duration = response.map_or(0, |r| r.duration_ms) as f64;
+    let har_response = match response {
+        Some(response) => {
+            // Entries recorded before sizes were tracked have both at zero.
+            let decoded_size = match response.decoded_size {
+                0 => response.body.len() as u64,
+                size => size,
+            };
+            let encoded_size = match response.encoded_size {
+                0 => decoded_size,
+                size => size,
+            };
+            let content_type = response
+                .headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            HarResponse {
+                status: response.status_code,
+                status_text: reason_phrase(response.status_code).to_string(),
+                http_version: default_http_version(),
+                cookies: response
+                    .headers
+                    .iter()
+                    .filter(|(k, _)| k.eq_ignore_ascii_case("set-cookie"))
+                    .filter_map(|(_, v)| parse_set_cookie(v))
+                    .map(|c| HarCookie {
+                        name: c.name.unwrap_or_default(),
+                        value: c.value.unwrap_or_default(),
+                        path: c.path,
+                        domain: c.domain,
+                        expires: c.expires,
+                        http_only: c.http_only,
+                        secure: c.secure,
+                    })
+                    .collect(),
+                headers: response
+                    .headers
+                    .iter()
+                    .map(|(name, value)| HarNameValue {
+                        name: name.clone(),
+                        value: value.clone(),
+                    })
+                    .collect(),
+                content: HarContent {
+                    size: decoded_size as i64,
+                    mime_type: content_type,
+                    text: Some(response.body_base64.clone().unwrap_or_else(|| response.body.clone())),
+                    encoding: response.body_base64.as_ref().map(|_| "base64".to_string()),
+                },
+                redirect_url: response
+                    .headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("location"))
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or_default(),
+                headers_size: -1,
+                body_size: encoded_size as i64,
+            }
+        }
+        // HAR represents requests that never got a response with status 0.
+        None => HarResponse {
+            status: 0,
+            status_text: error.unwrap_or("").to_string(),
+            http_version: String::new(),
+            cookies: Vec::new(),
+            headers: Vec::new(),
+            content: HarContent::default(),
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+    };
+
+    HarEntry {
+        pageref: None,
+        started_date_time: format_timestamp(started_at_ms),
+        time: duration,
+        request: HarRequest {
+            method: request.method.as_deref().unwrap_or("GET").to_uppercase(),
+            url,
+            http_version: default_http_version(),
+            cookies: request_cookies,
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| HarNameValue { name, value })
+                .collect(),
+            query_string,
+            post_data,
+            headers_size: -1,
+            body_size,
+        },
+        response: har_response,
+        cache: serde_json::json!({}),
+        timings: HarTimings {
+            wait: duration,
+            ..HarTimings::default()
+        },
+        server_ip_address: None,
+        comment: None,
+    }
+}
+
+fn post_data_for(body: &Body, headers: &[(String, String)]) -> Option<HarPostData> {
+    let header_mime = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.clone());
+
+    let params = |fields: &Option<Vec<FormData>>| -> Vec<HarParam> {
+        fields
+            .iter()
+            .flatten()
+            .filter(|f| !f.disabled.unwrap_or(false))
+            .map(|f| {
+                let is_file = f.data_type.as_deref() == Some("file");
+                HarParam {
+                    name: f.key.clone(),
+                    value: if is_file { None } else { f.value.clone() },
+                    file_name: if is_file { f.value.clone() } else { None },
+                    content_type: None,
+                }
+            })
+            .collect()
+    };
+
+    match body.mode.as_deref().unwrap_or("raw") {
+        "urlencoded" => Some(HarPostData {
+            mime_type: header_mime.unwrap_or_else(|| "application/x-www-form-urlencoded".to_string()),
+            params: params(&body.urlencoded),
+            text: crate::build_body_from_struct(Some(body)).ok().flatten(),
+        }),
+        "formdata" => Some(HarPostData {
+            mime_type: header_mime.unwrap_or_else(|| "multipart/form-data".to_string()),
+            params: params(&body.formdata),
+            text: None,
+        }),
+        "graphql" => Some(HarPostData {
+            mime_type: header_mime.unwrap_or_else(|| "application/json".to_string()),
+            params: Vec::new(),
+            text: crate::build_body_from_struct(Some(body)).ok().flatten(),
+        }),
+        _ => body.raw.as_ref().map(|raw| HarPostData {
+            mime_type: header_mime.unwrap_or_else(|| "text/plain".to_string()),
+            params: Vec::new(),
+            text: Some(raw.clone()),
+        }),
+    }
+}
+
+fn new_har(entries: Vec<HarEntry>) -> Har {
+    Har {
+        log: HarLog {
+            version: default_version(),
+            creator: HarCreator {
+                name: "Ababil Studio".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            pages: Vec::new(),
+            entries,
+            comment: None,
+        },
+    }
+}
+
+/// Exports history entries as a HAR log, oldest first.
+pub fn history_to_har(entries: &[HistoryEntry]) -> Har {
+    let mut entries: Vec<&HistoryEntry> = entries.iter().collect();
+    entries.sort_by_key(|e| e.executed_at_ms);
+    new_har(
+        entries
+            .into_iter()
+            .map(|e| entry_from_execution(e.executed_at_ms, &e.request, e.response.as_ref(), e.error.as_deref()))
+            .collect(),
+    )
+}
+
+/// Exports every request executed in a collection run as a HAR log.
+pub fn run_to_har(summary: &RunSummary) -> Har {
+    new_har(
+        summary
+            .executions
+            .iter()
+            .map(|e| entry_from_execution(e.started_at_ms, &e.request, e.response.as_ref(), e.error.as_deref()))
+            .collect(),
+    )
+}
+
+/// Convert a HAR log into a Postman collection, one request per entry.
+/// When `keep_responses` is true, recorded responses are saved as examples.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn har_to_collection_json(har_json: *const c_char, keep_responses: bool) -> *mut c_char {
+    ffi::respond(unsafe { ffi::json_arg::<Har>(har_json, "HAR log") }.map(|har| har_to_collection(&har, keep_responses)))
+}
+
+/// Export the history entries matching a `HistoryQuery` (may be null) as HAR.
+/// Without a `limit`, every matching entry is exported, not just one page.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn history_to_har_json(query_json: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let mut query: HistoryQuery = match unsafe { ffi::optional_arg(query_json, "query") }? {
+            Some(json) => serde_json::from_str(json).map_err(|e| FfiError::json("query", &e))?,
+            None => HistoryQuery::default(),
+        };
+        query.limit.get_or_insert(u32::MAX);
+        let page = crate::history::query(&query)?;
+        serde_json::to_string_pretty(&history_to_har(&page.entries)).map_err(FfiError::internal)
+    })();
+    ffi::respond(result)
+}
+
+/// Export a run summary from `run_collection_json` as HAR.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn run_summary_to_har_json(summary_json: *const c_char) -> *mut c_char {
+    ffi::respond(
+        unsafe { ffi::json_arg::<RunSummary>(summary_json, "summary") }
+            .and_then(|summary| serde_json::to_string_pretty(&run_to_har(&summary)).map_err(FfiError::internal)),
+    )
+}
@@ -0,0 +1,453 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::request::Request;
+use crate::HttpResponse;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The store used by the FFI functions, by `make_http_request` and by the
+/// collection runner, which record every execution while a store is open.
+static STORE: Mutex<Option<HistoryStore>> = Mutex::new(None);
+
+/// One executed request as written to the history database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    #[serde(default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub executed_at_ms: u64,
+    /// Name of the environment the request was resolved against, if any.
+    #[serde(default)]
+    pub environment: Option<String>,
+    pub request: Request,
+    #[serde(default)]
+    pub response: Option<HttpResponse>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Filters for `HistoryStore::query`. All fields are optional; results are
+/// returned newest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Substring match against the request URL.
+    pub url: Option<String>,
+    pub method: Option<String>,
+    pub status_code: Option<u16>,
+    /// Inclusive status range, e.g. 400..=499 for client errors.
+    pub status_min: Option<u16>,
+    pub status_max: Option<u16>,
+    pub environment: Option<String>,
+    /// Inclusive bounds on `executed_at_ms`.
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    /// Number of entries matching the filters, ignoring `limit`/`offset`.
+    pub total: u64,
+}
+
+/// Limits applied after every insert. Unset fields are not enforced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_entries: Option<u64>,
+    pub max_age_days: Option<u64>,
+}
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+pub struct HistoryStore {
+    conn: Connection,
+    retention: RetentionPolicy,
+}
+
+impl HistoryStore {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                executed_at_ms INTEGER NOT NULL,
+                method TEXT NOT NULL,
+                url TEXT NOT NULL,
+                status_code INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                environment TEXT,
+                request_json TEXT NOT NULL,
+                response_json TEXT,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS history_executed_at ON history (executed_at_ms);
+            CREATE INDEX IF NOT EXISTS history_url ON history (url);
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+        )?;
+        // A policy that no longer parses is dropped rather than failing to open.
+        let retention = conn
+            .query_row("SELECT value FROM settings WHERE key = 'retention'", [], |row| row.get::<_, String>(0))
+            .optional()?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Ok(HistoryStore { conn, retention })
+    }
+
+    pub fn retention(&self) -> &RetentionPolicy {
+        &self.retention
+    }
+
+    /// Saves the policy in the database, so it applies the next time the
+    /// store is opened too, and prunes existing entries.
+    pub fn set_retention(&mut self, retention: RetentionPolicy) -> Result<usize, Box<dyn std::error::Error>> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES ('retention', ?1)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![serde_json::to_string(&retention)?],
+        )?;
+        self.retention = retention;
+        Ok(self.apply_retention()?)
+    }
+
+    /// Inserts `entry` and returns its id. A zero `executed_at_ms` is
+    /// replaced with the current time.
+    pub fn record(&self, entry: &HistoryEntry) -> Result<i64, Box<dyn std::error::Error>> {
+        let executed_at_ms = if entry.executed_at_ms == 0 {
+            now_ms()
+        } else {
+            entry.executed_at_ms
+        };
+        let method = entry.request.method.as_deref().unwrap_or("GET").to_uppercase();
+        let url = crate::build_url_from_struct(entry.request.url.as_ref()).unwrap_or_default();
+        let status_code = entry.response.as_ref().map_or(0, |r| r.status_code);
+        let duration_ms = entry.response.as_ref().map_or(0, |r| r.duration_ms);
+        let response_json = match &entry.response {
+            Some(response) => Some(serde_json::to_string(response)?),
+            None => None,
+        };
+
+        self.conn.execute(
+            "INSERT INTO history (executed_at_ms, method, url, status_code, duration_ms, environment, request_json, response_json, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                executed_at_ms as i64,
+                method,
+                url,
+                status_code,
+                duration_ms as i64,
+                entry.environment,
+                serde_json::to_string(&entry.request)?,
+                response_json,
+                entry.error,
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.apply_retention()?;
+        Ok(id)
+    }
+
+    pub fn query(&self, query: &HistoryQuery) -> Result<HistoryPage, Box<dyn std::error::Error>> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(url) = &query.url {
+            conditions.push("url LIKE '%' || ? || '%' ESCAPE '\\'");
+            values.push(escape_like(url).into());
+        }
+        if let Some(method) = &query.method {
+            conditions.push("method = ?");
+            values.push(method.to_uppercase().into());
+        }
+        if let Some(status) = query.status_code {
+            conditions.push("status_code = ?");
+            values.push(i64::from(status).into());
+        }
+        if let Some(min) = query.status_min {
+            conditions.push("status_code >= ?");
+            values.push(i64::from(min).into());
+        }
+        if let Some(max) = query.status_max {
+            conditions.push("status_code <= ?");
+            values.push(i64::from(max).into());
+        }
+        if let Some(environment) = &query.environment {
+            conditions.push("environment = ?");
+            values.push(environment.clone().into());
+        }
+        if let Some(from) = query.from_ms {
+            conditions.push("executed_at_ms >= ?");
+            values.push((from as i64).into());
+        }
+        if let Some(to) = query.to_ms {
+            conditions.push("executed_at_ms <= ?");
+            values.push((to as i64).into());
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        let total: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM history{}", where_clause),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        values.push(i64::from(query.limit.unwrap_or(DEFAULT_PAGE_SIZE)).into());
+        values.push(i64::from(query.offset.unwrap_or(0)).into());
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT id, executed_at_ms, environment, request_json, response_json, error
+             FROM history{} ORDER BY executed_at_ms DESC, id DESC LIMIT ? OFFSET ?",
+            where_clause
+        ))?;
+        let rows = statement.query_map(params_from_iter(values.iter()), row_to_parts)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(parts_to_entry(row?)?);
+        }
+
+        Ok(HistoryPage {
+            entries,
+            total: total as u64,
+        })
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<HistoryEntry>, Box<dyn std::error::Error>> {
+        let parts = self
+            .conn
+            .query_row(
+                "SELECT id, executed_at_ms, environment, request_json, response_json, error
+                 FROM history WHERE id = ?1",
+                params![id],
+                row_to_parts,
+            )
+            .optional()?;
+        match parts {
+            Some(parts) => Ok(Some(parts_to_entry(parts)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete(&self, id: i64) -> rusqlite::Result<usize> {
+        self.conn.execute("DELETE FROM history WHERE id = ?1", params![id])
+    }
+
+    pub fn clear(&self) -> rusqlite::Result<usize> {
+        self.conn.execute("DELETE FROM history", [])
+    }
+
+    /// Deletes entries outside the retention policy and returns how many
+    /// were removed.
+    pub fn apply_retention(&self) -> rusqlite::Result<usize> {
+        let mut removed = 0;
+
+        if let Some(days) = self.retention.max_age_days {
+            let cutoff = now_ms().saturating_sub(days.saturating_mul(86_400_000));
+            removed += self.conn.execute(
+                "DELETE FROM history WHERE executed_at_ms < ?1",
+                params![i64::try_from(cutoff).unwrap_or(i64::MAX)],
+            )?;
+        }
+
+        if let Some(max) = self.retention.max_entries {
+            removed += self.conn.execute(
+                "DELETE FROM history WHERE id NOT IN (
+                    SELECT id FROM history ORDER BY executed_at_ms DESC, id DESC LIMIT ?1
+                )",
+                // SQLite reads a negative LIMIT as no limit at all.
+                params![i64::try_from(max).unwrap_or(i64::MAX)],
+            )?;
+        }
+
+        Ok(removed)
+    }
+}
+
+type EntryParts = (i64, i64, Option<String>, String, Option<String>, Option<String>);
+
+fn row_to_parts(row: &rusqlite::Row) -> rusqlite::Result<EntryParts> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+    ))
+}
+
+fn parts_to_entry(parts: EntryParts) -> Result<HistoryEntry, serde_json::Error> {
+    let (id, executed_at_ms, environment, request_json, response_json, error) = parts;
+    Ok(HistoryEntry {
+        id: Some(id),
+        executed_at_ms: executed_at_ms as u64,
+        environment,
+        request: serde_json::from_str(&request_json)?,
+        response: match response_json {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        },
+        error,
+    })
+}
+
+/// Escapes `%`, `_` and the escape character itself, so a URL filter
+/// matches them literally.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Records an execution in the open store, if there is one. Failures are
+/// ignored so that history problems never break a request.
+pub(crate) fn record_if_open(request: &Request, environment: Option<&str>, result: Result<&HttpResponse, &str>) {
+    if let Ok(guard) = STORE.lock() {
+        if let Some(store) = guard.as_ref() {
+            let (response, error) = match result {
+                Ok(response) => (Some(response.clone()), None),
+                Err(error) => (None, Some(error.to_string())),
+            };
+            let _ = store.record(&HistoryEntry {
+                id: None,
+                executed_at_ms: 0,
+                environment: environment.map(str::to_string),
+                request: request.clone(),
+                response,
+                error,
+            });
+        }
+    }
+}
+
+/// Runs `query` against the open store, for exporters that read history.
+pub(crate) fn query(query: &HistoryQuery) -> Result<HistoryPage, FfiError> {
+    let guard = STORE.lock().map_err(|_| FfiError::internal("History store is poisoned"))?;
+    match guard.as_ref() {
+        Some(store) => Ok(store.query(query)?),
+        None => Err(not_open()),
+    }
+}
+
+fn not_open() -> FfiError {
+    FfiError::new(ErrorCategory::Io, "history_not_open", "History store is not open")
+}
+
+fn with_store<T, F>(f: F) -> *mut c_char
+where
+    T: Serialize,
+    F: FnOnce(&mut HistoryStore) -> Result<T, FfiError>,
+{
+    let result = match STORE.lock() {
+        Ok(mut guard) => match guard.as_mut() {
+            Some(store) => f(store),
+            None => Err(not_open()),
+        },
+        Err(_) => Err(FfiError::internal("History store is poisoned")),
+    };
+    ffi::respond(result)
+}
+
+/// Open (or create) the history database at `path`. Once open, every call to
+/// `make_http_request` is recorded.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn history_open(path: *const c_char) -> *mut c_char {
+    let result = unsafe { ffi::arg(path, "path") }.and_then(|path| {
+        let store = HistoryStore::open(path).map_err(|e| {
+            FfiError::new(ErrorCategory::Io, "database_error", e.to_string()).with_details(json!({ "path": path }))
+        })?;
+        let mut guard = STORE.lock().map_err(|_| FfiError::internal("History store is poisoned"))?;
+        *guard = Some(store);
+        Ok(true)
+    });
+    ffi::respond(result)
+}
+
+/// Close the history database; requests are no longer recorded.
+#[no_mangle]
+pub extern "C" fn history_close() {
+    if let Ok(mut guard) = STORE.lock() {
+        *guard = None;
+    }
+}
+
+/// Record a `HistoryEntry` explicitly, e.g. with the environment name set.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn history_record(entry_json: *const c_char) -> *mut c_char {
+    match unsafe { ffi::json_arg::<HistoryEntry>(entry_json, "entry") } {
+        Ok(entry) => with_store(|store| Ok(json!({ "id": store.record(&entry)? }))),
+        Err(e) => ffi::respond::<()>(Err(e)),
+    }
+}
+
+/// Query history with a `HistoryQuery` (may be null); returns a `HistoryPage`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn history_query(query_json: *const c_char) -> *mut c_char {
+    let query = match unsafe { ffi::optional_arg(query_json, "query") } {
+        Ok(Some(json)) => serde_json::from_str(json).map_err(|e| FfiError::json("query", &e)),
+        Ok(None) => Ok(HistoryQuery::default()),
+        Err(e) => Err(e),
+    };
+    match query {
+        Ok(query) => with_store(|store| Ok(store.query(&query)?)),
+        Err(e) => ffi::respond::<()>(Err(e)),
+    }
+}
+
+/// Fetch a single entry by id; the result is `null` if it does not exist.
+#[no_mangle]
+pub extern "C" fn history_get(id: i64) -> *mut c_char {
+    with_store(|store| Ok(store.get(id)?))
+}
+
+#[no_mangle]
+pub extern "C" fn history_delete(id: i64) -> *mut c_char {
+    with_store(|store| Ok(json!({ "removed": store.delete(id)? })))
+}
+
+#[no_mangle]
+pub extern "C" fn history_clear() -> *mut c_char {
+    with_store(|store| Ok(json!({ "removed": store.clear()? })))
+}
+
+/// Set the `RetentionPolicy`, saved with the history, and prune existing
+/// entries immediately.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn history_set_retention(policy_json: *const c_char) -> *mut c_char {
+    match unsafe { ffi::json_arg::<RetentionPolicy>(policy_json, "policy") } {
+        Ok(policy) => with_store(|store| Ok(json!({ "removed": store.set_retention(policy)? }))),
+        Err(e) => ffi::respond::<()>(Err(e)),
+    }
+}
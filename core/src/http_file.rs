@@ -0,0 +1,713 @@
+use crate::curl::{auth_from_header, basic_auth, strip_prefix_ignore_case};
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::collection::{Collection, CollectionInfo, CollectionItem};
+use crate::models::environment::Environment;
+use crate::models::request::{Body, FileBody, FormData, GraphQLBody, Header, Request, Url};
+use crate::models::variable::Variable;
+use serde_json::{Map, Value};
+use std::os::raw::c_char;
+
+const METHODS: [&str; 10] = [
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT", "GRAPHQL",
+];
+
+/// Separates folder names from the request name in `###` titles, so that
+/// folders survive a write/parse round trip.
+const FOLDER_SEPARATOR: &str = " / ";
+
+/// Written in place of ` / ` inside a folder or request name.
+const ESCAPED_SEPARATOR: &str = " \\/ ";
+
+/// Parses a JetBrains HTTP Client / VS Code REST Client file. Requests are
+/// separated by `###` lines and `@name = value` lines become collection
+/// variables. A request is named by its `###` title, else `# @name`, else
+/// its request line. `###` titles containing ` / ` are placed in folders;
+/// ` \/ ` in a title is a literal ` / `.
+pub fn parse_http_file(text: &str, name: &str) -> Collection {
+    let mut variables: Vec<Variable> = Vec::new();
+    let mut items: Vec<CollectionItem> = Vec::new();
+
+    let mut blocks: Vec<(Option<String>, Vec<&str>)> = vec![(None, Vec::new())];
+    for line in text.lines() {
+        if let Some(title) = line.strip_prefix("###") {
+            let title = title.trim();
+            blocks.push((Some(title.to_string()).filter(|t| !t.is_empty()), Vec::new()));
+        } else {
+            blocks.last_mut().unwrap().1.push(line);
+        }
+    }
+
+    for (title, lines) in blocks {
+        let Some((item_name, request)) = parse_block(&lines, &mut variables) else {
+            continue;
+        };
+        let mut path: Vec<String> = match title {
+            Some(title) => title
+                .split(FOLDER_SEPARATOR)
+                .map(|part| part.replace(ESCAPED_SEPARATOR, FOLDER_SEPARATOR))
+                .collect(),
+            None => vec![item_name],
+        };
+        let request_name = path.pop().unwrap_or_default();
+        let item = CollectionItem {
+            name: request_name,
+            item: None,
+            request: Some(request),
+            response: None,
+            event: None,
+            description: None,
+            auth: None,
+            variable: None,
+            extra: Default::default(),
+        };
+        insert_at(&mut items, &path, item);
+    }
+
+    Collection {
+        info: CollectionInfo {
+            name: name.to_string(),
+            description: None,
+            schema: Some("https://schema.getpostman.com/json/collection/v2.1.0/collection.json".to_string()),
+            postman_id: None,
+            exporter_id: None,
+            extra: Default::default(),
+        },
+        item: items,
+        variable: if variables.is_empty() { None } else { Some(variables) },
+        event: None,
+        auth: None,
+        extra: Default::default(),
+    }
+}
+
+fn insert_at(items: &mut Vec<CollectionItem>, folders: &[String], item: CollectionItem) {
+    let Some((first, rest)) = folders.split_first() else {
+        items.push(item);
+        return;
+    };
+    let index = match items.iter().position(|i| i.item.is_some() && i.name == *first) {
+        Some(index) => index,
+        None => {
+            items.push(CollectionItem {
+                name: first.clone(),
+                item: Some(Vec::new()),
+                request: None,
+                response: None,
+                event: None,
+                description: None,
+                auth: None,
+                variable: None,
+                extra: Default::default(),
+            });
+            items.len() - 1
+        }
+    };
+    insert_at(items[index].item.as_mut().unwrap(), rest, item);
+}
+
+/// Parses the lines between two `###` separators. Returns `None` for blocks
+/// that only hold variables or comments.
+fn parse_block(lines: &[&str], variables: &mut Vec<Variable>) -> Option<(String, Request)> {
+    let mut lines = lines.iter().map(|line| line.trim_end()).peekable();
+    let mut name = None;
+
+    // Variables, comments and `# @name` before the request line.
+    let request_line = loop {
+        let line = lines.next()?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#').or_else(|| trimmed.strip_prefix("//")) {
+            if let Some(value) = comment.trim().strip_prefix("@name") {
+                let value = value.trim().trim_start_matches('=').trim();
+                if !value.is_empty() {
+                    name = Some(value.to_string());
+                }
+            }
+            continue;
+        }
+        if let Some(declaration) = trimmed.strip_prefix('@') {
+            if let Some((key, value)) = declaration.split_once('=') {
+                let key = key.trim().to_string();
+                variables.retain(|v| v.key != key);
+                variables.push(Variable {
+                    key,
+                    value: value.trim().to_string(),
+                    var_type: Some("string".to_string()),
+                    disabled: None,
+                    extra: Default::default(),
+                });
+            }
+            continue;
+        }
+        break trimmed;
+    };
+
+    let (method, rest) = match request_line.split_once(char::is_whitespace) {
+        Some((method, rest)) if METHODS.contains(&method.to_ascii_uppercase().as_str()) => {
+            (method.to_ascii_uppercase(), rest.trim())
+        }
+        _ => ("GET".to_string(), request_line),
+    };
+    let mut url = rest.to_string();
+
+    // Query strings may continue on indented `?`/`&` lines.
+    while let Some(line) = lines.peek() {
+        let trimmed = line.trim_start();
+        if line.starts_with(char::is_whitespace) && (trimmed.starts_with('?') || trimmed.starts_with('&')) {
+            url.push_str(trimmed);
+            lines.next();
+        } else {
+            break;
+        }
+    }
+    if let Some((without_version, version)) = url.rsplit_once(char::is_whitespace) {
+        if version.starts_with("HTTP/") {
+            url = without_version.trim().to_string();
+        }
+    }
+
+    let mut headers: Vec<Header> = Vec::new();
+    for line in lines.by_ref() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            break;
+        }
+        if trimmed.starts_with('#') || trimmed.starts_with("//") {
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once(':') {
+            headers.push(Header {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+                disabled: None,
+                description: None,
+                extra: Default::default(),
+            });
+        }
+    }
+
+    // The body runs to the end of the block, or to a `>` response handler
+    // or `>>` response redirect.
+    let mut body_lines: Vec<&str> = Vec::new();
+    for line in lines {
+        if ends_body(line) {
+            break;
+        }
+        body_lines.push(line);
+    }
+    while body_lines.last().is_some_and(|line| line.trim().is_empty()) {
+        body_lines.pop();
+    }
+    let body_text = body_lines.join("\n");
+
+    let mut auth = None;
+    if let Some(index) = headers.iter().position(|h| h.key.eq_ignore_ascii_case("authorization")) {
+        auth = auth_from_header_value(&headers[index].value);
+        if auth.is_some() {
+            headers.remove(index);
+        }
+    }
+
+    // JetBrains uses a `GRAPHQL` method, REST Client an `X-REQUEST-TYPE` header.
+    let graphql_header = headers
+        .iter()
+        .position(|h| h.key.eq_ignore_ascii_case("x-request-type") && h.value.eq_ignore_ascii_case("graphql"));
+    let (method, body) = if method == "GRAPHQL" {
+        ("POST".to_string(), Some(graphql_body(&body_text)))
+    } else if let Some(index) = graphql_header {
+        headers.remove(index);
+        (method, Some(graphql_body(&body_text)))
+    } else {
+        (method, parse_body(&body_text, &headers))
+    };
+
+    let name = name.unwrap_or_else(|| format!("{} {}", method, url));
+    Some((
+        name,
+        Request {
+            method: Some(method),
+            header: if headers.is_empty() { None } else { Some(headers) },
+            body,
+            url: Some(Url::parse(&url)),
+            description: None,
+            auth,
+            options: None,
+            extra: Default::default(),
+        },
+    ))
+}
+
+/// A `>` response handler or `>>` response redirect line.
+fn ends_body(line: &str) -> bool {
+    line.starts_with("> ") || line.starts_with(">>") || line.starts_with(">{%")
+}
+
+/// Besides base64 credentials, both clients accept `Basic user password`
+/// and `Basic user:password` with plain text.
+fn auth_from_header_value(value: &str) -> Option<crate::models::request::Auth> {
+    if let Some(credentials) = strip_prefix_ignore_case(value, "Basic ") {
+        let credentials = credentials.trim();
+        if let Some((username, password)) = credentials.split_once(' ') {
+            return Some(basic_auth(username, password.trim()));
+        }
+        if let Some((username, password)) = credentials.split_once(':') {
+            return Some(basic_auth(username, password));
+        }
+    }
+    auth_from_header(value)
+}
+
+/// A GraphQL request body: the query, optionally followed by a JSON object
+/// of variables after a blank line.
+fn graphql_body(text: &str) -> Body {
+    let (query, variables) = match text.rsplit_once("\n\n") {
+        Some((query, variables)) if serde_json::from_str::<Value>(variables.trim()).is_ok_and(|v| v.is_object()) => {
+            (query.to_string(), Some(variables.trim().to_string()))
+        }
+        _ => (text.to_string(), None),
+    };
+    Body {
+        mode: Some("graphql".to_string()),
+        graphql: Some(GraphQLBody {
+            query: Some(query),
+            variables,
+            extra: Default::default(),
+        }),
+        ..Body::empty()
+    }
+}
+
+fn parse_body(text: &str, headers: &[Header]) -> Option<Body> {
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let content_type = headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| h.value.clone())
+        .unwrap_or_default();
+
+    if let Some(path) = file_include(text.trim()) {
+        return Some(Body {
+            mode: Some("file".to_string()),
+            file: Some(FileBody {
+                src: Some(path.to_string()),
+                extra: Default::default(),
+            }),
+            ..Body::empty()
+        });
+    }
+
+    if content_type.to_ascii_lowercase().starts_with("application/x-www-form-urlencoded") {
+        let joined: String = text.lines().map(str::trim).collect();
+        let fields = joined
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                form_field(key, value, false)
+            })
+            .collect();
+        return Some(Body {
+            mode: Some("urlencoded".to_string()),
+            urlencoded: Some(fields),
+            ..Body::empty()
+        });
+    }
+
+    if content_type.to_ascii_lowercase().starts_with("multipart/form-data") {
+        if let Some(fields) = parse_multipart(text, &content_type) {
+            return Some(Body {
+                mode: Some("formdata".to_string()),
+                formdata: Some(fields),
+                ..Body::empty()
+            });
+        }
+    }
+
+    Some(Body {
+        mode: Some("raw".to_string()),
+        raw: Some(text.to_string()),
+        ..Body::empty()
+    })
+}
+
+/// The path of a `< ./file` or `<@ ./file` include line.
+fn file_include(line: &str) -> Option<&str> {
+    if line.contains('\n') {
+        return None;
+    }
+    let path = line.strip_prefix("<@").or_else(|| line.strip_prefix('<'))?.trim();
+    if path.is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Splits a multipart body on its boundary. Parts whose content is a file
+/// include become file fields.
+fn parse_multipart(text: &str, content_type: &str) -> Option<Vec<FormData>> {
+    let boundary = content_type
+        .split(';')
+        .filter_map(|part| part.trim().strip_prefix("boundary="))
+        .next()?
+        .trim_matches('"');
+    let delimiter = format!("--{}", boundary);
+
+    let mut fields = Vec::new();
+    for part in text.split(delimiter.as_str()).skip(1) {
+        if part.starts_with("--") {
+            break;
+        }
+        let part = part.trim_start_matches(['\r', '\n']);
+        let (part_headers, content) = part.split_once("\n\n").unwrap_or((part, ""));
+        let disposition = part_headers
+            .lines()
+            .find(|line| line.to_ascii_lowercase().starts_with("content-disposition"))?;
+        let name = disposition_param(disposition, "name")?;
+        let content = content.trim_end_matches(['\r', '\n']);
+        match file_include(content.trim()) {
+            Some(path) => fields.push(form_field(&name, path, true)),
+            None => fields.push(form_field(&name, content, false)),
+        }
+    }
+    Some(fields)
+}
+
+fn disposition_param(disposition: &str, key: &str) -> Option<String> {
+    disposition.split(';').find_map(|param| {
+        let (k, v) = param.trim().split_once('=')?;
+        (k == key).then(|| v.trim_matches('"').to_string())
+    })
+}
+
+fn form_field(key: &str, value: &str, is_file: bool) -> FormData {
+    FormData {
+        key: key.to_string(),
+        value: Some(value.to_string()),
+        data_type: Some(if is_file { "file" } else { "text" }.to_string()),
+        disabled: None,
+        description: None,
+        extra: Default::default(),
+    }
+}
+
+/// Writes a collection as an `.http` file. Collection variables become
+/// `@name = value` lines, folders are flattened into `Folder / Request`
+/// titles, and bearer and basic auth become `Authorization` headers.
+/// GraphQL bodies use REST Client's `X-REQUEST-TYPE: GraphQL` form.
+///
+/// Fails for requests the format cannot hold, rather than writing a file
+/// that reads back differently: line breaks in names, URLs, headers or
+/// variables, and body lines that would start a new request or a response
+/// handler.
+pub fn write_http_file(collection: &Collection) -> Result<String, String> {
+    let mut out = String::new();
+    for variable in collection.variable.iter().flatten().filter(|v| !v.disabled.unwrap_or(false)) {
+        single_line(&format!("Variable '{}'", variable.key), &format!("{}{}", variable.key, variable.value))?;
+        out.push_str(&format!("@{} = {}\n", variable.key, variable.value));
+    }
+
+    let mut requests = Vec::new();
+    collect_requests(&collection.item, &mut Vec::new(), &mut requests)?;
+    for (title, request) in requests {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("### {}\n", title));
+        write_request(request, collection.auth.as_ref(), &mut out)
+            .map_err(|e| format!("Cannot write request '{}': {}", title, e))?;
+    }
+    Ok(out)
+}
+
+fn single_line(what: &str, text: &str) -> Result<(), String> {
+    if text.contains(['\n', '\r']) {
+        Err(format!("{} contains a line break", what))
+    } else {
+        Ok(())
+    }
+}
+
+fn collect_requests<'a>(
+    items: &'a [CollectionItem],
+    folders: &mut Vec<String>,
+    out: &mut Vec<(String, &'a Request)>,
+) -> Result<(), String> {
+    for item in items {
+        single_line(&format!("Name '{}'", item.name.trim()), &item.name)?;
+        if item.name.contains(ESCAPED_SEPARATOR) {
+            return Err(format!("Name '{}' contains '{}', which is ambiguous in a title", item.name, ESCAPED_SEPARATOR.trim()));
+        }
+        let name = item.name.replace(FOLDER_SEPARATOR, ESCAPED_SEPARATOR);
+        if let Some(children) = &item.item {
+            folders.push(name);
+            collect_requests(children, folders, out)?;
+            folders.pop();
+        } else if let Some(request) = &item.request {
+            let mut title = folders.join(FOLDER_SEPARATOR);
+            if !title.is_empty() {
+                title.push_str(FOLDER_SEPARATOR);
+            }
+            title.push_str(&name);
+            out.push((title, request));
+        }
+    }
+    Ok(())
+}
+
+fn write_request(
+    request: &Request,
+    collection_auth: Option<&crate::models::request::Auth>,
+    out: &mut String,
+) -> Result<(), String> {
+    let url = request.url.as_ref().map(Url::to_raw).unwrap_or_default();
+    let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
+    single_line("The URL", &url)?;
+    single_line("The method", &method)?;
+    out.push_str(&format!("{} {}\n", method, url));
+
+    let auth = request.auth.as_ref().or(collection_auth);
+    if let Some(value) = auth.and_then(authorization_header) {
+        single_line("The Authorization header", &value)?;
+        out.push_str(&format!("Authorization: {}\n", value));
+    }
+    for header in request.header.iter().flatten() {
+        single_line(&format!("Header '{}'", header.key.trim()), &format!("{}{}", header.key, header.value))?;
+        // Disabled headers are kept as comments so they are not lost.
+        let prefix = if header.disabled.unwrap_or(false) { "# " } else { "" };
+        out.push_str(&format!("{}{}: {}\n", prefix, header.key, header.value));
+    }
+
+    let has_content_type = request
+        .header
+        .iter()
+        .flatten()
+        .any(|h| h.key.eq_ignore_ascii_case("content-type") && !h.disabled.unwrap_or(false));
+    let Some(body) = &request.body else {
+        return Ok(());
+    };
+
+    let text = match body.mode.as_deref().unwrap_or("raw") {
+        "urlencoded" => {
+            if !has_content_type {
+                out.push_str("Content-Type: application/x-www-form-urlencoded\n");
+            }
+            let pairs: Vec<String> = body
+                .urlencoded
+                .iter()
+                .flatten()
+                .filter(|f| !f.disabled.unwrap_or(false))
+                .map(|f| format!("{}={}", f.key, f.value.as_deref().unwrap_or("")))
+                .collect();
+            pairs.join("&")
+        }
+        "formdata" => {
+            let declared = request
+                .header
+                .iter()
+                .flatten()
+                .filter(|h| h.key.eq_ignore_ascii_case("content-type") && !h.disabled.unwrap_or(false))
+                .find_map(|h| h.value.split(';').find_map(|p| p.trim().strip_prefix("boundary=")))
+                .map(|b| b.trim_matches('"').to_string());
+            let boundary = match declared {
+                Some(boundary) => boundary,
+                None => {
+                    if !has_content_type {
+                        out.push_str("Content-Type: multipart/form-data; boundary=ababil-boundary\n");
+                    }
+                    "ababil-boundary".to_string()
+                }
+            };
+            let mut text = String::new();
+            for field in body.formdata.iter().flatten().filter(|f| !f.disabled.unwrap_or(false)) {
+                let value = field.value.as_deref().unwrap_or("");
+                text.push_str(&format!("--{}\n", boundary));
+                if field.data_type.as_deref() == Some("file") {
+                    let file_name = value.rsplit(['/', '\\']).next().unwrap_or(value);
+                    text.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n\n< {}\n",
+                        field.key, file_name, value
+                    ));
+                } else {
+                    text.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{}\"\n\n{}\n",
+                        field.key, value
+                    ));
+                }
+            }
+            text.push_str(&format!("--{}--", boundary));
+            text
+        }
+        "file" => match body.file.as_ref().and_then(|f| f.src.as_deref()) {
+            Some(src) => {
+                single_line("The file path", src)?;
+                format!("< {}", src)
+            }
+            None => return Ok(()),
+        },
+        "graphql" => {
+            out.push_str("X-REQUEST-TYPE: GraphQL\n");
+            let graphql = body.graphql.as_ref();
+            let mut text = graphql.and_then(|g| g.query.clone()).unwrap_or_default();
+            if let Some(variables) = graphql.and_then(|g| g.variables.as_deref()).filter(|v| !v.trim().is_empty()) {
+                text.push_str("\n\n");
+                text.push_str(variables);
+            }
+            text
+        }
+        _ => match body.raw.as_deref() {
+            Some(raw) if !raw.is_empty() => {
+                if file_include(raw.trim()).is_some() {
+                    return Err(format!("the body '{}' would be read as a file include", raw.trim()));
+                }
+                raw.to_string()
+            }
+            _ => return Ok(()),
+        },
+    };
+
+    if let Some(line) = text.lines().find(|line| ends_body(line) || line.starts_with("###")) {
+        return Err(format!(
+            "the body line '{}' would be read as a request separator or response handler",
+            line
+        ));
+    }
+    out.push('\n');
+    out.push_str(&text);
+    out.push('\n');
+    Ok(())
+}
+
+fn authorization_header(auth: &crate::models::request::Auth) -> Option<String> {
+    let find = |vars: &Option<Vec<Variable>>, keys: &[&str]| {
+        vars.iter()
+            .flatten()
+            .find(|v| keys.contains(&v.key.as_str()))
+            .map(|v| v.value.clone())
+            .unwrap_or_default()
+    };
+    match auth.auth_type.as_deref()? {
+        "bearer" => Some(format!("Bearer {}", find(&auth.bearer, &["token", "Token"]))),
+        "basic" => Some(format!(
+            "Basic {} {}",
+            find(&auth.basic, &["username", "Username", "user", "User"]),
+            find(&auth.basic, &["password", "Password", "pass", "Pass"])
+        )),
+        _ => None,
+    }
+}
+
+/// Parses an `http-client.env.json` (JetBrains) or REST Client
+/// `environmentVariables` object. Values in `$shared` are added to every
+/// environment unless it overrides them.
+pub fn parse_http_environments(json: &str) -> Result<Vec<Environment>, String> {
+    let root: Map<String, Value> = serde_json::from_str(json).map_err(|e| format!("Invalid environment file: {}", e))?;
+    let to_variables = |value: &Value| -> Vec<Variable> {
+        value
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| Variable {
+                key: key.clone(),
+                value: match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                },
+                var_type: Some("string".to_string()),
+                disabled: None,
+                extra: Default::default(),
+            })
+            .collect()
+    };
+    let shared = root.get("$shared").map(to_variables).unwrap_or_default();
+
+    Ok(root
+        .iter()
+        .filter(|(name, _)| name.as_str() != "$shared")
+        .map(|(name, values)| {
+            let mut values = to_variables(values);
+            for variable in &shared {
+                if !values.iter().any(|v| v.key == variable.key) {
+                    values.push(variable.clone());
+                }
+            }
+            Environment {
+                id: None,
+                name: name.clone(),
+                values: Some(values),
+                postman_variable_scope: Some("environment".to_string()),
+                postman_exported_at: None,
+                postman_exported_using: None,
+                extra: Default::default(),
+            }
+        })
+        .collect())
+}
+
+/// Writes environments in the `http-client.env.json` shape, skipping
+/// disabled values.
+pub fn write_http_environments(environments: &[Environment]) -> String {
+    let root: Map<String, Value> = environments
+        .iter()
+        .map(|environment| {
+            let values: Map<String, Value> = environment
+                .values
+                .iter()
+                .flatten()
+                .filter(|v| !v.disabled.unwrap_or(false))
+                .map(|v| (v.key.clone(), Value::String(v.value.clone())))
+                .collect();
+            (environment.name.clone(), Value::Object(values))
+        })
+        .collect();
+    serde_json::to_string_pretty(&root).unwrap_or_default()
+}
+
+/// Parse an `.http` file into a collection. `name` (may be null) names the
+/// collection, e.g. after the file.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn http_file_to_collection_json(text: *const c_char, name: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let text = unsafe { ffi::arg(text, "text") }?;
+        let name = unsafe { ffi::optional_arg(name, "name") }?.unwrap_or("HTTP file");
+        Ok(parse_http_file(text, name))
+    })();
+    ffi::respond(result)
+}
+
+/// Write a collection as `.http` file text.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn collection_to_http_file(collection_json: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::arg(collection_json, "collection") }.and_then(|json| {
+        let collection = Collection::from_json(json).map_err(|e| FfiError::json("collection", &e))?;
+        write_http_file(&collection)
+            .map_err(|e| FfiError::new(ErrorCategory::Unsupported, "unsupported_content", e))
+    }))
+}
+
+/// Parse an `http-client.env.json` file into an array of environments.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn http_env_to_environments_json(env_json: *const c_char) -> *mut c_char {
+    ffi::respond(
+        unsafe { ffi::arg(env_json, "env_json") }
+            .and_then(|json| parse_http_environments(json).map_err(|e| FfiError::parse("environment file", e))),
+    )
+}
+
+/// Write a JSON array of environments as an `http-client.env.json` file.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn environments_to_http_env(environments_json: *const c_char) -> *mut c_char {
+    ffi::respond(
+        unsafe { ffi::json_arg::<Vec<Environment>>(environments_json, "environments") }
+            .map(|environments| write_http_environments(&environments)),
+    )
+}
@@ -0,0 +1,499 @@
+use crate::ffi::{self, FfiError};
+use crate::models::collection::{Collection, CollectionInfo, CollectionItem};
+use crate::models::environment::Environment;
+use crate::models::request::{
+    Auth, Body, Description, FileBody, FormData, GraphQLBody, Header, QueryParam, Request, Url,
+};
+use crate::models::variable::Variable;
+use serde_json::{json, Value};
+use std::os::raw::c_char;
+
+/// Parses an Insomnia v4 export (JSON or YAML) into a collection and one
+/// environment per sub-environment. An export with several workspaces
+/// becomes a collection with one folder per workspace.
+pub fn parse_insomnia(export_text: &str) -> Result<(Collection, Vec<Environment>), String> {
+    let export: Value = match serde_json::from_str(export_text) {
+        Ok(export) => export,
+        Err(_) => serde_yaml::from_str(export_text).map_err(|e| format!("Invalid Insomnia export: {}", e))?,
+    };
+
+    if export.get("_type").and_then(Value::as_str) != Some("export") {
+        return Err("Not an Insomnia export: missing '_type: export'".to_string());
+    }
+    match export.get("__export_format").and_then(Value::as_u64) {
+        Some(4) => {}
+        Some(other) => return Err(format!("Unsupported Insomnia export format {}", other)),
+        None => return Err("Not an Insomnia export: missing '__export_format'".to_string()),
+    }
+
+    let resources: Vec<&Value> = export
+        .get("resources")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().collect())
+        .unwrap_or_default();
+
+    let workspaces: Vec<&Value> = resources.iter().copied().filter(|r| kind(r) == "workspace").collect();
+    let (name, description, item, environments) = match workspaces.as_slice() {
+        [] => ("Insomnia import".to_string(), None, children(&resources, ""), environments(&resources, "")),
+        [workspace] => (
+            text(workspace, "name").to_string(),
+            description(workspace),
+            children(&resources, id(workspace)),
+            environments(&resources, id(workspace)),
+        ),
+        // Several workspaces become one top-level folder each; their
+        // environment names are prefixed with the workspace's name.
+        workspaces => {
+            let mut folders = Vec::new();
+            let mut all_environments = Vec::new();
+            for workspace in workspaces {
+                let workspace_name = text(workspace, "name");
+                folders.push(CollectionItem {
+                    name: workspace_name.to_string(),
+                    item: Some(children(&resources, id(workspace))),
+                    request: None,
+                    response: None,
+                    event: None,
+                    description: description(workspace),
+                    auth: None,
+                    variable: None,
+                    extra: Default::default(),
+                });
+                for mut environment in environments(&resources, id(workspace)) {
+                    environment.name = format!("{} / {}", workspace_name, environment.name);
+                    all_environments.push(environment);
+                }
+            }
+            ("Insomnia import".to_string(), None, folders, all_environments)
+        }
+    };
+
+    let collection = Collection {
+        info: CollectionInfo {
+            name: Some(name).filter(|n| !n.is_empty()).unwrap_or_else(|| "Insomnia import".to_string()),
+            description,
+            schema: Some("https://schema.getpostman.com/json/collection/v2.1.0/collection.json".to_string()),
+            postman_id: None,
+            exporter_id: None,
+            extra: Default::default(),
+        },
+        item,
+        variable: None,
+        event: None,
+        auth: None,
+        extra: Default::default(),
+    };
+
+    Ok((collection, environments))
+}
+
+fn kind(resource: &Value) -> &str {
+    resource.get("_type").and_then(Value::as_str).unwrap_or("")
+}
+
+fn id(resource: &Value) -> &str {
+    resource.get("_id").and_then(Value::as_str).unwrap_or("")
+}
+
+fn text<'a>(resource: &'a Value, key: &str) -> &'a str {
+    resource.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+fn description(resource: &Value) -> Option<Description> {
+    Some(text(resource, "description"))
+        .filter(|d| !d.is_empty())
+        .map(Description::from)
+}
+
+fn is_disabled(value: &Value) -> Option<bool> {
+    value.get("disabled").and_then(Value::as_bool).filter(|d| *d)
+}
+
+/// Request groups and requests under `parent_id`, in Insomnia's sidebar order.
+/// Without a workspace, resources whose parent is not a group are the roots.
+fn children(resources: &[&Value], parent_id: &str) -> Vec<CollectionItem> {
+    let group_ids: Vec<&str> = resources
+        .iter()
+        .filter(|r| kind(r) == "request_group")
+        .map(|r| id(r))
+        .collect();
+
+    let mut items: Vec<&Value> = resources
+        .iter()
+        .copied()
+        .filter(|r| matches!(kind(r), "request_group" | "request"))
+        .filter(|r| {
+            let parent = text(r, "parentId");
+            if parent_id.is_empty() {
+                !group_ids.contains(&parent)
+            } else {
+                parent == parent_id
+            }
+        })
+        .collect();
+    items.sort_by(|a, b| {
+        let key = |r: &Value| r.get("metaSortKey").and_then(Value::as_f64).unwrap_or(0.0);
+        key(a).total_cmp(&key(b))
+    });
+
+    items
+        .into_iter()
+        .map(|resource| {
+            if kind(resource) == "request_group" {
+                let variables = flatten_data(resource.get("environment"));
+                CollectionItem {
+                    name: text(resource, "name").to_string(),
+                    item: Some(children(resources, id(resource))),
+                    request: None,
+                    response: None,
+                    event: None,
+                    description: description(resource),
+                    auth: None,
+                    variable: if variables.is_empty() { None } else { Some(variables) },
+                    extra: Default::default(),
+                }
+            } else {
+                CollectionItem {
+                    name: text(resource, "name").to_string(),
+                    item: None,
+                    request: Some(to_request(resource)),
+                    response: None,
+                    event: None,
+                    description: description(resource),
+                    auth: None,
+                    variable: None,
+                    extra: Default::default(),
+                }
+            }
+        })
+        .collect()
+}
+
+fn to_request(resource: &Value) -> Request {
+    let mut url = Url::parse(&convert_template(text(resource, "url")));
+    let parameters: Vec<QueryParam> = resource
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|p| QueryParam {
+            key: convert_template(text(p, "name")),
+            value: Some(convert_template(text(p, "value"))),
+            disabled: is_disabled(p),
+            description: None,
+            extra: Default::default(),
+        })
+        .collect();
+    if !parameters.is_empty() {
+        url.query.get_or_insert_with(Vec::new).extend(parameters);
+        url.update_raw();
+    }
+
+    let mut headers: Vec<Header> = resource
+        .get("headers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|h| Header {
+            key: convert_template(text(h, "name")),
+            value: convert_template(text(h, "value")),
+            disabled: is_disabled(h),
+            description: None,
+            extra: Default::default(),
+        })
+        .collect();
+
+    let body = resource.get("body").and_then(|body| to_body(body, &mut headers));
+    let auth = resource.get("authentication").and_then(|auth| to_auth(auth, &mut headers));
+
+    Request {
+        method: Some(text(resource, "method").to_uppercase()).filter(|m| !m.is_empty()),
+        header: if headers.is_empty() { None } else { Some(headers) },
+        body,
+        url: Some(url),
+        description: None,
+        auth,
+        options: None,
+        extra: Default::default(),
+    }
+}
+
+fn to_body(body: &Value, headers: &mut Vec<Header>) -> Option<Body> {
+    let mime_type = text(body, "mimeType");
+    let fields = || -> Vec<FormData> {
+        body.get("params")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|p| {
+                let is_file = text(p, "type") == "file";
+                FormData {
+                    key: convert_template(text(p, "name")),
+                    value: Some(if is_file {
+                        text(p, "fileName").to_string()
+                    } else {
+                        convert_template(text(p, "value"))
+                    }),
+                    data_type: Some(if is_file { "file" } else { "text" }.to_string()),
+                    disabled: is_disabled(p),
+                    description: None,
+                    extra: Default::default(),
+                }
+            })
+            .collect()
+    };
+
+    let mut converted = Body::empty();
+    match mime_type {
+        "application/x-www-form-urlencoded" => {
+            converted.mode = Some("urlencoded".to_string());
+            converted.urlencoded = Some(fields());
+        }
+        "multipart/form-data" => {
+            converted.mode = Some("formdata".to_string());
+            converted.formdata = Some(fields());
+        }
+        "application/graphql" => {
+            let payload: Value = serde_json::from_str(text(body, "text")).unwrap_or(Value::Null);
+            converted.mode = Some("graphql".to_string());
+            converted.graphql = Some(GraphQLBody {
+                query: payload.get("query").and_then(Value::as_str).map(convert_template),
+                variables: payload
+                    .get("variables")
+                    .filter(|v| !v.is_null())
+                    .map(|v| serde_json::to_string_pretty(v).unwrap_or_default()),
+                extra: Default::default(),
+            });
+            // Insomnia stores this pseudo type; the request itself is sent as JSON.
+            for header in headers.iter_mut() {
+                if header.key.eq_ignore_ascii_case("content-type") && header.value == "application/graphql" {
+                    header.value = "application/json".to_string();
+                }
+            }
+        }
+        "application/octet-stream" if body.get("fileName").is_some() => {
+            converted.mode = Some("file".to_string());
+            converted.file = Some(FileBody {
+                src: Some(text(body, "fileName").to_string()),
+                extra: Default::default(),
+            });
+        }
+        _ => {
+            let raw = body.get("text").and_then(Value::as_str)?;
+            converted.mode = Some("raw".to_string());
+            converted.raw = Some(convert_template(raw));
+            if !mime_type.is_empty() && !headers.iter().any(|h| h.key.eq_ignore_ascii_case("content-type")) {
+                headers.push(Header {
+                    key: "Content-Type".to_string(),
+                    value: mime_type.to_string(),
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                });
+            }
+        }
+    }
+    Some(converted)
+}
+
+/// Bearer, basic, digest and OAuth 2 map to `Auth`. API keys have no
+/// `Auth` type and become a header (query placement is not supported).
+fn to_auth(auth: &Value, headers: &mut Vec<Header>) -> Option<Auth> {
+    if auth.get("disabled").and_then(Value::as_bool).unwrap_or(false) {
+        return None;
+    }
+    let field = |key: &str| convert_template(text(auth, key));
+    let var = |key: &str, value: String| Variable {
+        key: key.to_string(),
+        value,
+        var_type: Some("string".to_string()),
+        disabled: None,
+        extra: Default::default(),
+    };
+    let mut converted = Auth::new(text(auth, "type"));
+
+    match text(auth, "type") {
+        "bearer" => {
+            if !matches!(text(auth, "prefix"), "" | "Bearer") {
+                headers.push(Header {
+                    key: "Authorization".to_string(),
+                    value: format!("{} {}", text(auth, "prefix"), field("token")),
+                    disabled: None,
+                    description: None,
+                    extra: Default::default(),
+                });
+                return None;
+            }
+            converted.bearer = Some(vec![var("token", field("token"))]);
+        }
+        "basic" => {
+            converted.basic = Some(vec![var("username", field("username")), var("password", field("password"))]);
+        }
+        "digest" => {
+            converted.digest = Some(vec![var("username", field("username")), var("password", field("password"))]);
+        }
+        "oauth2" => {
+            let mut values = vec![
+                var("grant_type", field("grantType")),
+                var("accessTokenUrl", field("accessTokenUrl")),
+                var("authUrl", field("authorizationUrl")),
+                var("clientId", field("clientId")),
+                var("clientSecret", field("clientSecret")),
+                var("scope", field("scope")),
+            ];
+            values.retain(|v| !v.value.is_empty());
+            converted.oauth2 = Some(values);
+        }
+        "apikey" => {
+            headers.push(Header {
+                key: field("key"),
+                value: field("value"),
+                disabled: None,
+                description: None,
+                extra: Default::default(),
+            });
+            return None;
+        }
+        _ => return None,
+    }
+    Some(converted)
+}
+
+/// The base environment's values overlaid with each sub-environment's. An
+/// export with only a base environment yields that environment.
+fn environments(resources: &[&Value], root_id: &str) -> Vec<Environment> {
+    let environments: Vec<&Value> = resources.iter().copied().filter(|r| kind(r) == "environment").collect();
+    let Some(base) = environments
+        .iter()
+        .find(|e| root_id.is_empty() || text(e, "parentId") == root_id)
+    else {
+        return Vec::new();
+    };
+
+    let base_values = flatten_data(base.get("data"));
+    let subs: Vec<&Value> = environments
+        .iter()
+        .copied()
+        .filter(|e| text(e, "parentId") == id(base))
+        .collect();
+    if subs.is_empty() {
+        return vec![environment(base, base_values)];
+    }
+
+    subs.into_iter()
+        .map(|sub| {
+            let mut values = base_values.clone();
+            for value in flatten_data(sub.get("data")) {
+                values.retain(|v| v.key != value.key);
+                values.push(value);
+            }
+            environment(sub, values)
+        })
+        .collect()
+}
+
+fn environment(resource: &Value, values: Vec<Variable>) -> Environment {
+    Environment {
+        id: Some(id(resource).to_string()).filter(|i| !i.is_empty()),
+        name: text(resource, "name").to_string(),
+        values: Some(values),
+        postman_variable_scope: Some("environment".to_string()),
+        postman_exported_at: None,
+        postman_exported_using: None,
+        extra: Default::default(),
+    }
+}
+
+/// Environment data is a JSON object that may nest; nested keys are joined
+/// with dots to match `{{ _.a.b }}` references.
+fn flatten_data(data: Option<&Value>) -> Vec<Variable> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<Variable>) {
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    walk(&key, value, out);
+                }
+            }
+            Value::String(s) => out.push(variable(prefix, convert_template(s))),
+            Value::Null => out.push(variable(prefix, String::new())),
+            other => out.push(variable(prefix, other.to_string())),
+        }
+    }
+
+    let mut out = Vec::new();
+    if let Some(data @ Value::Object(_)) = data {
+        walk("", data, &mut out);
+    }
+    out
+}
+
+fn variable(key: &str, value: String) -> Variable {
+    Variable {
+        key: key.to_string(),
+        value,
+        var_type: Some("string".to_string()),
+        disabled: None,
+        extra: Default::default(),
+    }
+}
+
+/// Rewrites Insomnia's Nunjucks templates in Postman syntax: `{{ _.name }}`
+/// becomes `{{name}}`, `uuid` becomes `{{$guid}}`, `now` becomes
+/// `{{$isoTimestamp}}` or, with the `unix` format, `{{$timestamp}}`, and
+/// `timestamp` becomes `{{$timestamp}}`. Other tags, including `now` with a
+/// millisecond or custom format, are kept as written.
+pub fn convert_template(input: &str) -> String {
+    let mut out = String::new();
+    let mut rest = input;
+    loop {
+        let next_var = rest.find("{{");
+        let next_tag = rest.find("{%");
+        let (start, is_tag) = match (next_var, next_tag) {
+            (Some(v), Some(t)) if t < v => (t, true),
+            (Some(v), _) => (v, false),
+            (None, Some(t)) => (t, true),
+            (None, None) => break,
+        };
+        let close = if is_tag { "%}" } else { "}}" };
+        let Some(length) = rest[start + 2..].find(close) else {
+            break;
+        };
+        let inner = rest[start + 2..start + 2 + length].trim();
+        out.push_str(&rest[..start]);
+
+        if is_tag {
+            let (tag, args) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+            let format = args.split(',').next().unwrap_or("").trim().trim_matches(|c| c == '\'' || c == '"');
+            match (tag, format) {
+                ("uuid", _) => out.push_str("{{$guid}}"),
+                ("now", "" | "iso-8601") => out.push_str("{{$isoTimestamp}}"),
+                ("now", "unix") | ("timestamp", _) => out.push_str("{{$timestamp}}"),
+                _ => out.push_str(&rest[start..start + 4 + length]),
+            }
+        } else {
+            let name = inner.strip_prefix("_.").unwrap_or(inner);
+            let name = name
+                .strip_prefix("_['")
+                .and_then(|n| n.strip_suffix("']"))
+                .unwrap_or(name);
+            out.push_str("{{");
+            out.push_str(name);
+            out.push_str("}}");
+        }
+        rest = &rest[start + 4 + length..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Import an Insomnia v4 export (JSON or YAML). Returns
+/// `{"collection": {...}, "environments": [...]}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn insomnia_to_collection_json(export_text: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::arg(export_text, "export_text") }.and_then(|text| {
+        let (collection, environments) = parse_insomnia(text).map_err(|e| FfiError::parse("Insomnia export", e))?;
+        Ok(json!({ "collection": collection, "environments": environments }))
+    }))
+}
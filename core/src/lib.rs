@@ -1,225 +1,258 @@
-mod models;
+pub mod bruno;
+pub mod codegen;
+pub mod curl;
+mod decompress;
+pub mod examples;
+pub mod ffi;
+pub mod graphql;
+pub mod graphql_ws;
+pub mod grpc;
+pub mod har;
+pub mod history;
+pub mod http_file;
+pub mod insomnia;
+pub mod mock;
+pub mod models;
+pub mod openapi;
 mod postman;
+pub mod report;
+pub mod runner;
+pub mod scripts;
+pub mod soap;
+pub mod sse;
+pub mod validate;
+pub mod variables;
+pub mod websocket;
 
+use crate::ffi::{ErrorCategory, FfiError};
 use crate::models::request::{Request, Url, Body, Auth};
+use crate::models::url::form_encode;
 use serde::{Deserialize, Serialize};
-use std::ffi::{CStr, CString};
+use std::ffi::CString;
 use std::os::raw::c_char;
-use std::ptr;
 use base64::Engine;
 
-// Simple URL encoding helper
-fn url_encode(s: &str) -> String {
-    s.chars()
-        .map(|c| match c {
-            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '~' => c.to_string(),
-            ' ' => "+".to_string(),
-            _ => format!("%{:02X}", c as u8),
-        })
-        .collect()
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpResponse {
     pub status_code: u16,
     pub headers: Vec<(String, String)>,
     pub body: String,
     pub duration_ms: u64,
+    /// Body size in bytes as received, before `Content-Encoding` is undone.
+    #[serde(default)]
+    pub encoded_size: u64,
+    /// Body size in bytes after decompression. Equal to `encoded_size` when
+    /// the body was not compressed or decompression was disabled.
+    #[serde(default)]
+    pub decoded_size: u64,
+    /// The body bytes, base64-encoded, when they are not text in the
+    /// response's charset (binary content, or a compressed body with
+    /// decompression disabled), or could not be decompressed. `body` then holds a lossy UTF-8 rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_base64: Option<String>,
+    /// Set when the response was received but something about it could not
+    /// be handled, such as a body that failed to decompress and is returned
+    /// as received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
 
+/// Send a request. Returns an `HttpResponse` in the result envelope; network
+/// failures are classified (DNS, TLS, timeout, ...) in the error.
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn make_http_request(request_json: *const c_char) -> *mut c_char {
-    if request_json.is_null() {
-        return ptr::null_mut();
+    ffi::respond(unsafe { ffi::json_arg::<Request>(request_json, "request") }.and_then(|request| {
+        let start = std::time::Instant::now();
+        match execute_request_from_struct(&request) {
+            Ok(response) => {
+                let response = HttpResponse {
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    ..response
+                };
+                history::record_if_open(&request, environment_name(&request), Ok(&response));
+                Ok(response)
+            }
+            Err(e) => {
+                let url = build_url_from_struct(request.url.as_ref()).ok();
+                let error = FfiError::from_request_error(e.as_ref(), url.as_deref());
+                history::record_if_open(&request, environment_name(&request), Err(&error.message));
+                Err(error)
+            }
+        }
+    }))
+}
+
+/// A request with variables resolved, auth applied and the body rendered,
+/// ready to be sent by any of the clients.
+pub(crate) struct PreparedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl PreparedRequest {
+    pub fn new(request: &Request) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut headers: Vec<(String, String)> = request
+            .header
+            .iter()
+            .flatten()
+            .filter(|header| !header.disabled.unwrap_or(false))
+            .map(|header| (header.key.clone(), header.value.clone()))
+            .collect();
+        if let Some(auth) = &request.auth {
+            apply_auth(auth, &mut headers)?;
+        }
+
+        Ok(PreparedRequest {
+            method: request.method.as_deref().unwrap_or("GET").to_uppercase(),
+            url: build_url_from_struct(request.url.as_ref())?,
+            headers,
+            body: build_body_from_struct(request.body.as_ref())?,
+        })
     }
 
-    let json_str = unsafe {
-        match CStr::from_ptr(request_json).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
+    /// Adds a header unless the request already sets it.
+    pub fn default_header(&mut self, key: &str, value: &str) {
+        if !self.headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            self.headers.push((key.to_string(), value.to_string()));
         }
-    };
+    }
 
-    let request: Request = match serde_json::from_str(json_str) {
-        Ok(req) => req,
-        Err(e) => {
-            let error_response = HttpResponse {
-                status_code: 0,
-                headers: Vec::new(),
-                body: format!("Error parsing request: {}", e),
-                duration_ms: 0,
-            };
-            match serde_json::to_string(&error_response) {
-                Ok(json) => return CString::new(json).unwrap().into_raw(),
-                Err(_) => return ptr::null_mut(),
+    pub fn into_builder(self, client: &reqwest::Client) -> Result<reqwest::RequestBuilder, FfiError> {
+        let url = &self.url;
+        let mut request_builder = match self.method.as_str() {
+            "GET" => client.get(url),
+            "POST" => client.post(url),
+            "PUT" => client.put(url),
+            "PATCH" => client.patch(url),
+            "DELETE" => client.delete(url),
+            "HEAD" => client.head(url),
+            "OPTIONS" => client.request(reqwest::Method::OPTIONS, url),
+            method => {
+                let message = format!("Unsupported HTTP method '{}'", method);
+                return Err(FfiError::new(ErrorCategory::Unsupported, "unsupported_method", message));
             }
-        }
-    };
+        };
 
-    let start = std::time::Instant::now();
-    let response_result = execute_request_from_struct(&request);
-    
-    let response = match response_result {
-        Ok(resp) => resp,
-        Err(e) => HttpResponse {
-            status_code: 0,
-            headers: Vec::new(),
-            body: format!("Error: {}", e),
-            duration_ms: start.elapsed().as_millis() as u64,
-        },
-    };
+        for (key, value) in &self.headers {
+            request_builder = request_builder.header(key, value);
+        }
+        if let Some(body) = self.body {
+            request_builder = request_builder.body(body);
+        }
+        Ok(request_builder)
+    }
+}
 
-    let duration_ms = start.elapsed().as_millis() as u64;
-    let final_response = HttpResponse {
-        duration_ms,
-        ..response
-    };
+fn environment_name(request: &Request) -> Option<&str> {
+    request.options.as_ref().and_then(|options| options.environment.as_deref())
+}
 
-    match serde_json::to_string(&final_response) {
-        Ok(json) => CString::new(json).unwrap().into_raw(),
-        Err(_) => ptr::null_mut(),
-    }
+pub(crate) fn response_headers(response: &reqwest::Response) -> Vec<(String, String)> {
+    response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect()
 }
 
 fn execute_request_from_struct(
     request: &Request,
 ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
-    // Extract method
-    let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
-    
-    // Build URL from Url struct
-    let url_str = build_url_from_struct(request.url.as_ref())?;
-    
-    // Extract headers
-    let mut headers: Vec<(String, String)> = Vec::new();
-    if let Some(header_list) = &request.header {
-        for header in header_list {
-            if header.disabled.unwrap_or(false) {
-                continue;
-            }
-            headers.push((header.key.clone(), header.value.clone()));
-        }
-    }
-    
-    // Handle authentication
-    if let Some(auth) = &request.auth {
-        apply_auth(auth, &mut headers)?;
-    }
-    
-    // Build body
-    let body_content = build_body_from_struct(request.body.as_ref())?;
-    
+    let mut prepared = PreparedRequest::new(request)?;
+    graphql::check_before_sending(request, &prepared.url)?;
+    prepared.default_header("Accept-Encoding", decompress::ACCEPT_ENCODING);
+    let decompression = request.options.as_ref().and_then(|options| options.decompress).unwrap_or(true);
+
     // Execute request
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
         let client = reqwest::Client::new();
-        let mut request_builder = match method.as_str() {
-            "GET" => client.get(&url_str),
-            "POST" => client.post(&url_str),
-            "PUT" => client.put(&url_str),
-            "PATCH" => client.patch(&url_str),
-            "DELETE" => client.delete(&url_str),
-            "HEAD" => client.head(&url_str),
-            "OPTIONS" => client.request(reqwest::Method::OPTIONS, &url_str),
-            _ => return Err("Unsupported HTTP method".into()),
-        };
-
-        for (key, value) in &headers {
-            request_builder = request_builder.header(key, value);
-        }
-
-        if let Some(body_str) = body_content {
-            request_builder = request_builder.body(body_str);
-        }
-
-        let response = request_builder.send().await?;
+        let response = prepared.into_builder(&client)?.send().await?;
         let status_code = response.status().as_u16();
-        let response_headers: Vec<(String, String)> = response
-            .headers()
-            .iter()
-            .map(|(k, v): (&reqwest::header::HeaderName, &reqwest::header::HeaderValue)| {
-                (
-                    k.to_string(),
-                    v.to_str().unwrap_or("").to_string(),
-                )
-            })
-            .collect();
+        let response_headers = response_headers(&response);
 
-        let body_text = response.text().await?;
+        let encoded = response.bytes().await?.to_vec();
+        let encoded_size = encoded.len() as u64;
+        let content_encoding = header_value(&response_headers, "content-encoding");
+        // A body that cannot be decoded (a mislabelled or truncated stream)
+        // is returned as received, with a warning, rather than failing the
+        // request. An empty body, as in a 204 or HEAD response, has nothing
+        // to decode.
+        let (bytes, warning) = match content_encoding {
+            Some(coding) if decompression && !encoded.is_empty() => match decompress::decode(&encoded, coding) {
+                Ok(decoded) => (decoded, None),
+                Err(e) => (encoded, Some(e.message)),
+            },
+            _ => (encoded, None),
+        };
+        let (body, mut body_base64) = decode_text(&bytes, header_value(&response_headers, "content-type"));
+        if warning.is_some() && body_base64.is_none() {
+            body_base64 = Some(base64::engine::general_purpose::STANDARD.encode(&bytes));
+        }
 
         Ok(HttpResponse {
             status_code,
             headers: response_headers,
-            body: body_text,
+            body,
             duration_ms: 0, // Will be set by caller
+            encoded_size,
+            decoded_size: bytes.len() as u64,
+            body_base64,
+            warning,
         })
     })
 }
 
-fn build_url_from_struct(url: Option<&Url>) -> Result<String, Box<dyn std::error::Error>> {
-    let url = match url {
-        Some(u) => u,
-        None => return Err("URL is required".into()),
-    };
-    
-    // If raw URL is provided, use it directly
-    if let Some(raw) = &url.raw {
-        if !raw.is_empty() {
-            return Ok(raw.clone());
-        }
-    }
-    
-    // Otherwise, build from components
-    let mut url_string = String::new();
-    
-    // Protocol
-    if let Some(protocol) = &url.protocol {
-        url_string.push_str(protocol);
-        url_string.push_str("://");
-    } else {
-        url_string.push_str("http://");
-    }
-    
-    // Host
-    if let Some(host_parts) = &url.host {
-        url_string.push_str(&host_parts.join("."));
-    } else {
-        return Err("Host is required".into());
-    }
-    
-    // Path
-    if let Some(path_parts) = &url.path {
-        let path = path_parts.join("/");
-        if !path.is_empty() {
-            url_string.push_str("/");
-            url_string.push_str(&path);
-        }
-    }
-    
-    // Query parameters
-    if let Some(query_params) = &url.query {
-        let mut query_string = String::new();
-        for param in query_params {
-            if param.disabled.unwrap_or(false) {
-                continue;
-            }
-            if let Some(value) = &param.value {
-                if !query_string.is_empty() {
-                    query_string.push('&');
-                }
-                query_string.push_str(&format!("{}={}", 
-                    url_encode(&param.key),
-                    url_encode(value)
-                ));
-            }
-        }
-        if !query_string.is_empty() {
-            url_string.push('?');
-            url_string.push_str(&query_string);
-        }
-    }
-    
-    Ok(url_string)
+pub(crate) fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Decodes body bytes as text in the `charset` named by the content type
+/// (UTF-8 by default). Bytes that are not valid text are also returned as
+/// base64, so nothing is lost.
+fn decode_text(bytes: &[u8], content_type: Option<&str>) -> (String, Option<String>) {
+    let encoding = content_type
+        .and_then(|value| {
+            value.split(';').skip(1).find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches('"'))
+            })
+        })
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+
+    let (text, _, had_errors) = encoding.decode(bytes);
+    let base64 = had_errors.then(|| base64::engine::general_purpose::STANDARD.encode(bytes));
+    (text.into_owned(), base64)
+}
+
+/// Split a URL string into its parts (protocol, auth, host, port, path,
+/// query, hash), as stored in a request's `url`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn parse_url(raw: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::arg(raw, "raw") }.map(Url::parse))
+}
+
+/// Build a `url` object back into text. Returns `{"raw": ..., "encoded": ...}`:
+/// the address-bar form, and the percent-encoded form that is sent.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn build_url(url_json: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::json_arg::<Url>(url_json, "url") }.map(|url| {
+        serde_json::json!({ "raw": url.to_raw(), "encoded": url.to_request_url() })
+    }))
+}
+
+pub(crate) fn build_url_from_struct(url: Option<&Url>) -> Result<String, FfiError> {
+    url.and_then(Url::to_request_url)
+        .ok_or_else(|| FfiError::new(ErrorCategory::Argument, "missing_url", "URL is required"))
 }
 
 fn build_body_from_struct(body: Option<&Body>) -> Result<Option<String>, Box<dyn std::error::Error>> {
@@ -247,8 +280,8 @@ fn build_body_from_struct(body: Option<&Body>) -> Result<Option<String>, Box<dyn
                     }
                     if let Some(value) = &item.value {
                         pairs.push(format!("{}={}", 
-                            url_encode(&item.key),
-                            url_encode(value)
+                            form_encode(&item.key),
+                            form_encode(value)
                         ));
                     }
                 }
@@ -269,8 +302,8 @@ fn build_body_from_struct(body: Option<&Body>) -> Result<Option<String>, Box<dyn
                     }
                     if let Some(value) = &item.value {
                         pairs.push(format!("{}={}", 
-                            url_encode(&item.key),
-                            url_encode(value)
+                            form_encode(&item.key),
+                            form_encode(value)
                         ));
                     }
                 }
@@ -305,10 +338,14 @@ fn build_body_from_struct(body: Option<&Body>) -> Result<Option<String>, Box<dyn
     }
 }
 
-fn apply_auth(auth: &Auth, headers: &mut Vec<(String, String)>) -> Result<(), Box<dyn std::error::Error>> {
+/// Adds the `Authorization` (or API key) header for `auth`. Auth types that
+/// cannot be applied, and OAuth 2.0 without an access token, fail with an
+/// `auth` error rather than sending the request unauthenticated.
+fn apply_auth(auth: &Auth, headers: &mut Vec<(String, String)>) -> Result<(), FfiError> {
     let auth_type = auth.auth_type.as_deref().unwrap_or("noauth");
     
     match auth_type {
+        "noauth" | "inherit" => {}
         "bearer" => {
             if let Some(bearer_vars) = &auth.bearer {
                 for var in bearer_vars {
@@ -342,16 +379,63 @@ fn apply_auth(auth: &Auth, headers: &mut Vec<(String, String)>) -> Result<(), Bo
                 }
             }
         }
-        _ => {
-            // Other auth types (digest, awsv4, hawk, oauth1, oauth2, ntlm) would need more complex handling
-            // For now, we'll skip them
+        "oauth2" => {
+            let param = |key: &str| {
+                auth.oauth2
+                    .iter()
+                    .flatten()
+                    .find(|var| var.key == key)
+                    .map(|var| var.value.as_str())
+                    .filter(|value| !value.is_empty())
+            };
+            let token = param("accessToken").ok_or_else(|| {
+                FfiError::new(
+                    ErrorCategory::Auth,
+                    "missing_access_token",
+                    "OAuth 2.0 auth has no access token; request one and save it first",
+                )
+            })?;
+            if param("addTokenTo") == Some("queryParams") {
+                return Err(unsupported_auth("oauth2 with the token in the query string"));
+            }
+            let prefix = param("headerPrefix").unwrap_or("Bearer");
+            headers.push(("Authorization".to_string(), format!("{} {}", prefix, token)));
+        }
+        "apikey" => {
+            // `Auth` has no `apikey` field; the parameters stay in `extra`.
+            let param = |key: &str| {
+                auth.extra
+                    .get("apikey")
+                    .and_then(|params| params.as_array())
+                    .into_iter()
+                    .flatten()
+                    .find(|param| param["key"] == key)
+                    .and_then(|param| param["value"].as_str())
+            };
+            if param("in") == Some("query") {
+                return Err(unsupported_auth("apikey in the query string"));
+            }
+            let name = param("key").filter(|name| !name.is_empty()).unwrap_or("api_key");
+            headers.push((name.to_string(), param("value").unwrap_or_default().to_string()));
         }
+        // digest, awsv4, hawk, oauth1, ntlm, edgegrid and anything new
+        other => return Err(unsupported_auth(other)),
     }
     
     Ok(())
 }
 
+fn unsupported_auth(auth_type: &str) -> FfiError {
+    FfiError::new(
+        ErrorCategory::Auth,
+        "unsupported_auth",
+        format!("Auth type '{}' is not supported", auth_type),
+    )
+    .with_details(serde_json::json!({ "type": auth_type }))
+}
+
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn free_string(ptr: *mut c_char) {
     if !ptr.is_null() {
         unsafe {
@@ -0,0 +1,408 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::collection::{Collection, CollectionItem, Response};
+use crate::models::request::Request;
+use crate::models::url::{decode, form_decode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+static SERVERS: Mutex<Option<HashMap<u32, MockServer>>> = Mutex::new(None);
+
+/// Options for a mock server. Query, header and body matching are opt-in
+/// and only narrow the choice between examples on the same route.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockConfig {
+    pub host: String,
+    /// 0 picks a free port.
+    pub port: u16,
+    /// Delay added before every response.
+    pub latency_ms: u64,
+    pub match_query: bool,
+    pub match_headers: bool,
+    pub match_body: bool,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        MockConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            latency_ms: 0,
+            match_query: false,
+            match_headers: false,
+            match_body: false,
+        }
+    }
+}
+
+/// A saved example together with the request shape it is served for.
+#[derive(Debug, Clone)]
+struct Route {
+    method: String,
+    segments: Vec<String>,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    example: Response,
+}
+
+pub struct MockServer {
+    server: Arc<tiny_http::Server>,
+    thread: Option<JoinHandle<()>>,
+    pub port: u16,
+}
+
+impl MockServer {
+    /// Binds the configured address and starts serving the examples saved
+    /// in `collection` on a background thread.
+    pub fn start(collection: &Collection, config: MockConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut routes = Vec::new();
+        collect_routes(&collection.item, &mut routes);
+
+        let server = Arc::new(tiny_http::Server::http((config.host.as_str(), config.port))?);
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .ok_or("Mock server is not bound to an IP address")?;
+
+        let routes = Arc::new(routes);
+        let config = Arc::new(config);
+        let listener = Arc::clone(&server);
+        let thread = std::thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                let routes = Arc::clone(&routes);
+                let config = Arc::clone(&config);
+                std::thread::spawn(move || handle(request, &routes, &config));
+            }
+        });
+
+        Ok(MockServer {
+            server,
+            thread: Some(thread),
+            port,
+        })
+    }
+
+    pub fn stop(mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn collect_routes(items: &[CollectionItem], routes: &mut Vec<Route>) {
+    for item in items {
+        if let Some(children) = &item.item {
+            collect_routes(children, routes);
+        }
+        for example in item.response.iter().flatten() {
+            let request = example.original_request.as_ref().or(item.request.as_ref());
+            if let Some(request) = request {
+                routes.push(route_for(request, example.clone()));
+            }
+        }
+    }
+}
+
+fn route_for(request: &Request, example: Response) -> Route {
+    let url = request.url.as_ref();
+    let (segments, mut query) = match url.and_then(|u| u.path.as_ref()) {
+        Some(path) => (path.iter().filter(|s| !s.is_empty()).cloned().collect(), Vec::new()),
+        None => split_raw_url(url.and_then(|u| u.raw.as_deref()).unwrap_or("")),
+    };
+
+    if let Some(params) = url.and_then(|u| u.query.as_ref()) {
+        query = params
+            .iter()
+            .filter(|p| !p.disabled.unwrap_or(false))
+            .map(|p| (p.key.clone(), p.value.clone().unwrap_or_default()))
+            .collect();
+    }
+
+    let headers = request
+        .header
+        .iter()
+        .flatten()
+        .filter(|h| !h.disabled.unwrap_or(false))
+        .map(|h| (h.key.to_ascii_lowercase(), h.value.clone()))
+        .collect();
+
+    Route {
+        method: request.method.as_deref().unwrap_or("GET").to_uppercase(),
+        segments,
+        query,
+        headers,
+        body: request.body.as_ref().and_then(|b| b.raw.clone()),
+        example,
+    }
+}
+
+/// Splits a raw URL such as `{{baseUrl}}/users/:id?active=true` into path
+/// segments and query pairs, dropping the scheme and host.
+fn split_raw_url(raw: &str) -> (Vec<String>, Vec<(String, String)>) {
+    let without_fragment = raw.split('#').next().unwrap_or("");
+    let (path_part, query_part) = match without_fragment.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (without_fragment, None),
+    };
+
+    let mut path = path_part;
+    if let Some((_, rest)) = path.split_once("://") {
+        path = rest.find('/').map_or("", |i| &rest[i..]);
+    } else if !path.starts_with('/') {
+        // Host or `{{baseUrl}}` without a scheme.
+        path = path.find('/').map_or("", |i| &path[i..]);
+    }
+
+    let segments = path.split('/').filter(|s| !s.is_empty()).map(str::to_string).collect();
+    let query = query_part.map(parse_query).unwrap_or_default();
+    (segments, query)
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (form_decode(key), form_decode(value)),
+            None => (form_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+fn is_template_segment(segment: &str) -> bool {
+    segment.starts_with(':') || (segment.starts_with("{{") && segment.ends_with("}}"))
+}
+
+struct Incoming {
+    method: String,
+    segments: Vec<String>,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Incoming {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Scores how well `route` matches `incoming`; `None` means it does not
+/// match at all. Literal path segments outrank `:param` segments.
+fn score(route: &Route, incoming: &Incoming, config: &MockConfig) -> Option<u32> {
+    if route.method != incoming.method || route.segments.len() != incoming.segments.len() {
+        return None;
+    }
+
+    let mut score = 0;
+    for (expected, actual) in route.segments.iter().zip(&incoming.segments) {
+        if is_template_segment(expected) {
+            score += 1;
+        } else if expected == actual {
+            score += 2;
+        } else {
+            return None;
+        }
+    }
+
+    if let Some(name) = incoming.header("x-mock-response-name") {
+        if route.example.name.as_deref() != Some(name) {
+            return None;
+        }
+    }
+    if let Some(code) = incoming.header("x-mock-response-code") {
+        if route.example.code.map(|c| c.to_string()).as_deref() != Some(code.trim()) {
+            return None;
+        }
+    }
+
+    if config.match_query {
+        for (key, value) in &route.query {
+            if incoming.query.iter().any(|(k, v)| k == key && (v == value || value.starts_with("{{"))) {
+                score += 1;
+            } else {
+                return None;
+            }
+        }
+    }
+
+    if config.match_headers {
+        for (key, value) in &route.headers {
+            match incoming.header(key) {
+                Some(actual) if actual == value || value.starts_with("{{") => score += 1,
+                _ => return None,
+            }
+        }
+    }
+
+    if config.match_body {
+        if let Some(expected) = &route.body {
+            if !bodies_equal(expected, &incoming.body) {
+                return None;
+            }
+            score += 1;
+        }
+    }
+
+    // Prefer successful examples when several are otherwise equal.
+    if route.example.code.is_none_or(|c| (200..300).contains(&c)) {
+        score += 1;
+    }
+
+    Some(score)
+}
+
+fn bodies_equal(expected: &str, actual: &str) -> bool {
+    match (
+        serde_json::from_str::<serde_json::Value>(expected),
+        serde_json::from_str::<serde_json::Value>(actual),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => expected.trim() == actual.trim(),
+    }
+}
+
+fn handle(mut request: tiny_http::Request, routes: &[Route], config: &MockConfig) {
+    let url = request.url().to_string();
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path.to_string(), parse_query(query)),
+        None => (url.clone(), Vec::new()),
+    };
+
+    let mut body = String::new();
+    let _ = request.as_reader().read_to_string(&mut body);
+
+    let incoming = Incoming {
+        method: request.method().as_str().to_uppercase(),
+        segments: path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(decode)
+            .collect(),
+        query,
+        headers: request
+            .headers()
+            .iter()
+            .map(|h| (h.field.as_str().to_string(), h.value.as_str().to_string()))
+            .collect(),
+        body,
+    };
+
+    if config.latency_ms > 0 {
+        std::thread::sleep(Duration::from_millis(config.latency_ms));
+    }
+
+    let mut best: Option<(u32, &Route)> = None;
+    for route in routes {
+        if let Some(s) = score(route, &incoming, config) {
+            if best.is_none_or(|(best_score, _)| s > best_score) {
+                best = Some((s, route));
+            }
+        }
+    }
+
+    let response = match best {
+        Some((_, route)) => example_response(&route.example),
+        None => {
+            let body = serde_json::json!({
+                "error": "No matching example",
+                "method": incoming.method,
+                "path": path,
+            })
+            .to_string();
+            tiny_http::Response::from_string(body)
+                .with_status_code(404)
+                .with_header(header("Content-Type", "application/json"))
+        }
+    };
+
+    let _ = request.respond(response);
+}
+
+fn example_response(example: &Response) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let mut response = tiny_http::Response::from_string(example.body.clone().unwrap_or_default())
+        .with_status_code(example.code.unwrap_or(200));
+
+    for h in example.header.iter().flatten() {
+        if h.disabled.unwrap_or(false) {
+            continue;
+        }
+        // The body is served decoded and re-framed by the server.
+        let key = h.key.to_ascii_lowercase();
+        if key == "content-length" || key == "content-encoding" || key == "transfer-encoding" {
+            continue;
+        }
+        if let Ok(h) = tiny_http::Header::from_bytes(h.key.as_bytes(), h.value.as_bytes()) {
+            response.add_header(h);
+        }
+    }
+
+    response
+}
+
+fn header(key: &str, value: &str) -> tiny_http::Header {
+    tiny_http::Header::from_bytes(key.as_bytes(), value.as_bytes()).unwrap()
+}
+
+/// Start a mock server for the examples saved in a collection.
+///
+/// `config_json` may be null; see `MockConfig`. Returns
+/// `{"id": 1, "port": 54321}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mock_server_start(collection_json: *const c_char, config_json: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let collection = Collection::from_json(unsafe { ffi::arg(collection_json, "collection") }?)
+            .map_err(|e| FfiError::json("collection", &e))?;
+        let config: MockConfig = match unsafe { ffi::optional_arg(config_json, "config") }? {
+            Some(json) => serde_json::from_str(json).map_err(|e| FfiError::json("config", &e))?,
+            None => MockConfig::default(),
+        };
+        let address = format!("{}:{}", config.host, config.port);
+        let server = MockServer::start(&collection, config).map_err(|e| {
+            FfiError::new(ErrorCategory::Io, "bind_failed", format!("Cannot listen on {}: {}", address, e))
+                .with_details(json!({ "address": address }))
+        })?;
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let port = server.port;
+
+        let mut servers = SERVERS.lock().map_err(|_| FfiError::internal("Mock server registry is poisoned"))?;
+        servers.get_or_insert_with(HashMap::new).insert(id, server);
+        Ok(json!({ "id": id, "port": port }))
+    })();
+    ffi::respond(result)
+}
+
+/// Stop a mock server started with `mock_server_start`.
+#[no_mangle]
+pub extern "C" fn mock_server_stop(id: u32) -> *mut c_char {
+    let server = SERVERS
+        .lock()
+        .ok()
+        .and_then(|mut servers| servers.as_mut().and_then(|s| s.remove(&id)));
+
+    ffi::respond(match server {
+        Some(server) => {
+            server.stop();
+            Ok(true)
+        }
+        None => Err(FfiError::new(
+            ErrorCategory::Argument,
+            "mock_server_not_found",
+            format!("No mock server with id {}", id),
+        )),
+    })
+}
@@ -1,58 +1,96 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use super::request::{Event, Request, Header};
+use super::request::{Description, Event, Header, Request};
+use super::schema::SchemaVersion;
 use super::variable::Variable;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CollectionInfo {
     pub name: String,
-    pub description: Option<String>,
+    #[serde(default, deserialize_with = "super::de::description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<String>,
-    #[serde(rename = "_postman_id")]
+    #[serde(rename = "_postman_id", skip_serializing_if = "Option::is_none")]
     pub postman_id: Option<String>,
-    #[serde(rename = "_exporter_id")]
+    #[serde(rename = "_exporter_id", skip_serializing_if = "Option::is_none")]
     pub exporter_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CollectionItem {
+    #[serde(default)]
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub item: Option<Vec<CollectionItem>>,
+    #[serde(default, deserialize_with = "super::de::request", skip_serializing_if = "Option::is_none")]
     pub request: Option<Request>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub response: Option<Vec<Response>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub event: Option<Vec<Event>>,
-    pub description: Option<String>,
+    #[serde(default, deserialize_with = "super::de::description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub variable: Option<Vec<Variable>>,
+    /// Folder auth, inherited by requests inside the folder that have none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<super::request::Auth>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(default, deserialize_with = "super::de::request", skip_serializing_if = "Option::is_none")]
     pub original_request: Option<Request>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<u16>,
-    #[serde(rename = "_postman_previewlanguage")]
+    #[serde(rename = "_postman_previewlanguage", skip_serializing_if = "Option::is_none")]
     pub postman_previewlanguage: Option<String>,
+    #[serde(default, deserialize_with = "super::de::headers", skip_serializing_if = "Option::is_none")]
     pub header: Option<Vec<Header>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cookie: Option<Vec<Cookie>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
+    #[serde(default, deserialize_with = "super::de::optional_value", skip_serializing_if = "Option::is_none")]
     pub response_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timings: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Cookie {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub secure: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -60,18 +98,38 @@ pub struct Cookie {
 pub struct Collection {
     pub info: CollectionInfo,
     pub item: Vec<CollectionItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub variable: Option<Vec<Variable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub event: Option<Vec<Event>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub auth: Option<super::request::Auth>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Collection {
+    /// Parses a Postman collection. v1 and v2.0 exports are detected and
+    /// migrated to the v2.1 model.
     pub fn from_json(json_str: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json_str)
+        let value: serde_json::Value = serde_json::from_str(json_str)?;
+        serde_json::from_value(super::schema::migrate_to_v21(value))
     }
 
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
     }
+
+    /// Serializes in the given schema version. Only v2.1 and v2.0 can be
+    /// written; v1 is import-only.
+    pub fn to_json_version(&self, version: SchemaVersion) -> Result<String, serde_json::Error> {
+        match version {
+            SchemaVersion::V20 => {
+                let value = super::schema::convert_v21_to_v20(serde_json::to_value(self)?);
+                serde_json::to_string_pretty(&value)
+            }
+            SchemaVersion::V21 | SchemaVersion::V1 => self.to_json(),
+        }
+    }
 }
 
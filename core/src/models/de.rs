@@ -0,0 +1,161 @@
+//! Deserializers for fields the Postman schema allows in several shapes.
+//! Most accept every documented variant and normalize it to the one our
+//! models use. Descriptions and variable values are instead exported in the
+//! shape they were read in, so that a collection round-trips unchanged.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use super::request::{Description, Header, Request, Url};
+
+/// `"text"` or `{"content": "text", "type": "text/markdown", "version": ...}`.
+pub fn description<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Description>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(value @ (Value::String(_) | Value::Object(_))) => {
+            serde_json::from_value(value).map(Some).map_err(D::Error::custom)
+        }
+        Some(Value::Null) | None => Ok(None),
+        Some(other) => Err(D::Error::custom(format!("invalid description: {}", other))),
+    }
+}
+
+/// A raw URL string, parsed into its components, or a URL object.
+pub fn url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Url>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(raw)) => Ok(Some(Url::parse(&raw))),
+        Some(Value::Null) | None => Ok(None),
+        Some(value) => serde_json::from_value(value).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// A request object, or just its URL (which implies a GET).
+pub fn request<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Request>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(raw)) => Ok(Some(Request {
+            method: Some("GET".to_string()),
+            header: None,
+            body: None,
+            url: Some(Url::parse(&raw)),
+            description: None,
+            auth: None,
+            options: None,
+            extra: Default::default(),
+        })),
+        Some(Value::Null) | None => Ok(None),
+        Some(value) => serde_json::from_value(value).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// A list of header objects or `Key: value` strings, or a single string of
+/// such lines.
+pub fn headers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Header>>, D::Error> {
+    let items = match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(text)) => text.lines().map(|line| Value::String(line.to_string())).collect(),
+        Some(Value::Array(items)) => items,
+        Some(Value::Null) | None => return Ok(None),
+        Some(other) => return Err(D::Error::custom(format!("invalid header list: {}", other))),
+    };
+
+    let mut headers = Vec::new();
+    for item in items {
+        match item {
+            Value::String(line) => {
+                if let Some((key, value)) = line.split_once(':') {
+                    headers.push(Header {
+                        key: key.trim().to_string(),
+                        value: value.trim().to_string(),
+                        disabled: None,
+                        description: None,
+                        extra: Default::default(),
+                    });
+                }
+            }
+            value => headers.push(serde_json::from_value(value).map_err(D::Error::custom)?),
+        }
+    }
+    Ok(Some(headers))
+}
+
+/// `"api.example.com"` or `["api", "example", "com"]`.
+pub fn host<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    segments(deserializer, '.')
+}
+
+/// `"/users/:id"` or `["users", ":id"]`, where segments may also be
+/// `{"type": "string", "value": "users"}` objects.
+pub fn path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    segments(deserializer, '/')
+}
+
+fn segments<'de, D: Deserializer<'de>>(deserializer: D, separator: char) -> Result<Option<Vec<String>>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(text)) => {
+            let text = text.strip_prefix(separator).unwrap_or(&text);
+            Ok(Some(text.split(separator).map(str::to_string).collect()))
+        }
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(segment) => Ok(segment),
+                Value::Object(mut fields) => match fields.remove("value") {
+                    Some(Value::String(segment)) => Ok(segment),
+                    _ => Ok(String::new()),
+                },
+                other => Err(D::Error::custom(format!("invalid URL segment: {}", other))),
+            })
+            .collect::<Result<_, _>>()
+            .map(Some),
+        Some(Value::Null) | None => Ok(None),
+        Some(other) => Err(D::Error::custom(format!("invalid URL segments: {}", other))),
+    }
+}
+
+/// Script source as a list of lines or one string.
+pub fn lines<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(text)) => Ok(Some(text.split('\n').map(str::to_string).collect())),
+        Some(Value::Null) | None => Ok(None),
+        Some(value) => serde_json::from_value(value).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// Converts a variable or header `value` to our string form. Numbers and
+/// booleans become their JSON text and a missing or null value becomes
+/// empty; the original is then kept in `extra` for [`value_to_json`].
+pub fn value_from_json(value: Option<Value>, extra: &mut Map<String, Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text,
+        other => {
+            let original = other.unwrap_or(Value::Null);
+            let text = scalar(Some(original.clone())).unwrap_or_default();
+            extra.insert("value".to_string(), original);
+            text
+        }
+    }
+}
+
+/// Reverses [`value_from_json`], giving back the original value unless it
+/// has been changed since.
+pub fn value_to_json(value: String, extra: &mut Map<String, Value>) -> Option<Value> {
+    match extra.shift_remove("value") {
+        Some(Value::Null) if value.is_empty() => None,
+        Some(original) if scalar(Some(original.clone())).unwrap_or_default() == value => Some(original),
+        _ => Some(Value::String(value)),
+    }
+}
+
+/// Reads an optional string field that Postman may write as a number, such
+/// as `responseTime`. Numbers and booleans become their JSON text, as in
+/// [`value_from_json`], and a missing or null value stays `None`.
+pub fn optional_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(scalar(Option::<Value>::deserialize(deserializer)?))
+}
+
+fn scalar(value: Option<Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
@@ -1,18 +1,23 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use super::variable::Variable;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Environment {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub values: Option<Vec<Variable>>,
-    #[serde(rename = "_postman_variable_scope")]
+    #[serde(rename = "_postman_variable_scope", skip_serializing_if = "Option::is_none")]
     pub postman_variable_scope: Option<String>,
-    #[serde(rename = "_postman_exported_at")]
+    #[serde(rename = "_postman_exported_at", skip_serializing_if = "Option::is_none")]
     pub postman_exported_at: Option<String>,
-    #[serde(rename = "_postman_exported_using")]
+    #[serde(rename = "_postman_exported_using", skip_serializing_if = "Option::is_none")]
     pub postman_exported_using: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
@@ -1,5 +1,8 @@
 pub mod collection;
+mod de;
 pub mod environment;
 pub mod request;
+pub mod schema;
+pub mod url;
 pub mod variable;
 
@@ -0,0 +1,533 @@
+use serde_json::{json, Map, Value};
+
+use super::request::Url;
+
+pub const SCHEMA_V1: &str = "https://schema.getpostman.com/json/collection/v1.0.0/collection.json";
+pub const SCHEMA_V20: &str = "https://schema.getpostman.com/json/collection/v2.0.0/collection.json";
+pub const SCHEMA_V21: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Postman collection format versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaVersion {
+    V1,
+    V20,
+    V21,
+}
+
+impl SchemaVersion {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim_start_matches('v') {
+            "1" | "1.0" | "1.0.0" => Some(SchemaVersion::V1),
+            "2.0" | "2.0.0" => Some(SchemaVersion::V20),
+            "2.1" | "2.1.0" => Some(SchemaVersion::V21),
+            _ => None,
+        }
+    }
+
+    pub fn schema_url(self) -> &'static str {
+        match self {
+            SchemaVersion::V1 => SCHEMA_V1,
+            SchemaVersion::V20 => SCHEMA_V20,
+            SchemaVersion::V21 => SCHEMA_V21,
+        }
+    }
+}
+
+/// Detects the format from `info.schema`, falling back to the structure:
+/// v1 has top-level `requests`, and v2.0 stores auth parameters as objects
+/// rather than `{key, value}` arrays.
+pub fn detect(collection: &Value) -> SchemaVersion {
+    if let Some(schema) = collection.pointer("/info/schema").and_then(Value::as_str) {
+        if schema.contains("v2.1") {
+            return SchemaVersion::V21;
+        }
+        if schema.contains("v2.0") {
+            return SchemaVersion::V20;
+        }
+        if schema.contains("v1") {
+            return SchemaVersion::V1;
+        }
+    }
+
+    if collection.get("info").is_none() && (collection.get("requests").is_some() || collection.get("order").is_some()) {
+        return SchemaVersion::V1;
+    }
+    if has_object_auth(collection) {
+        SchemaVersion::V20
+    } else {
+        SchemaVersion::V21
+    }
+}
+
+fn has_object_auth(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => fields.iter().any(|(key, value)| {
+            (key == "auth" && is_object_auth(value)) || has_object_auth(value)
+        }),
+        Value::Array(items) => items.iter().any(has_object_auth),
+        _ => false,
+    }
+}
+
+fn is_object_auth(auth: &Value) -> bool {
+    auth.as_object().is_some_and(|fields| {
+        fields
+            .iter()
+            .any(|(key, value)| key != "type" && key != "noauth" && value.is_object())
+    })
+}
+
+/// Rewrites a collection of any supported version into the v2.1 shape.
+pub fn migrate_to_v21(collection: Value) -> Value {
+    match detect(&collection) {
+        SchemaVersion::V21 => collection,
+        SchemaVersion::V20 => migrate_v20(collection),
+        SchemaVersion::V1 => migrate_v1(&collection),
+    }
+}
+
+/// v2.0 differs from v2.1 in auth parameters (`{"token": "..."}` instead of
+/// `[{"key": "token", "value": "..."}]`); string URLs and requests are also
+/// expanded here.
+fn migrate_v20(mut collection: Value) -> Value {
+    walk_requests(&mut collection, &mut |request| {
+        if let Some(url @ Value::String(_)) = request.get_mut("url") {
+            *url = url_object(url.as_str().unwrap_or(""));
+        }
+    });
+    walk_auth(&mut collection, &mut |auth| {
+        if let Value::Object(fields) = auth {
+            for (key, params) in fields.iter_mut() {
+                if key != "type" {
+                    if let Value::Object(map) = params {
+                        *params = Value::Array(
+                            map.iter()
+                                .map(|(key, value)| {
+                                    // Values keep their JSON type, as in Postman's own v2.1 exports.
+                                    let value_type = match value {
+                                        Value::String(_) => "string",
+                                        Value::Bool(_) => "boolean",
+                                        Value::Number(_) => "number",
+                                        _ => "any",
+                                    };
+                                    json!({ "key": key, "value": value, "type": value_type })
+                                })
+                                .collect(),
+                        );
+                    }
+                }
+            }
+        }
+    });
+    if let Some(info) = collection.get_mut("info").and_then(Value::as_object_mut) {
+        info.insert("schema".to_string(), json!(SCHEMA_V21));
+    }
+    collection
+}
+
+/// The inverse of `migrate_v20`, for exporting to tools that only read v2.0.
+pub fn convert_v21_to_v20(mut collection: Value) -> Value {
+    walk_auth(&mut collection, &mut |auth| {
+        if let Value::Object(fields) = auth {
+            for (key, params) in fields.iter_mut() {
+                if key != "type" {
+                    if let Value::Array(list) = params {
+                        let map: Map<String, Value> = list
+                            .iter()
+                            .filter_map(|param| {
+                                let key = param.get("key")?.as_str()?.to_string();
+                                Some((key, param.get("value").cloned().unwrap_or(Value::Null)))
+                            })
+                            .collect();
+                        *params = Value::Object(map);
+                    }
+                }
+            }
+        }
+    });
+    if let Some(info) = collection.get_mut("info").and_then(Value::as_object_mut) {
+        info.insert("schema".to_string(), json!(SCHEMA_V20));
+    }
+    collection
+}
+
+/// Calls `f` on every request object, expanding requests given as a plain
+/// URL string first.
+fn walk_requests(value: &mut Value, f: &mut dyn FnMut(&mut Map<String, Value>)) {
+    match value {
+        Value::Object(fields) => {
+            for (key, child) in fields.iter_mut() {
+                if key == "request" || key == "originalRequest" {
+                    if let Value::String(url) = child {
+                        *child = json!({ "method": "GET", "url": url.clone() });
+                    }
+                    if let Value::Object(request) = child {
+                        f(request);
+                    }
+                }
+                walk_requests(child, f);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| walk_requests(item, f)),
+        _ => {}
+    }
+}
+
+fn walk_auth(value: &mut Value, f: &mut dyn FnMut(&mut Value)) {
+    match value {
+        Value::Object(fields) => {
+            for (key, child) in fields.iter_mut() {
+                if key == "auth" {
+                    f(child);
+                } else {
+                    walk_auth(child, f);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| walk_auth(item, f)),
+        _ => {}
+    }
+}
+
+fn url_object(raw: &str) -> Value {
+    serde_json::to_value(Url::parse(raw)).unwrap_or_else(|_| json!({ "raw": raw }))
+}
+
+/// Converts a v1 collection (flat `requests` and `folders` lists linked by
+/// `order`/`folders_order` ids) into a v2.1 item tree. Within a folder,
+/// subfolders come first, as in Postman's own converter. A request missing
+/// from every order list is appended to the folder named by its `folder`
+/// id, or at the root; unreferenced folders are appended at the root.
+fn migrate_v1(v1: &Value) -> Value {
+    let requests = v1.get("requests").and_then(Value::as_array).cloned().unwrap_or_default();
+    let folders = v1.get("folders").and_then(Value::as_array).cloned().unwrap_or_default();
+    // Every request id some order list places, so the rest can be filed by
+    // their `folder` field.
+    let mut used_requests: Vec<String> = std::iter::once(v1)
+        .chain(&folders)
+        .flat_map(|parent| ids(parent, "order"))
+        .map(str::to_string)
+        .collect();
+    let mut used_folders: Vec<String> = Vec::new();
+
+    let mut item = build_v1_items(v1, &requests, &folders, &mut used_requests, &mut used_folders);
+
+    // Folders nested in another folder's `folders_order` are not roots.
+    let nested: Vec<&str> = folders
+        .iter()
+        .flat_map(|f| ids(f, "folders_order"))
+        .collect();
+    for folder in &folders {
+        let id = str_field(folder, "id");
+        if !used_folders.iter().any(|used| used == id) && !nested.contains(&id) {
+            used_folders.push(id.to_string());
+            item.push(v1_folder(folder, &requests, &folders, &mut used_requests, &mut used_folders));
+        }
+    }
+    for request in &requests {
+        let id = str_field(request, "id");
+        if !used_requests.iter().any(|used| used == id) {
+            item.push(v1_request(request));
+        }
+    }
+
+    let mut info = Map::new();
+    info.insert("_postman_id".to_string(), v1.get("id").cloned().unwrap_or(Value::Null));
+    info.insert("name".to_string(), json!(str_field(v1, "name")));
+    if let Some(description) = v1.get("description").filter(|d| !d.is_null()) {
+        info.insert("description".to_string(), description.clone());
+    }
+    info.insert("schema".to_string(), json!(SCHEMA_V21));
+
+    let mut collection = Map::new();
+    collection.insert("info".to_string(), Value::Object(info));
+    collection.insert("item".to_string(), Value::Array(item));
+    if let Some(variables) = v1.get("variables").or_else(|| v1.get("variable")).filter(|v| v.is_array()) {
+        collection.insert("variable".to_string(), variables.clone());
+    }
+    if let Some(auth) = v1.get("auth").and_then(v1_auth) {
+        collection.insert("auth".to_string(), auth);
+    }
+    if let Some(events) = v1.get("events").filter(|e| e.is_array()) {
+        collection.insert("event".to_string(), events.clone());
+    }
+    Value::Object(collection)
+}
+
+fn build_v1_items(
+    parent: &Value,
+    requests: &[Value],
+    folders: &[Value],
+    used_requests: &mut Vec<String>,
+    used_folders: &mut Vec<String>,
+) -> Vec<Value> {
+    let mut items = Vec::new();
+    for id in ids(parent, "folders_order") {
+        if used_folders.iter().any(|used| used == id) {
+            continue;
+        }
+        if let Some(folder) = folders.iter().find(|f| str_field(f, "id") == id) {
+            used_folders.push(id.to_string());
+            items.push(v1_folder(folder, requests, folders, used_requests, used_folders));
+        }
+    }
+    for id in ids(parent, "order") {
+        if let Some(request) = requests.iter().find(|r| str_field(r, "id") == id) {
+            items.push(v1_request(request));
+        }
+    }
+    items
+}
+
+fn v1_folder(
+    folder: &Value,
+    requests: &[Value],
+    folders: &[Value],
+    used_requests: &mut Vec<String>,
+    used_folders: &mut Vec<String>,
+) -> Value {
+    let mut item = Map::new();
+    item.insert("name".to_string(), json!(str_field(folder, "name")));
+    if let Some(description) = folder.get("description").filter(|d| !d.is_null()) {
+        item.insert("description".to_string(), description.clone());
+    }
+    let mut children = build_v1_items(folder, requests, folders, used_requests, used_folders);
+    let id = str_field(folder, "id");
+    for request in requests.iter().filter(|r| str_field(r, "folder") == id) {
+        let request_id = str_field(request, "id");
+        if !used_requests.iter().any(|used| used == request_id) {
+            used_requests.push(request_id.to_string());
+            children.push(v1_request(request));
+        }
+    }
+    item.insert("item".to_string(), Value::Array(children));
+    Value::Object(item)
+}
+
+fn v1_request(v1: &Value) -> Value {
+    let mut request = Map::new();
+    request.insert("method".to_string(), json!(str_field(v1, "method").to_uppercase()));
+
+    let headers: Vec<Value> = match v1.get("headerData").and_then(Value::as_array) {
+        Some(data) => data.iter().map(v1_field).collect(),
+        None => str_field(v1, "headers")
+            .lines()
+            .filter_map(|line| {
+                let (line, disabled) = match line.trim().strip_prefix("//") {
+                    Some(line) => (line, true),
+                    None => (line.trim(), false),
+                };
+                let (key, value) = line.split_once(':')?;
+                let mut header = json!({ "key": key.trim(), "value": value.trim() });
+                if disabled {
+                    header["disabled"] = json!(true);
+                }
+                Some(header)
+            })
+            .collect(),
+    };
+    if !headers.is_empty() {
+        request.insert("header".to_string(), Value::Array(headers));
+    }
+
+    if let Some(body) = v1_body(v1) {
+        request.insert("body".to_string(), body);
+    }
+
+    let mut url = url_object(str_field(v1, "url"));
+    if let Some(query) = v1.get("queryParams").and_then(Value::as_array).filter(|q| !q.is_empty()) {
+        url["query"] = Value::Array(query.iter().map(v1_field).collect());
+    }
+    // Older exports only have the `pathVariables` name-to-value map.
+    if let Some(variables) = v1.get("pathVariableData").and_then(Value::as_array).filter(|v| !v.is_empty()) {
+        url["variable"] = Value::Array(variables.iter().map(v1_field).collect());
+    } else if let Some(variables) = v1.get("pathVariables").and_then(Value::as_object).filter(|v| !v.is_empty()) {
+        url["variable"] = Value::Array(
+            variables
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value }))
+                .collect(),
+        );
+    }
+    request.insert("url".to_string(), url);
+
+    if let Some(description) = v1.get("description").filter(|d| !d.is_null()) {
+        request.insert("description".to_string(), description.clone());
+    }
+    if let Some(auth) = v1_request_auth(v1) {
+        request.insert("auth".to_string(), auth);
+    }
+
+    let mut item = Map::new();
+    item.insert("name".to_string(), json!(str_field(v1, "name")));
+    item.insert("request".to_string(), Value::Object(request.clone()));
+
+    let events = v1_events(v1);
+    if !events.is_empty() {
+        item.insert("event".to_string(), Value::Array(events));
+    }
+
+    let responses: Vec<Value> = v1
+        .get("responses")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|response| v1_response(response, &request))
+        .collect();
+    if !responses.is_empty() {
+        item.insert("response".to_string(), Value::Array(responses));
+    }
+    Value::Object(item)
+}
+
+/// v1 key/value rows use `enabled` where v2 uses `disabled`.
+fn v1_field(field: &Value) -> Value {
+    let mut converted = json!({
+        "key": field.get("key").or_else(|| field.get("name")).cloned().unwrap_or(json!("")),
+        "value": field.get("value").cloned().unwrap_or(json!("")),
+    });
+    if field.get("enabled").and_then(Value::as_bool) == Some(false) {
+        converted["disabled"] = json!(true);
+    }
+    if let Some(description) = field.get("description").filter(|d| d.is_string()) {
+        converted["description"] = description.clone();
+    }
+    if let Some(field_type) = field.get("type").filter(|t| t.is_string()) {
+        converted["type"] = field_type.clone();
+    }
+    converted
+}
+
+fn v1_body(v1: &Value) -> Option<Value> {
+    // v1 exports set `dataMode: params` with empty data even on GETs.
+    let data = || -> Option<Value> {
+        let fields: Vec<Value> = v1.get("data")?.as_array()?.iter().map(v1_field).collect();
+        (!fields.is_empty()).then_some(Value::Array(fields))
+    };
+    match str_field(v1, "dataMode") {
+        "raw" => {
+            let raw = v1.get("rawModeData").and_then(Value::as_str).filter(|raw| !raw.is_empty())?;
+            Some(json!({ "mode": "raw", "raw": raw }))
+        }
+        "params" => Some(json!({ "mode": "formdata", "formdata": data()? })),
+        "urlencoded" => Some(json!({ "mode": "urlencoded", "urlencoded": data()? })),
+        "binary" => Some(json!({ "mode": "file", "file": { "src": null } })),
+        "graphql" => {
+            let graphql = v1.get("graphqlModeData")?;
+            let variables = match graphql.get("variables") {
+                Some(Value::String(s)) => json!(s),
+                Some(Value::Null) | None => Value::Null,
+                Some(other) => json!(other.to_string()),
+            };
+            Some(json!({
+                "mode": "graphql",
+                "graphql": { "query": graphql.get("query").cloned().unwrap_or(Value::Null), "variables": variables }
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Newer v1 exports carry a v2-style `auth`; older ones use `currentHelper`
+/// with `helperAttributes`.
+fn v1_request_auth(v1: &Value) -> Option<Value> {
+    if let Some(auth) = v1.get("auth").and_then(v1_auth) {
+        return Some(auth);
+    }
+    let attributes = v1.get("helperAttributes");
+    let attribute = |key: &str| attributes.and_then(|a| a.get(key)).cloned().unwrap_or(json!(""));
+    match str_field(v1, "currentHelper") {
+        "bearerAuth" => Some(json!({
+            "type": "bearer",
+            "bearer": [{ "key": "token", "value": attribute("token"), "type": "string" }]
+        })),
+        "basicAuth" => Some(json!({
+            "type": "basic",
+            "basic": [
+                { "key": "username", "value": attribute("username"), "type": "string" },
+                { "key": "password", "value": attribute("password"), "type": "string" }
+            ]
+        })),
+        "digestAuth" => Some(json!({
+            "type": "digest",
+            "digest": [
+                { "key": "username", "value": attribute("username"), "type": "string" },
+                { "key": "password", "value": attribute("password"), "type": "string" }
+            ]
+        })),
+        _ => None,
+    }
+}
+
+fn v1_auth(auth: &Value) -> Option<Value> {
+    auth.get("type")?;
+    // Parameters may be arrays already (v2.1 style) or objects (v2.0 style).
+    if is_object_auth(auth) {
+        let mut wrapped = migrate_v20(json!({ "auth": auth }));
+        Some(wrapped["auth"].take())
+    } else {
+        Some(auth.clone())
+    }
+}
+
+fn v1_events(v1: &Value) -> Vec<Value> {
+    if let Some(events) = v1.get("events").and_then(Value::as_array) {
+        return events.clone();
+    }
+    let mut events = Vec::new();
+    for (field, listen) in [("preRequestScript", "prerequest"), ("tests", "test")] {
+        if let Some(script) = v1.get(field).and_then(Value::as_str).filter(|s| !s.trim().is_empty()) {
+            events.push(json!({
+                "listen": listen,
+                "script": { "type": "text/javascript", "exec": script.lines().collect::<Vec<_>>() }
+            }));
+        }
+    }
+    events
+}
+
+fn v1_response(v1: &Value, request: &Map<String, Value>) -> Value {
+    let headers: Vec<Value> = v1
+        .get("headers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(v1_field)
+        .collect();
+    let code = v1
+        .pointer("/responseCode/code")
+        .and_then(Value::as_u64)
+        .or_else(|| v1.get("code").and_then(Value::as_u64));
+    let response_time = match v1.get("time") {
+        Some(Value::Number(n)) => json!(n.to_string()),
+        Some(Value::String(s)) => json!(s),
+        _ => Value::Null,
+    };
+
+    json!({
+        "name": v1.get("name").cloned().unwrap_or(Value::Null),
+        "originalRequest": match v1.get("request") {
+            Some(Value::Object(_)) => v1_request(&v1["request"])["request"].clone(),
+            _ => Value::Object(request.clone()),
+        },
+        "status": v1.pointer("/responseCode/name").cloned().unwrap_or(Value::Null),
+        "code": code,
+        "header": headers,
+        "body": v1.get("text").cloned().unwrap_or(Value::Null),
+        "responseTime": response_time,
+    })
+}
+
+fn ids<'a>(value: &'a Value, key: &str) -> Vec<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or("")
+}
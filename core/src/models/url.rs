@@ -0,0 +1,332 @@
+//! Parsing and building of request URLs.
+//!
+//! A `Url` holds its parts as they are written, unencoded, with `{{name}}`
+//! references intact; `raw` is the same URL as one string. `Url::parse` and
+//! `Url::to_raw` convert between the two, and `Url::to_request_url` produces
+//! the percent-encoded form (RFC 3986) that is sent.
+
+use super::request::{QueryParam, Url, UrlAuth};
+
+// Characters allowed unencoded in each component, besides the unreserved
+// `A-Z a-z 0-9 - . _ ~`. `%XX` sequences are always kept as they are.
+const USER: &[u8] = b"!$&'()*+,;=";
+const PASSWORD: &[u8] = b"!$&'()*+,;=:";
+const PATH_SEGMENT: &[u8] = b"!$&'()*+,;=:@";
+const QUERY_KEY: &[u8] = b"!$'()*+,;:@/?";
+const QUERY_VALUE: &[u8] = b"!$'()*+,;=:@/?";
+const FRAGMENT: &[u8] = b"!$&'()*+,;=:@/?";
+
+impl Url {
+    /// Splits a URL string into its parts, keeping `raw` as given. Delimiters
+    /// inside `{{...}}` references are not treated as delimiters.
+    pub fn parse(raw: &str) -> Url {
+        let (rest, hash) = split_first(raw, b'#');
+        let (rest, query) = split_first(rest, b'?');
+        let (protocol, rest) = match rest.find("://") {
+            Some(i) if !rest[..i].contains('/') => (Some(rest[..i].to_string()), &rest[i + 3..]),
+            _ => (None, rest),
+        };
+        let (authority, path) = match delimiters(rest, b'/').first() {
+            Some(&i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+        let (auth, host_port) = match delimiters(authority, b'@').last() {
+            Some(&i) => {
+                let (user, password) = split_first(&authority[..i], b':');
+                let auth = UrlAuth {
+                    user: Some(user.to_string()),
+                    password: password.map(str::to_string),
+                    extra: Default::default(),
+                };
+                (Some(auth), &authority[i + 1..])
+            }
+            None => (None, authority),
+        };
+        let (host, port) = split_port(host_port);
+
+        Url {
+            raw: Some(raw.to_string()),
+            protocol,
+            auth,
+            host: if host.is_empty() { None } else { Some(split(host, b'.')) },
+            port: port.map(str::to_string),
+            path: match path {
+                Some(path) if !path.is_empty() => Some(split(path, b'/')),
+                _ => None,
+            },
+            query: query.map(|query| {
+                split(query, b'&')
+                    .into_iter()
+                    .filter(|pair| !pair.is_empty())
+                    .map(|pair| {
+                        let (key, value) = split_first(&pair, b'=');
+                        QueryParam {
+                            key: key.to_string(),
+                            value: value.map(str::to_string),
+                            disabled: None,
+                            description: None,
+                            extra: Default::default(),
+                        }
+                    })
+                    .collect()
+            }),
+            hash: hash.map(str::to_string),
+            variable: None,
+            extra: Default::default(),
+        }
+    }
+
+    /// Whether the URL is described by its parts, rather than only by `raw`.
+    pub fn has_parts(&self) -> bool {
+        self.host.is_some() || self.path.is_some()
+    }
+
+    /// Joins the parts back into a URL string, unencoded, as Postman shows it
+    /// in the address bar. Disabled query parameters are left out. A URL given
+    /// only as `raw` is returned as is.
+    pub fn to_raw(&self) -> String {
+        if !self.has_parts() {
+            return self.raw.clone().unwrap_or_default();
+        }
+        self.join(|text, _| text.to_string(), None)
+    }
+
+    /// Regenerates `raw` from the parts, after they have been edited.
+    pub fn update_raw(&mut self) {
+        self.raw = Some(self.to_raw());
+    }
+
+    /// The URL to send: built from the parts (or parsed from `raw` if there
+    /// are none), with `:name` path segments replaced by the path variable
+    /// of that name and each component percent-encoded as UTF-8. Encoded
+    /// sequences already present are kept, so encoding is idempotent. A URL
+    /// without a scheme gets `http://`, unless its host is a `{{variable}}`
+    /// or already carries one. Returns `None` when there is no URL at all.
+    pub fn to_request_url(&self) -> Option<String> {
+        let mut url = if self.has_parts() {
+            self.clone()
+        } else {
+            Url {
+                variable: self.variable.clone(),
+                ..Url::parse(self.raw.as_deref().unwrap_or("").trim())
+            }
+        };
+        if !url.has_parts() {
+            return None;
+        }
+        url.substitute_path_variables();
+
+        let host = url.host.as_ref().map(|host| host.join(".")).unwrap_or_default();
+        let default_scheme = if host.contains("://") || host.starts_with("{{") || host.is_empty() {
+            None
+        } else {
+            Some("http")
+        };
+        Some(url.join(encode, default_scheme))
+    }
+
+    /// Replaces each `:name` path segment with the value of the enabled path
+    /// variable `name`. Segments without a variable, or whose variable is
+    /// empty, are sent as written.
+    fn substitute_path_variables(&mut self) {
+        let variables = match &self.variable {
+            Some(variables) => variables,
+            None => return,
+        };
+        for segment in self.path.iter_mut().flatten() {
+            let name = match segment.strip_prefix(':') {
+                Some(name) => name,
+                None => continue,
+            };
+            let value = variables
+                .iter()
+                .find(|var| var.key == name && !var.disabled.unwrap_or(false))
+                .map(|var| &var.value)
+                .filter(|value| !value.is_empty());
+            if let Some(value) = value {
+                *segment = value.clone();
+            }
+        }
+    }
+
+    fn join(&self, encode: impl Fn(&str, &[u8]) -> String, default_scheme: Option<&str>) -> String {
+        let mut out = String::new();
+        let host = self.host.as_ref().map(|host| host.join(".")).unwrap_or_default();
+
+        // A host variable resolved to a base URL brings its own scheme.
+        if !host.contains("://") {
+            if let Some(protocol) = self.protocol.as_deref().or(default_scheme) {
+                out.push_str(protocol);
+                out.push_str("://");
+            }
+        }
+        if let Some(auth) = &self.auth {
+            out.push_str(&encode(auth.user.as_deref().unwrap_or(""), USER));
+            if let Some(password) = &auth.password {
+                out.push(':');
+                out.push_str(&encode(password, PASSWORD));
+            }
+            out.push('@');
+        }
+        out.push_str(&host);
+        if let Some(port) = &self.port {
+            out.push(':');
+            out.push_str(port);
+        }
+        for segment in self.path.iter().flatten() {
+            out.push('/');
+            out.push_str(&encode(segment, PATH_SEGMENT));
+        }
+
+        let query: Vec<String> = self
+            .query
+            .iter()
+            .flatten()
+            .filter(|param| !param.disabled.unwrap_or(false))
+            .filter(|param| !param.key.is_empty() || param.value.is_some())
+            .map(|param| match &param.value {
+                Some(value) => format!("{}={}", encode(&param.key, QUERY_KEY), encode(value, QUERY_VALUE)),
+                None => encode(&param.key, QUERY_KEY),
+            })
+            .collect();
+        if !query.is_empty() {
+            out.push('?');
+            out.push_str(&query.join("&"));
+        }
+        if let Some(hash) = &self.hash {
+            out.push('#');
+            out.push_str(&encode(hash, FRAGMENT));
+        }
+        out
+    }
+}
+
+/// Percent-encodes the UTF-8 bytes of `text` that are neither unreserved nor
+/// in `allowed`. Existing `%XX` sequences and `{{name}}` references are kept.
+pub fn encode(text: &str, allowed: &[u8]) -> String {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if byte == b'{' {
+            if let Some(len) = reference_len(&text[i..]) {
+                out.push_str(&text[i..i + len]);
+                i += len;
+                continue;
+            }
+        }
+        let escaped = byte == b'%' && bytes.len() > i + 2 && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit();
+        if escaped || is_unreserved(byte) || allowed.contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Encodes `text` for an `application/x-www-form-urlencoded` body: spaces
+/// become `+` and everything but unreserved characters is escaped.
+pub fn form_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b' ' => "+".to_string(),
+            _ if is_unreserved(byte) => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Decodes `%XX` sequences in `text` as UTF-8 (lossily). A `%` not
+/// followed by two hex digits is kept as it is.
+pub fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes[i] == b'%' && bytes.len() > i + 2 && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit();
+        if escaped {
+            out.push(hex_value(bytes[i + 1]) << 4 | hex_value(bytes[i + 2]));
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Decodes an `application/x-www-form-urlencoded` key or value: `+` is a
+/// space, then `%XX` sequences are decoded as by `decode`.
+pub fn form_decode(text: &str) -> String {
+    decode(&text.replace('+', " "))
+}
+
+fn hex_value(digit: u8) -> u8 {
+    (digit as char).to_digit(16).unwrap_or(0) as u8
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// The length of the `{{...}}` reference `text` starts with, if any.
+fn reference_len(text: &str) -> Option<usize> {
+    if !text.starts_with("{{") {
+        return None;
+    }
+    text[2..].find("}}").map(|end| end + 4)
+}
+
+/// Byte offsets of `delimiter` in `text`, skipping `{{...}}` references.
+fn delimiters(text: &str, delimiter: u8) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' {
+            if let Some(len) = reference_len(&text[i..]) {
+                i += len;
+                continue;
+            }
+        }
+        if bytes[i] == delimiter {
+            found.push(i);
+        }
+        i += 1;
+    }
+    found
+}
+
+fn split(text: &str, delimiter: u8) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in delimiters(text, delimiter) {
+        parts.push(text[start..i].to_string());
+        start = i + 1;
+    }
+    parts.push(text[start..].to_string());
+    parts
+}
+
+fn split_first(text: &str, delimiter: u8) -> (&str, Option<&str>) {
+    match delimiters(text, delimiter).first() {
+        Some(&i) => (&text[..i], Some(&text[i + 1..])),
+        None => (text, None),
+    }
+}
+
+/// Separates `host:port`, allowing for bracketed IPv6 hosts like `[::1]:80`.
+fn split_port(authority: &str) -> (&str, Option<&str>) {
+    let search_from = if authority.starts_with('[') {
+        authority.find(']').unwrap_or(0)
+    } else {
+        0
+    };
+    match delimiters(&authority[search_from..], b':').last() {
+        Some(&i) => (&authority[..search_from + i], Some(&authority[search_from + i + 1..])),
+        None => (authority, None),
+    }
+}
@@ -1,12 +1,56 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
+#[serde(from = "RawVariable", into = "RawVariable")]
 pub struct Variable {
     pub key: String,
     pub value: String,
-    #[serde(rename = "type")]
     pub var_type: Option<String>,
     pub disabled: Option<bool>,
+    /// Unknown fields. A `value` that was missing or not a string (Postman
+    /// writes numbers and booleans as they are) is kept here too, so it is
+    /// exported the way it was imported.
+    pub extra: Map<String, Value>,
+}
+
+/// [`Variable`] as it is written in a collection or environment.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawVariable {
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<Value>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    var_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disabled: Option<bool>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
 }
 
+impl From<RawVariable> for Variable {
+    fn from(raw: RawVariable) -> Self {
+        let mut extra = raw.extra;
+        Variable {
+            key: raw.key,
+            value: super::de::value_from_json(raw.value, &mut extra),
+            var_type: raw.var_type,
+            disabled: raw.disabled,
+            extra,
+        }
+    }
+}
+
+impl From<Variable> for RawVariable {
+    fn from(variable: Variable) -> Self {
+        let mut extra = variable.extra;
+        RawVariable {
+            key: variable.key,
+            value: super::de::value_to_json(variable.value, &mut extra),
+            var_type: variable.var_type,
+            disabled: variable.disabled,
+            extra,
+        }
+    }
+}
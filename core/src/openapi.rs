@@ -0,0 +1,1242 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::collection::{Collection, CollectionInfo, CollectionItem};
+use crate::models::request::{Auth, Body, Description, FormData, Header, QueryParam, Request, Url};
+use crate::models::variable::Variable;
+use serde_json::{json, Map, Value};
+use std::os::raw::c_char;
+
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Limit on nested `$ref`s when generating examples from schemas.
+const MAX_SCHEMA_DEPTH: usize = 16;
+
+/// Parses an OpenAPI document from JSON or YAML.
+pub fn parse_spec(text: &str) -> Result<Value, String> {
+    let spec: Value = match serde_json::from_str(text) {
+        Ok(spec) => spec,
+        Err(_) => serde_yaml::from_str(text).map_err(|e| format!("Invalid OpenAPI document: {}", e))?,
+    };
+
+    match spec.get("openapi").map(value_to_string).as_deref() {
+        Some(version) if version.starts_with("3.") => Ok(spec),
+        _ if spec.get("swagger").is_some() => Err("Swagger 2.0 documents are not supported".to_string()),
+        Some(version) => Err(format!("Unsupported OpenAPI version '{}'", version)),
+        None => Err("Not an OpenAPI 3 document: missing 'openapi' field".to_string()),
+    }
+}
+
+/// Follows local `$ref`s (`#/components/...`) until a non-reference value is
+/// reached. External references are left unresolved.
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut current = value;
+    for _ in 0..32 {
+        match current.get("$ref").and_then(Value::as_str) {
+            Some(reference) if reference.starts_with('#') => match spec.pointer(&reference[1..]) {
+                Some(target) => current = target,
+                None => return current,
+            },
+            _ => return current,
+        }
+    }
+    current
+}
+
+/// Converts an OpenAPI 3.0/3.1 document into a collection. Operations are
+/// grouped into one folder per (first) tag; untagged operations stay at the
+/// root. Requests use `{{baseUrl}}`, set from the first server.
+pub fn openapi_to_collection(spec: &Value) -> Collection {
+    let info = spec.get("info").cloned().unwrap_or(Value::Null);
+
+    let mut variables = server_variables(spec);
+    let collection_auth = spec
+        .get("security")
+        .and_then(|security| security_to_auth(spec, security, &mut variables));
+
+    let mut folders: Vec<CollectionItem> = spec
+        .get("tags")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| {
+            Some(folder(
+                tag.get("name")?.as_str()?,
+                tag.get("description").and_then(Value::as_str),
+            ))
+        })
+        .collect();
+    let mut root_items = Vec::new();
+
+    let paths = spec.get("paths").and_then(Value::as_object);
+    for (path, path_item) in paths.into_iter().flatten() {
+        let path_item = resolve(spec, path_item);
+        for method in METHODS {
+            let Some(operation) = path_item.get(method) else {
+                continue;
+            };
+            let item = operation_to_item(spec, path, method, path_item, operation, &mut variables);
+
+            match operation
+                .get("tags")
+                .and_then(|tags| tags.get(0))
+                .and_then(Value::as_str)
+            {
+                Some(tag) => {
+                    let index = match folders.iter().position(|f| f.name == tag) {
+                        Some(index) => index,
+                        None => {
+                            folders.push(folder(tag, None));
+                            folders.len() - 1
+                        }
+                    };
+                    folders[index].item.get_or_insert_with(Vec::new).push(item);
+                }
+                None => root_items.push(item),
+            }
+        }
+    }
+
+    // Tags declared at the top level but never used would be empty folders.
+    folders.retain(|f| f.item.as_ref().is_some_and(|items| !items.is_empty()));
+    folders.extend(root_items);
+
+    Collection {
+        info: CollectionInfo {
+            name: info
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("OpenAPI import")
+                .to_string(),
+            description: info.get("description").and_then(Value::as_str).map(Description::from),
+            schema: Some("https://schema.getpostman.com/json/collection/v2.1.0/collection.json".to_string()),
+            postman_id: None,
+            exporter_id: None,
+            extra: Default::default(),
+        },
+        item: folders,
+        variable: if variables.is_empty() { None } else { Some(variables) },
+        event: None,
+        auth: collection_auth,
+        extra: Default::default(),
+    }
+}
+
+fn folder(name: &str, description: Option<&str>) -> CollectionItem {
+    CollectionItem {
+        name: name.to_string(),
+        item: Some(Vec::new()),
+        request: None,
+        response: None,
+        event: None,
+        description: description.map(Description::from),
+        auth: None,
+        variable: None,
+        extra: Default::default(),
+    }
+}
+
+fn variable(key: &str, value: &str) -> Variable {
+    Variable {
+        key: key.to_string(),
+        value: value.to_string(),
+        var_type: Some("string".to_string()),
+        disabled: None,
+        extra: Default::default(),
+    }
+}
+
+fn add_variable(variables: &mut Vec<Variable>, key: &str, value: &str) {
+    if !variables.iter().any(|v| v.key == key) {
+        variables.push(variable(key, value));
+    }
+}
+
+/// `baseUrl` from the first server, with `{name}` server variables turned
+/// into `{{name}}` collection variables holding their defaults.
+fn server_variables(spec: &Value) -> Vec<Variable> {
+    let mut variables = Vec::new();
+    let Some(server) = spec.get("servers").and_then(|s| s.get(0)) else {
+        return variables;
+    };
+
+    let url = server.get("url").and_then(Value::as_str).unwrap_or("");
+    add_variable(
+        &mut variables,
+        "baseUrl",
+        url.trim_end_matches('/').replace('{', "{{").replace('}', "}}").as_str(),
+    );
+    for (name, definition) in server.get("variables").and_then(Value::as_object).into_iter().flatten() {
+        let default = definition.get("default").map(value_to_string).unwrap_or_default();
+        add_variable(&mut variables, name, &default);
+    }
+    variables
+}
+
+fn operation_to_item(
+    spec: &Value,
+    path: &str,
+    method: &str,
+    path_item: &Value,
+    operation: &Value,
+    variables: &mut Vec<Variable>,
+) -> CollectionItem {
+    // Operation parameters override path-level ones with the same name and location.
+    let mut parameters: Vec<&Value> = Vec::new();
+    let declared = [path_item.get("parameters"), operation.get("parameters")];
+    for parameter in declared.into_iter().flatten().filter_map(Value::as_array).flatten() {
+        let parameter = resolve(spec, parameter);
+        let key = (parameter.get("name"), parameter.get("in"));
+        parameters.retain(|p| (p.get("name"), p.get("in")) != key);
+        parameters.push(parameter);
+    }
+
+    let mut headers = Vec::new();
+    let mut query = Vec::new();
+    let mut path_variables = Vec::new();
+    let mut cookies = Vec::new();
+    for parameter in parameters {
+        let name = parameter.get("name").and_then(Value::as_str).unwrap_or("").to_string();
+        let value = parameter_example(spec, parameter);
+        let description = parameter.get("description").and_then(Value::as_str).map(str::to_string);
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => path_variables.push(Variable {
+                key: name,
+                value,
+                var_type: Some("string".to_string()),
+                disabled: None,
+                extra: Default::default(),
+            }),
+            Some("query") => query.push(QueryParam {
+                key: name,
+                value: Some(value),
+                disabled: None,
+                description: description.map(Description::from),
+                extra: Default::default(),
+            }),
+            Some("header") => headers.push(Header {
+                key: name,
+                value,
+                disabled: None,
+                description: description.map(Description::from),
+                extra: Default::default(),
+            }),
+            Some("cookie") => cookies.push(format!("{}={}", name, value)),
+            _ => {}
+        }
+    }
+    if !cookies.is_empty() {
+        headers.push(Header {
+            key: "Cookie".to_string(),
+            value: cookies.join("; "),
+            disabled: None,
+            description: None,
+            extra: Default::default(),
+        });
+    }
+
+    let body = operation
+        .get("requestBody")
+        .map(|request_body| resolve(spec, request_body))
+        .and_then(|request_body| request_body_to_body(spec, request_body, &mut headers));
+
+    let auth = operation
+        .get("security")
+        .map(|security| security_to_auth(spec, security, variables).unwrap_or_else(|| Auth::new("noauth")));
+    if let Some(auth) = &auth {
+        apply_api_key_location(spec, operation.get("security"), auth, &mut headers, &mut query);
+    } else {
+        apply_api_key_location(spec, spec.get("security"), &Auth::new("noauth"), &mut headers, &mut query);
+    }
+
+    // A whole `{id}` segment becomes a `:id` path variable. Path variables
+    // only replace whole segments, so templates within a segment, as in
+    // `{name}.{ext}`, become `{{name}}` references to item variables.
+    let mut inline_names: Vec<String> = Vec::new();
+    let mut segments: Vec<String> = Vec::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        match template_name(segment, "{", "}") {
+            Some(name) => segments.push(format!(":{}", name)),
+            None => segments.push(replace_templates(segment, "{", "}", |name| {
+                inline_names.push(name.to_string());
+                format!("{{{{{}}}}}", name)
+            })),
+        }
+    }
+    let (item_variables, path_variables): (Vec<Variable>, Vec<Variable>) =
+        path_variables.into_iter().partition(|v| inline_names.contains(&v.key));
+
+    let mut raw = format!("{{{{baseUrl}}}}/{}", segments.join("/"));
+    let enabled_query: Vec<String> = query
+        .iter()
+        .map(|q| format!("{}={}", q.key, q.value.as_deref().unwrap_or("")))
+        .collect();
+    if !enabled_query.is_empty() {
+        raw.push('?');
+        raw.push_str(&enabled_query.join("&"));
+    }
+
+    let name = operation
+        .get("summary")
+        .or_else(|| operation.get("operationId"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+    let description = operation
+        .get("description")
+        .or_else(|| path_item.get("description"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    CollectionItem {
+        name,
+        item: None,
+        request: Some(Request {
+            method: Some(method.to_uppercase()),
+            header: if headers.is_empty() { None } else { Some(headers) },
+            body,
+            url: Some(Url {
+                raw: Some(raw),
+                protocol: None,
+                auth: None,
+                host: Some(vec!["{{baseUrl}}".to_string()]),
+                port: None,
+                path: if segments.is_empty() { None } else { Some(segments) },
+                query: if query.is_empty() { None } else { Some(query) },
+                hash: None,
+                variable: if path_variables.is_empty() { None } else { Some(path_variables) },
+                extra: Default::default(),
+            }),
+            description: description.clone().map(Description::from),
+            auth,
+            options: None,
+            extra: Default::default(),
+        }),
+        response: None,
+        event: None,
+        description: description.map(Description::from),
+        variable: if item_variables.is_empty() { None } else { Some(item_variables) },
+        auth: None,
+        extra: Default::default(),
+    }
+}
+
+/// The name in a segment that is exactly `open name close`.
+fn template_name<'a>(segment: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    segment
+        .strip_prefix(open)
+        .and_then(|rest| rest.strip_suffix(close))
+        .filter(|name| !name.is_empty() && !name.contains(['{', '}']))
+}
+
+/// Replaces each `open name close` in `text` with `replace(name)`.
+fn replace_templates(text: &str, open: &str, close: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        let Some(length) = rest[start + open.len()..].find(close) else {
+            break;
+        };
+        let name = &rest[start + open.len()..start + open.len() + length];
+        out.push_str(&rest[..start]);
+        out.push_str(&replace(name));
+        rest = &rest[start + open.len() + length + close.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parameter_example(spec: &Value, parameter: &Value) -> String {
+    if let Some(example) = parameter.get("example") {
+        return value_to_string(example);
+    }
+    if let Some(example) = first_example(spec, parameter.get("examples")) {
+        return value_to_string(&example);
+    }
+    match parameter.get("schema") {
+        Some(schema) => value_to_string(&example_from_schema(spec, schema)),
+        None => String::new(),
+    }
+}
+
+/// The `value` of the first entry in an OpenAPI `examples` map.
+fn first_example(spec: &Value, examples: Option<&Value>) -> Option<Value> {
+    let (_, example) = examples?.as_object()?.iter().next()?;
+    resolve(spec, example).get("value").cloned()
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Picks the request body media type, preferring JSON, then forms, then
+/// whatever is listed first.
+fn request_body_to_body(spec: &Value, request_body: &Value, headers: &mut Vec<Header>) -> Option<Body> {
+    let content = request_body.get("content")?.as_object()?;
+    let (media_type, media) = content
+        .iter()
+        .find(|(media_type, _)| is_json(media_type))
+        .or_else(|| content.iter().find(|(t, _)| t.as_str() == "application/x-www-form-urlencoded"))
+        .or_else(|| content.iter().find(|(t, _)| t.as_str() == "multipart/form-data"))
+        .or_else(|| content.iter().next())?;
+
+    let example = media
+        .get("example")
+        .cloned()
+        .or_else(|| first_example(spec, media.get("examples")))
+        .or_else(|| media.get("schema").map(|schema| example_from_schema(spec, schema)))
+        .unwrap_or(Value::Null);
+
+    if media_type == "application/x-www-form-urlencoded" || media_type == "multipart/form-data" {
+        let schema = media.get("schema").map(|schema| resolve(spec, schema));
+        let fields = form_fields(spec, schema, &example);
+        return Some(if media_type == "multipart/form-data" {
+            Body {
+                mode: Some("formdata".to_string()),
+                formdata: Some(fields),
+                ..Body::empty()
+            }
+        } else {
+            headers.push(content_type_header(media_type));
+            Body {
+                mode: Some("urlencoded".to_string()),
+                urlencoded: Some(fields),
+                ..Body::empty()
+            }
+        });
+    }
+
+    headers.push(content_type_header(media_type));
+    let raw = if is_json(media_type) {
+        serde_json::to_string_pretty(&example).unwrap_or_default()
+    } else {
+        value_to_string(&example)
+    };
+    Some(Body {
+        mode: Some("raw".to_string()),
+        raw: Some(raw),
+        ..Body::empty()
+    })
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+fn content_type_header(media_type: &str) -> Header {
+    Header {
+        key: "Content-Type".to_string(),
+        value: media_type.to_string(),
+        disabled: None,
+        description: None,
+        extra: Default::default(),
+    }
+}
+
+fn form_fields(spec: &Value, schema: Option<&Value>, example: &Value) -> Vec<FormData> {
+    let properties = schema.and_then(|s| s.get("properties")).and_then(Value::as_object);
+    let Some(properties) = properties else {
+        return example
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| form_field(key, value_to_string(value), false, None))
+            .collect();
+    };
+
+    properties
+        .iter()
+        .map(|(key, property)| {
+            let property = resolve(spec, property);
+            let is_file = property.get("format").and_then(Value::as_str) == Some("binary")
+                || property.get("contentMediaType").is_some();
+            let value = if is_file {
+                String::new()
+            } else {
+                example.get(key).map(value_to_string).unwrap_or_default()
+            };
+            let description = property.get("description").and_then(Value::as_str);
+            form_field(key, value, is_file, description)
+        })
+        .collect()
+}
+
+fn form_field(key: &str, value: String, is_file: bool, description: Option<&str>) -> FormData {
+    FormData {
+        key: key.to_string(),
+        value: Some(value),
+        data_type: Some(if is_file { "file" } else { "text" }.to_string()),
+        disabled: None,
+        description: description.map(Description::from),
+        extra: Default::default(),
+    }
+}
+
+/// Builds a sample value from a JSON schema: explicit examples and defaults
+/// first, otherwise a placeholder for the type.
+pub fn example_from_schema(spec: &Value, schema: &Value) -> Value {
+    schema_example(spec, schema, &mut Vec::new()).unwrap_or(Value::Null)
+}
+
+/// `expanding` holds the `$ref`s currently being expanded; a schema that
+/// refers back to one of them yields `None` and is left out of its parent.
+fn schema_example<'a>(spec: &'a Value, schema: &'a Value, expanding: &mut Vec<&'a str>) -> Option<Value> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if expanding.contains(&reference) || expanding.len() > MAX_SCHEMA_DEPTH {
+            return None;
+        }
+        expanding.push(reference);
+        let example = schema_example(spec, resolve(spec, schema), expanding);
+        expanding.pop();
+        return example;
+    }
+
+    if let Some(example) = schema.get("example") {
+        return Some(example.clone());
+    }
+    if let Some(example) = schema.get("examples").and_then(|e| e.get(0)) {
+        return Some(example.clone());
+    }
+    for key in ["default", "const"] {
+        if let Some(value) = schema.get(key) {
+            return Some(value.clone());
+        }
+    }
+    if let Some(first) = schema.get("enum").and_then(|e| e.get(0)) {
+        return Some(first.clone());
+    }
+
+    if let Some(all_of) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in all_of {
+            match schema_example(spec, part, expanding) {
+                Some(Value::Object(fields)) => merged.extend(fields),
+                Some(other) if all_of.len() == 1 => return Some(other),
+                _ => {}
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(first) = schema.get(key).and_then(|v| v.get(0)) {
+            return schema_example(spec, first, expanding);
+        }
+    }
+
+    // 3.1 allows `type: [string, "null"]`; use the first non-null type.
+    let schema_type = match schema.get("type") {
+        Some(Value::String(t)) => Some(t.as_str()),
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|t| *t != "null"),
+        _ => None,
+    };
+    let schema_type = schema_type.or_else(|| {
+        if schema.get("properties").is_some() {
+            Some("object")
+        } else if schema.get("items").is_some() {
+            Some("array")
+        } else {
+            None
+        }
+    });
+
+    Some(match schema_type {
+        Some("object") => {
+            let mut object = Map::new();
+            for (key, property) in schema.get("properties").and_then(Value::as_object).into_iter().flatten() {
+                if let Some(value) = schema_example(spec, property, expanding) {
+                    object.insert(key.clone(), value);
+                }
+            }
+            Value::Object(object)
+        }
+        Some("array") => {
+            let items = schema.get("items").and_then(|items| schema_example(spec, items, expanding));
+            Value::Array(items.into_iter().collect())
+        }
+        Some("string") => json!(match schema.get("format").and_then(Value::as_str) {
+            Some("date-time") => "2024-01-01T00:00:00Z",
+            Some("date") => "2024-01-01",
+            Some("time") => "00:00:00",
+            Some("email") => "user@example.com",
+            Some("uuid") => "00000000-0000-0000-0000-000000000000",
+            Some("uri") | Some("url") => "https://example.com",
+            Some("hostname") => "example.com",
+            Some("ipv4") => "127.0.0.1",
+            Some("ipv6") => "::1",
+            Some("byte") | Some("binary") => "",
+            _ => "string",
+        }),
+        Some("integer") => json!(0),
+        Some("number") => json!(0.0),
+        Some("boolean") => json!(true),
+        _ => Value::Null,
+    })
+}
+
+/// Maps the first scheme of the first security requirement to `Auth`,
+/// with credentials left as `{{variables}}` added to the collection. An
+/// empty requirement list means the operation is public.
+fn security_to_auth(spec: &Value, security: &Value, variables: &mut Vec<Variable>) -> Option<Auth> {
+    let requirement = security.as_array()?.first()?.as_object()?;
+    let Some((name, scopes)) = requirement.iter().next() else {
+        return Some(Auth::new("noauth"));
+    };
+    let scheme = resolve(spec, spec.pointer(&format!("/components/securitySchemes/{}", name))?);
+
+    let scheme_type = scheme.get("type").and_then(Value::as_str).unwrap_or("");
+    let http_scheme = scheme
+        .get("scheme")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    match (scheme_type, http_scheme.as_str()) {
+        ("http", "bearer") => {
+            add_variable(variables, "bearerToken", "");
+            Some(Auth {
+                bearer: Some(vec![variable("token", "{{bearerToken}}")]),
+                ..Auth::new("bearer")
+            })
+        }
+        ("http", "basic") => {
+            add_variable(variables, "username", "");
+            add_variable(variables, "password", "");
+            Some(Auth {
+                basic: Some(vec![
+                    variable("username", "{{username}}"),
+                    variable("password", "{{password}}"),
+                ]),
+                ..Auth::new("basic")
+            })
+        }
+        ("oauth2", _) | ("openIdConnect", _) => {
+            add_variable(variables, "accessToken", "");
+            let mut values = vec![
+                variable("accessToken", "{{accessToken}}"),
+                variable("addTokenTo", "header"),
+            ];
+            // The first flow's endpoints, so that the flow survives export.
+            let flows = scheme.get("flows").and_then(Value::as_object);
+            let flow = [
+                ("authorizationCode", "authorization_code"),
+                ("clientCredentials", "client_credentials"),
+                ("implicit", "implicit"),
+                ("password", "password_credentials"),
+            ]
+            .into_iter()
+            .find_map(|(key, grant_type)| Some((flows?.get(key)?, grant_type)));
+            if let Some((flow, grant_type)) = flow {
+                values.push(variable("grant_type", grant_type));
+                for (key, field) in [("authUrl", "authorizationUrl"), ("accessTokenUrl", "tokenUrl")] {
+                    if let Some(url) = flow.get(field).and_then(Value::as_str) {
+                        values.push(variable(key, url));
+                    }
+                }
+            }
+            let scopes: Vec<&str> = scopes.as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+            if !scopes.is_empty() {
+                values.push(variable("scope", &scopes.join(" ")));
+            }
+            Some(Auth {
+                oauth2: Some(values),
+                ..Auth::new("oauth2")
+            })
+        }
+        ("apiKey", _) => {
+            add_variable(variables, "apiKey", "");
+            None
+        }
+        _ => None,
+    }
+}
+
+/// `Auth` has no API key type, so API key schemes become a header or query
+/// parameter on each request that uses them.
+fn apply_api_key_location(
+    spec: &Value,
+    security: Option<&Value>,
+    auth: &Auth,
+    headers: &mut Vec<Header>,
+    query: &mut Vec<QueryParam>,
+) {
+    if auth.auth_type.as_deref() != Some("noauth") {
+        return;
+    }
+    let Some(name) = security
+        .and_then(|s| s.get(0))
+        .and_then(Value::as_object)
+        .and_then(|requirement| requirement.keys().next())
+    else {
+        return;
+    };
+    let Some(scheme) = spec.pointer(&format!("/components/securitySchemes/{}", name)) else {
+        return;
+    };
+    let scheme = resolve(spec, scheme);
+    if scheme.get("type").and_then(Value::as_str) != Some("apiKey") {
+        return;
+    }
+
+    let key = scheme.get("name").and_then(Value::as_str).unwrap_or("X-API-Key").to_string();
+    match scheme.get("in").and_then(Value::as_str) {
+        Some("query") => query.push(QueryParam {
+            key,
+            value: Some("{{apiKey}}".to_string()),
+            disabled: None,
+            description: None,
+            extra: Default::default(),
+        }),
+        Some("cookie") => headers.push(Header {
+            key: "Cookie".to_string(),
+            value: format!("{}={{{{apiKey}}}}", key),
+            disabled: None,
+            description: None,
+            extra: Default::default(),
+        }),
+        _ => headers.push(Header {
+            key,
+            value: "{{apiKey}}".to_string(),
+            disabled: None,
+            description: None,
+            extra: Default::default(),
+        }),
+    }
+}
+
+/// Generates an OpenAPI 3.1 document from a collection. Folders become tags,
+/// saved examples become responses, and request and response schemas are
+/// inferred from JSON bodies. Requests that share a method and path after
+/// conversion are merged into the first one.
+pub fn collection_to_openapi(collection: &Collection) -> Value {
+    let mut exporter = Exporter {
+        base_url: collection
+            .variable
+            .iter()
+            .flatten()
+            .find(|v| v.key == "baseUrl")
+            .map(|v| v.value.clone()),
+        servers: Vec::new(),
+        paths: Map::new(),
+        tags: Vec::new(),
+        schemes: Map::new(),
+        operation_ids: Vec::new(),
+    };
+
+    let collection_security = collection.auth.as_ref().and_then(|auth| exporter.security(auth));
+    exporter.add_items(&collection.item, None, collection_security.as_ref());
+
+    let mut info = Map::new();
+    info.insert("title".to_string(), json!(collection.info.name));
+    if let Some(description) = &collection.info.description {
+        info.insert("description".to_string(), json!(description.text()));
+    }
+    info.insert("version".to_string(), json!("1.0.0"));
+
+    let mut document = Map::new();
+    document.insert("openapi".to_string(), json!("3.1.0"));
+    document.insert("info".to_string(), Value::Object(info));
+    if !exporter.servers.is_empty() {
+        let servers = exporter
+            .servers
+            .iter()
+            .map(|url| server_object(url, collection.variable.as_deref().unwrap_or_default()))
+            .collect();
+        document.insert("servers".to_string(), Value::Array(servers));
+    }
+    if !exporter.tags.is_empty() {
+        document.insert("tags".to_string(), Value::Array(exporter.tags));
+    }
+    if let Some(security) = collection_security {
+        document.insert("security".to_string(), security);
+    }
+    document.insert("paths".to_string(), Value::Object(exporter.paths));
+    if !exporter.schemes.is_empty() {
+        document.insert("components".to_string(), json!({ "securitySchemes": exporter.schemes }));
+    }
+    Value::Object(document)
+}
+
+struct Exporter {
+    base_url: Option<String>,
+    servers: Vec<String>,
+    paths: Map<String, Value>,
+    tags: Vec<Value>,
+    schemes: Map<String, Value>,
+    operation_ids: Vec<String>,
+}
+
+impl Exporter {
+    fn add_items(&mut self, items: &[CollectionItem], tag: Option<&str>, inherited: Option<&Value>) {
+        for item in items {
+            if let Some(children) = &item.item {
+                let mut tag_object = Map::new();
+                tag_object.insert("name".to_string(), json!(item.name));
+                if let Some(description) = &item.description {
+                    tag_object.insert("description".to_string(), json!(description.text()));
+                }
+                if !self.tags.iter().any(|t| t["name"] == json!(item.name)) {
+                    self.tags.push(Value::Object(tag_object));
+                }
+                self.add_items(children, Some(&item.name), inherited);
+            } else if let Some(request) = &item.request {
+                self.add_request(item, request, tag, inherited);
+            }
+        }
+    }
+
+    fn add_request(&mut self, item: &CollectionItem, request: &Request, tag: Option<&str>, inherited: Option<&Value>) {
+        let parsed;
+        let url = match &request.url {
+            Some(url) if url.has_parts() => url,
+            Some(Url { raw: Some(raw), .. }) => {
+                parsed = Url::parse(raw);
+                &parsed
+            }
+            _ => return,
+        };
+
+        if let Some(server) = self.server_for(url) {
+            if !self.servers.contains(&server) {
+                self.servers.push(server);
+            }
+        }
+
+        let mut parameters = Vec::new();
+        let mut path = String::new();
+        let mut path_names: Vec<String> = Vec::new();
+        for segment in url.path.iter().flatten().filter(|s| !s.is_empty()) {
+            path.push('/');
+            match segment.strip_prefix(':').or_else(|| template_name(segment, "{{", "}}")) {
+                Some(name) => {
+                    path.push_str(&format!("{{{}}}", name));
+                    path_names.push(name.to_string());
+                }
+                None => path.push_str(&replace_templates(segment, "{{", "}}", |name| {
+                    path_names.push(name.to_string());
+                    format!("{{{}}}", name)
+                })),
+            }
+        }
+        for (index, name) in path_names.iter().enumerate() {
+            if path_names[..index].contains(name) {
+                continue;
+            }
+            // Whole-segment values are path variables, others item variables.
+            let example = url
+                .variable
+                .iter()
+                .chain(item.variable.iter())
+                .flatten()
+                .find(|v| &v.key == name)
+                .map(|v| v.value.clone())
+                .unwrap_or_default();
+            parameters.push(parameter(name, "path", true, &example, None));
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        let method = request.method.as_deref().unwrap_or("GET").to_ascii_lowercase();
+        if self.paths.get(&path).is_some_and(|item| item.get(&method).is_some()) {
+            return;
+        }
+
+        for query in url.query.iter().flatten().filter(|q| !q.disabled.unwrap_or(false)) {
+            let value = query.value.as_deref().unwrap_or("");
+            parameters.push(parameter(&query.key, "query", false, value, query.description.as_deref()));
+        }
+
+        let headers: Vec<&Header> = request
+            .header
+            .iter()
+            .flatten()
+            .filter(|h| !h.disabled.unwrap_or(false))
+            .collect();
+        // OpenAPI describes these through requestBody, responses and security instead.
+        for header in headers.iter().filter(|h| {
+            !["content-type", "accept", "authorization"].contains(&h.key.to_ascii_lowercase().as_str())
+        }) {
+            parameters.push(parameter(&header.key, "header", false, &header.value, header.description.as_deref()));
+        }
+
+        let mut operation = Map::new();
+        if let Some(tag) = tag {
+            operation.insert("tags".to_string(), json!([tag]));
+        }
+        operation.insert("summary".to_string(), json!(item.name));
+        if let Some(description) = item.description.as_ref().or(request.description.as_ref()) {
+            operation.insert("description".to_string(), json!(description.text()));
+        }
+        let mut id = operation_id(&item.name);
+        let mut suffix = 2;
+        while self.operation_ids.contains(&id) {
+            id = format!("{}{}", operation_id(&item.name), suffix);
+            suffix += 1;
+        }
+        self.operation_ids.push(id.clone());
+        operation.insert("operationId".to_string(), json!(id));
+        if !parameters.is_empty() {
+            operation.insert("parameters".to_string(), Value::Array(parameters));
+        }
+
+        let content_type = headers
+            .iter()
+            .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+            .map(|h| h.value.as_str());
+        if let Some(request_body) = request.body.as_ref().and_then(|body| request_body(body, content_type)) {
+            operation.insert("requestBody".to_string(), request_body);
+        }
+
+        let responses = responses(item);
+        if !responses.is_empty() {
+            operation.insert("responses".to_string(), Value::Object(responses));
+        }
+
+        if let Some(auth) = &request.auth {
+            let security = self.security(auth);
+            if security.as_ref() != inherited {
+                operation.insert("security".to_string(), security.unwrap_or_else(|| json!([])));
+            }
+        }
+
+        self.paths.entry(path).or_insert_with(|| json!({}))[method.as_str()] = Value::Object(operation);
+    }
+
+    /// The server URL a request is sent to, with `{{var}}` placeholders kept
+    /// for `server_object` to turn into server variables.
+    fn server_for(&self, url: &Url) -> Option<String> {
+        let mut host = url.host.as_ref()?.join(".");
+        if host == "{{baseUrl}}" {
+            return self.base_url.clone();
+        }
+        if let Some(port) = &url.port {
+            host = format!("{}:{}", host, port);
+        }
+        match &url.protocol {
+            Some(protocol) => Some(format!("{}://{}", protocol, host)),
+            // A variable host such as `{{base}}` usually carries its own scheme.
+            None if host.starts_with("{{") => Some(host),
+            None => Some(format!("https://{}", host)),
+        }
+    }
+
+    /// Registers the security scheme for `auth` and returns the requirement
+    /// list, or `None` when the request is unauthenticated.
+    fn security(&mut self, auth: &Auth) -> Option<Value> {
+        let find = |vars: &Option<Vec<Variable>>, key: &str| {
+            vars.iter()
+                .flatten()
+                .find(|v| v.key == key)
+                .map(|v| v.value.clone())
+                .filter(|v| !v.is_empty())
+        };
+
+        let (name, scheme) = match auth.auth_type.as_deref()? {
+            "bearer" => ("bearerAuth", json!({ "type": "http", "scheme": "bearer" })),
+            "basic" => ("basicAuth", json!({ "type": "http", "scheme": "basic" })),
+            "digest" => ("digestAuth", json!({ "type": "http", "scheme": "digest" })),
+            "oauth2" => {
+                let token_url = find(&auth.oauth2, "accessTokenUrl");
+                let auth_url = find(&auth.oauth2, "authUrl");
+                let scopes: Map<String, Value> = find(&auth.oauth2, "scope")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|scope| (scope.to_string(), json!("")))
+                    .collect();
+                let flows = match (auth_url, token_url) {
+                    (Some(auth_url), Some(token_url)) => json!({
+                        "authorizationCode": {
+                            "authorizationUrl": auth_url,
+                            "tokenUrl": token_url,
+                            "scopes": scopes,
+                        }
+                    }),
+                    (None, Some(token_url)) => json!({
+                        "clientCredentials": { "tokenUrl": token_url, "scopes": scopes }
+                    }),
+                    (Some(auth_url), None) => json!({
+                        "implicit": { "authorizationUrl": auth_url, "scopes": scopes }
+                    }),
+                    // Without endpoints there is nothing to describe beyond the token itself.
+                    (None, None) => {
+                        let scheme = json!({ "type": "http", "scheme": "bearer" });
+                        self.schemes.insert("oauth2".to_string(), scheme);
+                        return Some(json!([{ "oauth2": [] }]));
+                    }
+                };
+                ("oauth2", json!({ "type": "oauth2", "flows": flows }))
+            }
+            _ => return None,
+        };
+
+        self.schemes.insert(name.to_string(), scheme);
+        Some(json!([{ name: [] }]))
+    }
+}
+
+/// A server object for `url`; `{{name}}` placeholders become `{name}` server
+/// variables defaulting to the collection variable's value.
+fn server_object(url: &str, collection_variables: &[Variable]) -> Value {
+    let mut server_url = String::new();
+    let mut variables = Map::new();
+    let mut rest = url;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = &rest[start + 2..start + end];
+        server_url.push_str(&rest[..start]);
+        server_url.push_str(&format!("{{{}}}", name));
+        let default = collection_variables
+            .iter()
+            .find(|v| v.key == name)
+            .map(|v| v.value.clone())
+            .unwrap_or_default();
+        variables.insert(name.to_string(), json!({ "default": default }));
+        rest = &rest[start + end + 2..];
+    }
+    server_url.push_str(rest);
+
+    let mut server = Map::new();
+    server.insert("url".to_string(), json!(server_url));
+    if !variables.is_empty() {
+        server.insert("variables".to_string(), Value::Object(variables));
+    }
+    Value::Object(server)
+}
+
+fn parameter(name: &str, location: &str, required: bool, example: &str, description: Option<&str>) -> Value {
+    let mut parameter = Map::new();
+    parameter.insert("name".to_string(), json!(name));
+    parameter.insert("in".to_string(), json!(location));
+    if required {
+        parameter.insert("required".to_string(), json!(true));
+    }
+    if let Some(description) = description {
+        parameter.insert("description".to_string(), json!(description));
+    }
+    let typed = typed_value(example);
+    parameter.insert("schema".to_string(), schema_from_value(&typed));
+    if !example.is_empty() {
+        parameter.insert("example".to_string(), typed);
+    }
+    Value::Object(parameter)
+}
+
+/// Reads a parameter string as a number or boolean when it looks like one.
+fn typed_value(value: &str) -> Value {
+    if let Ok(integer) = value.parse::<i64>() {
+        return json!(integer);
+    }
+    if let Ok(number) = value.parse::<f64>() {
+        if number.is_finite() {
+            return json!(number);
+        }
+    }
+    match value {
+        "true" => json!(true),
+        "false" => json!(false),
+        _ => json!(value),
+    }
+}
+
+/// A lowerCamelCase operation id from the request name, e.g. "GET /pets"
+/// becomes `getPets`.
+fn operation_id(name: &str) -> String {
+    let mut id = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()) {
+        if id.is_empty() {
+            if word.chars().all(|c| !c.is_ascii_lowercase()) {
+                id.push_str(&word.to_ascii_lowercase());
+            } else {
+                id.push_str(&word[..1].to_ascii_lowercase());
+                id.push_str(&word[1..]);
+            }
+        } else {
+            id.push_str(&word[..1].to_ascii_uppercase());
+            id.push_str(&word[1..]);
+        }
+    }
+    id
+}
+
+/// Infers a JSON schema from a sample value. Arrays take the schema of
+/// their first element.
+pub fn schema_from_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "type": "null" }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "type": "integer" }),
+        Value::Number(_) => json!({ "type": "number" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => match items.first() {
+            Some(first) => json!({ "type": "array", "items": schema_from_value(first) }),
+            None => json!({ "type": "array", "items": {} }),
+        },
+        Value::Object(fields) => {
+            let properties: Map<String, Value> = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), schema_from_value(value)))
+                .collect();
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+/// A media type object for a body: JSON gets an inferred schema and the
+/// parsed example, anything else is described as a string.
+fn media_type(text: &str, content_type: &str) -> Value {
+    match serde_json::from_str::<Value>(text) {
+        Ok(value) if is_json(content_type) || content_type.is_empty() => {
+            json!({ "schema": schema_from_value(&value), "example": value })
+        }
+        _ => json!({ "schema": { "type": "string" }, "example": text }),
+    }
+}
+
+fn request_body(body: &Body, content_type: Option<&str>) -> Option<Value> {
+    let form_schema = |fields: &Option<Vec<FormData>>| {
+        let mut properties = Map::new();
+        for field in fields.iter().flatten().filter(|f| !f.disabled.unwrap_or(false)) {
+            let schema = if field.data_type.as_deref() == Some("file") {
+                json!({ "type": "string", "format": "binary" })
+            } else {
+                let mut schema = schema_from_value(&typed_value(field.value.as_deref().unwrap_or("")));
+                if let Some(description) = &field.description {
+                    schema["description"] = json!(description.text());
+                }
+                schema
+            };
+            properties.insert(field.key.clone(), schema);
+        }
+        json!({ "schema": { "type": "object", "properties": properties } })
+    };
+
+    let (media, content) = match body.mode.as_deref().unwrap_or("raw") {
+        "urlencoded" => ("application/x-www-form-urlencoded".to_string(), form_schema(&body.urlencoded)),
+        "formdata" => ("multipart/form-data".to_string(), form_schema(&body.formdata)),
+        "file" => (
+            content_type.unwrap_or("application/octet-stream").to_string(),
+            json!({ "schema": { "type": "string", "contentMediaType": content_type.unwrap_or("application/octet-stream") } }),
+        ),
+        "graphql" => {
+            let text = crate::build_body_from_struct(Some(body)).ok().flatten()?;
+            ("application/json".to_string(), media_type(&text, "application/json"))
+        }
+        _ => {
+            let raw = body.raw.as_deref().filter(|raw| !raw.trim().is_empty())?;
+            let is_json_body = serde_json::from_str::<Value>(raw).is_ok();
+            let media = content_type
+                .map(str::to_string)
+                .unwrap_or_else(|| if is_json_body { "application/json" } else { "text/plain" }.to_string());
+            let content = media_type(raw, &media);
+            (media, content)
+        }
+    };
+
+    let media = media.split(';').next().unwrap_or("").trim().to_string();
+    Some(json!({ "content": { media: content } }))
+}
+
+/// Responses keyed by status code, built from the item's saved examples.
+/// Several examples with the same status and media type are kept as
+/// named `examples`.
+fn responses(item: &CollectionItem) -> Map<String, Value> {
+    let mut responses = Map::new();
+    let mut bodies: Vec<(String, String, String, Value)> = Vec::new();
+
+    for example in item.response.iter().flatten() {
+        let code = example.code.map_or("default".to_string(), |c| c.to_string());
+        let description = example
+            .status
+            .clone()
+            .or_else(|| example.code.map(|c| crate::scripts::reason_phrase(c).to_string()))
+            .filter(|d| !d.is_empty())
+            .or_else(|| example.name.clone())
+            .unwrap_or_else(|| "Response".to_string());
+        responses
+            .entry(code.clone())
+            .or_insert_with(|| json!({ "description": description }));
+
+        let Some(body) = example.body.as_deref().filter(|b| !b.is_empty()) else {
+            continue;
+        };
+        let content_type = example
+            .header
+            .iter()
+            .flatten()
+            .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+            .map(|h| h.value.split(';').next().unwrap_or("").trim().to_string())
+            .unwrap_or_else(|| {
+                if serde_json::from_str::<Value>(body).is_ok() {
+                    "application/json".to_string()
+                } else {
+                    "text/plain".to_string()
+                }
+            });
+        let name = example.name.clone().unwrap_or_else(|| "example".to_string());
+        bodies.push((code, content_type.clone(), name, media_type(body, &content_type)));
+    }
+
+    for (code, content_type, _, media) in &bodies {
+        let content = &mut responses[code.as_str()]["content"];
+        if content.get(content_type).is_some() {
+            continue;
+        }
+        let group: Vec<&(String, String, String, Value)> = bodies
+            .iter()
+            .filter(|(c, t, _, _)| c == code && t == content_type)
+            .collect();
+        if group.len() == 1 {
+            content[content_type.as_str()] = media.clone();
+            continue;
+        }
+
+        // The schema comes from the first example; every example is kept by name.
+        let mut examples = Map::new();
+        for (_, _, name, media) in &group {
+            let mut key = name.clone();
+            let mut suffix = 2;
+            while examples.contains_key(&key) {
+                key = format!("{} {}", name, suffix);
+                suffix += 1;
+            }
+            examples.insert(key, json!({ "value": media["example"] }));
+        }
+        content[content_type.as_str()] = json!({ "schema": media["schema"], "examples": examples });
+    }
+    responses
+}
+
+/// Import an OpenAPI 3.0/3.1 document (JSON or YAML) as a Postman collection.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn openapi_to_collection_json(spec_text: *const c_char) -> *mut c_char {
+    ffi::respond(unsafe { ffi::arg(spec_text, "spec_text") }.and_then(|text| {
+        let spec = parse_spec(text).map_err(|e| FfiError::parse("OpenAPI document", e))?;
+        Ok(openapi_to_collection(&spec))
+    }))
+}
+
+/// Export a collection as an OpenAPI 3.1 document. `format` is `json`
+/// (the default when null) or `yaml`; the document is returned as text.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn collection_to_openapi_json(collection_json: *const c_char, format: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let collection = Collection::from_json(unsafe { ffi::arg(collection_json, "collection") }?)
+            .map_err(|e| FfiError::json("collection", &e))?;
+        let document = collection_to_openapi(&collection);
+        match unsafe { ffi::optional_arg(format, "format") }?.unwrap_or("json") {
+            "json" => serde_json::to_string_pretty(&document).map_err(FfiError::internal),
+            "yaml" => serde_yaml::to_string(&document).map_err(FfiError::internal),
+            other => Err(FfiError::new(
+                ErrorCategory::Unsupported,
+                "unknown_format",
+                format!("Unknown format '{}'", other),
+            )),
+        }
+    })();
+    ffi::respond(result)
+}
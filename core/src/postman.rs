@@ -1,118 +1,60 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
 use crate::models::collection::Collection;
 use crate::models::environment::Environment;
-use std::ffi::{CStr, CString};
+use crate::models::schema::SchemaVersion;
 use std::os::raw::c_char;
-use std::ptr;
 
 /// Parse a Postman collection JSON string into a Collection struct
 #[no_mangle]
 pub extern "C" fn parse_postman_collection(json_str: *const c_char) -> *mut c_char {
-    if json_str.is_null() {
-        return ptr::null_mut();
-    }
-
-    let json = unsafe {
-        match CStr::from_ptr(json_str).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        }
-    };
-
-    match Collection::from_json(json) {
-        Ok(collection) => {
-            match serde_json::to_string(&collection) {
-                Ok(result_json) => CString::new(result_json).unwrap().into_raw(),
-                Err(_) => ptr::null_mut(),
-            }
-        }
-        Err(e) => {
-            let error_json = format!(r#"{{"error": "{}"}}"#, e);
-            CString::new(error_json).unwrap().into_raw()
-        }
-    }
+    ffi::respond(
+        unsafe { ffi::arg(json_str, "collection") }
+            .and_then(|json| Collection::from_json(json).map_err(|e| FfiError::json("collection", &e))),
+    )
 }
 
-/// Convert a Collection struct to Postman-compatible JSON
+/// Convert a Collection struct to Postman-compatible JSON text
 #[no_mangle]
 pub extern "C" fn collection_to_json(collection_json: *const c_char) -> *mut c_char {
-    if collection_json.is_null() {
-        return ptr::null_mut();
-    }
-
-    let json = unsafe {
-        match CStr::from_ptr(collection_json).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        }
-    };
+    ffi::respond(
+        unsafe { ffi::json_arg::<Collection>(collection_json, "collection") }
+            .and_then(|collection| collection.to_json().map_err(FfiError::internal)),
+    )
+}
 
-    match serde_json::from_str::<Collection>(json) {
-        Ok(collection) => {
-            match collection.to_json() {
-                Ok(result_json) => CString::new(result_json).unwrap().into_raw(),
-                Err(_) => ptr::null_mut(),
-            }
-        }
-        Err(e) => {
-            let error_json = format!(r#"{{"error": "{}"}}"#, e);
-            CString::new(error_json).unwrap().into_raw()
-        }
-    }
+/// Convert a Collection struct to Postman JSON text in the given schema
+/// version ("2.1" or "2.0"; null means 2.1)
+#[no_mangle]
+pub extern "C" fn collection_to_postman_json(
+    collection_json: *const c_char,
+    version: *const c_char,
+) -> *mut c_char {
+    let result = (|| {
+        let collection: Collection = unsafe { ffi::json_arg(collection_json, "collection") }?;
+        let version = unsafe { ffi::optional_arg(version, "version") }?.unwrap_or("2.1");
+        match SchemaVersion::from_name(version) {
+            Some(SchemaVersion::V1) | None => Err(FfiError::new(
+                ErrorCategory::Unsupported,
+                "unsupported_version",
+                format!("Unsupported export version '{}': expected 2.1 or 2.0", version),
+            )),
+            Some(version) => collection.to_json_version(version).map_err(FfiError::internal),
+        }
+    })();
+    ffi::respond(result)
 }
 
 /// Parse a Postman environment JSON string
 #[no_mangle]
 pub extern "C" fn parse_postman_environment(json_str: *const c_char) -> *mut c_char {
-    if json_str.is_null() {
-        return ptr::null_mut();
-    }
-
-    let json = unsafe {
-        match CStr::from_ptr(json_str).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        }
-    };
-
-    match serde_json::from_str::<Environment>(json) {
-        Ok(environment) => {
-            match serde_json::to_string(&environment) {
-                Ok(result_json) => CString::new(result_json).unwrap().into_raw(),
-                Err(_) => ptr::null_mut(),
-            }
-        }
-        Err(e) => {
-            let error_json = format!(r#"{{"error": "{}"}}"#, e);
-            CString::new(error_json).unwrap().into_raw()
-        }
-    }
+    ffi::respond(unsafe { ffi::json_arg::<Environment>(json_str, "environment") })
 }
 
-/// Convert an Environment struct to Postman-compatible JSON
+/// Convert an Environment struct to Postman-compatible JSON text
 #[no_mangle]
 pub extern "C" fn environment_to_json(environment_json: *const c_char) -> *mut c_char {
-    if environment_json.is_null() {
-        return ptr::null_mut();
-    }
-
-    let json = unsafe {
-        match CStr::from_ptr(environment_json).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        }
-    };
-
-    match serde_json::from_str::<Environment>(json) {
-        Ok(environment) => {
-            match serde_json::to_string_pretty(&environment) {
-                Ok(result_json) => CString::new(result_json).unwrap().into_raw(),
-                Err(_) => ptr::null_mut(),
-            }
-        }
-        Err(e) => {
-            let error_json = format!(r#"{{"error": "{}"}}"#, e);
-            CString::new(error_json).unwrap().into_raw()
-        }
-    }
+    ffi::respond(
+        unsafe { ffi::json_arg::<Environment>(environment_json, "environment") }
+            .and_then(|environment| serde_json::to_string_pretty(&environment).map_err(FfiError::internal)),
+    )
 }
-
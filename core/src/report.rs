@@ -0,0 +1,418 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::runner::{RequestExecution, RunSummary};
+use crate::scripts::reason_phrase;
+use serde::Serialize;
+use std::fmt::Write;
+use std::os::raw::c_char;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    JUnit,
+    Json,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "junit" | "xml" => Some(ReportFormat::JUnit),
+            "json" => Some(ReportFormat::Json),
+            "html" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+pub fn render(summary: &RunSummary, format: ReportFormat) -> Result<String, serde_json::Error> {
+    match format {
+        ReportFormat::JUnit => Ok(junit_report(summary)),
+        ReportFormat::Json => json_report(summary),
+        ReportFormat::Html => Ok(html_report(summary)),
+    }
+}
+
+#[derive(Serialize)]
+struct RunStats {
+    iterations: usize,
+    requests: usize,
+    failed_requests: usize,
+    assertions: usize,
+    failed_assertions: usize,
+    skipped_assertions: usize,
+    average_response_time_ms: u64,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    collection: &'a str,
+    environment: Option<&'a str>,
+    started_at: String,
+    duration_ms: u64,
+    success: bool,
+    stats: RunStats,
+    executions: &'a [RequestExecution],
+}
+
+fn stats(summary: &RunSummary) -> RunStats {
+    let timings: Vec<u64> = summary
+        .executions
+        .iter()
+        .filter_map(|e| e.response.as_ref().map(|r| r.duration_ms))
+        .collect();
+    let average = if timings.is_empty() {
+        0
+    } else {
+        timings.iter().sum::<u64>() / timings.len() as u64
+    };
+
+    RunStats {
+        iterations: summary.iterations,
+        requests: summary.executions.len(),
+        failed_requests: summary.failed_requests(),
+        assertions: summary.total_assertions(),
+        failed_assertions: summary.failed_assertions(),
+        skipped_assertions: summary.skipped_assertions(),
+        average_response_time_ms: average,
+    }
+}
+
+/// Full machine-readable report with every resolved request and response.
+pub fn json_report(summary: &RunSummary) -> Result<String, serde_json::Error> {
+    let report = JsonReport {
+        collection: &summary.collection_name,
+        environment: summary.environment_name.as_deref(),
+        started_at: format_timestamp(summary.started_at_ms),
+        duration_ms: summary.duration_ms,
+        success: summary.is_success(),
+        stats: stats(summary),
+        executions: &summary.executions,
+    };
+    serde_json::to_string_pretty(&report)
+}
+
+/// JUnit XML with one `<testsuite>` per executed request and one
+/// `<testcase>` per assertion. A request that failed to execute gets an
+/// extra testcase for the error, after any assertions recorded before it
+/// failed; a request with neither gets a single testcase for itself.
+pub fn junit_report(summary: &RunSummary) -> String {
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="{}" tests="{}" failures="{}" time="{}">"#,
+        escape_xml(&summary.collection_name),
+        summary.executions.iter().map(junit_case_count).sum::<usize>(),
+        summary.failed_requests() + summary.failed_assertions(),
+        seconds(summary.duration_ms)
+    );
+
+    for (index, execution) in summary.executions.iter().enumerate() {
+        let name = execution_label(execution, summary.iterations);
+        let time = seconds(execution.response.as_ref().map_or(0, |r| r.duration_ms));
+        let classname = escape_xml(&execution.folder_path.join("."));
+        let failures = execution.error.is_some() as usize
+            + execution.assertions.iter().filter(|a| !a.passed && !a.skipped).count();
+        let skipped = execution.assertions.iter().filter(|a| a.skipped).count();
+
+        let _ = writeln!(
+            xml,
+            r#"  <testsuite name="{}" id="{}" timestamp="{}" tests="{}" failures="{}" skipped="{}" time="{}">"#,
+            escape_xml(&name),
+            index,
+            format_timestamp(execution.started_at_ms),
+            junit_case_count(execution),
+            failures,
+            skipped,
+            time
+        );
+
+        for assertion in &execution.assertions {
+            let _ = write!(
+                xml,
+                r#"    <testcase name="{}" classname="{}" time="{}""#,
+                escape_xml(&assertion.name),
+                classname,
+                time
+            );
+            if assertion.skipped {
+                let message = assertion.error.as_deref().unwrap_or("not evaluated");
+                let _ = writeln!(
+                    xml,
+                    ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                    escape_xml(message)
+                );
+            } else if !assertion.passed {
+                let message = assertion.error.as_deref().unwrap_or("assertion failed");
+                let _ = writeln!(
+                    xml,
+                    ">\n      <failure type=\"AssertionError\" message=\"{}\"/>\n    </testcase>",
+                    escape_xml(message)
+                );
+            } else {
+                let _ = writeln!(xml, "/>");
+            }
+        }
+
+        if let Some(error) = &execution.error {
+            let _ = writeln!(
+                xml,
+                r#"    <testcase name="{}" classname="{}" time="{}">"#,
+                escape_xml(&execution.name),
+                classname,
+                time
+            );
+            let _ = writeln!(
+                xml,
+                r#"      <failure type="RequestError" message="{}"/>"#,
+                escape_xml(error)
+            );
+            let _ = writeln!(xml, "    </testcase>");
+        } else if execution.assertions.is_empty() {
+            let _ = writeln!(
+                xml,
+                r#"    <testcase name="{}" classname="{}" time="{}"/>"#,
+                escape_xml(&execution.name),
+                classname,
+                time
+            );
+        }
+
+        let _ = writeln!(xml, "  </testsuite>");
+    }
+
+    let _ = writeln!(xml, "</testsuites>");
+    xml
+}
+
+/// The number of `<testcase>`s written for an execution: one per assertion,
+/// plus one for the request itself when it errored or has no assertions.
+fn junit_case_count(execution: &RequestExecution) -> usize {
+    let request_case = execution.error.is_some() || execution.assertions.is_empty();
+    execution.assertions.len() + request_case as usize
+}
+
+/// Single-file HTML report; styles are inlined and request/response details
+/// use `<details>` so it works without JavaScript.
+pub fn html_report(summary: &RunSummary) -> String {
+    let stats = stats(summary);
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title} - Ababil run report</title>
+<style>
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; margin: 2rem; color: #1f2328; }}
+h1 {{ margin-bottom: 0.25rem; }}
+.meta {{ color: #656d76; margin-bottom: 1.5rem; }}
+table.stats {{ border-collapse: collapse; margin-bottom: 2rem; }}
+table.stats td, table.stats th {{ border: 1px solid #d0d7de; padding: 0.4rem 0.8rem; text-align: right; }}
+table.stats th:first-child, table.stats td:first-child {{ text-align: left; }}
+details {{ border: 1px solid #d0d7de; border-radius: 6px; margin-bottom: 0.5rem; padding: 0.5rem 0.75rem; }}
+details.failed {{ border-left: 4px solid #cf222e; }}
+details.passed {{ border-left: 4px solid #1a7f37; }}
+summary {{ cursor: pointer; font-weight: 600; }}
+.method {{ font-family: monospace; margin-right: 0.5rem; }}
+.status {{ float: right; font-weight: normal; color: #656d76; }}
+ul.assertions {{ list-style: none; padding-left: 0; }}
+.pass {{ color: #1a7f37; }}
+.fail {{ color: #cf222e; }}
+.skip {{ color: #9a6700; }}
+pre {{ background: #f6f8fa; padding: 0.75rem; overflow-x: auto; white-space: pre-wrap; word-break: break-all; }}
+h4 {{ margin: 0.75rem 0 0.25rem; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div class="meta">{environment}Started {started} &middot; {duration} ms &middot; <strong class="{result_class}">{result}</strong></div>
+<table class="stats">
+<tr><th></th><th>executed</th><th>failed</th><th>skipped</th></tr>
+<tr><td>iterations</td><td>{iterations}</td><td></td><td></td></tr>
+<tr><td>requests</td><td>{requests}</td><td>{failed_requests}</td><td></td></tr>
+<tr><td>assertions</td><td>{assertions}</td><td>{failed_assertions}</td><td>{skipped_assertions}</td></tr>
+<tr><td>average response time</td><td>{average} ms</td><td></td><td></td></tr>
+</table>
+"#,
+        title = escape_xml(&summary.collection_name),
+        environment = summary
+            .environment_name
+            .as_ref()
+            .map(|e| format!("Environment {} &middot; ", escape_xml(e)))
+            .unwrap_or_default(),
+        started = format_timestamp(summary.started_at_ms),
+        duration = summary.duration_ms,
+        result_class = if summary.is_success() { "pass" } else { "fail" },
+        result = if summary.is_success() { "passed" } else { "failed" },
+        iterations = stats.iterations,
+        requests = stats.requests,
+        failed_requests = stats.failed_requests,
+        assertions = stats.assertions,
+        failed_assertions = stats.failed_assertions,
+        skipped_assertions = stats.skipped_assertions,
+        average = stats.average_response_time_ms,
+    );
+
+    for execution in &summary.executions {
+        write_html_execution(&mut html, execution, summary.iterations);
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn write_html_execution(html: &mut String, execution: &RequestExecution, iterations: usize) {
+    let request = &execution.request;
+    let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
+    let url = request.url.as_ref().and_then(|u| u.raw.clone()).unwrap_or_default();
+    let status = match (&execution.response, &execution.error) {
+        (Some(r), _) => format!("{} {} &middot; {} ms", r.status_code, reason_phrase(r.status_code), r.duration_ms),
+        (None, Some(_)) => "errored".to_string(),
+        (None, None) => String::new(),
+    };
+
+    let _ = writeln!(
+        html,
+        r#"<details class="{}"><summary><span class="method">{}</span>{}<span class="status">{}</span></summary>"#,
+        if execution.is_success() { "passed" } else { "failed" },
+        escape_xml(&method),
+        escape_xml(&execution_label(execution, iterations)),
+        status
+    );
+    let _ = writeln!(html, "<p><code>{}</code></p>", escape_xml(&url));
+
+    if let Some(error) = &execution.error {
+        let _ = writeln!(html, r#"<p class="fail">{}</p>"#, escape_xml(error));
+    }
+
+    if !execution.assertions.is_empty() {
+        html.push_str("<ul class=\"assertions\">\n");
+        for assertion in &execution.assertions {
+            let (class, mark) = if assertion.skipped {
+                ("skip", "&#8211;")
+            } else if assertion.passed {
+                ("pass", "&#10003;")
+            } else {
+                ("fail", "&#10007;")
+            };
+            let detail = assertion
+                .error
+                .as_ref()
+                .map(|e| format!(" &mdash; {}", escape_xml(e)))
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                r#"<li class="{}">{} {}{}</li>"#,
+                class,
+                mark,
+                escape_xml(&assertion.name),
+                detail
+            );
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<h4>Request headers</h4>\n<pre>");
+    for header in request.header.iter().flatten().filter(|h| !h.disabled.unwrap_or(false)) {
+        let _ = writeln!(html, "{}: {}", escape_xml(&header.key), escape_xml(&header.value));
+    }
+    html.push_str("</pre>\n");
+
+    if let Some(raw) = request.body.as_ref().and_then(|b| b.raw.as_ref()) {
+        let _ = writeln!(html, "<h4>Request body</h4>\n<pre>{}</pre>", escape_xml(raw));
+    }
+
+    if let Some(response) = &execution.response {
+        html.push_str("<h4>Response headers</h4>\n<pre>");
+        for (key, value) in &response.headers {
+            let _ = writeln!(html, "{}: {}", escape_xml(key), escape_xml(value));
+        }
+        html.push_str("</pre>\n");
+        let _ = writeln!(html, "<h4>Response body</h4>\n<pre>{}</pre>", escape_xml(&response.body));
+    }
+
+    html.push_str("</details>\n");
+}
+
+fn execution_label(execution: &RequestExecution, iterations: usize) -> String {
+    let mut parts = execution.folder_path.clone();
+    parts.push(execution.name.clone());
+    let label = parts.join(" / ");
+    if iterations > 1 {
+        format!("{} (iteration {})", label, execution.iteration + 1)
+    } else {
+        label
+    }
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Escapes text for use in XML/HTML content and attribute values.
+pub fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Formats milliseconds since the Unix epoch as an RFC 3339 UTC timestamp.
+pub fn format_timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60,
+        ms % 1000
+    )
+}
+
+/// Render a run summary (as produced by the runner) in the named format:
+/// `junit`, `json` or `html`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn render_run_report(summary_json: *const c_char, format: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let summary: RunSummary = unsafe { ffi::json_arg(summary_json, "summary") }?;
+        let format_name = unsafe { ffi::arg(format, "format") }?;
+        let format = ReportFormat::from_name(format_name).ok_or_else(|| {
+            FfiError::new(
+                ErrorCategory::Unsupported,
+                "unknown_format",
+                format!("Unknown report format: {}", format_name),
+            )
+        })?;
+        render(&summary, format).map_err(FfiError::internal)
+    })();
+    ffi::respond(result)
+}
@@ -0,0 +1,450 @@
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::collection::{Collection, CollectionItem};
+use crate::models::environment::Environment;
+use crate::models::request::{Auth, Event, Request};
+use crate::models::variable::Variable;
+use crate::scripts::{self, AssertionResult, VariableChange, VariableTarget};
+use crate::variables::VariableScope;
+use crate::HttpResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Settings for a collection run, mirroring the Newman command-line options.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RunOptions {
+    pub environment: Option<Environment>,
+    /// One map of variables per iteration, loaded from a CSV or JSON file.
+    pub iteration_data: Vec<HashMap<String, String>>,
+    /// Only run requests inside folders with these names.
+    pub folders: Vec<String>,
+    /// Defaults to the number of data rows, or 1 without a data file.
+    pub iteration_count: Option<usize>,
+    /// Values that take precedence over the environment file.
+    pub env_var_overrides: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestExecution {
+    pub iteration: usize,
+    /// Names of the enclosing folders, outermost first.
+    pub folder_path: Vec<String>,
+    pub name: String,
+    /// The request after variable substitution and auth inheritance.
+    pub request: Request,
+    pub response: Option<HttpResponse>,
+    pub error: Option<String>,
+    pub assertions: Vec<AssertionResult>,
+    pub started_at_ms: u64,
+}
+
+impl RequestExecution {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && self.assertions.iter().all(|a| a.passed || a.skipped)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub collection_name: String,
+    pub environment_name: Option<String>,
+    pub iterations: usize,
+    pub started_at_ms: u64,
+    pub duration_ms: u64,
+    pub executions: Vec<RequestExecution>,
+}
+
+impl RunSummary {
+    pub fn failed_requests(&self) -> usize {
+        self.executions.iter().filter(|e| e.error.is_some()).count()
+    }
+
+    pub fn total_assertions(&self) -> usize {
+        self.executions.iter().map(|e| e.assertions.len()).sum()
+    }
+
+    pub fn failed_assertions(&self) -> usize {
+        self.executions
+            .iter()
+            .flat_map(|e| &e.assertions)
+            .filter(|a| !a.passed && !a.skipped)
+            .count()
+    }
+
+    pub fn skipped_assertions(&self) -> usize {
+        self.executions
+            .iter()
+            .flat_map(|e| &e.assertions)
+            .filter(|a| a.skipped)
+            .count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed_requests() == 0 && self.failed_assertions() == 0
+    }
+}
+
+/// A request item together with everything it inherits from its parents.
+struct PlannedRequest<'a> {
+    folder_path: Vec<String>,
+    item: &'a CollectionItem,
+    request: &'a Request,
+    auth: Option<&'a Auth>,
+    /// Folder variables, outermost first, followed by the item's own.
+    variables: Vec<Variable>,
+    events: Vec<Event>,
+}
+
+/// Runs every request in `collection` (optionally restricted to some
+/// folders) once per iteration, with its pre-request scripts before it and
+/// its test scripts after. Variables set by scripts carry over to later
+/// requests.
+pub fn run_collection(
+    collection: &Collection,
+    options: &RunOptions,
+) -> Result<RunSummary, Box<dyn std::error::Error>> {
+    for folder in &options.folders {
+        if !has_folder(&collection.item, folder) {
+            let message = format!("Folder '{}' not found in collection", folder);
+            return Err(FfiError::new(ErrorCategory::Argument, "folder_not_found", message).into());
+        }
+    }
+
+    let mut planned = Vec::new();
+    let root = Inherited {
+        auth: collection.auth.as_ref(),
+        variables: Vec::new(),
+        events: collection.event.clone().unwrap_or_default(),
+    };
+    plan_items(&collection.item, &mut Vec::new(), &root, &options.folders, false, &mut planned);
+
+    let iterations = options
+        .iteration_count
+        .unwrap_or_else(|| options.iteration_data.len().max(1));
+
+    let run_start = Instant::now();
+    let started_at_ms = now_ms();
+    let mut executions = Vec::new();
+    let mut variables = RunVariables::new(collection, options);
+    let environment_name = options.environment.as_ref().map(|e| e.name.as_str());
+
+    for iteration in 0..iterations {
+        variables.data = if options.iteration_data.is_empty() {
+            HashMap::new()
+        } else {
+            options.iteration_data[iteration % options.iteration_data.len()].clone()
+        };
+        variables.local.clear();
+
+        for plan in &planned {
+            executions.push(execute_planned(plan, &mut variables, environment_name, iteration));
+        }
+    }
+
+    Ok(RunSummary {
+        collection_name: collection.info.name.clone(),
+        environment_name: environment_name.map(str::to_string),
+        iterations,
+        started_at_ms,
+        duration_ms: run_start.elapsed().as_millis() as u64,
+        executions,
+    })
+}
+
+/// What a folder (or the collection itself) passes down to its items.
+struct Inherited<'a> {
+    auth: Option<&'a Auth>,
+    variables: Vec<Variable>,
+    events: Vec<Event>,
+}
+
+impl<'a> Inherited<'a> {
+    /// Layers `item`'s own auth, variables and scripts over these.
+    fn with(&self, item: &'a CollectionItem) -> Inherited<'a> {
+        let mut variables = self.variables.clone();
+        variables.extend(item.variable.clone().unwrap_or_default());
+        let mut events = self.events.clone();
+        events.extend(item.event.clone().unwrap_or_default());
+        Inherited {
+            auth: own_auth(item.auth.as_ref()).or(self.auth),
+            variables,
+            events,
+        }
+    }
+}
+
+/// An item's auth, or `None` when it has none or is set to `inherit`, so the
+/// nearest parent folder with auth (or else the collection) supplies it.
+fn own_auth(auth: Option<&Auth>) -> Option<&Auth> {
+    auth.filter(|auth| auth.auth_type.as_deref() != Some("inherit"))
+}
+
+fn plan_items<'a>(
+    items: &'a [CollectionItem],
+    folder_path: &mut Vec<String>,
+    inherited: &Inherited<'a>,
+    folders: &[String],
+    inside_selected: bool,
+    planned: &mut Vec<PlannedRequest<'a>>,
+) {
+    for item in items {
+        if let Some(children) = &item.item {
+            let selected = inside_selected || folders.is_empty() || folders.contains(&item.name);
+            folder_path.push(item.name.clone());
+            plan_items(children, folder_path, &inherited.with(item), folders, selected, planned);
+            folder_path.pop();
+            continue;
+        }
+
+        if !inside_selected && !folders.is_empty() {
+            continue;
+        }
+
+        if let Some(request) = &item.request {
+            let inherited = inherited.with(item);
+            planned.push(PlannedRequest {
+                folder_path: folder_path.clone(),
+                item,
+                request,
+                auth: own_auth(request.auth.as_ref()).or(inherited.auth),
+                variables: inherited.variables,
+                events: inherited.events,
+            });
+        }
+    }
+}
+
+/// The variable scopes of a run. Scripts can change the global, collection,
+/// environment and local ones; those changes are seen by every later request,
+/// except local ones, which last until the end of the iteration.
+struct RunVariables {
+    globals: HashMap<String, String>,
+    collection: HashMap<String, String>,
+    environment: HashMap<String, String>,
+    data: HashMap<String, String>,
+    local: HashMap<String, String>,
+}
+
+impl RunVariables {
+    fn new(collection: &Collection, options: &RunOptions) -> Self {
+        let mut environment = enabled(options.environment.as_ref().and_then(|e| e.values.as_ref()));
+        environment.extend(options.env_var_overrides.iter().cloned());
+        RunVariables {
+            globals: HashMap::new(),
+            collection: enabled(collection.variable.as_ref()),
+            environment,
+            data: HashMap::new(),
+            local: HashMap::new(),
+        }
+    }
+
+    /// The flattened scope for a request, with `item_variables` (from its
+    /// folders and the item itself) between the data row and local ones.
+    fn scope(&self, item_variables: &[Variable]) -> VariableScope {
+        let mut scope = VariableScope::new();
+        for layer in [&self.globals, &self.collection, &self.environment, &self.data] {
+            for (key, value) in layer {
+                scope.set(key, value);
+            }
+        }
+        for var in item_variables.iter().filter(|var| !var.disabled.unwrap_or(false)) {
+            scope.set(&var.key, &var.value);
+        }
+        for (key, value) in &self.local {
+            scope.set(key, value);
+        }
+        scope
+    }
+
+    fn apply(&mut self, changes: &[VariableChange]) {
+        for change in changes {
+            let layer = match change.target {
+                VariableTarget::Globals => &mut self.globals,
+                VariableTarget::CollectionVariables => &mut self.collection,
+                VariableTarget::Environment => &mut self.environment,
+                VariableTarget::Variables => &mut self.local,
+            };
+            match &change.value {
+                Some(value) => layer.insert(change.key.clone(), value.clone()),
+                None => layer.remove(&change.key),
+            };
+        }
+    }
+}
+
+fn enabled(variables: Option<&Vec<Variable>>) -> HashMap<String, String> {
+    variables
+        .into_iter()
+        .flatten()
+        .filter(|var| !var.disabled.unwrap_or(false))
+        .map(|var| (var.key.clone(), var.value.clone()))
+        .collect()
+}
+
+fn has_folder(items: &[CollectionItem], name: &str) -> bool {
+    items.iter().any(|item| match &item.item {
+        Some(children) => item.name == name || has_folder(children, name),
+        None => false,
+    })
+}
+
+/// Runs one planned request and its scripts. The request is recorded in the
+/// history store, if one is open, like requests sent from the app.
+fn execute_planned(
+    plan: &PlannedRequest,
+    variables: &mut RunVariables,
+    environment_name: Option<&str>,
+    iteration: usize,
+) -> RequestExecution {
+    let pre_request = scripts::run_scripts("prerequest", &plan.events, None, &variables.scope(&plan.variables));
+    variables.apply(&pre_request.changes);
+    let item_scope = variables.scope(&plan.variables);
+    let mut assertions = pre_request.assertions;
+
+    let mut request = plan.request.clone();
+    request.auth = plan.auth.cloned();
+    let started_at_ms = now_ms();
+
+    let request = match item_scope.resolve_request(&request) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            return RequestExecution {
+                iteration,
+                folder_path: plan.folder_path.clone(),
+                name: plan.item.name.clone(),
+                request,
+                response: None,
+                error: Some(format!("Error resolving variables: {}", e)),
+                assertions,
+                started_at_ms,
+            }
+        }
+    };
+
+    let start = Instant::now();
+    let (response, error) = match crate::execute_request_from_struct(&request) {
+        Ok(response) => (
+            Some(HttpResponse {
+                duration_ms: start.elapsed().as_millis() as u64,
+                ..response
+            }),
+            None,
+        ),
+        Err(e) => (None, Some(format!("Error: {}", e))),
+    };
+    match (&response, &error) {
+        (Some(response), _) => crate::history::record_if_open(&request, environment_name, Ok(response)),
+        (None, Some(error)) => crate::history::record_if_open(&request, environment_name, Err(error)),
+        (None, None) => {}
+    }
+
+    if let Some(response) = &response {
+        let tests = scripts::run_scripts("test", &plan.events, Some(response), &item_scope);
+        variables.apply(&tests.changes);
+        assertions.extend(tests.assertions);
+    }
+
+    RequestExecution {
+        iteration,
+        folder_path: plan.folder_path.clone(),
+        name: plan.item.name.clone(),
+        request,
+        response,
+        error,
+        assertions,
+        started_at_ms,
+    }
+}
+
+/// Loads iteration data from a JSON array of objects or a CSV file with a
+/// header row.
+pub fn parse_iteration_data(contents: &str) -> Result<Vec<HashMap<String, String>>, Box<dyn std::error::Error>> {
+    let trimmed = contents.trim_start();
+    if trimmed.starts_with('[') {
+        let rows: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(trimmed)?;
+        return Ok(rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(k, v)| match v {
+                        serde_json::Value::String(s) => (k, s),
+                        other => (k, other.to_string()),
+                    })
+                    .collect()
+            })
+            .collect());
+    }
+
+    let mut records = parse_csv(contents).into_iter();
+    let header = match records.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+    Ok(records
+        .filter(|record| record.iter().any(|field| !field.is_empty()))
+        .map(|record| header.iter().cloned().zip(record).collect())
+        .collect())
+}
+
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Run a collection with the given options (may be null) and return the
+/// `RunSummary`, which can be passed to `render_run_report`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn run_collection_json(collection_json: *const c_char, options_json: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let collection = Collection::from_json(unsafe { ffi::arg(collection_json, "collection") }?)
+            .map_err(|e| FfiError::json("collection", &e))?;
+        let options = match unsafe { ffi::optional_arg(options_json, "options") }? {
+            Some(json) => serde_json::from_str(json).map_err(|e| FfiError::json("options", &e))?,
+            None => RunOptions::default(),
+        };
+        Ok(run_collection(&collection, &options)?)
+    })();
+    ffi::respond(result)
+}
@@ -0,0 +1,944 @@
+use crate::models::request::Event;
+use crate::variables::VariableScope;
+use crate::HttpResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Outcome of one `pm.test(...)` block, or of the statements of a script
+/// outside any block when they could not all be run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionResult {
+    pub name: String,
+    pub passed: bool,
+    /// Set when the block contains a statement we cannot evaluate, or no
+    /// assertion at all. `error` then says why.
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+/// The variable scope a `pm.<scope>.set(...)` call writes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VariableTarget {
+    Globals,
+    CollectionVariables,
+    Environment,
+    /// `pm.variables`, which lasts until the end of the iteration.
+    Variables,
+}
+
+impl VariableTarget {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "globals" => Some(Self::Globals),
+            "collectionVariables" => Some(Self::CollectionVariables),
+            "environment" => Some(Self::Environment),
+            "variables" => Some(Self::Variables),
+            _ => None,
+        }
+    }
+}
+
+/// A variable set by a script, or unset when `value` is `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariableChange {
+    pub target: VariableTarget,
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// What running one kind of script (`prerequest` or `test`) produced.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOutcome {
+    pub assertions: Vec<AssertionResult>,
+    /// Variable changes in the order the scripts made them.
+    pub changes: Vec<VariableChange>,
+}
+
+/// Evaluates the `test` scripts attached to a request against its response,
+/// with no variables in scope. See [`run_scripts`].
+pub fn evaluate_tests(events: &[Event], response: &HttpResponse) -> Vec<AssertionResult> {
+    run_scripts("test", events, Some(response), &VariableScope::new()).assertions
+}
+
+/// Runs the scripts attached to a request that listen for `listen`:
+/// `prerequest` (with no `response`) or `test`.
+///
+/// There is no JavaScript engine in the core, so only a few kinds of
+/// statement are understood: the common chai-style assertions, `pm.<scope>
+/// .set/unset` calls, and `const`/`let`/`var` bindings, where values are
+/// literals or are read from the response (`pm.response.json().a[0]`,
+/// headers, status, text) or from other variables. A `pm.test` block
+/// containing any other statement is reported as skipped rather than passing
+/// on the strength of the statements we did understand, unless one of those
+/// already failed. Other statements outside a block are reported as one
+/// skipped result for the whole script.
+pub fn run_scripts(
+    listen: &str,
+    events: &[Event],
+    response: Option<&HttpResponse>,
+    scope: &VariableScope,
+) -> ScriptOutcome {
+    let mut context = Context {
+        response,
+        scope,
+        locals: HashMap::new(),
+        changes: Vec::new(),
+    };
+    let mut assertions = Vec::new();
+    let script_name = match listen {
+        "prerequest" => "pre-request script",
+        _ => "test script",
+    };
+
+    for event in events {
+        if event.listen.as_deref() != Some(listen) {
+            continue;
+        }
+        let source = match event.script.as_ref().and_then(|s| s.exec.as_ref()) {
+            Some(lines) => lines.join("\n"),
+            None => continue,
+        };
+
+        context.locals.clear();
+        let mut unsupported = None;
+        for statement in split_statements(&source) {
+            if let Some((name, body)) = test_block(&statement) {
+                assertions.push(evaluate_block(name, &body, &mut context));
+                continue;
+            }
+            match execute_statement(&statement, &mut context) {
+                Some(Ok(())) => {}
+                Some(Err(error)) => {
+                    // An exception ends the script, as it would in Postman.
+                    assertions.push(AssertionResult {
+                        name: script_name.to_string(),
+                        passed: false,
+                        skipped: false,
+                        error: Some(error),
+                    });
+                    unsupported = None;
+                    break;
+                }
+                None => {
+                    unsupported.get_or_insert(statement);
+                }
+            }
+        }
+        if let Some(statement) = unsupported {
+            assertions.push(AssertionResult {
+                name: script_name.to_string(),
+                passed: false,
+                skipped: true,
+                error: Some(unsupported_reason(&statement)),
+            });
+        }
+    }
+
+    ScriptOutcome {
+        assertions,
+        changes: context.changes,
+    }
+}
+
+/// What a script can see and what it has done so far.
+struct Context<'a> {
+    response: Option<&'a HttpResponse>,
+    /// Variables as they were when the script started.
+    scope: &'a VariableScope,
+    locals: HashMap<String, Value>,
+    changes: Vec<VariableChange>,
+}
+
+impl Context<'_> {
+    /// Reads a variable, seeing changes made earlier in the script. Every
+    /// getter falls back to the merged scope the request was resolved with.
+    fn variable(&self, target: VariableTarget, key: &str) -> Value {
+        let changed = self
+            .changes
+            .iter()
+            .rev()
+            .find(|c| c.key == key && (c.target == target || target == VariableTarget::Variables));
+        let value = match changed {
+            Some(change) => change.value.clone(),
+            None => self.scope.get(key).cloned(),
+        };
+        value.map(Value::String).unwrap_or(Value::Null)
+    }
+}
+
+fn evaluate_block(name: String, body: &str, context: &mut Context) -> AssertionResult {
+    let mut evaluated = 0;
+    let mut unsupported = None;
+
+    for statement in split_statements(body) {
+        match execute_statement(&statement, context) {
+            Some(Ok(())) => evaluated += 1,
+            Some(Err(error)) => {
+                return AssertionResult {
+                    name,
+                    passed: false,
+                    skipped: false,
+                    error: Some(error),
+                }
+            }
+            None => {
+                unsupported.get_or_insert(statement);
+            }
+        }
+    }
+
+    let reason = match unsupported {
+        Some(statement) => Some(unsupported_reason(&statement)),
+        None if evaluated == 0 => Some("no assertions".to_string()),
+        None => None,
+    };
+    AssertionResult {
+        name,
+        passed: reason.is_none(),
+        skipped: reason.is_some(),
+        error: reason,
+    }
+}
+
+fn unsupported_reason(statement: &str) -> String {
+    format!("unsupported statement: {}", statement.replace('\n', " "))
+}
+
+/// Runs one statement. Returns `None` for statements we do not understand.
+fn execute_statement(statement: &str, context: &mut Context) -> Option<Result<(), String>> {
+    for keyword in ["const ", "let ", "var "] {
+        if let Some(declaration) = statement.strip_prefix(keyword) {
+            let (name, expr) = declaration.split_once('=')?;
+            let name = name.trim();
+            if !is_identifier(name) {
+                return None;
+            }
+            return Some(evaluate_expr(&strip_whitespace(expr), context)?.map(|value| {
+                context.locals.insert(name.to_string(), value);
+            }));
+        }
+    }
+
+    let stmt = strip_whitespace(statement);
+
+    if let Some((target, call)) = stmt.strip_prefix("pm.").and_then(|rest| rest.split_once('.')) {
+        if let Some(target) = VariableTarget::from_name(target) {
+            let (value, args) = match (whole_call(call, "set("), whole_call(call, "unset(")) {
+                (Some(args), _) => (true, args),
+                (_, Some(args)) => (false, args),
+                _ => return None,
+            };
+            let args = split_args(args);
+            let key = match evaluate_expr(args.first()?, context)? {
+                Ok(key) => display_value(&key),
+                Err(error) => return Some(Err(error)),
+            };
+            let value = match (value, args.get(1)) {
+                (true, Some(expr)) => match evaluate_expr(expr, context)? {
+                    Ok(value) => Some(display_value(&value)),
+                    Err(error) => return Some(Err(error)),
+                },
+                (true, None) => return None,
+                (false, _) => None,
+            };
+            context.changes.push(VariableChange { target, key, value });
+            return Some(Ok(()));
+        }
+    }
+
+    if let Some(call) = stmt.strip_prefix("console.") {
+        let logged = ["log(", "info(", "warn(", "error(", "debug("]
+            .iter()
+            .any(|method| whole_call(call, method).is_some());
+        return logged.then_some(Ok(()));
+    }
+
+    evaluate_statement(&stmt, context)
+}
+
+/// Returns `None` for statements that are not a recognised assertion.
+/// Whitespace outside string literals has already been stripped from `stmt`.
+fn evaluate_statement(stmt: &str, context: &Context) -> Option<Result<(), String>> {
+    let response = context.response?;
+    let code = response.status_code;
+
+    if let Some(arg) = call_arg(stmt, "pm.response.to.have.status(") {
+        return Some(match arg.parse::<u16>() {
+            Ok(expected) => expect_eq(code, expected, "status code"),
+            Err(_) => {
+                let expected = unquote(arg)?;
+                let actual = reason_phrase(code);
+                if actual.eq_ignore_ascii_case(&expected) {
+                    Ok(())
+                } else {
+                    Err(format!("expected status '{}' but got '{}'", expected, actual))
+                }
+            }
+        });
+    }
+
+    let class_checks: [StatusClassCheck; 6] = [
+        ("pm.response.to.be.ok", |c| c == 200),
+        ("pm.response.to.be.success", |c| (200..300).contains(&c)),
+        ("pm.response.to.be.redirection", |c| (300..400).contains(&c)),
+        ("pm.response.to.be.clientError", |c| (400..500).contains(&c)),
+        ("pm.response.to.be.serverError", |c| (500..600).contains(&c)),
+        ("pm.response.to.be.error", |c| c >= 400),
+    ];
+    for (pattern, check) in class_checks {
+        if stmt == pattern {
+            return Some(if check(code) {
+                Ok(())
+            } else {
+                Err(format!("{} failed for status {}", pattern, code))
+            });
+        }
+    }
+
+    if let Some(arg) = call_arg(stmt, "pm.response.to.have.header(") {
+        let mut parts = split_args(arg).into_iter();
+        let key = unquote(&parts.next()?)?;
+        let expected = parts.next().and_then(|v| unquote(&v));
+        let actual = response
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&key))
+            .map(|(_, v)| v.clone());
+        return Some(match (actual, expected) {
+            (None, _) => Err(format!("expected response to have header '{}'", key)),
+            (Some(actual), Some(expected)) if actual != expected => Err(format!(
+                "expected header '{}' to be '{}' but got '{}'",
+                key, expected, actual
+            )),
+            _ => Ok(()),
+        });
+    }
+
+    if let Some(arg) = call_arg(stmt, "pm.response.to.have.body(") {
+        let expected = unquote(arg)?;
+        return Some(if response.body == expected {
+            Ok(())
+        } else {
+            Err("response body did not match".to_string())
+        });
+    }
+
+    if let Some(rest) = stmt.strip_prefix("pm.expect(") {
+        let close = matching_paren(rest)?;
+        // A second argument is only a custom failure message.
+        let subject = split_args(&rest[..close]).into_iter().next()?;
+        let chain = &rest[close + 1..];
+        return evaluate_expect(&subject, chain, context);
+    }
+
+    None
+}
+
+fn evaluate_expect(subject: &str, chain: &str, context: &Context) -> Option<Result<(), String>> {
+    let actual = match evaluate_expr(subject, context)? {
+        Ok(actual) => actual,
+        Err(error) => return Some(Err(error)),
+    };
+
+    let (negated, chain) = match chain.strip_prefix(".to.not") {
+        Some(rest) => (true, rest),
+        None => (false, chain.strip_prefix(".to")?),
+    };
+    let chain = chain.strip_prefix(".be").unwrap_or(chain);
+    let chain = chain.strip_prefix(".have").unwrap_or(chain);
+    let chain = chain.strip_prefix(".deep").unwrap_or(chain);
+
+    let (method, arg) = match chain.find('(') {
+        Some(open) => {
+            let args = &chain[open + 1..];
+            let close = matching_paren(args)?;
+            if close + 1 != args.len() {
+                return None;
+            }
+            (&chain[..open], &args[..close])
+        }
+        None => (chain, ""),
+    };
+    let expected = || match evaluate_expr(arg, context) {
+        Some(Ok(value)) => Some(value),
+        _ => None,
+    };
+    let shown = display_quoted(&actual);
+
+    let (passed, message) = match method {
+        ".eql" | ".equal" | ".equals" | ".eq" => {
+            let expected = expected()?;
+            (
+                json_eq(&actual, &expected),
+                format!("expected {} to equal {}", shown, display_quoted(&expected)),
+            )
+        }
+        ".below" | ".lessThan" | ".lt" | ".above" | ".greaterThan" | ".gt" => {
+            let limit = expected()?.as_f64()?;
+            let n = match actual.as_f64() {
+                Some(n) => n,
+                None => return Some(Err(format!("expected {} to be a number", shown))),
+            };
+            if matches!(method, ".below" | ".lessThan" | ".lt") {
+                (n < limit, format!("expected {} to be below {}", shown, limit))
+            } else {
+                (n > limit, format!("expected {} to be above {}", shown, limit))
+            }
+        }
+        ".oneOf" => {
+            let options = expected()?;
+            let options = options.as_array()?;
+            (
+                options.iter().any(|option| json_eq(&actual, option)),
+                format!("expected {} to be one of {}", shown, arg),
+            )
+        }
+        ".include" | ".contain" | ".string" => {
+            let expected = expected()?;
+            let found = match (&actual, &expected) {
+                (Value::String(s), Value::String(part)) => s.contains(part.as_str()),
+                (Value::Array(items), _) => items.iter().any(|item| json_eq(item, &expected)),
+                _ => return None,
+            };
+            (
+                found,
+                format!("expected {} to include {}", shown, display_quoted(&expected)),
+            )
+        }
+        ".property" => {
+            let mut args = split_args(arg).into_iter();
+            let key = unquote(&args.next()?)?;
+            let value = actual.as_object().and_then(|object| object.get(&key));
+            let passed = match (value, args.next()) {
+                (Some(value), Some(expected)) => match evaluate_expr(&expected, context)? {
+                    Ok(expected) => json_eq(value, &expected),
+                    Err(error) => return Some(Err(error)),
+                },
+                (value, _) => value.is_some(),
+            };
+            (passed, format!("expected {} to have property '{}'", shown, key))
+        }
+        ".lengthOf" | ".length" => {
+            let expected = expected()?.as_u64()?;
+            let length = match &actual {
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                _ => return Some(Err(format!("expected {} to have a length", shown))),
+            };
+            (
+                length as u64 == expected,
+                format!("expected {} to have length {} but got {}", shown, expected, length),
+            )
+        }
+        ".a" | ".an" => {
+            let expected = unquote(arg)?.to_lowercase();
+            (
+                type_name(&actual) == expected,
+                format!(
+                    "expected {} to be {} {}",
+                    shown,
+                    method.trim_start_matches('.'),
+                    expected
+                ),
+            )
+        }
+        ".true" => (actual == Value::Bool(true), format!("expected {} to be true", shown)),
+        ".false" => (actual == Value::Bool(false), format!("expected {} to be false", shown)),
+        ".null" => (actual.is_null(), format!("expected {} to be null", shown)),
+        ".exist" => (!actual.is_null(), format!("expected {} to exist", shown)),
+        ".ok" => (is_truthy(&actual), format!("expected {} to be truthy", shown)),
+        ".empty" => {
+            let empty = match &actual {
+                Value::String(s) => s.is_empty(),
+                Value::Array(items) => items.is_empty(),
+                Value::Object(object) => object.is_empty(),
+                _ => return Some(Err(format!("expected {} to have a length", shown))),
+            };
+            (empty, format!("expected {} to be empty", shown))
+        }
+        _ => return None,
+    };
+
+    Some(if passed != negated {
+        Ok(())
+    } else if negated {
+        Err(format!("negated assertion failed: {}", message))
+    } else {
+        Err(message)
+    })
+}
+
+/// Evaluates an expression (with whitespace already stripped) to a JSON
+/// value. Returns `None` when the expression is not one we understand, and
+/// an error where JavaScript would throw.
+fn evaluate_expr(expr: &str, context: &Context) -> Option<Result<Value, String>> {
+    if let Some(text) = unquote(expr) {
+        if expr.starts_with('`') && text.contains("${") {
+            return None;
+        }
+        return Some(Ok(Value::String(text)));
+    }
+    if let Ok(value) = serde_json::from_str::<Value>(expr) {
+        return Some(Ok(value));
+    }
+
+    let (base, path) = evaluate_base(expr, context)?;
+    match base {
+        Ok(value) => follow_path(value, path),
+        Err(error) => Some(Err(error)),
+    }
+}
+
+/// Evaluates the leading part of `expr` that yields a value, returning it
+/// with the rest of the expression (a property path, if anything).
+fn evaluate_base<'e>(expr: &'e str, context: &Context) -> Option<(Result<Value, String>, &'e str)> {
+    if let Some(rest) = expr.strip_prefix("pm.response.").or_else(|| legacy_response(expr)) {
+        let response = context.response?;
+        for prefix in ["json()", "JSON.parse(responseBody)"] {
+            if let Some(path) = rest.strip_prefix(prefix) {
+                let body =
+                    serde_json::from_str(&response.body).map_err(|e| format!("response body is not valid JSON: {}", e));
+                return Some((body, path));
+            }
+        }
+        let fields: [(&str, Value); 6] = [
+            ("text()", Value::String(response.body.clone())),
+            ("responseBody", Value::String(response.body.clone())),
+            ("code", Value::from(response.status_code)),
+            ("responseCode.code", Value::from(response.status_code)),
+            ("responseTime", Value::from(response.duration_ms)),
+            ("status", Value::String(reason_phrase(response.status_code).to_string())),
+        ];
+        for (field, value) in fields {
+            if let Some(path) = rest.strip_prefix(field) {
+                return Some((Ok(value), path));
+            }
+        }
+        let args = rest.strip_prefix("headers.get(")?;
+        let close = matching_paren(args)?;
+        let key = display_value(&evaluate_expr(&args[..close], context)?.ok()?);
+        let value = response
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&key))
+            .map(|(_, v)| Value::String(v.clone()))
+            .unwrap_or(Value::Null);
+        return Some((Ok(value), &args[close + 1..]));
+    }
+
+    if let Some((target, call)) = expr.strip_prefix("pm.").and_then(|rest| rest.split_once('.')) {
+        let target = VariableTarget::from_name(target)?;
+        let args = call.strip_prefix("get(")?;
+        let close = matching_paren(args)?;
+        let key = display_value(&evaluate_expr(&args[..close], context)?.ok()?);
+        return Some((Ok(context.variable(target, &key)), &args[close + 1..]));
+    }
+
+    let end = expr
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(expr.len());
+    let value = context.locals.get(&expr[..end])?.clone();
+    Some((Ok(value), &expr[end..]))
+}
+
+/// The pre-`pm` globals (`responseBody`, `responseCode.code`, ...), which
+/// share their names with the `pm.response` fields they stand for.
+fn legacy_response(expr: &str) -> Option<&str> {
+    [
+        "JSON.parse(responseBody)",
+        "responseBody",
+        "responseCode.code",
+        "responseTime",
+    ]
+    .iter()
+    .any(|name| expr.starts_with(name))
+    .then_some(expr)
+}
+
+/// Follows `.name`, `["name"]` and `[0]` accessors from `value`.
+fn follow_path(mut value: Value, mut path: &str) -> Option<Result<Value, String>> {
+    while !path.is_empty() {
+        let (key, rest) = if let Some(rest) = path.strip_prefix('.') {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            (rest[..end].to_string(), &rest[end..])
+        } else {
+            let rest = path.strip_prefix('[')?;
+            let close = rest.find(']')?;
+            let key = &rest[..close];
+            match unquote(key) {
+                Some(key) => (key, &rest[close + 1..]),
+                None => (key.parse::<usize>().ok()?.to_string(), &rest[close + 1..]),
+            }
+        };
+
+        value = match &value {
+            Value::Null => return Some(Err(format!("Cannot read properties of undefined (reading '{}')", key))),
+            Value::String(s) if key == "length" => Value::from(s.chars().count()),
+            Value::Array(items) if key == "length" => Value::from(items.len()),
+            Value::Array(items) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index).cloned())
+                .unwrap_or(Value::Null),
+            Value::Object(object) => object.get(&key).cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        };
+        path = rest;
+    }
+    Some(Ok(value))
+}
+
+/// The text a value becomes when it is stored in a variable.
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn display_quoted(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("'{}'", s),
+        other => other.to_string(),
+    }
+}
+
+/// Equality as chai's `eql` sees it, where `1` and `1.0` are the same.
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        _ => true,
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+type StatusClassCheck = (&'static str, fn(u16) -> bool);
+
+fn expect_eq(actual: u16, expected: u16, what: &str) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("expected {} {} but got {}", what, expected, actual))
+    }
+}
+
+/// Recognises a `pm.test("name", function () { ... })` statement (or one
+/// with an arrow function) and returns the name with the function body.
+fn test_block(statement: &str) -> Option<(String, String)> {
+    let args = statement.strip_prefix("pm.test")?.trim_start().strip_prefix('(')?;
+    let close = matching_paren(args)?;
+    if !args[close + 1..].trim().is_empty() {
+        return None;
+    }
+    let mut parts = split_args(&args[..close]).into_iter();
+    let name = parts.next()?;
+    let func = parts.next()?;
+    let name = unquote(name.trim()).unwrap_or(name);
+    Some((name, function_body(&func).to_string()))
+}
+
+/// Strips the `function (...)` or `(...) =>` header and the braces around a
+/// function's body. An arrow function's expression body is returned as is.
+fn function_body(func: &str) -> &str {
+    let func = func.trim();
+    let rest = match func.strip_prefix("function") {
+        Some(rest) => rest.trim_start().strip_prefix('(').and_then(|args| {
+            let close = matching_paren(args)?;
+            Some(&args[close + 1..])
+        }),
+        None => func.split_once("=>").map(|(_, rest)| rest),
+    };
+    let rest = match rest {
+        Some(rest) => rest.trim(),
+        None => return func,
+    };
+    match rest.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+        Some(body) => body,
+        None => rest,
+    }
+}
+
+/// Given text just after an opening parenthesis, returns the index of the
+/// matching closing one, skipping over string literals.
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                if depth == 0 {
+                    return if c == ')' { Some(i) } else { None };
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits a script body into top-level statements, dropping comments and
+/// collapsing whitespace outside string literals to single spaces (or line
+/// breaks, inside braces). Chained
+/// calls continued on the next line (`pm.expect(x)\n  .to.eql(1)`) stay in
+/// one statement.
+fn split_statements(body: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut chars = body.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                push_space(&mut current);
+            }
+            '(' | '[' | '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            }
+            ';' if depth == 0 => {
+                statements.push(std::mem::take(&mut current));
+            }
+            '\n' if depth == 0 => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.peek() == Some(&'.') {
+                    continue;
+                }
+                if chars.peek() == Some(&'/') {
+                    // A comment line between a call and its `.to...` chain
+                    // must not end the statement, so look past it first.
+                    push_space(&mut current);
+                    continue;
+                }
+                statements.push(std::mem::take(&mut current));
+            }
+            '\n' => push_newline(&mut current),
+            c if c.is_whitespace() => push_space(&mut current),
+            _ => current.push(c),
+        }
+    }
+    statements.push(current);
+    statements
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn push_space(current: &mut String) {
+    if !current.is_empty() && !current.ends_with([' ', '\n']) {
+        current.push(' ');
+    }
+}
+
+/// Keeps line breaks inside braces, so a block's body can be split into
+/// statements in turn.
+fn push_newline(current: &mut String) {
+    if current.ends_with(' ') {
+        current.pop();
+    }
+    if !current.is_empty() && !current.ends_with('\n') {
+        current.push('\n');
+    }
+}
+
+/// Removes whitespace outside string literals, so `pm.expect( x ).to .eql(1)`
+/// can be matched as `pm.expect(x).to.eql(1)`.
+fn strip_whitespace(statement: &str) -> String {
+    let mut out = String::with_capacity(statement.len());
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for c in statement.chars() {
+        if let Some(q) = quote {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            c if c.is_whitespace() => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Splits a JavaScript argument list on top-level commas.
+fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for c in s.chars() {
+        if let Some(q) = quote {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '(' | '[' | '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            }
+            ',' if depth == 0 => {
+                args.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        args.push(current.trim().to_string());
+    }
+    args
+}
+
+fn call_arg<'a>(stmt: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = stmt.strip_prefix(prefix)?;
+    let close = matching_paren(rest)?;
+    Some(&rest[..close])
+}
+
+/// Like `call_arg`, but only when the call is all of `stmt`.
+fn whole_call<'a>(stmt: &'a str, prefix: &str) -> Option<&'a str> {
+    let args = call_arg(stmt, prefix)?;
+    (prefix.len() + args.len() + 1 == stmt.len()).then_some(args)
+}
+
+fn unquote(s: &str) -> Option<String> {
+    let first = s.chars().next()?;
+    if !matches!(first, '"' | '\'' | '`') || s.len() < 2 || !s.ends_with(first) {
+        return None;
+    }
+    let inner = &s[1..s.len() - 1];
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+pub fn reason_phrase(code: u16) -> &'static str {
+    reqwest::StatusCode::from_u16(code)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("")
+}
@@ -0,0 +1,823 @@
+use crate::ffi::{self, FfiError};
+use crate::models::collection::{Collection, CollectionInfo, CollectionItem};
+use crate::models::request::{Body, Description, Header, Request, Url};
+use roxmltree::{Document, Node};
+use serde::Serialize;
+use serde_json::json;
+use std::os::raw::c_char;
+
+const WSDL: &str = "http://schemas.xmlsoap.org/wsdl/";
+const SOAP11_BINDING: &str = "http://schemas.xmlsoap.org/wsdl/soap/";
+const SOAP12_BINDING: &str = "http://schemas.xmlsoap.org/wsdl/soap12/";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema";
+const SOAP11_ENVELOPE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP12_ENVELOPE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+/// Nesting deeper than this is left empty in sample envelopes.
+const MAX_DEPTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SoapVersion {
+    #[serde(rename = "1.1")]
+    Soap11,
+    #[serde(rename = "1.2")]
+    Soap12,
+}
+
+impl SoapVersion {
+    fn envelope_namespace(self) -> &'static str {
+        match self {
+            SoapVersion::Soap11 => SOAP11_ENVELOPE,
+            SoapVersion::Soap12 => SOAP12_ENVELOPE,
+        }
+    }
+
+    fn binding_namespace(self) -> &'static str {
+        match self {
+            SoapVersion::Soap11 => SOAP11_BINDING,
+            SoapVersion::Soap12 => SOAP12_BINDING,
+        }
+    }
+}
+
+/// A SOAP fault. SOAP 1.1's `faultcode`, `faultstring` and `faultactor`
+/// fill `code`, `reason` and `role`; `subcodes` and `node` are SOAP 1.2 only.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SoapFault {
+    /// The fault code as written, e.g. `soap:Server`.
+    pub code: String,
+    pub subcodes: Vec<String>,
+    pub reason: String,
+    pub role: Option<String>,
+    pub node: Option<String>,
+    /// The contents of the fault's detail element, as XML.
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SoapResponse {
+    pub version: SoapVersion,
+    /// The contents of the SOAP body, as XML.
+    pub body: String,
+    pub fault: Option<SoapFault>,
+}
+
+/// Converts a WSDL 1.1 document into a collection with one folder per SOAP
+/// port and one request per operation, each with a sample envelope built
+/// from the XML Schema types. Ports with HTTP bindings are skipped.
+pub fn wsdl_to_collection(text: &str) -> Result<Collection, String> {
+    let document = Document::parse(text).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !is(root, WSDL, "definitions") {
+        return Err("Not a WSDL 1.1 document: the root element is not 'wsdl:definitions'".to_string());
+    }
+    let wsdl = Wsdl {
+        root,
+        schemas: children(root, WSDL, "types")
+            .flat_map(|types| children(types, XSD, "schema"))
+            .collect(),
+    };
+
+    let mut folders = Vec::new();
+    for service in children(root, WSDL, "service") {
+        for port in children(service, WSDL, "port") {
+            if let Some(folder) = wsdl.port_folder(port) {
+                folders.push(folder);
+            }
+        }
+    }
+    if folders.is_empty() {
+        return Err("The WSDL has no services with SOAP 1.1 or 1.2 ports".to_string());
+    }
+
+    let service = children(root, WSDL, "service").next();
+    let name = root
+        .attribute("name")
+        .or_else(|| service.and_then(|s| s.attribute("name")))
+        .unwrap_or("WSDL import");
+    let description = documentation(root).or_else(|| service.and_then(documentation));
+
+    Ok(Collection {
+        info: CollectionInfo {
+            name: name.to_string(),
+            description: description.map(Description::from),
+            schema: Some("https://schema.getpostman.com/json/collection/v2.1.0/collection.json".to_string()),
+            postman_id: None,
+            exporter_id: None,
+            extra: Default::default(),
+        },
+        item: folders,
+        variable: None,
+        event: None,
+        auth: None,
+        extra: Default::default(),
+    })
+}
+
+struct Wsdl<'a, 'input> {
+    root: Node<'a, 'input>,
+    schemas: Vec<Node<'a, 'input>>,
+}
+
+impl<'a, 'input> Wsdl<'a, 'input> {
+    /// A top-level WSDL definition such as a message or binding, by the
+    /// local part of its name. Imported WSDL documents are not followed.
+    fn definition(&self, kind: &'static str, qname: &str) -> Option<Node<'a, 'input>> {
+        let name = local_name(qname);
+        children(self.root, WSDL, kind).find(|node| node.attribute("name") == Some(name))
+    }
+
+    /// A top-level schema component (`element`, `complexType` or
+    /// `simpleType`), preferring the schema for its namespace.
+    fn component(&self, kind: &'static str, namespace: Option<&str>, name: &str) -> Option<Node<'a, 'input>> {
+        let find = |schema: &Node<'a, 'input>| children(*schema, XSD, kind).find(|n| n.attribute("name") == Some(name));
+        self.schemas
+            .iter()
+            .filter(|schema| schema.attribute("targetNamespace") == namespace)
+            .find_map(find)
+            .or_else(|| self.schemas.iter().find_map(find))
+    }
+
+    fn port_folder(&self, port: Node) -> Option<CollectionItem> {
+        let binding = self.definition("binding", port.attribute("binding")?)?;
+        let version = if children(binding, SOAP11_BINDING, "binding").next().is_some() {
+            SoapVersion::Soap11
+        } else if children(binding, SOAP12_BINDING, "binding").next().is_some() {
+            SoapVersion::Soap12
+        } else {
+            return None;
+        };
+        let soap = version.binding_namespace();
+        let location = children(port, soap, "address")
+            .next()
+            .and_then(|address| address.attribute("location"))
+            .unwrap_or("");
+        let style = children(binding, soap, "binding")
+            .next()
+            .and_then(|b| b.attribute("style"))
+            .unwrap_or("document");
+        let port_type = binding.attribute("type").and_then(|t| self.definition("portType", t));
+
+        let mut items = Vec::new();
+        for operation in children(binding, WSDL, "operation") {
+            let name = operation.attribute("name").unwrap_or("");
+            let abstract_operation = port_type.and_then(|port_type| {
+                children(port_type, WSDL, "operation").find(|o| o.attribute("name") == Some(name))
+            });
+            let soap_operation = children(operation, soap, "operation").next();
+            let action = soap_operation.and_then(|o| o.attribute("soapAction")).unwrap_or("");
+            let style = soap_operation.and_then(|o| o.attribute("style")).unwrap_or(style);
+            let envelope = self.envelope(version, style, operation, abstract_operation);
+            let description = abstract_operation.and_then(documentation);
+            items.push(soap_item(name, location, version, action, envelope, description));
+        }
+
+        let version_name = match version {
+            SoapVersion::Soap11 => "SOAP 1.1",
+            SoapVersion::Soap12 => "SOAP 1.2",
+        };
+        Some(CollectionItem {
+            name: port.attribute("name").unwrap_or("").to_string(),
+            item: Some(items),
+            request: None,
+            response: None,
+            event: None,
+            description: Some(format!("{} port at {}", version_name, location)).map(Description::from),
+            auth: None,
+            variable: None,
+            extra: Default::default(),
+        })
+    }
+
+    /// A sample request envelope for a binding operation, with placeholder
+    /// values for every element its input message declares.
+    fn envelope(&self, version: SoapVersion, style: &str, operation: Node, abstract_operation: Option<Node>) -> String {
+        let soap = version.binding_namespace();
+        let input = children(operation, WSDL, "input").next();
+        let message = abstract_operation
+            .and_then(|o| children(o, WSDL, "input").next())
+            .and_then(|input| input.attribute("message"))
+            .and_then(|message| self.definition("message", message));
+        let soap_body = input.and_then(|input| children(input, soap, "body").next());
+        let body_parts: Option<Vec<&str>> = soap_body
+            .and_then(|body| body.attribute("parts"))
+            .map(|parts| parts.split_whitespace().collect());
+
+        let mut writer = XmlWriter::new(self);
+        let mut header = String::new();
+        for soap_header in input.into_iter().flat_map(|input| children(input, soap, "header")) {
+            let part = soap_header
+                .attribute("message")
+                .and_then(|m| self.definition("message", m))
+                .and_then(|m| {
+                    let part = soap_header.attribute("part")?;
+                    children(m, WSDL, "part").find(|p| p.attribute("name") == Some(part))
+                });
+            if let Some(part) = part {
+                writer.part(&mut header, part, 2, false);
+            }
+        }
+
+        let mut body = String::new();
+        let parts: Vec<Node> = message
+            .into_iter()
+            .flat_map(|message| children(message, WSDL, "part"))
+            .filter(|part| {
+                body_parts
+                    .as_ref()
+                    .is_none_or(|names| names.contains(&part.attribute("name").unwrap_or("")))
+            })
+            .collect();
+        if style == "rpc" {
+            let namespace = soap_body
+                .and_then(|body| body.attribute("namespace"))
+                .or_else(|| self.root.attribute("targetNamespace"))
+                .unwrap_or("");
+            let name = writer.name(Some(namespace), operation.attribute("name").unwrap_or(""));
+            if parts.is_empty() {
+                body.push_str(&format!("    <{}/>\n", name));
+            } else {
+                body.push_str(&format!("    <{}>\n", name));
+                for part in parts {
+                    writer.part(&mut body, part, 3, true);
+                }
+                body.push_str(&format!("    </{}>\n", name));
+            }
+        } else {
+            for part in parts {
+                writer.part(&mut body, part, 2, false);
+            }
+        }
+
+        let envelope_name = match version {
+            SoapVersion::Soap11 => "soapenv",
+            SoapVersion::Soap12 => "soap",
+        };
+        let mut declarations = format!(" xmlns:{}=\"{}\"", envelope_name, version.envelope_namespace());
+        for (namespace, prefix) in &writer.prefixes {
+            declarations.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape(namespace)));
+        }
+        let header = match header.is_empty() {
+            true => format!("  <{}:Header/>\n", envelope_name),
+            false => format!("  <{e}:Header>\n{}  </{e}:Header>\n", header, e = envelope_name),
+        };
+        format!(
+            "<{e}:Envelope{}>\n{}  <{e}:Body>\n{}  </{e}:Body>\n</{e}:Envelope>",
+            declarations,
+            header,
+            body,
+            e = envelope_name
+        )
+    }
+}
+
+/// Writes sample XML for schema elements, collecting the namespace prefixes
+/// it uses for the envelope to declare.
+struct XmlWriter<'w, 'a, 'input> {
+    wsdl: &'w Wsdl<'a, 'input>,
+    prefixes: Vec<(String, String)>,
+    /// Named types being expanded, to stop at recursive ones.
+    expanding: Vec<String>,
+}
+
+impl<'w, 'a, 'input> XmlWriter<'w, 'a, 'input> {
+    fn new(wsdl: &'w Wsdl<'a, 'input>) -> Self {
+        XmlWriter {
+            wsdl,
+            prefixes: Vec::new(),
+            expanding: Vec::new(),
+        }
+    }
+
+    /// An element name with the prefix for its namespace, which is the one
+    /// the WSDL uses if it declares one.
+    fn name(&mut self, namespace: Option<&str>, name: &str) -> String {
+        let Some(namespace) = namespace.filter(|ns| !ns.is_empty()) else {
+            return name.to_string();
+        };
+        if let Some((_, prefix)) = self.prefixes.iter().find(|(ns, _)| ns == namespace) {
+            return format!("{}:{}", prefix, name);
+        }
+        let declared = self
+            .wsdl
+            .root
+            .namespaces()
+            .find(|ns| ns.uri() == namespace)
+            .and_then(|ns| ns.name())
+            .filter(|prefix| !self.prefixes.iter().any(|(_, p)| p == prefix) && !prefix.starts_with("soap"));
+        let prefix = declared
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("ns{}", self.prefixes.len() + 1));
+        self.prefixes.push((namespace.to_string(), prefix.clone()));
+        format!("{}:{}", prefix, name)
+    }
+
+    /// A message part: its element, or for `type` parts (and all rpc
+    /// parts) an unqualified element named after the part.
+    fn part(&mut self, out: &mut String, part: Node, depth: usize, rpc: bool) {
+        if let (Some(element), false) = (part.attribute("element"), rpc) {
+            let (namespace, name) = resolve(part, element);
+            if let Some(element) = self.wsdl.component("element", namespace.as_deref(), name) {
+                self.element(out, element, depth);
+            }
+            return;
+        }
+        let name = part.attribute("name").unwrap_or("");
+        let content = match part.attribute("type").or_else(|| part.attribute("element")) {
+            Some(qname) => self.typed_content(part, qname, depth),
+            None => Content::Text("?".to_string()),
+        };
+        self.write(out, name, content, depth);
+    }
+
+    fn element(&mut self, out: &mut String, element: Node, depth: usize) {
+        let element = match element.attribute("ref") {
+            Some(reference) => {
+                let (namespace, name) = resolve(element, reference);
+                match self.wsdl.component("element", namespace.as_deref(), name) {
+                    Some(target) => {
+                        self.occurs(out, element, depth);
+                        return self.declared_element(out, target, depth);
+                    }
+                    None => return,
+                }
+            }
+            None => element,
+        };
+        self.occurs(out, element, depth);
+        self.declared_element(out, element, depth);
+    }
+
+    fn occurs(&self, out: &mut String, element: Node, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let min = element.attribute("minOccurs").unwrap_or("1");
+        match element.attribute("maxOccurs").unwrap_or("1") {
+            "1" if min == "0" => out.push_str(&format!("{}<!--Optional:-->\n", indent)),
+            "1" => {}
+            _ if min == "0" => out.push_str(&format!("{}<!--Zero or more repetitions:-->\n", indent)),
+            _ => out.push_str(&format!("{}<!--1 or more repetitions:-->\n", indent)),
+        }
+    }
+
+    fn declared_element(&mut self, out: &mut String, element: Node, depth: usize) {
+        let schema = schema_of(element);
+        let top_level = element.parent_element().is_some_and(|parent| is(parent, XSD, "schema"));
+        let qualified = match element.attribute("form") {
+            Some(form) => form == "qualified",
+            None => top_level || schema.and_then(|s| s.attribute("elementFormDefault")) == Some("qualified"),
+        };
+        let namespace = schema
+            .and_then(|s| s.attribute("targetNamespace"))
+            .filter(|_| qualified);
+        let name = self.name(namespace, element.attribute("name").unwrap_or(""));
+
+        let content = if depth > MAX_DEPTH {
+            Content::Empty
+        } else if let Some(qname) = element.attribute("type") {
+            self.typed_content(element, qname, depth)
+        } else if let Some(complex) = children(element, XSD, "complexType").next() {
+            self.complex_content(complex, depth)
+        } else if let Some(simple) = children(element, XSD, "simpleType").next() {
+            Content::Text(self.simple_sample(simple))
+        } else {
+            Content::Text(
+                element
+                    .attribute("fixed")
+                    .or(element.attribute("default"))
+                    .unwrap_or("?")
+                    .to_string(),
+            )
+        };
+        let content = match (element.attribute("fixed").or(element.attribute("default")), content) {
+            (Some(value), Content::Text(_)) => Content::Text(value.to_string()),
+            (_, content) => content,
+        };
+        self.write(out, &name, content, depth);
+    }
+
+    fn write(&self, out: &mut String, name: &str, content: Content, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let (attributes, text) = match content {
+            Content::Empty => (String::new(), None),
+            Content::Text(text) => (String::new(), Some(text)),
+            Content::Simple { attributes, text } => (attributes, Some(text)),
+            Content::Complex { attributes, children } if children.is_empty() => (attributes, None),
+            Content::Complex { attributes, children } => {
+                out.push_str(&format!(
+                    "{}<{n}{}>\n{}{}</{n}>\n",
+                    indent,
+                    attributes,
+                    children,
+                    indent,
+                    n = name
+                ));
+                return;
+            }
+        };
+        match text {
+            Some(text) => out.push_str(&format!(
+                "{}<{n}{}>{}</{n}>\n",
+                indent,
+                attributes,
+                escape(&text),
+                n = name
+            )),
+            None => out.push_str(&format!("{}<{}{}/>\n", indent, name, attributes)),
+        }
+    }
+
+    /// Content for a `type` reference: a built-in XML Schema type or a named
+    /// simple or complex type.
+    fn typed_content(&mut self, context: Node, qname: &str, depth: usize) -> Content {
+        let (namespace, name) = resolve(context, qname);
+        if namespace.as_deref() == Some(XSD) {
+            return Content::Text(builtin_sample(name).to_string());
+        }
+        if let Some(simple) = self.wsdl.component("simpleType", namespace.as_deref(), name) {
+            return Content::Text(self.simple_sample(simple));
+        }
+        let Some(complex) = self.wsdl.component("complexType", namespace.as_deref(), name) else {
+            return Content::Text("?".to_string());
+        };
+        let key = format!("{}#{}", namespace.unwrap_or_default(), name);
+        if self.expanding.contains(&key) {
+            return Content::Empty;
+        }
+        self.expanding.push(key);
+        let content = self.complex_content(complex, depth);
+        self.expanding.pop();
+        content
+    }
+
+    fn complex_content(&mut self, complex: Node, depth: usize) -> Content {
+        let mut attributes = String::new();
+        let mut body = String::new();
+        self.particles(&mut body, &mut attributes, complex, depth + 1);
+
+        let simple_content = children(complex, XSD, "simpleContent")
+            .flat_map(|c| c.children().filter(Node::is_element))
+            .next();
+        if let Some(extension) = simple_content {
+            self.attributes(&mut attributes, extension);
+            let text = match extension
+                .attribute("base")
+                .map(|base| self.typed_content(extension, base, depth))
+            {
+                Some(Content::Text(text)) => text,
+                _ => "?".to_string(),
+            };
+            return Content::Simple { attributes, text };
+        }
+        Content::Complex {
+            attributes,
+            children: body,
+        }
+    }
+
+    /// The elements and attributes of a complex type, a `complexContent`
+    /// extension (base type first) or a model group.
+    fn particles(&mut self, out: &mut String, attributes: &mut String, node: Node, depth: usize) {
+        for child in node.children().filter(Node::is_element) {
+            if child.tag_name().namespace() != Some(XSD) {
+                continue;
+            }
+            match child.tag_name().name() {
+                "sequence" | "all" => self.particles(out, attributes, child, depth),
+                "choice" => {
+                    let count = child.children().filter(Node::is_element).count();
+                    let indent = "  ".repeat(depth);
+                    out.push_str(&format!(
+                        "{}<!--You have a CHOICE of the next {} items at this level-->\n",
+                        indent, count
+                    ));
+                    self.particles(out, attributes, child, depth);
+                }
+                "element" => self.element(out, child, depth),
+                "group" => {
+                    let group = child.attribute("ref").and_then(|reference| {
+                        let (namespace, name) = resolve(child, reference);
+                        self.wsdl.component("group", namespace.as_deref(), name)
+                    });
+                    if let Some(group) = group {
+                        self.particles(out, attributes, group, depth);
+                    }
+                }
+                "any" => out.push_str(&format!(
+                    "{}<!--You may enter ANY elements at this point-->\n",
+                    "  ".repeat(depth)
+                )),
+                "complexContent" => self.particles(out, attributes, child, depth),
+                "extension" | "restriction" => {
+                    if child.tag_name().name() == "extension" {
+                        if let Some(base) = child.attribute("base") {
+                            let (namespace, name) = resolve(child, base);
+                            if let Some(base) = self.wsdl.component("complexType", namespace.as_deref(), name) {
+                                self.particles(out, attributes, base, depth);
+                            }
+                        }
+                    }
+                    self.particles(out, attributes, child, depth);
+                }
+                "attribute" => self.attribute(attributes, child),
+                _ => {}
+            }
+        }
+    }
+
+    fn attributes(&mut self, out: &mut String, node: Node) {
+        for attribute in children(node, XSD, "attribute") {
+            self.attribute(out, attribute);
+        }
+    }
+
+    fn attribute(&mut self, out: &mut String, attribute: Node) {
+        if attribute.attribute("use") == Some("prohibited") {
+            return;
+        }
+        let Some(name) = attribute.attribute("name") else {
+            return;
+        };
+        let value = match (
+            attribute.attribute("fixed").or(attribute.attribute("default")),
+            attribute.attribute("type"),
+        ) {
+            (Some(value), _) => value.to_string(),
+            (None, Some(qname)) => match resolve(attribute, qname) {
+                (Some(namespace), name) if namespace == XSD => builtin_sample(name).to_string(),
+                (namespace, name) => match self.wsdl.component("simpleType", namespace.as_deref(), name) {
+                    Some(simple) => self.simple_sample(simple),
+                    None => "?".to_string(),
+                },
+            },
+            (None, None) => "?".to_string(),
+        };
+        out.push_str(&format!(" {}=\"{}\"", name, escape(&value)));
+    }
+
+    /// The first enumeration value of a simple type, or a sample of its base.
+    fn simple_sample(&mut self, simple: Node) -> String {
+        let Some(restriction) = children(simple, XSD, "restriction").next() else {
+            return "?".to_string();
+        };
+        if let Some(value) = children(restriction, XSD, "enumeration").find_map(|e| e.attribute("value")) {
+            return value.to_string();
+        }
+        match restriction.attribute("base") {
+            Some(base) => match self.typed_content(restriction, base, 0) {
+                Content::Text(text) => text,
+                _ => "?".to_string(),
+            },
+            None => "?".to_string(),
+        }
+    }
+}
+
+enum Content {
+    Empty,
+    Text(String),
+    /// Text with attributes, from `simpleContent`.
+    Simple {
+        attributes: String,
+        text: String,
+    },
+    Complex {
+        attributes: String,
+        children: String,
+    },
+}
+
+fn builtin_sample(name: &str) -> &'static str {
+    match name {
+        "boolean" => "false",
+        "int" | "integer" | "long" | "short" | "byte" | "decimal" | "nonNegativeInteger" | "unsignedInt"
+        | "unsignedLong" | "unsignedShort" | "unsignedByte" => "0",
+        "positiveInteger" => "1",
+        "negativeInteger" | "nonPositiveInteger" => "-1",
+        "float" | "double" => "0.0",
+        "dateTime" => "2000-01-01T00:00:00Z",
+        "date" => "2000-01-01",
+        "time" => "00:00:00",
+        "duration" => "P0D",
+        "base64Binary" | "hexBinary" => "",
+        "string" | "normalizedString" | "token" => "string",
+        _ => "?",
+    }
+}
+
+fn soap_item(
+    name: &str,
+    location: &str,
+    version: SoapVersion,
+    action: &str,
+    envelope: String,
+    description: Option<String>,
+) -> CollectionItem {
+    let header = |key: &str, value: String| Header {
+        key: key.to_string(),
+        value,
+        disabled: None,
+        description: None,
+        extra: Default::default(),
+    };
+    // SOAP 1.2 moves the action into the media type.
+    let headers = match version {
+        SoapVersion::Soap11 => vec![
+            header("Content-Type", "text/xml; charset=utf-8".to_string()),
+            header("SOAPAction", format!("\"{}\"", action)),
+        ],
+        SoapVersion::Soap12 if action.is_empty() => {
+            vec![header(
+                "Content-Type",
+                "application/soap+xml; charset=utf-8".to_string(),
+            )]
+        }
+        SoapVersion::Soap12 => vec![header(
+            "Content-Type",
+            format!("application/soap+xml; charset=utf-8; action=\"{}\"", action),
+        )],
+    };
+
+    let mut body = Body {
+        mode: Some("raw".to_string()),
+        raw: Some(envelope),
+        ..Body::empty()
+    };
+    body.extra
+        .insert("options".to_string(), json!({ "raw": { "language": "xml" } }));
+
+    CollectionItem {
+        name: name.to_string(),
+        item: None,
+        request: Some(Request {
+            method: Some("POST".to_string()),
+            header: Some(headers),
+            body: Some(body),
+            url: Some(Url::parse(location)),
+            description: description.clone().map(Description::from),
+            auth: None,
+            options: None,
+            extra: Default::default(),
+        }),
+        response: None,
+        event: None,
+        description: description.map(Description::from),
+        auth: None,
+        variable: None,
+        extra: Default::default(),
+    }
+}
+
+/// Reads a SOAP 1.1 or 1.2 response envelope, extracting the fault if the
+/// body holds one.
+pub fn parse_soap_response(text: &str) -> Result<SoapResponse, String> {
+    let document = Document::parse(text).map_err(|e| e.to_string())?;
+    let envelope = document.root_element();
+    let version = match envelope.tag_name().namespace() {
+        Some(SOAP11_ENVELOPE) if envelope.tag_name().name() == "Envelope" => SoapVersion::Soap11,
+        Some(SOAP12_ENVELOPE) if envelope.tag_name().name() == "Envelope" => SoapVersion::Soap12,
+        _ => return Err("Not a SOAP envelope".to_string()),
+    };
+    let namespace = version.envelope_namespace();
+    let body = children(envelope, namespace, "Body")
+        .next()
+        .ok_or_else(|| "The SOAP envelope has no Body".to_string())?;
+
+    let fault = children(body, namespace, "Fault").next().map(|fault| match version {
+        // SOAP 1.1 fault children are unqualified.
+        SoapVersion::Soap11 => {
+            let field = |name: &str| local_child(fault, name).map(|n| n.text().unwrap_or("").trim().to_string());
+            SoapFault {
+                code: field("faultcode").unwrap_or_default(),
+                subcodes: Vec::new(),
+                reason: field("faultstring").unwrap_or_default(),
+                role: field("faultactor"),
+                node: None,
+                detail: local_child(fault, "detail").map(|detail| inner_xml(text, detail)),
+            }
+        }
+        SoapVersion::Soap12 => {
+            let value = |code: Node| {
+                children(code, namespace, "Value")
+                    .next()
+                    .and_then(|v| v.text())
+                    .unwrap_or("")
+                    .trim()
+                    .to_string()
+            };
+            let code = children(fault, namespace, "Code").next();
+            let mut subcodes = Vec::new();
+            let mut subcode = code.and_then(|c| children(c, namespace, "Subcode").next());
+            while let Some(node) = subcode {
+                subcodes.push(value(node));
+                subcode = children(node, namespace, "Subcode").next();
+            }
+            let text_of = |name: &'static str| {
+                children(fault, namespace, name)
+                    .next()
+                    .map(|n| n.text().unwrap_or("").trim().to_string())
+            };
+            SoapFault {
+                code: code.map(value).unwrap_or_default(),
+                subcodes,
+                reason: children(fault, namespace, "Reason")
+                    .next()
+                    .and_then(|reason| children(reason, namespace, "Text").next())
+                    .and_then(|text| text.text())
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                role: text_of("Role"),
+                node: text_of("Node"),
+                detail: children(fault, namespace, "Detail")
+                    .next()
+                    .map(|detail| inner_xml(text, detail)),
+            }
+        }
+    });
+
+    Ok(SoapResponse {
+        version,
+        body: inner_xml(text, body),
+        fault,
+    })
+}
+
+fn is(node: Node, namespace: &str, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == Some(namespace) && node.tag_name().name() == name
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    namespace: &'static str,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| is(*child, namespace, name))
+}
+
+fn local_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn schema_of<'a, 'input>(node: Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    node.ancestors().find(|ancestor| is(*ancestor, XSD, "schema"))
+}
+
+fn documentation(node: Node) -> Option<String> {
+    children(node, WSDL, "documentation")
+        .next()
+        .and_then(|doc| doc.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn local_name(qname: &str) -> &str {
+    qname.rsplit(':').next().unwrap_or(qname)
+}
+
+/// Resolves a `prefix:name` attribute value against the namespaces in
+/// scope at `node`.
+fn resolve<'q>(node: Node, qname: &'q str) -> (Option<String>, &'q str) {
+    let (prefix, name) = match qname.split_once(':') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, qname),
+    };
+    (node.lookup_namespace_uri(prefix).map(str::to_string), name)
+}
+
+fn inner_xml(text: &str, node: Node) -> String {
+    match (node.first_child(), node.last_child()) {
+        (Some(first), Some(last)) => text[first.range().start..last.range().end].trim().to_string(),
+        _ => String::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Import a WSDL 1.1 document as a Postman collection of SOAP requests.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn wsdl_to_collection_json(wsdl_text: *const c_char) -> *mut c_char {
+    ffi::respond(
+        unsafe { ffi::arg(wsdl_text, "wsdl_text") }
+            .and_then(|text| wsdl_to_collection(text).map_err(|e| FfiError::parse("WSDL document", e))),
+    )
+}
+
+/// Parse a SOAP response body. Returns `{"version": "1.1", "body": "...",
+/// "fault": null}`, with the fault's code, reason and detail in `fault` when
+/// the server returned one.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn parse_soap_response_json(body_text: *const c_char) -> *mut c_char {
+    ffi::respond(
+        unsafe { ffi::arg(body_text, "body_text") }
+            .and_then(|text| parse_soap_response(text).map_err(|e| FfiError::parse("SOAP response", e))),
+    )
+}
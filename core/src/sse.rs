@@ -0,0 +1,475 @@
+//! Server-Sent Events client. Each connection runs on its own thread and
+//! delivers events as they arrive, either to a callback or to a queue the
+//! caller polls. Dropped streams are reopened with `Last-Event-ID`, as an
+//! `EventSource` would.
+
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::request::Request;
+use crate::PreparedRequest;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::watch;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+static STREAMS: Mutex<Option<HashMap<u32, SseStream>>> = Mutex::new(None);
+
+/// Receives each message of stream `id` as JSON. The string is only valid
+/// for the duration of the call. Called from the stream's own thread.
+pub type SseCallback = extern "C" fn(id: u32, message_json: *const c_char);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SseOptions {
+    /// Sent as `Last-Event-ID` on the first connection.
+    pub last_event_id: Option<String>,
+    /// Reopen the stream when it ends or the connection drops.
+    pub reconnect: bool,
+    /// Delay before reconnecting, until the server sets one with `retry:`.
+    pub retry_ms: u64,
+    /// Give up after this many reconnects in a row without receiving an
+    /// event. 0 means never.
+    pub max_retries: u32,
+}
+
+impl Default for SseOptions {
+    fn default() -> Self {
+        SseOptions {
+            last_event_id: None,
+            reconnect: true,
+            retry_ms: 3000,
+            max_retries: 0,
+        }
+    }
+}
+
+/// One dispatched event. `id` is the last event ID seen on the stream, which
+/// carries over to later events that do not set their own.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+    pub id: String,
+    /// Reconnection delay set by a `retry:` field in this event's block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SseMessage {
+    Open { status_code: u16, headers: Vec<(String, String)> },
+    Event(SseEvent),
+    Reconnecting { delay_ms: u64, last_event_id: String },
+    Error { error: FfiError },
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SseState {
+    Connecting,
+    Open,
+    Closed,
+}
+
+/// Splits an `text/event-stream` body into events. Input may be fed in
+/// arbitrary chunks; lines may end in CRLF, LF or CR.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    after_cr: bool,
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    retry: Option<u64>,
+    pub last_event_id: String,
+    /// The latest `retry:` value, which outlives the event it came with.
+    pub reconnection_ms: Option<u64>,
+}
+
+impl SseParser {
+    pub fn new(last_event_id: String) -> Self {
+        SseParser {
+            last_event_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            if self.after_cr {
+                self.after_cr = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+            match byte {
+                b'\r' | b'\n' => {
+                    self.after_cr = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+                }
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    /// Forgets a partly received event, as when the connection drops.
+    pub fn reset(&mut self) {
+        *self = SseParser {
+            last_event_id: std::mem::take(&mut self.last_event_id),
+            reconnection_ms: self.reconnection_ms,
+            ..Default::default()
+        };
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        let mut line = String::from_utf8_lossy(line).into_owned();
+        if !self.started {
+            self.started = true;
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_string();
+            }
+        }
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_event_id = value.to_string(),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+                self.reconnection_ms = self.retry.or(self.reconnection_ms);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        let mut data = std::mem::take(&mut self.data);
+        let retry = self.retry.take();
+        if !std::mem::take(&mut self.has_data) {
+            return None;
+        }
+        data.pop();
+        Some(SseEvent {
+            event: if event.is_empty() { "message".to_string() } else { event },
+            data,
+            id: self.last_event_id.clone(),
+            retry,
+        })
+    }
+}
+
+struct Shared {
+    state: SseState,
+    queue: VecDeque<SseMessage>,
+    callback: Option<SseCallback>,
+}
+
+struct Connection {
+    id: u32,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Connection {
+    fn set_state(&self, state: SseState) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.state = state;
+        }
+    }
+
+    fn deliver(&self, message: SseMessage) {
+        let callback = match self.shared.lock() {
+            Ok(mut shared) => match shared.callback {
+                Some(callback) => callback,
+                None => {
+                    shared.queue.push_back(message);
+                    return;
+                }
+            },
+            Err(_) => return,
+        };
+        if let Ok(text) = serde_json::to_string(&message) {
+            if let Ok(text) = CString::new(text) {
+                callback(self.id, text.as_ptr());
+            }
+        }
+    }
+}
+
+pub struct SseStream {
+    shared: Arc<Mutex<Shared>>,
+    stop: watch::Sender<bool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SseStream {
+    pub fn open(id: u32, request: Request, options: SseOptions, callback: Option<SseCallback>) -> Self {
+        let mut stream = SseStream::new(callback);
+        stream.start(id, request, options);
+        stream
+    }
+
+    /// A stream that has not connected yet; `start` opens it.
+    fn new(callback: Option<SseCallback>) -> Self {
+        let shared = Arc::new(Mutex::new(Shared {
+            state: SseState::Connecting,
+            queue: VecDeque::new(),
+            callback,
+        }));
+        let (stop, _) = watch::channel(false);
+        SseStream {
+            shared,
+            stop,
+            thread: None,
+        }
+    }
+
+    /// Spawns the thread that reads the stream. Kept apart from `new` so the
+    /// registry holds the stream before its first message is delivered.
+    fn start(&mut self, id: u32, request: Request, options: SseOptions) {
+        let stopped = self.stop.subscribe();
+        let connection = Connection {
+            id,
+            shared: Arc::clone(&self.shared),
+        };
+        self.thread = Some(std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    connection.deliver(SseMessage::Error { error: FfiError::internal(e) });
+                    connection.deliver(SseMessage::Closed);
+                    connection.set_state(SseState::Closed);
+                    return;
+                }
+            };
+            runtime.block_on(run(&connection, &request, &options, stopped));
+            connection.set_state(SseState::Closed);
+            connection.deliver(SseMessage::Closed);
+        }));
+    }
+
+    /// Takes the messages received since the last poll.
+    pub fn poll(&self) -> (SseState, Vec<SseMessage>) {
+        match self.shared.lock() {
+            Ok(mut shared) => (shared.state, shared.queue.drain(..).collect()),
+            Err(_) => (SseState::Closed, Vec::new()),
+        }
+    }
+
+    pub fn close(mut self) {
+        let _ = self.stop.send(true);
+        if let Some(thread) = self.thread.take() {
+            // From a callback this runs on the reader thread, which stops by
+            // itself once the callback returns.
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+/// How a single connection attempt ended.
+enum Outcome {
+    /// The stream ended or dropped after connecting; try again.
+    Dropped { received: bool },
+    /// A failure the spec says not to retry, such as a non-200 status.
+    Failed,
+    Stopped,
+}
+
+async fn run(connection: &Connection, request: &Request, options: &SseOptions, mut stopped: watch::Receiver<bool>) {
+    let client = reqwest::Client::new();
+    let mut parser = SseParser::new(options.last_event_id.clone().unwrap_or_default());
+    let mut retries = 0;
+
+    loop {
+        connection.set_state(SseState::Connecting);
+        let outcome = tokio::select! {
+            outcome = connect(connection, &client, request, &mut parser) => outcome,
+            _ = stopped.changed() => Outcome::Stopped,
+        };
+        match outcome {
+            Outcome::Dropped { received } if options.reconnect => {
+                retries = if received { 1 } else { retries + 1 };
+                if options.max_retries > 0 && retries > options.max_retries {
+                    return;
+                }
+            }
+            _ => return,
+        }
+
+        parser.reset();
+        let retry_ms = parser.reconnection_ms.unwrap_or(options.retry_ms);
+        connection.deliver(SseMessage::Reconnecting {
+            delay_ms: retry_ms,
+            last_event_id: parser.last_event_id.clone(),
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(retry_ms)) => {}
+            _ = stopped.changed() => return,
+        }
+    }
+}
+
+async fn connect(
+    connection: &Connection,
+    client: &reqwest::Client,
+    request: &Request,
+    parser: &mut SseParser,
+) -> Outcome {
+    let builder = PreparedRequest::new(request).map_err(FfiError::from).and_then(|mut prepared| {
+        prepared.default_header("Accept", "text/event-stream");
+        prepared.default_header("Cache-Control", "no-cache");
+        if !parser.last_event_id.is_empty() {
+            prepared.headers.retain(|(key, _)| !key.eq_ignore_ascii_case("last-event-id"));
+            prepared.headers.push(("Last-Event-ID".to_string(), parser.last_event_id.clone()));
+        }
+        let url = prepared.url.clone();
+        Ok((prepared.into_builder(client)?, url))
+    });
+    let (builder, url) = match builder {
+        Ok(built) => built,
+        Err(error) => {
+            connection.deliver(SseMessage::Error { error });
+            return Outcome::Failed;
+        }
+    };
+
+    let mut response = match builder.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let error = FfiError::from_request_error(&e, Some(&url));
+            connection.deliver(SseMessage::Error { error });
+            return Outcome::Dropped { received: false };
+        }
+    };
+
+    let status_code = response.status().as_u16();
+    let headers = crate::response_headers(&response);
+    let content_type = crate::header_value(&headers, "content-type").unwrap_or("").to_string();
+    if status_code == 204 {
+        // 204 is how a server tells the client to stop reconnecting.
+        return Outcome::Failed;
+    }
+    if status_code != 200 || !content_type.to_ascii_lowercase().starts_with("text/event-stream") {
+        let message = format!("Expected a 200 text/event-stream response, got {} '{}'", status_code, content_type);
+        let error = FfiError::new(ErrorCategory::Network, "not_an_event_stream", message)
+            .with_details(json!({ "status_code": status_code, "content_type": content_type, "url": url }));
+        connection.deliver(SseMessage::Error { error });
+        return Outcome::Failed;
+    }
+
+    connection.set_state(SseState::Open);
+    connection.deliver(SseMessage::Open { status_code, headers });
+
+    let mut received = false;
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                for event in parser.feed(&chunk) {
+                    received = true;
+                    connection.deliver(SseMessage::Event(event));
+                }
+            }
+            Ok(None) => return Outcome::Dropped { received },
+            Err(e) => {
+                let error = FfiError::from_request_error(&e, Some(&url));
+                connection.deliver(SseMessage::Error { error });
+                return Outcome::Dropped { received };
+            }
+        }
+    }
+}
+
+fn not_found(id: u32) -> FfiError {
+    FfiError::new(ErrorCategory::Argument, "sse_stream_not_found", format!("No event stream with id {}", id))
+}
+
+/// Open a Server-Sent Events stream for a request.
+///
+/// `options_json` may be null; see `SseOptions`. If `callback` is not null,
+/// every message is passed to it as it arrives; otherwise messages are
+/// queued for `sse_poll`. Returns `{"id": 1}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn sse_connect(
+    request_json: *const c_char,
+    options_json: *const c_char,
+    callback: Option<SseCallback>,
+) -> *mut c_char {
+    let result = (|| {
+        let request: Request = unsafe { ffi::json_arg(request_json, "request") }?;
+        let options: SseOptions = match unsafe { ffi::optional_arg(options_json, "options") }? {
+            Some(json) => serde_json::from_str(json).map_err(|e| FfiError::json("options", &e))?,
+            None => SseOptions::default(),
+        };
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let mut streams = STREAMS.lock().map_err(|_| FfiError::internal("Event stream registry is poisoned"))?;
+        let stream = streams.get_or_insert_with(HashMap::new).entry(id).or_insert(SseStream::new(callback));
+        stream.start(id, request, options);
+        Ok(json!({ "id": id }))
+    })();
+    ffi::respond(result)
+}
+
+/// Take the messages a stream received since the last call. Returns
+/// `{"state": "open", "messages": [...]}`.
+#[no_mangle]
+pub extern "C" fn sse_poll(id: u32) -> *mut c_char {
+    let streams = STREAMS.lock();
+    let result = match streams.as_ref().ok().and_then(|streams| streams.as_ref()?.get(&id)) {
+        Some(stream) => {
+            let (state, messages) = stream.poll();
+            Ok(json!({ "state": state, "messages": messages }))
+        }
+        None => Err(not_found(id)),
+    };
+    ffi::respond(result)
+}
+
+/// Stop a stream and release it. Returns `true`. This may be called from the
+/// stream's own callback.
+#[no_mangle]
+pub extern "C" fn sse_close(id: u32) -> *mut c_char {
+    let stream = STREAMS
+        .lock()
+        .ok()
+        .and_then(|mut streams| streams.as_mut().and_then(|s| s.remove(&id)));
+
+    ffi::respond(match stream {
+        Some(stream) => {
+            stream.close();
+            Ok(true)
+        }
+        None => Err(not_found(id)),
+    })
+}
@@ -0,0 +1,603 @@
+use crate::ffi::{self, FfiError};
+use crate::models::collection::Collection;
+use crate::models::environment::Environment;
+use crate::models::schema::{self, SchemaVersion};
+use crate::variables::find_references;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::os::raw::c_char;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The document does not match the Postman v2.1 schema.
+    Error,
+    /// Valid, but probably not what the author meant.
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub severity: Severity,
+    /// `json` or `schema` for errors, otherwise the lint rule that fired.
+    pub rule: String,
+    /// JSON pointer (RFC 6901) into the collection document (see
+    /// `ValidationReport::migrated_from`).
+    pub pointer: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// True when there are no errors; warnings do not make a collection invalid.
+    pub valid: bool,
+    /// `v1` or `v2.0` when the document was migrated before checking. Pointers
+    /// then refer to the migrated v2.1 document, not to the text as given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrated_from: Option<String>,
+    pub findings: Vec<Finding>,
+}
+
+/// Auth types defined by the v2.1 schema.
+pub const AUTH_TYPES: &[&str] = &[
+    "apikey", "awsv4", "basic", "bearer", "digest", "edgegrid", "hawk", "noauth", "oauth1", "oauth2", "ntlm",
+];
+
+const BODY_MODES: &[&str] = &["raw", "urlencoded", "formdata", "file", "graphql"];
+
+/// Headers that carry credentials whatever they are called.
+const SECRET_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie"];
+
+/// Header names containing one of these are assumed to carry credentials.
+const SECRET_WORDS: &[&str] = &["token", "secret", "password", "api-key", "apikey", "api_key"];
+
+/// Auth parameters that hold credentials (for `apikey`, only `value`).
+const SECRET_AUTH_PARAMS: &[&str] = &[
+    "token", "password", "accessToken", "refreshToken", "clientSecret", "secretKey", "sessionToken", "consumerSecret",
+    "tokenSecret", "authKey",
+];
+
+/// Script calls that define a variable named by their first argument.
+const VARIABLE_SETTERS: &[&str] = &[
+    "pm.environment.set(",
+    "pm.variables.set(",
+    "pm.collectionVariables.set(",
+    "pm.globals.set(",
+    "pm.iterationData.set(",
+    "postman.setEnvironmentVariable(",
+    "postman.setGlobalVariable(",
+];
+
+/// Checks a collection document against the Postman v2.1 schema and the lint
+/// rules: `duplicate-name`, `missing-url`, `unknown-auth`,
+/// `undefined-variable` and `hardcoded-secret`.
+///
+/// `{{name}}` references count as defined when they are a collection, folder
+/// or request variable, a value in `environment`, a dynamic variable such as
+/// `{{$guid}}`, or set by a script anywhere in the collection.
+pub fn validate_collection(text: &str, environment: Option<&Environment>) -> ValidationReport {
+    let mut validator = Validator::default();
+
+    let mut document: Value = match serde_json::from_str(text) {
+        Ok(document) => document,
+        Err(e) => {
+            validator.add(Severity::Error, "json", String::new(), format!("Invalid JSON: {}", e));
+            return validator.finish();
+        }
+    };
+
+    let version = schema::detect(&document);
+    if version != SchemaVersion::V21 {
+        let pointer = if document.pointer("/info/schema").is_some() { "/info/schema" } else { "" };
+        let name = if version == SchemaVersion::V1 { "v1" } else { "v2.0" };
+        validator.add(
+            Severity::Warning,
+            "schema-version",
+            pointer.to_string(),
+            format!("Collection uses the Postman {} format; findings refer to it after migration to v2.1", name),
+        );
+        document = schema::migrate_to_v21(document);
+        validator.migrated_from = Some(name.to_string());
+    }
+
+    collect_variables(&document, &mut validator.defined);
+    if let Some(values) = environment.and_then(|e| e.values.as_ref()) {
+        validator.defined.extend(values.iter().map(|v| v.key.clone()));
+    }
+
+    validator.check_root(&document);
+
+    if validator.findings.iter().all(|f| f.severity != Severity::Error) {
+        if let Err(e) = serde_json::from_value::<Collection>(document) {
+            validator.add(Severity::Error, "schema", String::new(), e.to_string());
+        }
+    }
+    validator.finish()
+}
+
+#[derive(Default)]
+struct Validator {
+    findings: Vec<Finding>,
+    defined: HashSet<String>,
+    migrated_from: Option<String>,
+}
+
+impl Validator {
+    fn add(&mut self, severity: Severity, rule: &str, pointer: String, message: String) {
+        self.findings.push(Finding { severity, rule: rule.to_string(), pointer, message });
+    }
+
+    fn error(&mut self, pointer: &str, message: String) {
+        self.add(Severity::Error, "schema", pointer.to_string(), message);
+    }
+
+    fn warn(&mut self, rule: &str, pointer: &str, message: String) {
+        self.add(Severity::Warning, rule, pointer.to_string(), message);
+    }
+
+    fn finish(self) -> ValidationReport {
+        ValidationReport {
+            valid: self.findings.iter().all(|f| f.severity != Severity::Error),
+            migrated_from: self.migrated_from,
+            findings: self.findings,
+        }
+    }
+
+    /// Returns `object[key]` when it has one of the allowed JSON types,
+    /// recording an error when it has another or is required but missing.
+    fn field<'v>(
+        &mut self,
+        object: &'v Map<String, Value>,
+        key: &str,
+        pointer: &str,
+        required: bool,
+        kinds: &[&str],
+    ) -> Option<&'v Value> {
+        match object.get(key) {
+            None => {
+                if required {
+                    self.error(pointer, format!("Missing required property '{}'", key));
+                }
+                None
+            }
+            Some(value) if kinds.contains(&kind(value)) => Some(value),
+            Some(value) => {
+                self.error(
+                    &child(pointer, key),
+                    format!("Expected {}, found {}", kinds.join(" or "), kind(value)),
+                );
+                None
+            }
+        }
+    }
+
+    fn object<'v>(&mut self, value: &'v Value, pointer: &str) -> Option<&'v Map<String, Value>> {
+        let object = value.as_object();
+        if object.is_none() {
+            self.error(pointer, format!("Expected object, found {}", kind(value)));
+        }
+        object
+    }
+
+    fn check_root(&mut self, document: &Value) {
+        let Some(root) = self.object(document, "") else { return };
+
+        if let Some(info) = self.field(root, "info", "", true, &["object"]).and_then(Value::as_object) {
+            self.field(info, "name", "/info", true, &["string"]);
+            self.field(info, "schema", "/info", true, &["string"]);
+            self.field(info, "description", "/info", false, &["string", "object", "null"]);
+        }
+        if let Some(items) = self.field(root, "item", "", true, &["array"]).and_then(Value::as_array) {
+            self.check_items(items, "/item");
+        }
+        self.check_common(root, "");
+    }
+
+    /// Fields shared by the collection, folders and requests.
+    fn check_common(&mut self, object: &Map<String, Value>, pointer: &str) {
+        if let Some(variables) = self.field(object, "variable", pointer, false, &["array"]).and_then(Value::as_array) {
+            self.check_variables(variables, &child(pointer, "variable"));
+        }
+        if let Some(events) = self.field(object, "event", pointer, false, &["array"]).and_then(Value::as_array) {
+            self.check_events(events, &child(pointer, "event"));
+        }
+        if let Some(auth) = self.field(object, "auth", pointer, false, &["object", "null"]) {
+            self.check_auth(auth, &child(pointer, "auth"), true);
+        }
+    }
+
+    fn check_items(&mut self, items: &[Value], pointer: &str) {
+        let mut names: HashMap<&str, usize> = HashMap::new();
+
+        for (index, item) in items.iter().enumerate() {
+            let pointer = child(pointer, &index.to_string());
+            let Some(object) = self.object(item, &pointer) else { continue };
+
+            if let Some(name) = self.field(object, "name", &pointer, false, &["string"]).and_then(Value::as_str) {
+                match names.get(name) {
+                    Some(first) => self.warn(
+                        "duplicate-name",
+                        &child(&pointer, "name"),
+                        format!("Another item in this folder (index {}) is also named '{}'", first, name),
+                    ),
+                    None => {
+                        names.insert(name, index);
+                    }
+                }
+            }
+            self.field(object, "description", &pointer, false, &["string", "object", "null"]);
+            self.check_common(object, &pointer);
+
+            if let Some(children) = object.get("item") {
+                match children.as_array() {
+                    Some(children) => self.check_items(children, &child(&pointer, "item")),
+                    None => self.error(&child(&pointer, "item"), format!("Expected array, found {}", kind(children))),
+                }
+            } else if let Some(request) = object.get("request") {
+                self.check_request(request, &child(&pointer, "request"), true);
+                if let Some(responses) = self.field(object, "response", &pointer, false, &["array"]).and_then(Value::as_array) {
+                    self.check_responses(responses, &child(&pointer, "response"));
+                }
+            } else {
+                self.error(&pointer, "Item must have either 'request' or 'item'".to_string());
+            }
+        }
+    }
+
+    /// `lint` is false for saved example requests, which are not sent.
+    fn check_request(&mut self, request: &Value, pointer: &str, lint: bool) {
+        let request = match request {
+            Value::String(url) => {
+                if lint {
+                    if url.trim().is_empty() {
+                        self.warn("missing-url", pointer, "Request has no URL".to_string());
+                    }
+                    self.check_references(request, pointer);
+                }
+                return;
+            }
+            Value::Object(request) => request,
+            other => {
+                self.error(pointer, format!("Expected string or object, found {}", kind(other)));
+                return;
+            }
+        };
+
+        self.field(request, "method", pointer, false, &["string"]);
+        self.field(request, "description", pointer, false, &["string", "object", "null"]);
+
+        let url = self.field(request, "url", pointer, false, &["string", "object"]);
+        let url_pointer = child(pointer, "url");
+        if let Some(Value::Object(url)) = url {
+            self.check_url(url, &url_pointer);
+        }
+        if lint {
+            match url {
+                Some(raw @ Value::String(text)) if !text.trim().is_empty() => self.check_references(raw, &url_pointer),
+                Some(parts @ Value::Object(fields)) if !url_is_empty(fields) => match fields.get("raw") {
+                    Some(raw) => self.check_references(raw, &child(&url_pointer, "raw")),
+                    None => self.check_references(parts, &url_pointer),
+                },
+                _ => self.warn("missing-url", pointer, "Request has no URL".to_string()),
+            }
+        }
+
+        if let Some(headers) = self.field(request, "header", pointer, false, &["array", "string", "null"]) {
+            self.check_headers(headers, &child(pointer, "header"), lint);
+        }
+        if let Some(body) = self.field(request, "body", pointer, false, &["object", "null"]) {
+            self.check_body(body, &child(pointer, "body"), lint);
+        }
+        if let Some(auth) = self.field(request, "auth", pointer, false, &["object", "null"]) {
+            self.check_auth(auth, &child(pointer, "auth"), lint);
+        }
+    }
+
+    fn check_url(&mut self, url: &Map<String, Value>, pointer: &str) {
+        self.field(url, "raw", pointer, false, &["string"]);
+        self.field(url, "protocol", pointer, false, &["string"]);
+        self.field(url, "host", pointer, false, &["string", "array"]);
+        self.field(url, "path", pointer, false, &["string", "array"]);
+        self.field(url, "port", pointer, false, &["string"]);
+        self.field(url, "hash", pointer, false, &["string"]);
+        if let Some(query) = self.field(url, "query", pointer, false, &["array"]).and_then(Value::as_array) {
+            let query_pointer = child(pointer, "query");
+            for (index, param) in query.iter().enumerate() {
+                let pointer = child(&query_pointer, &index.to_string());
+                if let Some(param) = self.object(param, &pointer) {
+                    self.field(param, "key", &pointer, false, &["string", "null"]);
+                    self.field(param, "value", &pointer, false, &["string", "null"]);
+                    self.field(param, "disabled", &pointer, false, &["boolean"]);
+                }
+            }
+        }
+        if let Some(variables) = self.field(url, "variable", pointer, false, &["array"]).and_then(Value::as_array) {
+            self.check_variables(variables, &child(pointer, "variable"));
+        }
+    }
+
+    fn check_headers(&mut self, headers: &Value, pointer: &str, lint: bool) {
+        let Value::Array(headers) = headers else {
+            if lint {
+                self.check_references(headers, pointer);
+            }
+            return;
+        };
+
+        for (index, header) in headers.iter().enumerate() {
+            let pointer = child(pointer, &index.to_string());
+            if header.is_string() {
+                continue;
+            }
+            let Some(object) = self.object(header, &pointer) else { continue };
+            let key = self.field(object, "key", &pointer, true, &["string"]).and_then(Value::as_str);
+            let value = self.field(object, "value", &pointer, true, &["string"]);
+            self.field(object, "disabled", &pointer, false, &["boolean"]);
+
+            if !lint || is_disabled(object) {
+                continue;
+            }
+            self.check_references(header, &pointer);
+            if let (Some(key), Some(Value::String(value))) = (key, value) {
+                if is_secret_header(key) && is_literal(value) {
+                    self.warn(
+                        "hardcoded-secret",
+                        &child(&pointer, "value"),
+                        format!("Header '{}' contains a literal credential; use a {{{{variable}}}}", key),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_body(&mut self, body: &Value, pointer: &str, lint: bool) {
+        let Some(body) = body.as_object() else { return };
+
+        if let Some(mode) = self.field(body, "mode", pointer, false, &["string"]).and_then(Value::as_str) {
+            if !BODY_MODES.contains(&mode) {
+                self.error(
+                    &child(pointer, "mode"),
+                    format!("Unknown body mode '{}'; expected one of {}", mode, BODY_MODES.join(", ")),
+                );
+            }
+        }
+        self.field(body, "raw", pointer, false, &["string"]);
+        self.field(body, "file", pointer, false, &["object"]);
+        self.field(body, "graphql", pointer, false, &["object"]);
+        self.field(body, "disabled", pointer, false, &["boolean"]);
+        for mode in ["urlencoded", "formdata"] {
+            let Some(fields) = self.field(body, mode, pointer, false, &["array"]).and_then(Value::as_array) else { continue };
+            let fields_pointer = child(pointer, mode);
+            for (index, field) in fields.iter().enumerate() {
+                let pointer = child(&fields_pointer, &index.to_string());
+                if let Some(field) = self.object(field, &pointer) {
+                    self.field(field, "key", &pointer, true, &["string"]);
+                    self.field(field, "disabled", &pointer, false, &["boolean"]);
+                }
+            }
+        }
+
+        if lint && !is_disabled(body) {
+            self.check_references(&Value::Object(body.clone()), pointer);
+        }
+    }
+
+    fn check_auth(&mut self, auth: &Value, pointer: &str, lint: bool) {
+        let Some(auth) = auth.as_object() else { return };
+        let Some(auth_type) = self.field(auth, "type", pointer, true, &["string"]).and_then(Value::as_str) else {
+            return;
+        };
+        if !AUTH_TYPES.contains(&auth_type) {
+            if lint {
+                self.warn(
+                    "unknown-auth",
+                    &child(pointer, "type"),
+                    format!("Unknown auth type '{}'; expected one of {}", auth_type, AUTH_TYPES.join(", ")),
+                );
+            }
+            return;
+        }
+
+        let Some(params) = self.field(auth, auth_type, pointer, false, &["array"]).and_then(Value::as_array) else {
+            return;
+        };
+        let params_pointer = child(pointer, auth_type);
+        for (index, param) in params.iter().enumerate() {
+            let pointer = child(&params_pointer, &index.to_string());
+            let Some(param) = self.object(param, &pointer) else { continue };
+            let Some(key) = self.field(param, "key", &pointer, true, &["string"]).and_then(Value::as_str) else {
+                continue;
+            };
+            if !lint {
+                continue;
+            }
+            self.check_references(&Value::Object(param.clone()), &pointer);
+
+            let secret = SECRET_AUTH_PARAMS.contains(&key) || (auth_type == "apikey" && key == "value");
+            if let Some(Value::String(value)) = param.get("value") {
+                if secret && is_literal(value) {
+                    self.warn(
+                        "hardcoded-secret",
+                        &child(&pointer, "value"),
+                        format!("{} auth '{}' is a literal credential; use a {{{{variable}}}}", auth_type, key),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_variables(&mut self, variables: &[Value], pointer: &str) {
+        for (index, variable) in variables.iter().enumerate() {
+            let pointer = child(pointer, &index.to_string());
+            let Some(variable) = self.object(variable, &pointer) else { continue };
+            if !variable.contains_key("key") && !variable.contains_key("id") {
+                self.error(&pointer, "Variable must have a 'key' or 'id'".to_string());
+            }
+            self.field(variable, "key", &pointer, false, &["string"]);
+            self.field(variable, "disabled", &pointer, false, &["boolean"]);
+        }
+    }
+
+    fn check_events(&mut self, events: &[Value], pointer: &str) {
+        for (index, event) in events.iter().enumerate() {
+            let pointer = child(pointer, &index.to_string());
+            let Some(event) = self.object(event, &pointer) else { continue };
+            self.field(event, "listen", &pointer, true, &["string"]);
+            self.field(event, "disabled", &pointer, false, &["boolean"]);
+            if let Some(script) = self.field(event, "script", &pointer, false, &["object"]).and_then(Value::as_object) {
+                let pointer = child(&pointer, "script");
+                self.field(script, "exec", &pointer, false, &["array", "string"]);
+                self.field(script, "type", &pointer, false, &["string"]);
+                self.field(script, "src", &pointer, false, &["string", "object"]);
+            }
+        }
+    }
+
+    fn check_responses(&mut self, responses: &[Value], pointer: &str) {
+        for (index, response) in responses.iter().enumerate() {
+            let pointer = child(pointer, &index.to_string());
+            let Some(response) = self.object(response, &pointer) else { continue };
+            self.field(response, "name", &pointer, false, &["string"]);
+            self.field(response, "code", &pointer, false, &["integer"]);
+            self.field(response, "status", &pointer, false, &["string"]);
+            self.field(response, "body", &pointer, false, &["string", "null"]);
+            self.field(response, "cookie", &pointer, false, &["array", "null"]);
+            if let Some(headers) = self.field(response, "header", &pointer, false, &["array", "string", "null"]) {
+                self.check_headers(headers, &child(&pointer, "header"), false);
+            }
+            if let Some(request) = response.get("originalRequest") {
+                self.check_request(request, &child(&pointer, "originalRequest"), false);
+            }
+        }
+    }
+
+    /// Reports `{{name}}` references in every string under `value` that are
+    /// not defined anywhere. Disabled entries are skipped.
+    fn check_references(&mut self, value: &Value, pointer: &str) {
+        match value {
+            Value::String(text) => {
+                for name in find_references(text) {
+                    if !name.starts_with('$') && !self.defined.contains(&name) {
+                        self.warn(
+                            "undefined-variable",
+                            pointer,
+                            format!("Variable '{{{{{}}}}}' is not defined in the collection, environment or scripts", name),
+                        );
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.check_references(item, &child(pointer, &index.to_string()));
+                }
+            }
+            Value::Object(fields) if !is_disabled(fields) => {
+                for (key, item) in fields {
+                    if key != "description" {
+                        self.check_references(item, &child(pointer, key));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds every variable declared in the document (at any level) and every
+/// name set by a script.
+fn collect_variables(value: &Value, defined: &mut HashSet<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, child) in fields {
+                match (key.as_str(), child) {
+                    ("variable", Value::Array(variables)) => {
+                        for variable in variables {
+                            if let Some(name) = variable.get("key").or_else(|| variable.get("id")).and_then(Value::as_str) {
+                                defined.insert(name.to_string());
+                            }
+                        }
+                    }
+                    ("exec", Value::Array(lines)) => {
+                        let source: Vec<&str> = lines.iter().filter_map(Value::as_str).collect();
+                        defined.extend(script_variables(&source.join("\n")));
+                    }
+                    ("exec", Value::String(source)) => defined.extend(script_variables(source)),
+                    _ => collect_variables(child, defined),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_variables(item, defined)),
+        _ => {}
+    }
+}
+
+fn script_variables(source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for setter in VARIABLE_SETTERS {
+        for (start, _) in source.match_indices(setter) {
+            let rest = source[start + setter.len()..].trim_start();
+            let Some(quote) = rest.chars().next().filter(|c| matches!(c, '"' | '\'' | '`')) else { continue };
+            if let Some(end) = rest[1..].find(quote) {
+                names.push(rest[1..1 + end].to_string());
+            }
+        }
+    }
+    names
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn child(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, token.replace('~', "~0").replace('/', "~1"))
+}
+
+fn is_disabled(object: &Map<String, Value>) -> bool {
+    object.get("disabled").and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn url_is_empty(url: &Map<String, Value>) -> bool {
+    let raw = url.get("raw").and_then(Value::as_str).unwrap_or("");
+    raw.trim().is_empty() && url.get("host").is_none_or(|host| host.as_array().is_some_and(Vec::is_empty))
+}
+
+fn is_secret_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_HEADERS.contains(&name.as_str()) || SECRET_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// A non-empty value that does not come from a variable.
+fn is_literal(value: &str) -> bool {
+    let value = value.trim();
+    let scheme_only = value
+        .split_once(' ')
+        .is_none_or(|(_, credential)| credential.trim().is_empty())
+        && ["bearer", "basic", "digest"].contains(&value.to_ascii_lowercase().as_str());
+    !value.is_empty() && !value.contains("{{") && !scheme_only
+}
+
+/// Validate and lint a collection. `environment_json` (may be null) supplies
+/// extra variable definitions. Returns a `ValidationReport`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn validate_collection_json(collection_json: *const c_char, environment_json: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let text = unsafe { ffi::arg(collection_json, "collection") }?;
+        let environment: Option<Environment> = match unsafe { ffi::optional_arg(environment_json, "environment") }? {
+            Some(json) => Some(serde_json::from_str(json).map_err(|e| FfiError::json("environment", &e))?),
+            None => None,
+        };
+        Ok(validate_collection(text, environment.as_ref()))
+    })();
+    ffi::respond(result)
+}
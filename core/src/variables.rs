@@ -0,0 +1,122 @@
+use crate::models::environment::Environment;
+use crate::models::request::Request;
+use crate::models::variable::Variable;
+use std::collections::HashMap;
+
+/// A flattened set of `{{name}}` substitutions built from the scopes that
+/// apply to a request (collection, environment, iteration data, overrides).
+#[derive(Debug, Clone, Default)]
+pub struct VariableScope {
+    values: HashMap<String, String>,
+}
+
+impl VariableScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds enabled variables, overriding any value already in scope.
+    pub fn extend_from_variables(&mut self, variables: Option<&Vec<Variable>>) {
+        if let Some(variables) = variables {
+            for var in variables {
+                if var.disabled.unwrap_or(false) {
+                    continue;
+                }
+                self.values.insert(var.key.clone(), var.value.clone());
+            }
+        }
+    }
+
+    pub fn extend_from_environment(&mut self, environment: Option<&Environment>) {
+        if let Some(environment) = environment {
+            self.extend_from_variables(environment.values.as_ref());
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.values.get(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Replaces every `{{name}}` in `input` with its value. References to
+    /// names that are not in scope are left untouched, as Postman does.
+    pub fn resolve_str(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match after.find("}}") {
+                Some(end) => {
+                    let name = after[..end].trim();
+                    match self.values.get(name) {
+                        Some(value) => output.push_str(value),
+                        None => output.push_str(&rest[start..start + 2 + end + 2]),
+                    }
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    output.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+
+    /// Returns a copy of `request` with every string field resolved.
+    pub fn resolve_request(&self, request: &Request) -> Result<Request, serde_json::Error> {
+        let mut value = serde_json::to_value(request)?;
+        self.resolve_value(&mut value);
+        serde_json::from_value(value)
+    }
+
+    fn resolve_value(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) if s.contains("{{") => {
+                *s = self.resolve_str(s);
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.resolve_value(item);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (_, item) in map.iter_mut() {
+                    self.resolve_value(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Lists the distinct `{{name}}` references found in `input`.
+pub fn find_references(input: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim().to_string();
+                if !name.is_empty() && !names.contains(&name) {
+                    names.push(name);
+                }
+                rest = &after[end + 2..];
+            }
+            None => break,
+        }
+    }
+    names
+}
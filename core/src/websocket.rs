@@ -0,0 +1,564 @@
+//! WebSocket client sessions. Each session runs on its own thread; received
+//! frames are delivered to a callback or queued for polling, and every frame
+//! sent or received is kept in a log that can be saved.
+
+use crate::ffi::{self, ErrorCategory, FfiError};
+use crate::models::request::Request;
+use crate::PreparedRequest;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+static SESSIONS: Mutex<Option<HashMap<u32, WsSession>>> = Mutex::new(None);
+
+/// How long `ws_close` waits for the server to answer the close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Receives each message of session `id` as JSON. The string is only valid
+/// for the duration of the call. Called from the session's own thread.
+pub type WsCallback = extern "C" fn(id: u32, message_json: *const c_char);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WsOptions {
+    /// Offered in `Sec-WebSocket-Protocol`, in order of preference.
+    pub protocols: Vec<String>,
+    /// Send a ping this often. 0 disables keep-alive pings.
+    pub ping_interval_ms: u64,
+}
+
+/// A WebSocket frame, as sent with `ws_send` and as logged. Binary payloads
+/// are base64-encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsFrame {
+    Text {
+        data: String,
+    },
+    Binary {
+        data_base64: String,
+    },
+    Ping {
+        #[serde(default)]
+        data_base64: String,
+    },
+    Pong {
+        #[serde(default)]
+        data_base64: String,
+    },
+    Close {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<u16>,
+        #[serde(default)]
+        reason: String,
+    },
+}
+
+impl WsFrame {
+    fn from_message(message: Message) -> Option<Self> {
+        let encode = |bytes: Vec<u8>| base64::engine::general_purpose::STANDARD.encode(bytes);
+        Some(match message {
+            Message::Text(data) => WsFrame::Text { data },
+            Message::Binary(bytes) => WsFrame::Binary { data_base64: encode(bytes) },
+            Message::Ping(bytes) => WsFrame::Ping { data_base64: encode(bytes) },
+            Message::Pong(bytes) => WsFrame::Pong { data_base64: encode(bytes) },
+            Message::Close(frame) => WsFrame::Close {
+                code: frame.as_ref().map(|f| u16::from(f.code)),
+                reason: frame.map(|f| f.reason.into_owned()).unwrap_or_default(),
+            },
+            Message::Frame(_) => return None,
+        })
+    }
+
+    fn into_message(self) -> Result<Message, FfiError> {
+        let decode = |text: String| {
+            base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .map_err(|e| FfiError::parse("data_base64", e))
+        };
+        Ok(match self {
+            WsFrame::Text { data } => Message::Text(data),
+            WsFrame::Binary { data_base64 } => Message::Binary(decode(data_base64)?),
+            WsFrame::Ping { data_base64 } => Message::Ping(decode(data_base64)?),
+            WsFrame::Pong { data_base64 } => Message::Pong(decode(data_base64)?),
+            WsFrame::Close { code, reason } => Message::Close(code.map(|code| CloseFrame {
+                code: CloseCode::from(code),
+                reason: Cow::Owned(reason),
+            })),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub time_ms: u64,
+    pub direction: Direction,
+    #[serde(flatten)]
+    pub frame: WsFrame,
+}
+
+/// Everything exchanged in a session, in order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WsLog {
+    pub url: String,
+    /// The subprotocol the server accepted.
+    pub protocol: Option<String>,
+    pub entries: Vec<LogEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsState {
+    Connecting,
+    Open,
+    Closing,
+    Closed,
+}
+
+enum Command {
+    Send(Message),
+    Close(Message),
+}
+
+struct Shared {
+    state: WsState,
+    queue: VecDeque<Value>,
+    callback: Option<WsCallback>,
+    log: WsLog,
+    /// The close frame that ended the session, sent by either side.
+    close: Option<(Option<u16>, String)>,
+}
+
+struct Connection {
+    id: u32,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Connection {
+    fn update(&self, f: impl FnOnce(&mut Shared)) {
+        if let Ok(mut shared) = self.shared.lock() {
+            f(&mut shared);
+        }
+    }
+
+    fn deliver(&self, message: Value) {
+        let callback = match self.shared.lock() {
+            Ok(mut shared) => match shared.callback {
+                Some(callback) => callback,
+                None => {
+                    shared.queue.push_back(message);
+                    return;
+                }
+            },
+            Err(_) => return,
+        };
+        if let Ok(text) = CString::new(message.to_string()) {
+            callback(self.id, text.as_ptr());
+        }
+    }
+
+    /// Logs a frame, and delivers it too if it was received.
+    fn record(&self, direction: Direction, frame: WsFrame) {
+        if let WsFrame::Close { code, reason } = &frame {
+            let close = (*code, reason.clone());
+            self.update(|shared| {
+                shared.close.get_or_insert(close);
+            });
+        }
+        let entry = LogEntry {
+            time_ms: now_ms(),
+            direction,
+            frame,
+        };
+        let message = serde_json::to_value(&entry).unwrap_or(Value::Null);
+        self.update(|shared| shared.log.entries.push(entry));
+        if direction == Direction::Received {
+            self.deliver(message);
+        }
+    }
+
+    fn error(&self, error: FfiError) {
+        self.deliver(json!({ "type": "error", "error": error }));
+    }
+}
+
+pub struct WsSession {
+    shared: Arc<Mutex<Shared>>,
+    commands: mpsc::UnboundedSender<Command>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WsSession {
+    pub fn connect(id: u32, request: &Request, options: WsOptions, callback: Option<WsCallback>) -> Result<Self, FfiError> {
+        let handshake = handshake_request(request, &options.protocols)?;
+        let (mut session, receiver) = WsSession::new(handshake.uri().to_string(), callback);
+        session.start(id, handshake, options, receiver);
+        Ok(session)
+    }
+
+    /// A session that has not connected yet, and the end of its command
+    /// channel that `start` hands to the reader.
+    fn new(url: String, callback: Option<WsCallback>) -> (Self, mpsc::UnboundedReceiver<Command>) {
+        let shared = Arc::new(Mutex::new(Shared {
+            state: WsState::Connecting,
+            queue: VecDeque::new(),
+            callback,
+            log: WsLog {
+                url,
+                ..Default::default()
+            },
+            close: None,
+        }));
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let session = WsSession {
+            shared,
+            commands,
+            thread: None,
+        };
+        (session, receiver)
+    }
+
+    /// Spawns the thread that runs the session. Kept apart from `new` so the
+    /// registry holds the session before its first message is delivered.
+    fn start(
+        &mut self,
+        id: u32,
+        handshake: tungstenite::handshake::client::Request,
+        options: WsOptions,
+        receiver: mpsc::UnboundedReceiver<Command>,
+    ) {
+        let connection = Connection {
+            id,
+            shared: Arc::clone(&self.shared),
+        };
+        self.thread = Some(std::thread::spawn(move || {
+            match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime.block_on(run(&connection, handshake, options, receiver)),
+                Err(e) => connection.error(FfiError::internal(e)),
+            }
+            let close = connection.shared.lock().ok().and_then(|mut shared| {
+                shared.state = WsState::Closed;
+                shared.close.clone()
+            });
+            let (code, reason) = close.unwrap_or_default();
+            connection.deliver(json!({ "type": "closed", "code": code, "reason": reason }));
+        }));
+    }
+
+    pub fn send(&self, frame: WsFrame) -> Result<(), FfiError> {
+        let message = frame.into_message()?;
+        let open = self.shared.lock().map(|shared| shared.state == WsState::Open).unwrap_or(false);
+        if !open || self.commands.send(Command::Send(message)).is_err() {
+            return Err(FfiError::new(ErrorCategory::Argument, "websocket_not_open", "The WebSocket is not open"));
+        }
+        Ok(())
+    }
+
+    /// Takes the messages received since the last poll.
+    pub fn poll(&self) -> (WsState, Vec<Value>) {
+        match self.shared.lock() {
+            Ok(mut shared) => (shared.state, shared.queue.drain(..).collect()),
+            Err(_) => (WsState::Closed, Vec::new()),
+        }
+    }
+
+    pub fn log(&self) -> WsLog {
+        self.shared.lock().map(|shared| shared.log.clone()).unwrap_or_default()
+    }
+
+    /// Performs the closing handshake and waits for the session to end.
+    /// Returns the close code and reason that ended it.
+    pub fn close(mut self, code: u16, reason: &str) -> (Option<u16>, String, WsLog) {
+        let frame = CloseFrame {
+            code: CloseCode::from(code),
+            reason: Cow::Owned(reason.to_string()),
+        };
+        let _ = self.commands.send(Command::Close(Message::Close(Some(frame))));
+        if let Some(thread) = self.thread.take() {
+            // From a callback this runs on the session's own thread, which
+            // finishes the handshake once the callback returns.
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+        let log = self.log();
+        let (code, reason) = self.shared.lock().ok().and_then(|shared| shared.close.clone()).unwrap_or_default();
+        (code, reason, log)
+    }
+}
+
+pub(crate) fn handshake_request(request: &Request, protocols: &[String]) -> Result<tungstenite::handshake::client::Request, FfiError> {
+    let prepared = PreparedRequest::new(request).map_err(FfiError::from)?;
+    let url = match prepared.url.split_once("://") {
+        Some(("http", rest)) => format!("ws://{}", rest),
+        Some(("https", rest)) => format!("wss://{}", rest),
+        _ => prepared.url.clone(),
+    };
+    let invalid = |message: String| FfiError::new(ErrorCategory::Argument, "invalid_request", message);
+
+    let mut handshake = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| invalid(format!("Invalid WebSocket URL '{}': {}", url, e)))?;
+    let headers = handshake.headers_mut();
+    for (key, value) in &prepared.headers {
+        let name = tungstenite::http::HeaderName::from_bytes(key.as_bytes())
+            .map_err(|_| invalid(format!("Invalid header name '{}'", key)))?;
+        let value = HeaderValue::from_str(value).map_err(|_| invalid(format!("Invalid value for header '{}'", key)))?;
+        headers.append(name, value);
+    }
+    if !protocols.is_empty() {
+        let value = HeaderValue::from_str(&protocols.join(", "))
+            .map_err(|_| invalid("Invalid subprotocol name".to_string()))?;
+        headers.insert("Sec-WebSocket-Protocol", value);
+    }
+    Ok(handshake)
+}
+
+async fn run(
+    connection: &Connection,
+    handshake: tungstenite::handshake::client::Request,
+    options: WsOptions,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+) {
+    let url = handshake.uri().to_string();
+    let (mut socket, response) = match tokio_tungstenite::connect_async(handshake).await {
+        Ok(connected) => connected,
+        Err(e) => return connection.error(classify(e, &url)),
+    };
+
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let protocol = crate::header_value(&headers, "sec-websocket-protocol").map(str::to_string);
+    connection.update(|shared| {
+        shared.state = WsState::Open;
+        shared.log.protocol = protocol.clone();
+    });
+    connection.deliver(json!({
+        "type": "open",
+        "status_code": response.status().as_u16(),
+        "headers": headers,
+        "protocol": protocol,
+    }));
+
+    let mut ping = match options.ping_interval_ms {
+        0 => None,
+        ms => Some(tokio::time::interval_at(
+            tokio::time::Instant::now() + Duration::from_millis(ms),
+            Duration::from_millis(ms),
+        )),
+    };
+    let mut closing_deadline = None;
+
+    loop {
+        let next_ping = async {
+            match ping.as_mut() {
+                Some(interval) => interval.tick().await,
+                None => std::future::pending().await,
+            }
+        };
+        let deadline = async {
+            match closing_deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        let outgoing = tokio::select! {
+            incoming = socket.next() => match incoming {
+                Some(Ok(message)) => {
+                    if let Some(frame) = WsFrame::from_message(message) {
+                        let is_close = matches!(frame, WsFrame::Close { .. });
+                        connection.record(Direction::Received, frame);
+                        if is_close {
+                            connection.update(|shared| shared.state = WsState::Closing);
+                        }
+                    }
+                    continue;
+                }
+                Some(Err(tungstenite::Error::ConnectionClosed)) | None => return,
+                Some(Err(e)) => return connection.error(classify(e, &url)),
+            },
+            command = commands.recv(), if closing_deadline.is_none() => match command {
+                Some(Command::Send(message)) => message,
+                Some(Command::Close(message)) => {
+                    connection.update(|shared| shared.state = WsState::Closing);
+                    closing_deadline = Some(tokio::time::Instant::now() + CLOSE_TIMEOUT);
+                    message
+                }
+                // The session was dropped without closing.
+                None => Message::Close(None),
+            },
+            _ = next_ping => Message::Ping(Vec::new()),
+            _ = deadline => return,
+        };
+
+        let frame = WsFrame::from_message(outgoing.clone());
+        match socket.send(outgoing).await {
+            Ok(()) => {
+                if let Some(frame) = frame {
+                    connection.record(Direction::Sent, frame);
+                }
+            }
+            // The server closed first; the close frame was already answered.
+            Err(tungstenite::Error::AlreadyClosed | tungstenite::Error::ConnectionClosed) => return,
+            Err(e) => return connection.error(classify(e, &url)),
+        }
+    }
+}
+
+pub(crate) fn classify(e: tungstenite::Error, url: &str) -> FfiError {
+    let details = json!({ "url": url });
+    match e {
+        tungstenite::Error::Http(response) => {
+            let status = response.status().as_u16();
+            let message = format!("Server rejected the WebSocket upgrade with status {}", status);
+            FfiError::new(ErrorCategory::Network, "upgrade_rejected", message)
+                .with_details(json!({ "url": url, "status_code": status }))
+        }
+        tungstenite::Error::Url(e) => {
+            FfiError::new(ErrorCategory::Argument, "invalid_url", e.to_string()).with_details(details)
+        }
+        tungstenite::Error::Tls(e) => {
+            let code = if e.to_string().to_lowercase().contains("certificate") {
+                "invalid_certificate"
+            } else {
+                "tls_handshake_failed"
+            };
+            FfiError::new(ErrorCategory::Tls, code, e.to_string()).with_details(details)
+        }
+        tungstenite::Error::Io(e) => match FfiError::from_request_error(&e, Some(url)) {
+            error if error.category == ErrorCategory::Internal => {
+                FfiError { category: ErrorCategory::Network, code: "websocket_error".to_string(), ..error }
+            }
+            error => error,
+        },
+        e => FfiError::new(ErrorCategory::Network, "websocket_error", e.to_string()).with_details(details),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn not_found(id: u32) -> FfiError {
+    FfiError::new(ErrorCategory::Argument, "websocket_not_found", format!("No WebSocket session with id {}", id))
+}
+
+fn with_session<T: Serialize>(id: u32, f: impl FnOnce(&WsSession) -> Result<T, FfiError>) -> *mut c_char {
+    let sessions = SESSIONS.lock();
+    ffi::respond(match sessions.as_ref().ok().and_then(|sessions| sessions.as_ref()?.get(&id)) {
+        Some(session) => f(session),
+        None => Err(not_found(id)),
+    })
+}
+
+/// Open a WebSocket session. The request's URL (`ws`, `wss`, or `http(s)`
+/// which is mapped to them), headers and auth are used for the handshake.
+///
+/// `options_json` may be null; see `WsOptions`. If `callback` is not null,
+/// every message is passed to it as it arrives; otherwise messages are
+/// queued for `ws_poll`. Returns `{"id": 1}`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn ws_connect(
+    request_json: *const c_char,
+    options_json: *const c_char,
+    callback: Option<WsCallback>,
+) -> *mut c_char {
+    let result = (|| {
+        let request: Request = unsafe { ffi::json_arg(request_json, "request") }?;
+        let options: WsOptions = match unsafe { ffi::optional_arg(options_json, "options") }? {
+            Some(json) => serde_json::from_str(json).map_err(|e| FfiError::json("options", &e))?,
+            None => WsOptions::default(),
+        };
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let handshake = handshake_request(&request, &options.protocols)?;
+        let (session, receiver) = WsSession::new(handshake.uri().to_string(), callback);
+
+        let mut sessions = SESSIONS.lock().map_err(|_| FfiError::internal("WebSocket registry is poisoned"))?;
+        let session = sessions.get_or_insert_with(HashMap::new).entry(id).or_insert(session);
+        session.start(id, handshake, options, receiver);
+        Ok(json!({ "id": id }))
+    })();
+    ffi::respond(result)
+}
+
+/// Send a frame: `{"type": "text", "data": "..."}`, or `binary`, `ping` or
+/// `pong` with `data_base64`. Returns `true`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn ws_send(id: u32, frame_json: *const c_char) -> *mut c_char {
+    match unsafe { ffi::json_arg::<WsFrame>(frame_json, "frame") } {
+        Ok(WsFrame::Close { .. }) => ffi::respond::<bool>(Err(FfiError::new(
+            ErrorCategory::Argument,
+            "invalid_frame",
+            "Use ws_close to close a session",
+        ))),
+        Ok(frame) => with_session(id, |session| session.send(frame).map(|_| true)),
+        Err(error) => ffi::respond::<bool>(Err(error)),
+    }
+}
+
+/// Take the messages a session received since the last call. Returns
+/// `{"state": "open", "messages": [...]}`.
+#[no_mangle]
+pub extern "C" fn ws_poll(id: u32) -> *mut c_char {
+    with_session(id, |session| {
+        let (state, messages) = session.poll();
+        Ok(json!({ "state": state, "messages": messages }))
+    })
+}
+
+/// The log of every frame sent and received so far, as a `WsLog`.
+#[no_mangle]
+pub extern "C" fn ws_log(id: u32) -> *mut c_char {
+    with_session(id, |session| Ok(session.log()))
+}
+
+/// Close a session with a close code (e.g. 1000) and reason, which may be
+/// null, and release it. Returns `{"code": 1000, "reason": "...", "log": {...}}`
+/// with the close frame that ended the session and the final log. Called
+/// from the session's own callback, it returns before the server answers, so
+/// the code and log may not include the closing handshake yet.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn ws_close(id: u32, code: u16, reason: *const c_char) -> *mut c_char {
+    let result = (|| {
+        let reason = unsafe { ffi::optional_arg(reason, "reason") }?.unwrap_or("").to_string();
+        let session = SESSIONS
+            .lock()
+            .ok()
+            .and_then(|mut sessions| sessions.as_mut().and_then(|s| s.remove(&id)))
+            .ok_or_else(|| not_found(id))?;
+        let (code, reason, log) = session.close(code, &reason);
+        Ok(json!({ "code": code, "reason": reason, "log": log }))
+    })();
+    ffi::respond(result)
+}
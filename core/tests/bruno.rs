@@ -0,0 +1,122 @@
+//! Bruno collection directories: folders, requests, the headers and auth
+//! set on the collection and its folders, and environments.
+
+use ababil_core::bruno::parse_bruno_collection;
+use ababil_core::models::collection::CollectionItem;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bruno").join(name)
+}
+
+fn item<'a>(items: &'a [CollectionItem], path: &[&str]) -> &'a CollectionItem {
+    let (name, rest) = path.split_first().unwrap();
+    let item = items.iter().find(|item| item.name == *name).unwrap();
+    if rest.is_empty() {
+        item
+    } else {
+        self::item(item.item.as_deref().unwrap(), rest)
+    }
+}
+
+fn value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap()
+}
+
+fn header_pairs(request: &Value) -> Vec<(&str, &str)> {
+    request["header"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| (h["key"].as_str().unwrap(), h["value"].as_str().unwrap()))
+        .collect()
+}
+
+#[test]
+fn folders_and_requests_are_imported_in_order() {
+    let (collection, _) = parse_bruno_collection(&fixture("shop")).unwrap();
+    assert_eq!(collection.info.name, "Shop");
+    assert_eq!(value(&collection.info.description), json!("Shop API."));
+    assert_eq!(value(&collection.variable), json!([{ "key": "apiVersion", "value": "2", "type": "string" }]));
+
+    let names: Vec<&str> = collection.item.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["Users", "Health"]);
+    let users: Vec<&str> = collection.item[0].item.as_ref().unwrap().iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(users, ["List users", "Admin"]);
+
+    let list = item(&collection.item, &["Users", "List users"]);
+    let request = value(&list.request);
+    assert_eq!(request["url"]["query"][1], json!({ "key": "limit", "value": "{{pageSize}}", "disabled": true }));
+    assert!(request.get("auth").is_none());
+    assert_eq!(value(&list.event)[0]["listen"], "test");
+
+    let create = value(&item(&collection.item, &["Users", "Admin", "Create user"]).request);
+    assert_eq!(create["method"], "POST");
+    assert_eq!(create["body"], json!({ "mode": "raw", "raw": "{\n  \"name\": \"Ada\"\n}" }));
+}
+
+#[test]
+fn collection_and_folder_headers_and_auth_apply_to_their_requests() {
+    let (collection, _) = parse_bruno_collection(&fixture("shop")).unwrap();
+    assert_eq!(value(&collection.auth)["bearer"][0]["value"], "{{token}}");
+
+    // Closer scopes replace headers of the same name.
+    let health = value(&item(&collection.item, &["Health"]).request);
+    assert_eq!(header_pairs(&health), [("X-Client", "ababil"), ("Accept", "*/*")]);
+    assert_eq!(health["auth"], json!({ "type": "noauth" }));
+    let list = value(&item(&collection.item, &["Users", "List users"]).request);
+    assert_eq!(
+        header_pairs(&list),
+        [("X-Client", "ababil"), ("Accept", "application/json"), ("X-Team", "people")]
+    );
+    let create = value(&item(&collection.item, &["Users", "Admin", "Create user"]).request);
+    assert_eq!(
+        header_pairs(&create),
+        [
+            ("X-Client", "ababil"),
+            ("Accept", "application/json"),
+            ("X-Team", "users"),
+            ("X-Admin", "1"),
+            ("X-Api-Key", "{{adminKey}}"),
+            ("Content-Type", "application/json"),
+        ]
+    );
+
+    let users = item(&collection.item, &["Users"]);
+    assert_eq!(
+        value(&users.auth),
+        json!({ "type": "basic", "basic": [
+            { "key": "username", "value": "ada", "type": "string" },
+            { "key": "password", "value": "{{password}}", "type": "string" }
+        ] })
+    );
+    assert_eq!(value(&users.variable)[0]["key"], "pageSize");
+    // An API key is sent as a header, so the folder stops inheriting basic auth.
+    assert_eq!(value(&item(&collection.item, &["Users", "Admin"]).auth), json!({ "type": "noauth" }));
+}
+
+#[test]
+fn environments_are_imported_with_empty_secrets() {
+    let (_, environments) = parse_bruno_collection(&fixture("shop")).unwrap();
+    assert_eq!(
+        value(&environments),
+        json!([{
+            "name": "local",
+            "values": [
+                { "key": "baseUrl", "value": "http://localhost:3000", "type": "string" },
+                { "key": "debug", "value": "true", "type": "string", "disabled": true },
+                { "key": "token", "value": "", "type": "secret" },
+                { "key": "adminKey", "value": "", "type": "secret", "disabled": true }
+            ],
+            "_postman_variable_scope": "environment"
+        }])
+    );
+}
+
+#[test]
+fn a_directory_without_bruno_json_is_an_error() {
+    let error = parse_bruno_collection(&fixture("missing")).unwrap_err();
+    assert_eq!(error.code, "read_failed");
+    assert!(error.message.starts_with("Cannot read "), "{}", error.message);
+}
@@ -0,0 +1,111 @@
+//! Snippets for each target, compared with the files in
+//! `tests/fixtures/codegen`. Values are chosen to break naive quoting: quotes,
+//! backslashes, `$`, newlines and U+2028. Run with `UPDATE_SNAPSHOTS=1` to
+//! rewrite the files after an intended change.
+
+use ababil_core::codegen::{generate, Target};
+use ababil_core::models::request::Request;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+fn requests() -> Vec<(&'static str, Request)> {
+    let text = json!({
+        "method": "POST",
+        "url": "https://api.test/it's/\"quoted\"?q=a b&home=$HOME",
+        "header": [
+            { "key": "X-Note", "value": "it's \"fine\" \\ $HOME" },
+            { "key": "X-Off", "value": "skipped", "disabled": true }
+        ],
+        "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "t'o\"k" }] },
+        "body": { "mode": "raw", "raw": "{\"a\": \"line\\nbreak\u{2028}\"}\n'end'" }
+    });
+    let urlencoded = json!({
+        "method": "POST",
+        "url": "https://api.test/form",
+        "body": { "mode": "urlencoded", "urlencoded": [
+            { "key": "a b&c", "value": "x=1&y 'z'" },
+            { "key": "off", "value": "1", "disabled": true }
+        ] }
+    });
+    let multipart = json!({
+        "method": "PUT",
+        "url": "https://api.test/upload",
+        "header": [{ "key": "Content-Type", "value": "multipart/form-data" }],
+        "body": { "mode": "formdata", "formdata": [
+            { "key": "note", "value": "say \"hi\" \\ 'there'", "type": "text" },
+            { "key": "photo", "value": "/tmp/it's \"a\".png", "type": "file" }
+        ] }
+    });
+    let file = json!({
+        "method": "POST",
+        "url": "https://api.test/file",
+        "body": { "mode": "file", "file": { "src": "/tmp/line\nbreak\\\u{2028}.bin" } }
+    });
+    [("text", text), ("urlencoded", urlencoded), ("multipart", multipart), ("file", file)]
+        .into_iter()
+        .map(|(name, value)| (name, serde_json::from_value(value).unwrap()))
+        .collect()
+}
+
+fn check(target: Target, file_name: &str) {
+    let mut actual = String::new();
+    for (name, request) in requests() {
+        actual.push_str(&format!("==> {} <==\n", name));
+        actual.push_str(&generate(&request, target).unwrap());
+        if !actual.ends_with('\n') {
+            actual.push('\n');
+        }
+        actual.push('\n');
+    }
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/codegen").join(file_name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).unwrap();
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert!(actual == expected, "{} differs from the snapshot:\n{}", file_name, actual);
+}
+
+#[test]
+fn curl() {
+    check(Target::Curl, "curl.sh");
+}
+
+#[test]
+fn fetch() {
+    check(Target::Fetch, "fetch.js");
+}
+
+#[test]
+fn axios() {
+    check(Target::Axios, "axios.js");
+}
+
+#[test]
+fn python() {
+    check(Target::PythonRequests, "requests.py");
+}
+
+#[test]
+fn go() {
+    check(Target::GoNetHttp, "net_http.go");
+}
+
+#[test]
+fn rust() {
+    check(Target::RustReqwest, "reqwest.rs");
+}
+
+#[test]
+fn auth_that_cannot_be_sent_is_an_error() {
+    let request: Request = serde_json::from_value(json!({
+        "url": "https://api.test",
+        "auth": { "type": "hawk" }
+    }))
+    .unwrap();
+    assert_eq!(
+        generate(&request, Target::Curl).unwrap_err().to_string(),
+        "Auth type 'hawk' is not supported"
+    );
+}
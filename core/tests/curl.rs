@@ -0,0 +1,171 @@
+//! `curl` command lines as copied from browsers and docs, and the requests
+//! they become.
+
+use ababil_core::curl::parse_curl;
+use serde_json::{json, Value};
+
+fn parse(command: &str) -> Value {
+    match parse_curl(command) {
+        Ok(request) => serde_json::to_value(request).unwrap(),
+        Err(e) => panic!("{}: {}", command, e),
+    }
+}
+
+/// `(method, raw URL)` for each command.
+#[test]
+fn methods_and_urls() {
+    let cases = [
+        ("curl example.com", "GET", "http://example.com"),
+        ("curl -XPOST https://api.test/a", "POST", "https://api.test/a"),
+        ("curl -X put https://api.test/a", "PUT", "https://api.test/a"),
+        ("curl --request=DELETE https://api.test/a", "DELETE", "https://api.test/a"),
+        ("curl -sSLXPATCH https://api.test/a", "PATCH", "https://api.test/a"),
+        ("curl -I https://api.test/a", "HEAD", "https://api.test/a"),
+        ("curl -d x=1 https://api.test/a", "POST", "https://api.test/a"),
+        ("curl -G -d x=1 -d y=2 https://api.test/a", "GET", "https://api.test/a?x=1&y=2"),
+        ("curl -Gd 'q=a b' 'https://api.test/a?page=1'", "GET", "https://api.test/a?page=1&q=a b"),
+        ("curl --url https://api.test/a -k --compressed", "GET", "https://api.test/a"),
+        ("curl --oauth2-bearer abc https://api.test/a", "GET", "https://api.test/a"),
+        ("curl --max-time 5 --no-progress-meter https://api.test/a", "GET", "https://api.test/a"),
+        ("curl https://api.test/a --url-query 'q=a&b' --url-query +x=%20", "GET", "https://api.test/a?q=a%26b&x=%20"),
+        ("curl '{{baseUrl}}/users'", "GET", "{{baseUrl}}/users"),
+    ];
+    for (command, method, url) in cases {
+        let request = parse(command);
+        assert_eq!(
+            (request["method"].as_str(), request["url"]["raw"].as_str()),
+            (Some(method), Some(url)),
+            "{}",
+            command
+        );
+    }
+}
+
+#[test]
+fn quoting_and_line_continuations() {
+    let cases = [
+        (r#"curl -H 'X-A: it'"'"'s' https://a.test"#, "it's"),
+        (r#"curl -H "X-A: say \"hi\" \$HOME" https://a.test"#, r#"say "hi" $HOME"#),
+        (r#"curl -H "X-A: back\slash" https://a.test"#, r"back\slash"),
+        (r"curl -H $'X-A: tab\there\x21' https://a.test", "tab\there!"),
+        (r"curl -H X-A:\ spaced https://a.test", "spaced"),
+        ("curl https://a.test \\\n  -H 'X-A: unix'", "unix"),
+        ("curl https://a.test \\\r\n  -H 'X-A: crlf'", "crlf"),
+        ("curl https://a.test ^\n  -H \"X-A: cmd\"", "cmd"),
+    ];
+    for (command, value) in cases {
+        let request = parse(command);
+        assert_eq!(request["header"], json!([{ "key": "X-A", "value": value }]), "{}", command);
+        assert_eq!(request["url"]["raw"], "https://a.test", "{}", command);
+    }
+
+    assert_eq!(parse_curl("curl 'https://a.test").unwrap_err(), "Unterminated single quote");
+    assert_eq!(parse_curl("wget https://a.test").unwrap_err(), "Expected a curl command, found 'wget'");
+}
+
+#[test]
+fn auth_options() {
+    let cases = [
+        ("curl -u ada:secret https://a.test", json!({ "type": "basic", "basic": [
+            { "key": "username", "value": "ada", "type": "string" },
+            { "key": "password", "value": "secret", "type": "string" }
+        ] })),
+        ("curl -u ada https://a.test", json!({ "type": "basic", "basic": [
+            { "key": "username", "value": "ada", "type": "string" },
+            { "key": "password", "value": "", "type": "string" }
+        ] })),
+        ("curl --digest -u ada:secret https://a.test", json!({ "type": "digest", "digest": [
+            { "key": "username", "value": "ada", "type": "string" },
+            { "key": "password", "value": "secret", "type": "string" }
+        ] })),
+        ("curl -H 'Authorization: Bearer t0k' https://a.test", json!({ "type": "bearer", "bearer": [
+            { "key": "token", "value": "t0k", "type": "string" }
+        ] })),
+        ("curl --oauth2-bearer t0k https://a.test", json!({ "type": "bearer", "bearer": [
+            { "key": "token", "value": "t0k", "type": "string" }
+        ] })),
+        ("curl -H 'Authorization: Basic YWRhOnNlY3JldA==' https://a.test", json!({ "type": "basic", "basic": [
+            { "key": "username", "value": "ada", "type": "string" },
+            { "key": "password", "value": "secret", "type": "string" }
+        ] })),
+    ];
+    for (command, auth) in cases {
+        let request = parse(command);
+        assert_eq!(request["auth"], auth, "{}", command);
+        assert!(request.get("header").is_none(), "{}", command);
+    }
+}
+
+#[test]
+fn bodies() {
+    let cases = [
+        (
+            "curl -d 'a=1&b=x+y%21' https://a.test",
+            json!({ "mode": "urlencoded", "urlencoded": [
+                { "key": "a", "value": "1", "type": "text" },
+                { "key": "b", "value": "x y!", "type": "text" }
+            ] }),
+        ),
+        ("curl -d '{\"a\": 1}' https://a.test", json!({ "mode": "raw", "raw": "{\"a\": 1}" })),
+        ("curl --data-raw '@not-a-file' https://a.test", json!({ "mode": "raw", "raw": "@not-a-file" })),
+        ("curl -d 'line\none' https://a.test", json!({ "mode": "raw", "raw": "lineone" })),
+        ("curl --data-binary $'line\\none' https://a.test", json!({ "mode": "raw", "raw": "line\none" })),
+        ("curl -d @body.json https://a.test", json!({ "mode": "file", "file": { "src": "body.json" } })),
+        ("curl --json @body.json https://a.test", json!({ "mode": "file", "file": { "src": "body.json" } })),
+        (
+            "curl --data-urlencode 'q=a b&c' --data-urlencode '=x/y' --data-urlencode 'ada@example.com' https://a.test",
+            json!({ "mode": "raw", "raw": "q=a+b%26c&x%2Fy&ada%40example.com" }),
+        ),
+        (
+            "curl --data-urlencode 'name=Ada Lovelace' --data-urlencode 'note=a@b' https://a.test",
+            json!({ "mode": "urlencoded", "urlencoded": [
+                { "key": "name", "value": "Ada Lovelace", "type": "text" },
+                { "key": "note", "value": "a@b", "type": "text" }
+            ] }),
+        ),
+        (
+            "curl -F 'name=Ada' -F 'photo=@me.png;type=image/png' --form-string 'at=@literal' https://a.test",
+            json!({ "mode": "formdata", "formdata": [
+                { "key": "name", "value": "Ada", "type": "text" },
+                { "key": "photo", "value": "me.png", "type": "file" },
+                { "key": "at", "value": "@literal", "type": "text" }
+            ] }),
+        ),
+    ];
+    for (command, body) in cases {
+        assert_eq!(parse(command)["body"], body, "{}", command);
+    }
+
+    let json = parse("curl --json '{\"a\":1}' https://a.test");
+    assert_eq!(
+        json["header"],
+        json!([
+            { "key": "Content-Type", "value": "application/json" },
+            { "key": "Accept", "value": "application/json" }
+        ])
+    );
+    assert_eq!(json["method"], "POST");
+}
+
+#[test]
+fn cookies() {
+    let request = parse("curl -b 'a=1' -b cookies.txt --cookie 'b=2; c=3' https://a.test");
+    assert_eq!(request["header"], json!([{ "key": "Cookie", "value": "a=1; b=2; c=3" }]));
+    assert!(parse("curl -b cookies.txt https://a.test").get("header").is_none());
+}
+
+#[test]
+fn unsupported_commands_are_errors() {
+    let cases = [
+        ("curl --made-up-option value https://a.test", "Unsupported curl option '--made-up-option'"),
+        ("curl -j -W https://a.test", "Unsupported curl option '-W'"),
+        ("curl -d @a.json -d @b.json https://a.test", "Cannot combine @a.json, @b.json with other data; the file is not read"),
+        ("curl -d @a.json -d x=1 https://a.test", "Cannot combine @a.json with other data; the file is not read"),
+        ("curl -G --data-urlencode @q.txt https://a.test", "Cannot combine @q.txt with -G; the file is not read"),
+        ("curl -H", "Missing value for -H"),
+        ("curl -s", "No URL found in curl command"),
+    ];
+    for (command, error) in cases {
+        assert_eq!(parse_curl(command).unwrap_err(), error, "{}", command);
+    }
+}
@@ -0,0 +1,124 @@
+//! Compressed responses are decoded unless the request disables it, and both
+//! body sizes are reported.
+
+use ababil_core::{free_string, make_http_request};
+use base64::Engine;
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::io::Write;
+
+const TEXT: &str = "hello";
+// `hello` compressed with brotli.
+const BROTLI: &[u8] = &[0x0b, 0x02, 0x80, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x03];
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn deflate(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zstd(bytes: &[u8]) -> Vec<u8> {
+    zstd::stream::encode_all(bytes, 0).unwrap()
+}
+
+/// Serves `(content-encoding, body)` pairs on `/0`, `/1`, ... and returns
+/// the base URL.
+fn serve(responses: Vec<(&'static str, Vec<u8>)>) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let index: usize = request.url().trim_start_matches('/').parse().unwrap();
+            let (encoding, body) = &responses[index];
+            let header = tiny_http::Header::from_bytes("Content-Encoding", *encoding).unwrap();
+            request.respond(tiny_http::Response::from_data(body.clone()).with_header(header)).unwrap();
+        }
+    });
+    format!("http://127.0.0.1:{}", port)
+}
+
+fn send(request: Value) -> Value {
+    let request = CString::new(request.to_string()).unwrap();
+    let result = make_http_request(request.as_ptr());
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    let mut envelope: Value = serde_json::from_str(&text).unwrap();
+    envelope["ok"].take()
+}
+
+#[test]
+fn responses_are_decompressed() {
+    let gzipped = gzip(TEXT.as_bytes());
+    let base = serve(vec![
+        ("gzip", gzipped.clone()),
+        ("deflate", deflate(TEXT.as_bytes())),
+        ("br", BROTLI.to_vec()),
+        ("zstd", zstd(TEXT.as_bytes())),
+        ("gzip, zstd", zstd(&gzipped)),
+        ("gzip", gzipped.clone()),
+    ]);
+
+    for (index, encoded) in [gzipped.clone(), deflate(TEXT.as_bytes()), BROTLI.to_vec(), zstd(TEXT.as_bytes()), zstd(&gzipped)]
+        .iter()
+        .enumerate()
+    {
+        let response = send(json!({ "method": "GET", "url": format!("{}/{}", base, index) }));
+        assert_eq!(response["body"], TEXT, "response {}", index);
+        assert_eq!(response["encoded_size"], encoded.len());
+        assert_eq!(response["decoded_size"], TEXT.len());
+        assert!(response.get("body_base64").is_none());
+    }
+
+    let raw = send(json!({
+        "method": "GET",
+        "url": format!("{}/5", base),
+        "ababilOptions": { "decompress": false }
+    }));
+    assert_eq!(raw["encoded_size"], gzipped.len());
+    assert_eq!(raw["decoded_size"], gzipped.len());
+    assert_eq!(raw["body_base64"], base64::engine::general_purpose::STANDARD.encode(&gzipped));
+    assert!(raw.get("warning").is_none());
+}
+
+#[test]
+fn bodies_that_cannot_be_decoded_are_returned_as_received() {
+    let gzipped = gzip(TEXT.as_bytes());
+    let truncated = gzipped[..gzipped.len() - 6].to_vec();
+    let base = serve(vec![
+        ("gzip", TEXT.as_bytes().to_vec()),
+        ("gzip", truncated.clone()),
+        ("compress", gzipped.clone()),
+        ("br", Vec::new()),
+    ]);
+    let get = |index: usize| send(json!({ "method": "GET", "url": format!("{}/{}", base, index) }));
+    let base64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+
+    // Labelled gzip but sent as plain text.
+    let mislabelled = get(0);
+    assert_eq!(mislabelled["body"], TEXT);
+    assert_eq!(mislabelled["body_base64"], base64(TEXT.as_bytes()));
+    assert_eq!(mislabelled["decoded_size"], TEXT.len());
+    assert!(mislabelled["warning"].as_str().unwrap().starts_with("Cannot decode gzip response body"));
+
+    let cut_off = get(1);
+    assert_eq!(cut_off["body_base64"], base64(&truncated));
+    assert_eq!(cut_off["encoded_size"], truncated.len());
+    assert_eq!(cut_off["decoded_size"], truncated.len());
+    assert!(cut_off["warning"].as_str().unwrap().starts_with("Cannot decode gzip response body"));
+
+    let unknown = get(2);
+    assert_eq!(unknown["body_base64"], base64(&gzipped));
+    assert_eq!(unknown["warning"], "Unsupported Content-Encoding 'compress'");
+
+    let empty = get(3);
+    assert_eq!(empty["body"], "");
+    assert_eq!(empty["decoded_size"], 0);
+    assert!(empty.get("body_base64").is_none());
+    assert!(empty.get("warning").is_none());
+}
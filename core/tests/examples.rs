@@ -0,0 +1,139 @@
+//! Saving executed responses as examples on collection requests.
+
+use ababil_core::examples::{attach_example, response_to_example, save_response_as_example};
+use ababil_core::free_string;
+use ababil_core::models::collection::Collection;
+use ababil_core::models::request::Request;
+use ababil_core::HttpResponse;
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+fn envelope(ptr: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn request() -> Request {
+    serde_json::from_value(json!({ "method": "GET", "url": { "raw": "https://api.test/users/1" } })).unwrap()
+}
+
+fn response(status_code: u16, headers: &[(&str, &str)], body: &str) -> HttpResponse {
+    serde_json::from_value(json!({
+        "status_code": status_code,
+        "headers": headers,
+        "body": body,
+        "duration_ms": 42
+    }))
+    .unwrap()
+}
+
+fn collection() -> Collection {
+    serde_json::from_value(json!({
+        "info": { "name": "Shop", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+        "item": [
+            { "name": "Users", "item": [{ "name": "Get user", "request": { "method": "GET", "url": "https://api.test/users/1" } }] },
+            { "name": "Ping", "request": { "method": "GET", "url": "https://api.test/ping" } }
+        ]
+    }))
+    .unwrap()
+}
+
+fn path(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn responses_become_examples() {
+    let headers = [
+        ("Content-Type", "application/json; charset=utf-8"),
+        ("Set-Cookie", "session=abc; Path=/; Domain=api.test; HttpOnly; Secure"),
+        ("set-cookie", "theme = dark ; Expires=Wed, 21 Oct 2026 07:28:00 GMT"),
+        ("Set-Cookie", "broken"),
+    ];
+    let example = serde_json::to_value(response_to_example(None, &request(), &response(200, &headers, "{\"id\":1}"))).unwrap();
+    assert_eq!(example["name"], "200 OK");
+    assert_eq!(example["status"], "OK");
+    assert_eq!(example["code"], 200);
+    assert_eq!(example["_postman_previewlanguage"], "json");
+    assert_eq!(example["body"], "{\"id\":1}");
+    assert_eq!(example["responseTime"], "42");
+    assert_eq!(example["originalRequest"]["url"]["raw"], "https://api.test/users/1");
+    assert_eq!(example["header"].as_array().unwrap().len(), 4);
+    assert_eq!(
+        example["cookie"],
+        json!([
+            { "name": "session", "value": "abc", "domain": "api.test", "path": "/", "httpOnly": true, "secure": true },
+            { "name": "theme", "value": "dark", "expires": "Wed, 21 Oct 2026 07:28:00 GMT", "httpOnly": false, "secure": false }
+        ])
+    );
+
+    // A given name is kept; a status without a reason phrase has no status text.
+    let named = response_to_example(Some("Teapot"), &request(), &response(299, &[], ""));
+    assert_eq!((named.name.as_deref(), named.status.as_deref()), (Some("Teapot"), None));
+    assert_eq!(response_to_example(Some(""), &request(), &response(299, &[], "")).name.as_deref(), Some("299"));
+
+    let languages = [
+        ("text/html", "html"),
+        ("application/problem+xml", "xml"),
+        ("text/javascript", "javascript"),
+        ("application/vnd.api+JSON", "json"),
+        ("image/png", "text"),
+    ];
+    for (content_type, language) in languages {
+        let example = response_to_example(None, &request(), &response(200, &[("content-type", content_type)], ""));
+        assert_eq!(example.postman_previewlanguage.as_deref(), Some(language), "{}", content_type);
+    }
+}
+
+#[test]
+fn examples_are_attached_by_path() {
+    let mut collection = collection();
+    let example = response_to_example(None, &request(), &response(404, &[], ""));
+    attach_example(&mut collection, &path(&["Users", "Get user"]), example.clone()).unwrap();
+    attach_example(&mut collection, &path(&["Users", "Get user"]), example.clone()).unwrap();
+    let saved = collection.item[0].item.as_ref().unwrap()[0].response.as_ref().unwrap();
+    assert_eq!(saved.len(), 2);
+    assert_eq!(saved[0].name.as_deref(), Some("404 Not Found"));
+
+    let errors = [
+        (path(&["Users"]), "'Users' is a folder, not a request"),
+        (path(&["Users", "Missing"]), "No item at path 'Users / Missing'"),
+        (path(&["Ping", "Get user"]), "No item at path 'Ping / Get user'"),
+        (path(&[]), "No item at path ''"),
+    ];
+    for (item_path, message) in errors {
+        let error = attach_example(&mut collection, &item_path, example.clone()).unwrap_err();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn examples_are_saved_across_the_ffi() {
+    let arg = |value: Value| CString::new(value.to_string()).unwrap();
+    let collection = arg(serde_json::to_value(collection()).unwrap());
+    let request = arg(serde_json::to_value(request()).unwrap());
+    let response = arg(json!({ "status_code": 201, "headers": [], "body": "", "duration_ms": 5 }));
+    let name = CString::new("Created").unwrap();
+
+    let saved = envelope(save_response_as_example(
+        collection.as_ptr(),
+        arg(json!(["Ping"])).as_ptr(),
+        request.as_ptr(),
+        response.as_ptr(),
+        name.as_ptr(),
+    ));
+    assert_eq!(saved["ok"]["item"][1]["response"][0]["name"], "Created");
+    assert_eq!(saved["ok"]["item"][1]["response"][0]["code"], 201);
+
+    let missing = envelope(save_response_as_example(
+        collection.as_ptr(),
+        arg(json!(["Nope"])).as_ptr(),
+        request.as_ptr(),
+        response.as_ptr(),
+        std::ptr::null(),
+    ));
+    assert_eq!(missing["error"]["code"], "item_not_found");
+    assert_eq!(missing["error"]["category"], "argument");
+}
@@ -0,0 +1,229 @@
+//! The result envelope, argument handling and error classification shared
+//! by every exported function.
+
+use ababil_core::ffi::{respond, ErrorCategory, FfiError};
+use ababil_core::http_file::http_file_to_collection_json;
+use ababil_core::{build_url, free_string, make_http_request, parse_url};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::time::Duration;
+
+/// Reads and frees an envelope returned across the FFI boundary.
+fn envelope(ptr: *mut c_char) -> Value {
+    assert!(!ptr.is_null());
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn classify(error: &reqwest::Error) -> (ErrorCategory, String) {
+    let error = FfiError::from_request_error(error, Some("http://example.test/"));
+    (error.category, error.code)
+}
+
+/// A server that answers every request on its own thread with `respond`.
+fn server(respond: impl Fn(tiny_http::Request) + Send + 'static) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://127.0.0.1:{}", server.server_addr().to_ip().unwrap().port());
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            respond(request);
+        }
+    });
+    base
+}
+
+#[test]
+fn respond_wraps_results_and_errors() {
+    assert_eq!(envelope(respond(Ok(json!({ "a": [1, "two"] })))), json!({ "ok": { "a": [1, "two"] } }));
+    assert_eq!(envelope(respond(Ok(()))), json!({ "ok": null }));
+
+    let error = FfiError::new(ErrorCategory::Io, "io_error", "disk\0full").with_details(json!({ "path": "/tmp" }));
+    assert_eq!(
+        envelope(respond::<()>(Err(error))),
+        json!({
+            "error": {
+                "code": "io_error",
+                "category": "io",
+                "message": "disk\u{0}full",
+                "details": { "path": "/tmp" }
+            }
+        })
+    );
+    let error = FfiError::new(ErrorCategory::Auth, "unsupported_auth", "nope");
+    assert_eq!(
+        envelope(respond::<()>(Err(error))),
+        json!({ "error": { "code": "unsupported_auth", "category": "auth", "message": "nope" } })
+    );
+}
+
+#[test]
+fn null_and_invalid_utf8_arguments_are_argument_errors() {
+    let error = envelope(parse_url(std::ptr::null()));
+    assert_eq!(
+        error,
+        json!({ "error": { "code": "null_argument", "category": "argument", "message": "'raw' must not be null" } })
+    );
+    let text = CString::new("GET https://example.test/").unwrap();
+    let parsed = envelope(http_file_to_collection_json(text.as_ptr(), std::ptr::null()));
+    assert_eq!(parsed["ok"]["info"]["name"], "HTTP file");
+
+    let invalid = CString::new(vec![b'a', 0xff, b'b']).unwrap();
+    let error = envelope(parse_url(invalid.as_ptr()));
+    assert_eq!(
+        error,
+        json!({ "error": { "code": "invalid_utf8", "category": "argument", "message": "'raw' is not valid UTF-8" } })
+    );
+
+    let valid = CString::new("héllo").unwrap();
+    assert_eq!(envelope(parse_url(valid.as_ptr()))["ok"]["raw"], "héllo");
+
+    let response = envelope(make_http_request(std::ptr::null()));
+    assert_eq!(response["error"]["code"], "null_argument");
+    assert_eq!(response["error"]["category"], "argument");
+}
+
+#[test]
+fn json_errors_carry_line_and_column() {
+    let syntax = CString::new("{\n  \"raw\": \"a\",\n  oops\n}").unwrap();
+    let error = &envelope(build_url(syntax.as_ptr()))["error"];
+    assert_eq!((&error["category"], &error["code"]), (&json!("parse"), &json!("invalid_json")));
+    assert!(error["message"].as_str().unwrap().starts_with("Invalid url: "), "{}", error["message"]);
+    assert_eq!(error["details"], json!({ "line": 3, "column": 3 }));
+
+    let shape = CString::new("{\n\"path\": [1,\n\"two\"]}").unwrap();
+    let error = &envelope(build_url(shape.as_ptr()))["error"];
+    assert_eq!((&error["category"], &error["code"]), (&json!("parse"), &json!("invalid_structure")));
+    assert_eq!(error["details"], json!({ "line": 3, "column": 7 }));
+}
+
+#[test]
+fn request_errors_are_classified() {
+    // Errors raised while building the request pass through unchanged.
+    let own = FfiError::new(ErrorCategory::Argument, "invalid_url", "bad");
+    let passed = FfiError::from_request_error(&own, None);
+    assert_eq!((passed.code.as_str(), passed.details), ("invalid_url", None));
+
+    let other = std::io::Error::other("odd");
+    let error = FfiError::from_request_error(&other, None);
+    assert_eq!((error.category, error.code.as_str()), (ErrorCategory::Internal, "internal_error"));
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(200))
+        .redirect(reqwest::redirect::Policy::limited(2))
+        .build()
+        .unwrap();
+
+    // Nothing listens on a port we just released.
+    let closed = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let refused = client.get(format!("http://127.0.0.1:{}/", closed)).send().unwrap_err();
+    assert_eq!(classify(&refused), (ErrorCategory::Connect, "connection_refused".into()));
+    let error = FfiError::from_request_error(&refused, Some("http://example.test/"));
+    let details = error.details.unwrap();
+    assert_eq!(details["url"], "http://example.test/");
+    assert!(details["causes"].as_array().unwrap().len() > 1);
+
+    let slow = server(|request| {
+        std::thread::sleep(Duration::from_secs(1));
+        let _ = request.respond(tiny_http::Response::empty(204));
+    });
+    let timeout = client.get(&slow).send().unwrap_err();
+    assert_eq!(classify(&timeout), (ErrorCategory::Timeout, "timeout".into()));
+
+    let looping = server(|request| {
+        let location = tiny_http::Header::from_bytes("Location", "/again").unwrap();
+        let _ = request.respond(tiny_http::Response::empty(302).with_header(location));
+    });
+    let redirect = client.get(&looping).send().unwrap_err();
+    assert_eq!(classify(&redirect), (ErrorCategory::Redirect, "too_many_redirects".into()));
+
+    let dns = client.get("http://ababil-does-not-exist.invalid/").send().unwrap_err();
+    assert_eq!(classify(&dns), (ErrorCategory::Dns, "dns_resolution_failed".into()));
+}
+
+#[test]
+fn auth_that_cannot_be_applied_is_an_auth_error() {
+    let send = |auth: Value| {
+        let request = json!({ "method": "GET", "url": "http://127.0.0.1:1/", "auth": auth });
+        let request = CString::new(request.to_string()).unwrap();
+        envelope(make_http_request(request.as_ptr()))
+    };
+
+    let response = send(json!({ "type": "digest", "digest": [{ "key": "username", "value": "ada" }] }));
+    assert_eq!(
+        response["error"],
+        json!({
+            "code": "unsupported_auth",
+            "category": "auth",
+            "message": "Auth type 'digest' is not supported",
+            "details": { "type": "digest" }
+        })
+    );
+
+    let response = send(json!({ "type": "oauth2", "oauth2": [{ "key": "grant_type", "value": "client_credentials" }] }));
+    assert_eq!(response["error"]["code"], "missing_access_token");
+    assert_eq!(response["error"]["category"], "auth");
+}
+
+#[test]
+fn oauth2_access_tokens_are_sent() {
+    let base = server(|request| {
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .map(|h| h.value.to_string())
+            .unwrap_or_default();
+        let _ = request.respond(tiny_http::Response::from_string(authorization));
+    });
+    let request = json!({
+        "method": "GET",
+        "url": base,
+        "auth": {
+            "type": "oauth2",
+            "oauth2": [
+                { "key": "accessToken", "value": "abc" },
+                { "key": "headerPrefix", "value": "Token" },
+                { "key": "addTokenTo", "value": "header" }
+            ]
+        }
+    });
+    let request = CString::new(request.to_string()).unwrap();
+    let response = envelope(make_http_request(request.as_ptr()));
+    assert_eq!(response["ok"]["body"], "Token abc");
+}
+
+#[test]
+fn api_keys_are_sent_as_headers() {
+    let base = server(|request| {
+        let key = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("X-Api-Key"))
+            .map(|h| h.value.to_string())
+            .unwrap_or_default();
+        let _ = request.respond(tiny_http::Response::from_string(key));
+    });
+    let send = |placement: &str| {
+        let request = json!({
+            "method": "GET",
+            "url": base,
+            "auth": {
+                "type": "apikey",
+                "apikey": [
+                    { "key": "key", "value": "X-Api-Key" },
+                    { "key": "value", "value": "secret" },
+                    { "key": "in", "value": placement }
+                ]
+            }
+        });
+        let request = CString::new(request.to_string()).unwrap();
+        envelope(make_http_request(request.as_ptr()))
+    };
+    assert_eq!(send("header")["ok"]["body"], "secret");
+    assert_eq!(send("query")["error"]["code"], "unsupported_auth");
+}
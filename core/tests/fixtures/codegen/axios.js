@@ -0,0 +1,93 @@
+==> text <==
+const axios = require('axios');
+
+let data = "{\"a\": \"line\\nbreak\u2028\"}\n'end'";
+
+let config = {
+  method: "post",
+  maxBodyLength: Infinity,
+  url: "https://api.test/it's/%22quoted%22?q=a%20b&home=$HOME",
+  headers: {
+    "X-Note": "it's \"fine\" \\ $HOME",
+    "Authorization": "Bearer t'o\"k",
+  },
+  data: data
+};
+
+axios.request(config)
+  .then((response) => {
+    console.log(JSON.stringify(response.data));
+  })
+  .catch((error) => {
+    console.log(error);
+  });
+
+==> urlencoded <==
+const axios = require('axios');
+
+let data = new URLSearchParams();
+data.append("a b&c", "x=1&y 'z'");
+
+let config = {
+  method: "post",
+  maxBodyLength: Infinity,
+  url: "https://api.test/form",
+  data: data
+};
+
+axios.request(config)
+  .then((response) => {
+    console.log(JSON.stringify(response.data));
+  })
+  .catch((error) => {
+    console.log(error);
+  });
+
+==> multipart <==
+const FormData = require('form-data');
+const fs = require('fs');
+const axios = require('axios');
+
+let data = new FormData();
+data.append("note", "say \"hi\" \\ 'there'");
+data.append("photo", fs.createReadStream("/tmp/it's \"a\".png"));
+
+let config = {
+  method: "put",
+  maxBodyLength: Infinity,
+  url: "https://api.test/upload",
+  headers: {
+    ...data.getHeaders()
+  },
+  data: data
+};
+
+axios.request(config)
+  .then((response) => {
+    console.log(JSON.stringify(response.data));
+  })
+  .catch((error) => {
+    console.log(error);
+  });
+
+==> file <==
+const fs = require('fs');
+const axios = require('axios');
+
+let data = fs.createReadStream("/tmp/line\nbreak\\\u2028.bin");
+
+let config = {
+  method: "post",
+  maxBodyLength: Infinity,
+  url: "https://api.test/file",
+  data: data
+};
+
+axios.request(config)
+  .then((response) => {
+    console.log(JSON.stringify(response.data));
+  })
+  .catch((error) => {
+    console.log(error);
+  });
+
@@ -0,0 +1,22 @@
+==> text <==
+curl --location --request POST 'https://api.test/it'\''s/%22quoted%22?q=a%20b&home=$HOME' \
+--header 'X-Note: it'\''s "fine" \ $HOME' \
+--header 'Authorization: Bearer t'\''o"k' \
+--data-raw '{"a": "line\nbreak "}
+'\''end'\'''
+
+==> urlencoded <==
+curl --location --request POST 'https://api.test/form' \
+--data-urlencode 'a+b%26c=x=1&y '\''z'\'''
+
+==> multipart <==
+curl --location --request PUT 'https://api.test/upload' \
+--header 'Content-Type: multipart/form-data' \
+--form 'note="say \"hi\" \\ '\''there'\''"' \
+--form 'photo=@"/tmp/it'\''s \"a\".png"'
+
+==> file <==
+curl --location --request POST 'https://api.test/file' \
+--data-binary '@/tmp/line
+break\ .bin'
+
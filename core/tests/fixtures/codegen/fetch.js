@@ -0,0 +1,67 @@
+==> text <==
+const myHeaders = new Headers();
+myHeaders.append("X-Note", "it's \"fine\" \\ $HOME");
+myHeaders.append("Authorization", "Bearer t'o\"k");
+
+const raw = "{\"a\": \"line\\nbreak\u2028\"}\n'end'";
+
+const requestOptions = {
+  method: "POST",
+  headers: myHeaders,
+  body: raw,
+  redirect: "follow"
+};
+
+fetch("https://api.test/it's/%22quoted%22?q=a%20b&home=$HOME", requestOptions)
+  .then((response) => response.text())
+  .then((result) => console.log(result))
+  .catch((error) => console.error(error));
+
+==> urlencoded <==
+const urlencoded = new URLSearchParams();
+urlencoded.append("a b&c", "x=1&y 'z'");
+
+const requestOptions = {
+  method: "POST",
+  body: urlencoded,
+  redirect: "follow"
+};
+
+fetch("https://api.test/form", requestOptions)
+  .then((response) => response.text())
+  .then((result) => console.log(result))
+  .catch((error) => console.error(error));
+
+==> multipart <==
+const myHeaders = new Headers();
+
+const formdata = new FormData();
+formdata.append("note", "say \"hi\" \\ 'there'");
+formdata.append("photo", fileInput.files[0], "it's \"a\".png");
+
+const requestOptions = {
+  method: "PUT",
+  headers: myHeaders,
+  body: formdata,
+  redirect: "follow"
+};
+
+fetch("https://api.test/upload", requestOptions)
+  .then((response) => response.text())
+  .then((result) => console.log(result))
+  .catch((error) => console.error(error));
+
+==> file <==
+const file = fileInput.files[0]; // "/tmp/line\nbreak\\\u2028.bin"
+
+const requestOptions = {
+  method: "POST",
+  body: file,
+  redirect: "follow"
+};
+
+fetch("https://api.test/file", requestOptions)
+  .then((response) => response.text())
+  .then((result) => console.log(result))
+  .catch((error) => console.error(error));
+
@@ -0,0 +1,189 @@
+==> text <==
+package main
+
+import (
+	"fmt"
+	"io"
+	"net/http"
+	"strings"
+)
+
+func main() {
+	requestURL := "https://api.test/it's/%22quoted%22?q=a%20b&home=$HOME"
+	method := "POST"
+
+	payload := strings.NewReader("{\"a\": \"line\\nbreak\u2028\"}\n'end'")
+
+	client := &http.Client{}
+	req, err := http.NewRequest(method, requestURL, payload)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	req.Header.Add("X-Note", "it's \"fine\" \\ $HOME")
+	req.Header.Add("Authorization", "Bearer t'o\"k")
+
+	res, err := client.Do(req)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	defer res.Body.Close()
+
+	body, err := io.ReadAll(res.Body)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	fmt.Println(string(body))
+}
+
+==> urlencoded <==
+package main
+
+import (
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"strings"
+)
+
+func main() {
+	requestURL := "https://api.test/form"
+	method := "POST"
+
+	form := url.Values{}
+	form.Add("a b&c", "x=1&y 'z'")
+	payload := strings.NewReader(form.Encode())
+
+	client := &http.Client{}
+	req, err := http.NewRequest(method, requestURL, payload)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+
+	res, err := client.Do(req)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	defer res.Body.Close()
+
+	body, err := io.ReadAll(res.Body)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	fmt.Println(string(body))
+}
+
+==> multipart <==
+package main
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"mime/multipart"
+	"net/http"
+	"os"
+	"path/filepath"
+)
+
+func main() {
+	requestURL := "https://api.test/upload"
+	method := "PUT"
+
+	payload := &bytes.Buffer{}
+	writer := multipart.NewWriter(payload)
+	_ = writer.WriteField("note", "say \"hi\" \\ 'there'")
+	{
+		file, err := os.Open("/tmp/it's \"a\".png")
+		if err != nil {
+			fmt.Println(err)
+			return
+		}
+		defer file.Close()
+		part, err := writer.CreateFormFile("photo", filepath.Base("/tmp/it's \"a\".png"))
+		if err != nil {
+			fmt.Println(err)
+			return
+		}
+		if _, err = io.Copy(part, file); err != nil {
+			fmt.Println(err)
+			return
+		}
+	}
+	if err := writer.Close(); err != nil {
+		fmt.Println(err)
+		return
+	}
+
+	client := &http.Client{}
+	req, err := http.NewRequest(method, requestURL, payload)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	req.Header.Set("Content-Type", writer.FormDataContentType())
+
+	res, err := client.Do(req)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	defer res.Body.Close()
+
+	body, err := io.ReadAll(res.Body)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	fmt.Println(string(body))
+}
+
+==> file <==
+package main
+
+import (
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+)
+
+func main() {
+	requestURL := "https://api.test/file"
+	method := "POST"
+
+	payload, err := os.Open("/tmp/line\nbreak\\\u2028.bin")
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	defer payload.Close()
+
+	client := &http.Client{}
+	req, err := http.NewRequest(method, requestURL, payload)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+
+	res, err := client.Do(req)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	defer res.Body.Close()
+
+	body, err := io.ReadAll(res.Body)
+	if err != nil {
+		fmt.Println(err)
+		return
+	}
+	fmt.Println(string(body))
+}
+
@@ -0,0 +1,61 @@
+==> text <==
+import requests
+
+url = "https://api.test/it's/%22quoted%22?q=a%20b&home=$HOME"
+
+payload = "{\"a\": \"line\\nbreak\u2028\"}\n'end'"
+headers = {
+    "X-Note": "it's \"fine\" \\ $HOME",
+    "Authorization": "Bearer t'o\"k",
+}
+
+response = requests.request("POST", url, headers=headers, data=payload)
+
+print(response.text)
+
+==> urlencoded <==
+import requests
+
+url = "https://api.test/form"
+
+payload = {
+    "a b&c": "x=1&y 'z'",
+}
+headers = {
+}
+
+response = requests.request("POST", url, headers=headers, data=payload)
+
+print(response.text)
+
+==> multipart <==
+import requests
+
+url = "https://api.test/upload"
+
+payload = {
+    "note": "say \"hi\" \\ 'there'",
+}
+files = [
+    ("photo", ("it's \"a\".png", open("/tmp/it's \"a\".png", "rb"), "application/octet-stream")),
+]
+headers = {
+}
+
+response = requests.request("PUT", url, headers=headers, data=payload, files=files)
+
+print(response.text)
+
+==> file <==
+import requests
+
+url = "https://api.test/file"
+
+payload = open("/tmp/line\nbreak\\\u2028.bin", "rb")
+headers = {
+}
+
+response = requests.request("POST", url, headers=headers, data=payload)
+
+print(response.text)
+
@@ -0,0 +1,81 @@
+==> text <==
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("X-Note", "it's \"fine\" \\ $HOME".parse()?);
+    headers.insert("Authorization", "Bearer t'o\"k".parse()?);
+
+    let data = "{\"a\": \"line\\nbreak\u{2028}\"}\n'end'";
+
+    let request = client.request(reqwest::Method::from_bytes("POST".as_bytes())?, "https://api.test/it's/%22quoted%22?q=a%20b&home=$HOME")
+        .headers(headers)
+        .body(data);
+
+    let response = request.send().await?;
+    let body = response.text().await?;
+
+    println!("{}", body);
+
+    Ok(())
+}
+
+==> urlencoded <==
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder().build()?;
+
+    let params = [
+        ("a b&c", "x=1&y 'z'"),
+    ];
+
+    let request = client.request(reqwest::Method::from_bytes("POST".as_bytes())?, "https://api.test/form")
+        .form(&params);
+
+    let response = request.send().await?;
+    let body = response.text().await?;
+
+    println!("{}", body);
+
+    Ok(())
+}
+
+==> multipart <==
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder().build()?;
+
+    let form = reqwest::multipart::Form::new()
+        .text("note", "say \"hi\" \\ 'there'")
+        .part("photo", reqwest::multipart::Part::bytes(std::fs::read("/tmp/it's \"a\".png")?).file_name("it's \"a\".png"));
+
+    let request = client.request(reqwest::Method::from_bytes("PUT".as_bytes())?, "https://api.test/upload")
+        .multipart(form);
+
+    let response = request.send().await?;
+    let body = response.text().await?;
+
+    println!("{}", body);
+
+    Ok(())
+}
+
+==> file <==
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder().build()?;
+
+    let data = std::fs::read("/tmp/line\nbreak\\\u{2028}.bin")?;
+
+    let request = client.request(reqwest::Method::from_bytes("POST".as_bytes())?, "https://api.test/file")
+        .body(data);
+
+    let response = request.send().await?;
+    let body = response.text().await?;
+
+    println!("{}", body);
+
+    Ok(())
+}
+
@@ -0,0 +1,203 @@
+//! Schemas are fetched by introspection and cached per URL, and GraphQL
+//! requests are checked against them before they are sent.
+
+use ababil_core::graphql::{graphql_introspect, graphql_schema, graphql_validate, validate, Schema};
+use ababil_core::{free_string, make_http_request};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::sync::mpsc;
+
+const INTROSPECTION: &str = include_str!("fixtures/graphql/introspection.json");
+
+fn take(result: *mut std::os::raw::c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn schema() -> Schema {
+    let result: Value = serde_json::from_str(INTROSPECTION).unwrap();
+    serde_json::from_value(result["data"]["__schema"].clone()).unwrap()
+}
+
+fn errors(query: &str, variables: Option<&str>) -> Vec<String> {
+    let report = validate(&schema(), query, variables, None);
+    assert_eq!(report.valid, report.errors.is_empty());
+    report.errors.into_iter().map(|e| e.message).collect()
+}
+
+#[test]
+fn valid_documents_pass() {
+    let query = r#"
+        query Users($role: Role, $first: Int = 5, $skip: Boolean!) {
+          users(filter: { role: $role, nameContains: "a" }, first: $first) {
+            __typename
+            ...UserFields
+            friends @skip(if: $skip) { id }
+          }
+          search(term: "x") {
+            ... on User { name }
+            ... on Post { title }
+          }
+        }
+
+        fragment UserFields on User { id name role }
+    "#;
+    assert_eq!(
+        errors(query, Some(r#"{"role": "ADMIN", "skip": false}"#)),
+        Vec::<String>::new()
+    );
+
+    let mutation = r#"mutation($input: CreateUserInput!) { createUser(input: $input) { id } }"#;
+    assert!(errors(mutation, Some(r#"{"input": {"name": "Ada"}}"#)).is_empty());
+    assert!(errors("{ __schema { types { name } } }", None).is_empty());
+}
+
+#[test]
+fn mistakes_are_reported_with_locations() {
+    let report = validate(&schema(), "{\n  user(id: 1) { nickname }\n}", None, None);
+    assert_eq!(
+        report.errors[0].message,
+        "Cannot query field \"nickname\" on type \"User\"."
+    );
+    assert_eq!(
+        (report.errors[0].locations[0].line, report.errors[0].locations[0].column),
+        (2, 17)
+    );
+
+    assert_eq!(
+        errors(
+            "{ user { id } users(first: \"ten\", sort: NAME) { role { x } } search(term: \"a\") { id } }",
+            None
+        ),
+        [
+            "Field \"Query.user\" argument \"id\" of type \"ID!\" is required, but it was not provided.",
+            "Expected value of type \"Int\", found \"ten\".",
+            "Unknown argument \"sort\" on field \"Query.users\".",
+            "Field \"role\" must not have a selection since type \"Role!\" has no subfields.",
+            "Cannot query field \"id\" on type \"SearchResult\".",
+        ]
+    );
+    assert_eq!(
+        errors("query($id: String) { user(id: $id) { friends } node(id: $missing) { id } }", None),
+        [
+            "Variable \"$id\" of type \"String\" used in position expecting type \"ID!\".",
+            "Field \"friends\" of type \"[User!]!\" must have a selection of subfields. Did you mean \"friends { ... }\"?",
+            "Variable \"$missing\" is not defined.",
+        ]
+    );
+    assert_eq!(
+        errors(
+            "{ users(filter: { role: OWNER, age: 3 }) { ...Missing } } fragment Unused on Post { id }",
+            None
+        ),
+        [
+            "Field \"age\" is not defined by type \"UserFilter\".",
+            "Value \"OWNER\" does not exist in \"Role\" enum.",
+            "Unknown fragment \"Missing\".",
+            "Fragment \"Unused\" is never used.",
+        ]
+    );
+    assert_eq!(
+        errors("subscription { users { id } }", None),
+        ["Schema is not configured to execute subscription operation."]
+    );
+    assert!(errors("{ users { id }", None)[0].starts_with("Syntax Error"));
+}
+
+#[test]
+fn variables_are_checked_against_their_types() {
+    let query = "query($id: ID!, $input: CreateUserInput!, $ids: [Int!]) { user(id: $id) { id } }";
+    assert_eq!(
+        errors(query, Some(r#"{"input": {"name": 1, "role": "OWNER", "extra": true}, "ids": [1, null, 2.5]}"#)),
+        [
+            "Variable \"$id\" of required type \"ID!\" was not provided.",
+            "Variable \"$input\" got invalid value {\"name\":1,\"role\":\"OWNER\",\"extra\":true}; Field \"extra\" is not defined by type \"CreateUserInput\".",
+            "Variable \"$input\" got invalid value {\"name\":1,\"role\":\"OWNER\",\"extra\":true} at \"input.name\"; Expected type \"String\".",
+            "Variable \"$input\" got invalid value {\"name\":1,\"role\":\"OWNER\",\"extra\":true} at \"input.role\"; Value \"OWNER\" does not exist in \"Role\" enum.",
+            "Variable \"$ids\" got invalid value [1,null,2.5] at \"ids[1]\"; Expected non-nullable type \"Int!\" not to be null.",
+            "Variable \"$ids\" got invalid value [1,null,2.5] at \"ids[2]\"; Expected type \"Int\".",
+        ]
+    );
+    assert_eq!(errors("{ users { id } }", Some("{nope")).len(), 1);
+}
+
+#[test]
+fn schemas_are_cached_and_checked_before_sending() {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!(
+        "http://127.0.0.1:{}/graphql",
+        server.server_addr().to_ip().unwrap().port()
+    );
+    let (seen_tx, seen_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.to_string());
+            let body: Value = serde_json::from_str(&body).unwrap();
+            seen_tx
+                .send((authorization, body["query"].as_str().unwrap().to_string()))
+                .unwrap();
+            let response = match body["query"].as_str().unwrap().contains("__schema") {
+                true => INTROSPECTION.to_string(),
+                false => json!({ "data": { "users": [] } }).to_string(),
+            };
+            request.respond(tiny_http::Response::from_string(response)).unwrap();
+        }
+    });
+
+    let graphql_request = |query: &str| {
+        json!({
+            "method": "POST",
+            "url": url,
+            "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "secret" }] },
+            "body": { "mode": "graphql", "graphql": { "query": query, "variables": "{}" } }
+        })
+    };
+    let send = |request: Value| take(make_http_request(CString::new(request.to_string()).unwrap().as_ptr()));
+
+    // Nothing is checked until a schema is cached for the URL.
+    let unchecked = send(graphql_request("{ nope }"));
+    assert_eq!(unchecked["ok"]["status_code"], 200);
+    seen_rx.recv().unwrap();
+    let c_url = CString::new(url.clone()).unwrap();
+    assert_eq!(
+        take(graphql_schema(c_url.as_ptr()))["error"]["code"],
+        "schema_not_cached"
+    );
+
+    let request = CString::new(graphql_request("").to_string()).unwrap();
+    let schema = take(graphql_introspect(request.as_ptr()))["ok"].take();
+    assert_eq!(schema["__schema"]["queryType"]["name"], "Query");
+    let (authorization, query) = seen_rx.recv().unwrap();
+    assert_eq!(authorization.as_deref(), Some("Bearer secret"));
+    assert!(query.starts_with("query IntrospectionQuery"));
+    assert_eq!(take(graphql_schema(c_url.as_ptr()))["ok"], schema);
+
+    let rejected = send(graphql_request("{ users { nope } }"));
+    assert_eq!(rejected["error"]["code"], "invalid_graphql");
+    assert_eq!(
+        rejected["error"]["details"]["errors"][0]["message"],
+        "Cannot query field \"nope\" on type \"User\"."
+    );
+    assert!(seen_rx.try_recv().is_err());
+
+    // Validation can be turned off, e.g. when the cached schema is stale.
+    let mut unvalidated = graphql_request("{ users { nope } }");
+    unvalidated["ababilOptions"] = json!({ "validateGraphql": false });
+    assert_eq!(send(unvalidated)["ok"]["status_code"], 200);
+    assert_eq!(seen_rx.recv().unwrap().1, "{ users { nope } }");
+
+    assert_eq!(send(graphql_request("{ users { id } }"))["ok"]["status_code"], 200);
+    assert_eq!(seen_rx.recv().unwrap().1, "{ users { id } }");
+
+    let request = CString::new(graphql_request("{ users { nope } }").to_string()).unwrap();
+    let report = take(graphql_validate(request.as_ptr()))["ok"].take();
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["errors"][0]["locations"], json!([{ "line": 1, "column": 11 }]));
+}
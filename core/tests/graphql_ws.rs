@@ -0,0 +1,249 @@
+//! GraphQL subscriptions against a local server that speaks both the
+//! `graphql-transport-ws` and the legacy `graphql-ws` protocol.
+
+use ababil_core::free_string;
+use ababil_core::graphql_ws::{graphql_subscribe, graphql_subscription_poll, graphql_unsubscribe, SubscriptionCallback};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+fn take(result: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    serde_json::from_str(&text).unwrap()
+}
+
+/// Picks the first offered protocol and follows it. `connection_init` must
+/// carry `{"Authorization": "Bearer secret"}`, or the connection is closed
+/// with 4403. A subscription gets a ping, then two results and `complete`
+/// once the pong arrives; a legacy one gets a keep-alive and one result.
+/// Every message the client sends is passed to the returned channel.
+// The handshake callback's error type is tungstenite's, not ours.
+#[allow(clippy::result_large_err)]
+fn server() -> (u16, mpsc::Receiver<Value>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (seen_tx, seen_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            listener.set_nonblocking(true).unwrap();
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut protocol = String::new();
+                let choose = |request: &Request, mut response: Response| {
+                    let offered = request
+                        .headers()
+                        .get("sec-websocket-protocol")
+                        .unwrap()
+                        .to_str()
+                        .unwrap();
+                    assert_eq!(request.headers().get("x-client").unwrap(), "ababil");
+                    protocol = offered.split(',').next().unwrap().trim().to_string();
+                    response
+                        .headers_mut()
+                        .insert("Sec-WebSocket-Protocol", HeaderValue::from_str(&protocol).unwrap());
+                    Ok(response)
+                };
+                let mut socket = tokio_tungstenite::accept_hdr_async(stream, choose).await.unwrap();
+                seen_tx.send(json!({ "protocol": protocol })).unwrap();
+
+                let send = |message: Value| Message::Text(message.to_string());
+                while let Some(Ok(message)) = socket.next().await {
+                    let Message::Text(text) = message else { continue };
+                    let message: Value = serde_json::from_str(&text).unwrap();
+                    seen_tx.send(message.clone()).unwrap();
+                    let replies = match message["type"].as_str().unwrap() {
+                        "connection_init" if message["payload"]["Authorization"] != "Bearer secret" => {
+                            let frame = CloseFrame {
+                                code: 4403.into(),
+                                reason: "Forbidden".into(),
+                            };
+                            vec![Message::Close(Some(frame))]
+                        }
+                        "connection_init" if protocol == "graphql-ws" => {
+                            vec![send(json!({ "type": "connection_ack" })), send(json!({ "type": "ka" }))]
+                        }
+                        "connection_init" => vec![send(json!({ "type": "connection_ack", "payload": { "v": 1 } }))],
+                        "subscribe" => vec![send(json!({ "type": "ping" }))],
+                        "pong" => vec![
+                            send(json!({ "id": "1", "type": "next", "payload": { "data": { "message": "one" } } })),
+                            send(json!({ "id": "1", "type": "next", "payload": { "data": { "message": "two" } } })),
+                            send(json!({ "id": "1", "type": "complete" })),
+                        ],
+                        "start" => vec![send(
+                            json!({ "id": "1", "type": "data", "payload": { "data": { "message": "one" } } }),
+                        )],
+                        _ => Vec::new(),
+                    };
+                    for reply in replies {
+                        socket.send(reply).await.unwrap();
+                    }
+                }
+            }
+        });
+    });
+    (port, seen_rx)
+}
+
+fn subscribe(port: u16, options: Value) -> u32 {
+    subscribe_with(port, options, None)
+}
+
+fn subscribe_with(port: u16, options: Value, callback: Option<SubscriptionCallback>) -> u32 {
+    let request = json!({
+        "method": "POST",
+        "url": format!("http://127.0.0.1:{}/graphql", port),
+        "header": [{ "key": "X-Client", "value": "ababil" }],
+        "body": {
+            "mode": "graphql",
+            "graphql": {
+                "query": "subscription OnMessage($room: String!) { message(room: $room) }",
+                "variables": "{\"room\": \"a\"}",
+                "operationName": "OnMessage"
+            }
+        }
+    });
+    let request = CString::new(request.to_string()).unwrap();
+    let options = CString::new(options.to_string()).unwrap();
+    let started = take(graphql_subscribe(request.as_ptr(), options.as_ptr(), callback));
+    started["ok"]["id"].as_u64().unwrap() as u32
+}
+
+/// Polls until a message of type `until` arrives, returning everything seen.
+fn poll_until(id: u32, until: &str) -> Vec<Value> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut messages = Vec::new();
+    while Instant::now() < deadline {
+        let polled = take(graphql_subscription_poll(id));
+        messages.extend(polled["ok"]["messages"].as_array().unwrap().iter().cloned());
+        if messages.iter().any(|m| m["type"] == until) {
+            return messages;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("no {} message in {:?}", until, messages);
+}
+
+#[test]
+fn graphql_transport_ws_delivers_each_result() {
+    let (port, seen) = server();
+    let id = subscribe(
+        port,
+        json!({ "connection_params": { "Authorization": "Bearer secret" } }),
+    );
+
+    let messages = poll_until(id, "closed");
+    let types: Vec<&str> = messages.iter().map(|m| m["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["ack", "next", "next", "complete", "closed"]);
+    assert_eq!(
+        messages[0],
+        json!({ "type": "ack", "protocol": "graphql-transport-ws", "payload": { "v": 1 } })
+    );
+    assert_eq!(messages[2]["payload"], json!({ "data": { "message": "two" } }));
+    assert_eq!(
+        (messages[4]["code"].clone(), messages[4]["reason"].clone()),
+        (json!(1000), json!("Normal Closure"))
+    );
+    assert_eq!(take(graphql_subscription_poll(id))["ok"]["state"], "closed");
+    assert_eq!(take(graphql_unsubscribe(id))["ok"], true);
+
+    let seen: Vec<Value> = seen.try_iter().collect();
+    assert_eq!(seen[0], json!({ "protocol": "graphql-transport-ws" }));
+    assert_eq!(
+        seen[1],
+        json!({ "type": "connection_init", "payload": { "Authorization": "Bearer secret" } })
+    );
+    assert_eq!(
+        seen[2],
+        json!({
+            "id": "1",
+            "type": "subscribe",
+            "payload": {
+                "query": "subscription OnMessage($room: String!) { message(room: $room) }",
+                "variables": { "room": "a" },
+                "operationName": "OnMessage"
+            }
+        })
+    );
+    assert_eq!(seen[3], json!({ "type": "pong" }));
+}
+
+#[test]
+fn legacy_protocol_stops_on_unsubscribe_and_rejections_are_reported() {
+    let (port, seen) = server();
+    let options = json!({ "protocol": "graphql-ws", "connection_params": { "Authorization": "Bearer secret" } });
+    let id = subscribe(port, options);
+    let messages = poll_until(id, "next");
+    assert_eq!(messages[0], json!({ "type": "ack", "protocol": "graphql-ws" }));
+    assert_eq!(
+        messages[1],
+        json!({ "type": "next", "payload": { "data": { "message": "one" } } })
+    );
+    assert_eq!(take(graphql_unsubscribe(id))["ok"], true);
+    assert_eq!(take(graphql_unsubscribe(id))["error"]["code"], "subscription_not_found");
+
+    let types: Vec<Value> = seen
+        .try_iter()
+        .map(|m| m.get("type").or(m.get("protocol")).unwrap().clone())
+        .collect();
+    assert_eq!(types, ["graphql-ws", "connection_init", "start", "stop"]);
+
+    let id = subscribe(port, json!({}));
+    let messages = poll_until(id, "closed");
+    assert_eq!(messages[0]["error"]["code"], "connection_rejected");
+    assert_eq!(
+        messages[0]["error"]["details"],
+        json!({ "code": 4403, "reason": "Forbidden" })
+    );
+    assert_eq!(
+        messages[1],
+        json!({ "type": "closed", "code": 4403, "reason": "Forbidden" })
+    );
+    take(graphql_unsubscribe(id));
+
+    let request =
+        CString::new(json!({ "url": "ws://127.0.0.1:1", "body": { "mode": "graphql" } }).to_string()).unwrap();
+    let missing = take(graphql_subscribe(request.as_ptr(), ptr::null(), None));
+    assert_eq!(missing["error"]["code"], "missing_query");
+}
+
+static UNSUBSCRIBED_FROM_CALLBACK: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+extern "C" fn unsubscribe_on_first_result(id: u32, message: *const c_char) {
+    let message: Value = serde_json::from_str(unsafe { CStr::from_ptr(message) }.to_str().unwrap()).unwrap();
+    let mut unsubscribed = UNSUBSCRIBED_FROM_CALLBACK.lock().unwrap();
+    if message["type"] == "next" && unsubscribed.is_empty() {
+        unsubscribed.push(take(graphql_unsubscribe(id)));
+        unsubscribed.push(take(graphql_subscription_poll(id)));
+    }
+}
+
+#[test]
+fn callbacks_can_unsubscribe_their_own_subscription() {
+    let (port, _seen) = server();
+    let options = json!({ "protocol": "graphql-ws", "connection_params": { "Authorization": "Bearer secret" } });
+    let id = subscribe_with(port, options, Some(unsubscribe_on_first_result));
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while UNSUBSCRIBED_FROM_CALLBACK.lock().unwrap().is_empty() {
+        assert!(Instant::now() < deadline, "the callback never ran");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let unsubscribed = UNSUBSCRIBED_FROM_CALLBACK.lock().unwrap();
+    assert_eq!(unsubscribed[0]["ok"], true);
+    assert_eq!(unsubscribed[1]["error"]["code"], "subscription_not_found");
+    assert_eq!(take(graphql_subscription_poll(id))["error"]["code"], "subscription_not_found");
+}
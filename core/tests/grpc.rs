@@ -0,0 +1,197 @@
+//! gRPC calls of every kind against a local server, with types loaded from
+//! a `.proto` file or by server reflection.
+
+use ababil_core::free_string;
+use ababil_core::grpc::{grpc_call, grpc_describe, DynamicCodec};
+use futures_util::StreamExt;
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::ffi::{CStr, CString};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, BoxStream, Context, Poll, Service};
+use tonic::server::NamedService;
+use tonic::transport::{Body, Server};
+use tonic::{Status, Streaming};
+
+const PROTO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/grpc/echo.proto");
+
+fn take(result: *mut std::os::raw::c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn call(request: Value) -> Value {
+    let request = CString::new(request.to_string()).unwrap();
+    take(grpc_call(request.as_ptr()))
+}
+
+/// Serves `echo.Echo`: `Say` echoes, `Count` streams 1..=to, `Join`
+/// concatenates and sums, `Shout` upper-cases each note. A note saying
+/// "fail" ends the call with `INVALID_ARGUMENT`.
+#[derive(Clone)]
+struct Echo {
+    pool: DescriptorPool,
+}
+
+impl NamedService for Echo {
+    const NAME: &'static str = "echo.Echo";
+}
+
+impl Service<http::Request<Body>> for Echo {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let name = request.uri().path().rsplit('/').next().unwrap().to_string();
+        let service = self.pool.get_service_by_name("echo.Echo").unwrap();
+        let method = service.methods().find(|m| m.name() == name).unwrap();
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(DynamicCodec::new(method.input()));
+            Ok(grpc.streaming(Handler { method }, request).await)
+        })
+    }
+}
+
+struct Handler {
+    method: MethodDescriptor,
+}
+
+impl Service<tonic::Request<Streaming<DynamicMessage>>> for Handler {
+    type Response = tonic::Response<BoxStream<DynamicMessage>>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Status>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: tonic::Request<Streaming<DynamicMessage>>) -> Self::Future {
+        let method = self.method.clone();
+        Box::pin(async move {
+            let user = request.metadata().get("x-user").cloned();
+            let mut stream = request.into_inner();
+            let mut notes = Vec::new();
+            while let Some(message) = stream.message().await? {
+                let note = serde_json::to_value(&message).unwrap();
+                if note["text"] == "fail" {
+                    return Err(Status::invalid_argument("asked to fail"));
+                }
+                notes.push(note);
+            }
+
+            let text = |note: &Value| note["text"].as_str().unwrap_or("").to_string();
+            let replies: Vec<Value> = match method.name() {
+                "Say" => notes,
+                "Count" => (1..=notes[0]["to"].as_i64().unwrap()).map(|n| json!({ "number": n })).collect(),
+                "Join" => vec![json!({
+                    "text": notes.iter().map(text).collect::<Vec<_>>().join(" "),
+                    "number": notes.iter().map(|n| n["number"].as_i64().unwrap_or(0)).sum::<i64>(),
+                })],
+                _ => notes.iter().map(|n| json!({ "text": text(n).to_uppercase() })).collect(),
+            };
+            let output = method.output();
+            let replies: Vec<DynamicMessage> =
+                replies.into_iter().map(|reply| DynamicMessage::deserialize(output.clone(), reply).unwrap()).collect();
+            let mut response = tonic::Response::new(futures_util::stream::iter(replies).map(Ok).boxed());
+            if let Some(user) = user {
+                response.metadata_mut().insert("x-user", user);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Starts the server, with reflection, and returns its address.
+fn serve() -> String {
+    let files = protox::compile([PROTO], [std::path::Path::new(PROTO).parent().unwrap()]).unwrap();
+    let pool = DescriptorPool::from_file_descriptor_set(files.clone()).unwrap();
+    let reflection = tonic_reflection::server::Builder::configure()
+        .register_file_descriptor_set(files)
+        .build()
+        .unwrap();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    listener.set_nonblocking(true).unwrap();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            let incoming = futures_util::stream::unfold(listener, |listener| async {
+                Some((listener.accept().await.map(|(stream, _)| stream), listener))
+            });
+            Server::builder()
+                .add_service(Echo { pool })
+                .add_service(reflection)
+                .serve_with_incoming(incoming)
+                .await
+                .unwrap();
+        });
+    });
+    address.to_string()
+}
+
+#[test]
+fn every_call_type_works_with_proto_files() {
+    let url = serve();
+    let base = json!({ "url": url, "proto_files": [PROTO] });
+    let with = |extra: Value| {
+        let mut request = base.clone();
+        request.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        call(request)
+    };
+
+    let say = with(json!({
+        "method": "echo.Echo/Say",
+        "metadata": [["X-User", "ada"]],
+        "messages": [{ "text": "hi", "number": 0 }],
+        "emit_defaults": true
+    }))["ok"]
+        .take();
+    assert_eq!(say["status"], json!({ "code": 0, "name": "OK", "message": "" }));
+    assert_eq!(say["messages"], json!([{ "text": "hi", "number": 0 }]));
+    assert!(say["headers"].as_array().unwrap().contains(&json!(["x-user", "ada"])));
+    assert!(say["trailers"].as_array().unwrap().contains(&json!(["grpc-status", "0"])));
+
+    let count = with(json!({ "method": "/echo.Echo/Count", "messages": [{ "to": 3 }] }))["ok"].take();
+    assert_eq!(count["messages"], json!([{ "number": 1 }, { "number": 2 }, { "number": 3 }]));
+
+    let notes = json!([{ "text": "a", "number": 1 }, { "text": "b", "number": 2 }]);
+    let join = with(json!({ "method": "echo.Echo.Join", "messages": notes }))["ok"].take();
+    assert_eq!(join["messages"], json!([{ "text": "a b", "number": 3 }]));
+
+    let shout = with(json!({ "method": "echo.Echo/Shout", "messages": notes }))["ok"].take();
+    assert_eq!(shout["messages"], json!([{ "text": "A" }, { "text": "B" }]));
+
+    let failed = with(json!({ "method": "echo.Echo/Say", "messages": [{ "text": "fail" }] }))["ok"].take();
+    assert_eq!((failed["status"]["code"].clone(), failed["status"]["name"].clone()), (json!(3), json!("INVALID_ARGUMENT")));
+    assert_eq!(failed["status"]["message"], "asked to fail");
+
+    let invalid = with(json!({ "method": "echo.Echo/Say", "messages": [{ "nope": 1 }] }));
+    assert_eq!(invalid["error"]["code"], "invalid_message");
+    let missing = with(json!({ "method": "echo.Echo/Nope" }));
+    assert_eq!(missing["error"]["code"], "method_not_found");
+}
+
+#[test]
+fn reflection_describes_and_calls_services() {
+    let url = serve();
+    let request = CString::new(json!({ "url": url }).to_string()).unwrap();
+    let described = take(grpc_describe(request.as_ptr()))["ok"].take();
+    let services = described["services"].as_array().unwrap();
+    assert_eq!(services.len(), 1);
+    let methods = &services[0]["methods"];
+    assert_eq!(methods[1]["path"], "echo.Echo/Count");
+    assert_eq!((methods[1]["client_streaming"].clone(), methods[1]["server_streaming"].clone()), (json!(false), json!(true)));
+    assert_eq!(methods[0]["input_template"], json!({ "text": "", "number": 0 }));
+
+    let say = call(json!({ "url": format!("http://{}", url), "method": "echo.Echo/Say", "messages": [{ "text": "hi" }] }));
+    assert_eq!(say["ok"]["messages"], json!([{ "text": "hi" }]));
+}
@@ -0,0 +1,237 @@
+//! HAR 1.2 import into collections, and export of history and runs.
+
+use ababil_core::free_string;
+use ababil_core::har::{har_to_collection, history_to_har, history_to_har_json, run_to_har, Har};
+use ababil_core::history::{history_close, history_open, history_record, HistoryEntry};
+use ababil_core::runner::RunSummary;
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::os::raw::c_char;
+use std::path::Path;
+
+fn envelope(ptr: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn browser_har() -> Har {
+    let text = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/har/browser.har")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+fn history_entry(executed_at_ms: u64, request: Value, response: Option<Value>, error: Option<&str>) -> HistoryEntry {
+    serde_json::from_value(json!({
+        "executed_at_ms": executed_at_ms,
+        "request": request,
+        "response": response,
+        "error": error
+    }))
+    .unwrap()
+}
+
+#[test]
+fn browser_exports_are_imported() {
+    let collection = serde_json::to_value(har_to_collection(&browser_har(), true)).unwrap();
+    assert_eq!(collection["info"]["name"], "Shop checkout");
+    let names: Vec<&str> = collection["item"].as_array().unwrap().iter().map(|i| i["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["GET /api/cart", "POST /api/login", "PUT /api/avatar", "POST /api/events"]);
+
+    // Pseudo-headers and connection headers are not replayed.
+    let cart = &collection["item"][0];
+    assert_eq!(
+        cart["request"]["header"],
+        json!([{ "key": "accept", "value": "application/json" }, { "key": "cookie", "value": "session=abc" }])
+    );
+    assert_eq!(cart["request"]["url"]["raw"], "https://shop.test/api/cart?currency=EUR&coupon=SPRING%2024");
+    assert_eq!(cart["description"], "Load the cart");
+    let example = &cart["response"][0];
+    assert_eq!((&example["name"], &example["code"], &example["responseTime"]), (&json!("200 OK"), &json!(200), &json!("84")));
+    assert_eq!(example["body"], "{\"items\":[]}");
+    assert_eq!(example["cookie"][0]["httpOnly"], true);
+
+    let login = &collection["item"][1]["request"];
+    assert_eq!(login["header"], json!([{ "key": "Content-Type", "value": "application/x-www-form-urlencoded" }]));
+    assert_eq!(
+        login["body"],
+        json!({
+            "mode": "urlencoded",
+            "urlencoded": [
+                { "key": "user", "value": "ada@shop.test", "type": "text" },
+                { "key": "password", "value": "s3cret&more", "type": "text" }
+            ]
+        })
+    );
+    assert_eq!(
+        collection["item"][2]["request"]["body"]["formdata"],
+        json!([{ "key": "caption", "value": "Me", "type": "text" }, { "key": "image", "value": "me.png", "type": "file" }])
+    );
+    assert_eq!(collection["item"][2]["response"][0]["body"], "\u{fffd}PNG");
+
+    // A request that never got a response has no example.
+    let events = &collection["item"][3];
+    assert_eq!(events["request"]["body"], json!({ "mode": "raw", "raw": "{\"event\":\"checkout\"}" }));
+    assert!(events.get("response").is_none());
+
+    let without_responses = har_to_collection(&browser_har(), false);
+    assert!(without_responses.item.iter().all(|item| item.response.is_none()));
+}
+
+#[test]
+fn history_is_exported_oldest_first() {
+    let bearer = json!({ "type": "bearer", "bearer": [{ "key": "token", "value": "t0k" }] });
+    let entries = [
+        history_entry(
+            1_714_557_602_000,
+            json!({ "method": "GET", "url": "https://shop.test/down" }),
+            None,
+            Some("connection refused"),
+        ),
+        history_entry(
+            1_714_557_600_000,
+            json!({
+                "method": "post",
+                "url": "https://shop.test/api/login?next=/a b&off=1",
+                "header": [
+                    { "key": "Cookie", "value": "a=1; b=2" },
+                    { "key": "X-Off", "value": "1", "disabled": true }
+                ],
+                "auth": bearer,
+                "body": {
+                    "mode": "urlencoded",
+                    "urlencoded": [
+                        { "key": "user", "value": "ada@shop.test" },
+                        { "key": "skip", "value": "x", "disabled": true }
+                    ]
+                }
+            }),
+            Some(json!({
+                "status_code": 302,
+                "headers": [["Location", "/account"], ["Set-Cookie", "s=1; Secure"], ["Content-Type", "text/plain"]],
+                "body": "",
+                "duration_ms": 120,
+                "encoded_size": 10,
+                "decoded_size": 20
+            })),
+            None,
+        ),
+        history_entry(
+            1_714_557_601_000,
+            json!({ "method": "GET", "url": "https://shop.test/logo.png" }),
+            Some(json!({
+                "status_code": 200,
+                "headers": [],
+                "body": "\u{fffd}PNG",
+                "body_base64": "iVBORw==",
+                "duration_ms": 8
+            })),
+            None,
+        ),
+    ];
+    let har = serde_json::to_value(history_to_har(&entries)).unwrap();
+    let log = &har["log"];
+    assert_eq!(log["version"], "1.2");
+    assert_eq!(log["creator"]["name"], "Ababil Studio");
+    let started: Vec<&str> = log["entries"].as_array().unwrap().iter().map(|e| e["startedDateTime"].as_str().unwrap()).collect();
+    assert_eq!(started, ["2024-05-01T10:00:00.000Z", "2024-05-01T10:00:01.000Z", "2024-05-01T10:00:02.000Z"]);
+
+    let login = &log["entries"][0];
+    assert_eq!(login["request"]["method"], "POST");
+    assert_eq!(login["request"]["url"], "https://shop.test/api/login?next=/a%20b&off=1");
+    assert_eq!(
+        login["request"]["headers"],
+        json!([{ "name": "Cookie", "value": "a=1; b=2" }, { "name": "Authorization", "value": "Bearer t0k" }])
+    );
+    assert_eq!(login["request"]["cookies"], json!([{ "name": "a", "value": "1" }, { "name": "b", "value": "2" }]));
+    assert_eq!(
+        login["request"]["queryString"],
+        json!([{ "name": "next", "value": "/a b" }, { "name": "off", "value": "1" }])
+    );
+    assert_eq!(
+        login["request"]["postData"],
+        json!({
+            "mimeType": "application/x-www-form-urlencoded",
+            "params": [{ "name": "user", "value": "ada@shop.test" }],
+            "text": "user=ada%40shop.test"
+        })
+    );
+    assert_eq!(login["request"]["bodySize"], 20);
+    let response = &login["response"];
+    assert_eq!((&response["status"], &response["statusText"]), (&json!(302), &json!("Found")));
+    assert_eq!(response["redirectURL"], "/account");
+    assert_eq!(response["cookies"], json!([{ "name": "s", "value": "1", "httpOnly": false, "secure": true }]));
+    assert_eq!((&response["bodySize"], &response["content"]["size"]), (&json!(10), &json!(20)));
+    assert_eq!(login["time"], 120.0);
+    assert_eq!(login["timings"]["wait"], 120.0);
+
+    // Binary bodies are exported as base64; sizes recorded as zero fall back
+    // to the body length.
+    let logo = &log["entries"][1]["response"];
+    assert_eq!(logo["content"], json!({ "size": 6, "mimeType": "", "text": "iVBORw==", "encoding": "base64" }));
+
+    let down = &log["entries"][2]["response"];
+    assert_eq!((&down["status"], &down["statusText"], &down["bodySize"]), (&json!(0), &json!("connection refused"), &json!(-1)));
+
+    // The export loads back as a collection, with the binary body intact.
+    let reimported: Har = serde_json::from_value(har).unwrap();
+    let collection = serde_json::to_value(har_to_collection(&reimported, true)).unwrap();
+    assert_eq!(collection["item"][0]["request"]["body"]["urlencoded"], json!([{ "key": "user", "value": "ada@shop.test", "type": "text" }]));
+    assert_eq!(collection["item"][1]["response"][0]["body"], "\u{fffd}PNG");
+}
+
+#[test]
+fn runs_are_exported_in_order() {
+    let summary: RunSummary = serde_json::from_value(json!({
+        "collection_name": "Shop",
+        "environment_name": null,
+        "iterations": 1,
+        "started_at_ms": 0,
+        "duration_ms": 10,
+        "executions": [
+            {
+                "iteration": 0, "folder_path": [], "name": "b", "started_at_ms": 2_000,
+                "request": { "method": "GET", "url": "https://shop.test/b" },
+                "response": null, "error": "timeout", "assertions": []
+            },
+            {
+                "iteration": 0, "folder_path": [], "name": "a", "started_at_ms": 1_000,
+                "request": { "method": "GET", "url": "https://shop.test/a" },
+                "response": null, "error": "timeout", "assertions": []
+            }
+        ]
+    }))
+    .unwrap();
+    let urls: Vec<String> = run_to_har(&summary).log.entries.into_iter().map(|e| e.request.url).collect();
+    assert_eq!(urls, ["https://shop.test/b", "https://shop.test/a"]);
+}
+
+#[test]
+fn history_exports_are_not_truncated() {
+    let memory = CString::new(":memory:").unwrap();
+    assert_eq!(envelope(history_open(memory.as_ptr())), json!({ "ok": true }));
+    for i in 0..120u64 {
+        let entry = json!({
+            "executed_at_ms": 1_714_557_600_000u64 + i,
+            "request": { "method": "GET", "url": format!("https://shop.test/items/{}", i) }
+        });
+        let entry = CString::new(entry.to_string()).unwrap();
+        assert!(envelope(history_record(entry.as_ptr())).get("ok").is_some());
+    }
+
+    let export = |query: Option<Value>| -> Vec<String> {
+        let query = query.map(|q| CString::new(q.to_string()).unwrap());
+        let result = envelope(history_to_har_json(query.as_ref().map_or(std::ptr::null(), |q| q.as_ptr())));
+        let har: Value = serde_json::from_str(result["ok"].as_str().unwrap()).unwrap();
+        har["log"]["entries"].as_array().unwrap().iter().map(|e| e["request"]["url"].as_str().unwrap().to_string()).collect()
+    };
+    let all = export(None);
+    assert_eq!(all.len(), 120);
+    assert_eq!((all[0].as_str(), all[119].as_str()), ("https://shop.test/items/0", "https://shop.test/items/119"));
+    assert_eq!(export(Some(json!({ "url": "items/1" }))).len(), 31);
+    assert_eq!(export(Some(json!({ "limit": 5 }))).len(), 5);
+    history_close();
+
+    let closed = envelope(history_to_har_json(std::ptr::null()));
+    assert_eq!(closed["error"]["code"], "history_not_open");
+}
@@ -0,0 +1,226 @@
+//! The history store: recording, filtering, retention, and what the app and
+//! the collection runner record while a store is open.
+
+use ababil_core::history::{history_close, history_open, history_query, HistoryEntry, HistoryQuery, HistoryStore, RetentionPolicy};
+use ababil_core::models::collection::Collection;
+use ababil_core::models::environment::Environment;
+use ababil_core::runner::{run_collection, RunOptions};
+use ababil_core::{free_string, make_http_request, HttpResponse};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY_MS: u64 = 86_400_000;
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+fn entry(method: &str, url: &str, status_code: u16, executed_at_ms: u64) -> HistoryEntry {
+    serde_json::from_value(json!({
+        "executed_at_ms": executed_at_ms,
+        "request": { "method": method, "url": url },
+        "response": {
+            "status_code": status_code,
+            "headers": [],
+            "body": "",
+            "duration_ms": 5
+        }
+    }))
+    .unwrap()
+}
+
+fn urls(store: &HistoryStore, query: HistoryQuery) -> Vec<String> {
+    store
+        .query(&query)
+        .unwrap()
+        .entries
+        .iter()
+        .map(|e| e.request.url.as_ref().unwrap().raw.clone().unwrap())
+        .collect()
+}
+
+fn envelope(ptr: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    serde_json::from_str(&text).unwrap()
+}
+
+#[test]
+fn entries_are_recorded_and_filtered() {
+    let store = HistoryStore::open(":memory:").unwrap();
+    let now = now_ms();
+    let mut staging = entry("post", "https://api.test/users", 201, now - 3_000);
+    staging.environment = Some("staging".to_string());
+    let id = store.record(&staging).unwrap();
+    store.record(&entry("GET", "https://api.test/users/7", 404, now - 2_000)).unwrap();
+    store.record(&entry("GET", "https://api.test/file_1%20a", 500, now - 1_000)).unwrap();
+    let mut failed = entry("GET", "https://down.test/", 0, now);
+    failed.response = None;
+    failed.error = Some("connection refused".to_string());
+    store.record(&failed).unwrap();
+
+    let all = store.query(&HistoryQuery::default()).unwrap();
+    assert_eq!(all.total, 4);
+    assert_eq!(all.entries[0].error.as_deref(), Some("connection refused"));
+    assert!(all.entries[0].response.is_none());
+
+    let saved = store.get(id).unwrap().unwrap();
+    assert_eq!((saved.id, saved.environment.as_deref()), (Some(id), Some("staging")));
+    assert_eq!(saved.request.method.as_deref(), Some("post"));
+    assert_eq!(saved.response.unwrap().status_code, 201);
+    assert!(store.get(id + 100).unwrap().is_none());
+
+    let query = |value: Value| serde_json::from_value::<HistoryQuery>(value).unwrap();
+    assert_eq!(
+        urls(&store, query(json!({ "method": "post" }))),
+        ["https://api.test/users"]
+    );
+    assert_eq!(
+        urls(&store, query(json!({ "status_min": 400, "status_max": 499 }))),
+        ["https://api.test/users/7"]
+    );
+    assert_eq!(
+        urls(&store, query(json!({ "environment": "staging" }))),
+        ["https://api.test/users"]
+    );
+    assert_eq!(
+        urls(&store, query(json!({ "url": "users", "limit": 1, "offset": 1 }))),
+        ["https://api.test/users"]
+    );
+    assert_eq!(store.query(&query(json!({ "url": "users", "limit": 1 }))).unwrap().total, 2);
+    assert_eq!(
+        urls(&store, query(json!({ "from_ms": now - 2_500, "to_ms": now - 500 }))),
+        ["https://api.test/file_1%20a", "https://api.test/users/7"]
+    );
+
+    // `%` and `_` in the filter are not wildcards.
+    assert_eq!(urls(&store, query(json!({ "url": "e_1%20" }))), ["https://api.test/file_1%20a"]);
+    assert_eq!(urls(&store, query(json!({ "url": "s%7" }))), Vec::<String>::new());
+    assert_eq!(urls(&store, query(json!({ "url": "users_" }))), Vec::<String>::new());
+
+    assert_eq!(store.delete(id).unwrap(), 1);
+    assert_eq!(store.delete(id).unwrap(), 0);
+    assert_eq!(store.clear().unwrap(), 3);
+    assert_eq!(store.query(&HistoryQuery::default()).unwrap().total, 0);
+}
+
+#[test]
+fn retention_is_applied_and_saved() {
+    let path = std::env::temp_dir().join(format!("ababil-history-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let path_str = path.to_str().unwrap();
+    let now = now_ms();
+
+    let mut store = HistoryStore::open(path_str).unwrap();
+    store.record(&entry("GET", "https://api.test/old", 200, now - 40 * DAY_MS)).unwrap();
+    store.record(&entry("GET", "https://api.test/1", 200, now - 3)).unwrap();
+    store.record(&entry("GET", "https://api.test/2", 200, now - 2)).unwrap();
+    let removed = store
+        .set_retention(RetentionPolicy {
+            max_entries: Some(2),
+            max_age_days: Some(30),
+        })
+        .unwrap();
+    assert_eq!(removed, 1);
+    drop(store);
+
+    let store = HistoryStore::open(path_str).unwrap();
+    assert_eq!(store.retention().max_entries, Some(2));
+    assert_eq!(store.retention().max_age_days, Some(30));
+    store.record(&entry("GET", "https://api.test/3", 200, now - 1)).unwrap();
+    store.record(&entry("GET", "https://api.test/older", 200, now - 31 * DAY_MS)).unwrap();
+    assert_eq!(
+        urls(&store, HistoryQuery::default()),
+        ["https://api.test/3", "https://api.test/2"]
+    );
+    drop(store);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn huge_retention_limits_keep_everything() {
+    let mut store = HistoryStore::open(":memory:").unwrap();
+    let now = now_ms();
+    store.record(&entry("GET", "https://api.test/ancient", 200, 1)).unwrap();
+    store.record(&entry("GET", "https://api.test/new", 200, now)).unwrap();
+    let removed = store
+        .set_retention(RetentionPolicy {
+            max_entries: Some(u64::MAX),
+            max_age_days: Some(u64::MAX),
+        })
+        .unwrap();
+    assert_eq!(removed, 0);
+    assert_eq!(
+        urls(&store, HistoryQuery::default()),
+        ["https://api.test/new", "https://api.test/ancient"]
+    );
+
+    store
+        .set_retention(RetentionPolicy {
+            max_entries: Some(1),
+            max_age_days: Some(u64::MAX),
+        })
+        .unwrap();
+    assert_eq!(urls(&store, HistoryQuery::default()), ["https://api.test/new"]);
+}
+
+#[test]
+fn requests_and_runs_are_recorded_while_open() {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://127.0.0.1:{}", server.server_addr().to_ip().unwrap().port());
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            request.respond(tiny_http::Response::from_string("ok")).unwrap();
+        }
+    });
+
+    let memory = CString::new(":memory:").unwrap();
+    assert_eq!(envelope(history_open(memory.as_ptr())), json!({ "ok": true }));
+
+    let request = json!({
+        "method": "GET",
+        "url": format!("{}/app", base),
+        "ababilOptions": { "environment": "local" }
+    });
+    let request = CString::new(request.to_string()).unwrap();
+    let response: HttpResponse =
+        serde_json::from_value(envelope(make_http_request(request.as_ptr()))["ok"].take()).unwrap();
+    assert_eq!(response.body, "ok");
+
+    let collection = Collection::from_json(
+        &json!({
+            "info": { "name": "Run", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+            "item": [{ "name": "ping", "request": { "method": "GET", "url": "{{baseUrl}}/run" } }]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let environment: Environment = serde_json::from_value(json!({
+        "name": "ci",
+        "values": [{ "key": "baseUrl", "value": base, "enabled": true }]
+    }))
+    .unwrap();
+    let options = RunOptions {
+        environment: Some(environment),
+        ..Default::default()
+    };
+    assert!(run_collection(&collection, &options).unwrap().is_success());
+
+    let page = envelope(history_query(std::ptr::null()));
+    history_close();
+    let recorded: Vec<_> = page["ok"]["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["request"]["url"]["raw"].clone(), e["environment"].clone()))
+        .collect();
+    assert_eq!(
+        recorded,
+        [
+            (json!(format!("{}/run", base)), json!("ci")),
+            (json!(format!("{}/app", base)), json!("local")),
+        ]
+    );
+}
@@ -0,0 +1,140 @@
+//! `.http` files: parsing, writing, and collections that survive a write and
+//! parse round trip or are rejected because the format cannot hold them.
+
+use ababil_core::http_file::{parse_http_file, write_http_file};
+use ababil_core::models::collection::{Collection, CollectionItem};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+fn fixture() -> Collection {
+    let text = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/http/api.http")).unwrap();
+    parse_http_file(&text, "API")
+}
+
+/// Item names as `folder/child` paths, in order.
+fn paths(items: &[CollectionItem], prefix: &str, out: &mut Vec<String>) {
+    for item in items {
+        let path = format!("{}{}", prefix, item.name);
+        out.push(path.clone());
+        if let Some(children) = &item.item {
+            paths(children, &format!("{}/", path), out);
+        }
+    }
+}
+
+fn request(collection: &Collection, index: usize) -> Value {
+    serde_json::to_value(collection.item[index].request.as_ref().unwrap()).unwrap()
+}
+
+fn collection(items: Value) -> Collection {
+    serde_json::from_value(json!({ "info": { "name": "API", "schema": "" }, "item": items })).unwrap()
+}
+
+#[test]
+fn files_are_parsed() {
+    let collection = fixture();
+    let mut names = Vec::new();
+    paths(&collection.item, "", &mut names);
+    assert_eq!(
+        names,
+        [
+            "Users",
+            "Users/List users",
+            "Users/Admin",
+            "Users/Admin/Create user",
+            "login",
+            "Upload",
+            "Import",
+            "Query",
+            "GET",
+            "GET/HEAD / OPTIONS",
+        ]
+    );
+    assert_eq!(collection.variable.as_ref().unwrap()[1].value, "abc");
+
+    let users = serde_json::to_value(&collection.item[0]).unwrap();
+    assert_eq!(users["item"][0]["request"]["url"]["raw"], "{{baseUrl}}/users?page=2&limit=10");
+    // The response handler is not part of the body.
+    let create = &users["item"][1]["item"][0]["request"];
+    assert_eq!(create["body"]["raw"], "{\n  \"name\": \"Ada\"\n}");
+    assert_eq!(create["auth"]["bearer"][0]["value"], "{{token}}");
+
+    let login = request(&collection, 1);
+    assert_eq!(login["auth"]["basic"][1]["value"], "secret");
+    assert_eq!(login["body"]["urlencoded"][1], json!({ "key": "remember", "value": "true", "type": "text" }));
+    assert_eq!(request(&collection, 2)["body"]["formdata"][1]["type"], "file");
+    assert_eq!(request(&collection, 3)["body"]["file"]["src"], "./data.json");
+    assert_eq!(
+        request(&collection, 4)["body"],
+        json!({ "mode": "graphql", "graphql": {
+            "query": "query User($id: ID!) { user(id: $id) { name } }",
+            "variables": "{\"id\": \"1\"}"
+        } })
+    );
+}
+
+#[test]
+fn written_files_parse_back_to_the_same_collection() {
+    let parsed = fixture();
+    assert_eq!(parse_http_file(&write_http_file(&parsed).unwrap(), "API"), parsed);
+
+    // Names with ` / ` and bodies with `#`, `>` and `<` that are not at the
+    // start of a line are kept.
+    let tricky = collection(json!([
+        { "name": "Read / write", "item": [
+            { "name": "A / B", "request": {
+                "method": "POST",
+                "url": "https://api.test/a",
+                "body": { "mode": "raw", "raw": "# heading\n ### indented\nx > y\n>not a handler\n<tag/>" }
+            } }
+        ] },
+        { "name": "Plain", "request": { "method": "GET", "url": "https://api.test/b" } }
+    ]));
+    let text = write_http_file(&tricky).unwrap();
+    assert!(text.starts_with("### Read \\/ write / A \\/ B\n"), "{}", text);
+    let back = parse_http_file(&text, "API");
+    let mut names = Vec::new();
+    paths(&back.item, "", &mut names);
+    assert_eq!(names, ["Read / write", "Read / write/A / B", "Plain"]);
+    assert_eq!(back.item[0].item.as_ref().unwrap()[0].request, tricky.item[0].item.as_ref().unwrap()[0].request);
+}
+
+#[test]
+fn collections_the_format_cannot_hold_are_rejected() {
+    let raw = |text: &str| json!([{ "name": "R", "request": { "method": "POST", "url": "https://a.test", "body": { "mode": "raw", "raw": text } } }]);
+    let cases = [
+        (
+            raw("first\n### second"),
+            "Cannot write request 'R': the body line '### second' would be read as a request separator or response handler",
+        ),
+        (
+            raw("a\n> b"),
+            "Cannot write request 'R': the body line '> b' would be read as a request separator or response handler",
+        ),
+        (
+            raw(">> out.json"),
+            "Cannot write request 'R': the body line '>> out.json' would be read as a request separator or response handler",
+        ),
+        (raw("< ./file.json"), "Cannot write request 'R': the body '< ./file.json' would be read as a file include"),
+        (
+            json!([{ "name": "R", "request": { "url": "https://a.test", "header": [{ "key": "X-A", "value": "1\r\nX-B: 2" }] } }]),
+            "Cannot write request 'R': Header 'X-A' contains a line break",
+        ),
+        (
+            json!([{ "name": "Two\nlines", "request": { "url": "https://a.test" } }]),
+            "Name 'Two\nlines' contains a line break",
+        ),
+        (
+            json!([{ "name": "a \\/ b", "request": { "url": "https://a.test" } }]),
+            "Name 'a \\/ b' contains '\\/', which is ambiguous in a title",
+        ),
+    ];
+    for (items, error) in cases {
+        assert_eq!(write_http_file(&collection(items.clone())).unwrap_err(), error, "{}", items);
+    }
+
+    let mut variables = collection(json!([]));
+    variables.variable = serde_json::from_value(json!([{ "key": "note", "value": "a\nb" }])).unwrap();
+    assert_eq!(write_http_file(&variables).unwrap_err(), "Variable 'note' contains a line break");
+}
@@ -0,0 +1,130 @@
+//! Insomnia v4 exports: workspaces, request groups, requests, environments
+//! and the template tags they use.
+
+use ababil_core::insomnia::{convert_template, parse_insomnia};
+use ababil_core::models::collection::CollectionItem;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+fn fixture(name: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/insomnia").join(name)).unwrap()
+}
+
+fn names(items: &[CollectionItem]) -> Vec<&str> {
+    items.iter().map(|item| item.name.as_str()).collect()
+}
+
+fn request(items: &[CollectionItem], path: &[&str]) -> Value {
+    let (name, rest) = path.split_first().unwrap();
+    let item = items.iter().find(|item| item.name == *name).unwrap();
+    if rest.is_empty() {
+        serde_json::to_value(item.request.as_ref().unwrap()).unwrap()
+    } else {
+        request(item.item.as_deref().unwrap(), rest)
+    }
+}
+
+#[test]
+fn a_workspace_becomes_a_collection() {
+    let (collection, environments) = parse_insomnia(&fixture("store.insomnia.json")).unwrap();
+    assert_eq!(collection.info.name, "Store");
+    assert_eq!(names(&collection.item), ["Orders", "Ping"]);
+    let orders = &collection.item[0];
+    assert_eq!(names(orders.item.as_deref().unwrap()), ["List orders", "Create order"]);
+    assert_eq!(orders.variable.as_ref().unwrap()[0].key, "orderPath");
+
+    let list = request(&collection.item, &["Orders", "List orders"]);
+    assert_eq!(
+        list["header"],
+        json!([
+            { "key": "X-Old", "value": "1", "disabled": true },
+            { "key": "X-Api-Key", "value": "{{apiKey}}" }
+        ])
+    );
+    assert_eq!(list["url"]["query"][1], json!({ "key": "page", "value": "2", "disabled": true }));
+    assert!(list.get("auth").is_none());
+
+    let create = request(&collection.item, &["Orders", "Create order"]);
+    assert_eq!(create["method"], "POST");
+    assert_eq!(create["url"]["raw"], "{{baseUrl}}/{{orderPath}}");
+    assert_eq!(create["header"][1], json!({ "key": "Content-Type", "value": "application/json" }));
+    assert_eq!(create["auth"]["bearer"][0]["value"], "{{token}}");
+
+    // Disabled auth is not imported.
+    assert!(request(&collection.item, &["Ping"]).get("auth").is_none());
+
+    let environments: Vec<(String, Vec<(String, String)>)> = environments
+        .into_iter()
+        .map(|e| (e.name, e.values.unwrap().into_iter().map(|v| (v.key, v.value)).collect()))
+        .collect();
+    let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+    assert_eq!(
+        environments,
+        [
+            (
+                "Staging".to_string(),
+                vec![pair("api.version", "2"), pair("baseUrl", "https://staging.test"), pair("token", "s3cret")]
+            ),
+            ("Production".to_string(), vec![pair("api.version", "2"), pair("baseUrl", "https://api.test")]),
+        ]
+    );
+}
+
+#[test]
+fn each_workspace_becomes_a_folder() {
+    let (collection, environments) = parse_insomnia(&fixture("workspaces.insomnia.yaml")).unwrap();
+    assert_eq!(collection.info.name, "Insomnia import");
+    assert_eq!(names(&collection.item), ["Billing", "Accounts"]);
+    assert_eq!(
+        serde_json::to_value(&collection.item[0].description).unwrap(),
+        json!("Invoices")
+    );
+    assert_eq!(
+        request(&collection.item, &["Accounts", "Users", "List users"])["url"]["raw"],
+        "{{baseUrl}}/users"
+    );
+
+    let environments: Vec<(&str, &str)> = environments
+        .iter()
+        .map(|e| (e.name.as_str(), e.values.as_ref().unwrap()[0].value.as_str()))
+        .collect();
+    assert_eq!(
+        environments,
+        [("Billing / Base Environment", "https://billing.test"), ("Accounts / Dev", "http://localhost:8080")]
+    );
+}
+
+#[test]
+fn template_tags() {
+    let cases = [
+        ("{{ _.baseUrl }}/a", "{{baseUrl}}/a"),
+        ("{{ _['api-key'] }}", "{{api-key}}"),
+        ("{{token}}", "{{token}}"),
+        ("{% uuid 'v4' %}", "{{$guid}}"),
+        ("{% now %}", "{{$isoTimestamp}}"),
+        ("{% now 'iso-8601', '' %}", "{{$isoTimestamp}}"),
+        ("{% now 'unix', '' %}", "{{$timestamp}}"),
+        ("{% timestamp %}", "{{$timestamp}}"),
+        // Postman has no equivalent for these.
+        ("{% now 'millis', '' %}", "{% now 'millis', '' %}"),
+        ("{% now 'custom', 'YYYY-MM-DD' %}", "{% now 'custom', 'YYYY-MM-DD' %}"),
+        ("{% base64 'encode', 'normal', 'x' %}", "{% base64 'encode', 'normal', 'x' %}"),
+        ("a {{ unclosed", "a {{ unclosed"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(convert_template(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn other_documents_are_rejected() {
+    let cases = [
+        ("{\"_type\": \"export\", \"__export_format\": 3}", "Unsupported Insomnia export format 3"),
+        ("{\"_type\": \"export\"}", "Not an Insomnia export: missing '__export_format'"),
+        ("{\"info\": {}}", "Not an Insomnia export: missing '_type: export'"),
+    ];
+    for (text, error) in cases {
+        assert_eq!(parse_insomnia(text).unwrap_err(), error);
+    }
+}
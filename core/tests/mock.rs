@@ -0,0 +1,166 @@
+//! The mock server: which saved example answers a request, and the
+//! start/stop functions.
+
+use ababil_core::free_string;
+use ababil_core::mock::{mock_server_start, mock_server_stop, MockConfig, MockServer};
+use ababil_core::models::collection::Collection;
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+fn envelope(ptr: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn example(name: &str, code: u16, body: &str) -> Value {
+    json!({
+        "name": name,
+        "code": code,
+        "header": [
+            { "key": "Content-Type", "value": "text/plain" },
+            { "key": "Content-Encoding", "value": "gzip" },
+            { "key": "X-Example", "value": name },
+            { "key": "X-Off", "value": "1", "disabled": true }
+        ],
+        "body": body
+    })
+}
+
+fn collection() -> Collection {
+    serde_json::from_value(json!({
+        "info": { "name": "Shop", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+        "item": [
+            {
+                "name": "Users",
+                "item": [
+                    {
+                        "name": "Get user",
+                        "request": { "method": "GET", "url": { "raw": "{{baseUrl}}/users/:id", "host": ["{{baseUrl}}"], "path": ["users", ":id"] } },
+                        "response": [example("Missing", 404, "no such user"), example("Found", 200, "a user")]
+                    },
+                    {
+                        "name": "Me",
+                        "request": { "method": "GET", "url": "{{baseUrl}}/users/me" },
+                        "response": [example("Me", 200, "me")]
+                    }
+                ]
+            },
+            {
+                "name": "Create user",
+                "request": {
+                    "method": "POST",
+                    "url": "https://api.test/users",
+                    "header": [{ "key": "X-Tenant", "value": "acme" }],
+                    "body": { "mode": "raw", "raw": "{\"name\": \"Ada\", \"admin\": false}" }
+                },
+                "response": [example("Created", 201, "created")]
+            },
+            {
+                "name": "Search",
+                "request": { "method": "GET", "url": "{{baseUrl}}/search/c++?q=rust lang&page={{page}}" },
+                "response": [example("Results", 200, "results")]
+            }
+        ]
+    }))
+    .unwrap()
+}
+
+/// Starts a server for `collection()` and returns it with its base URL.
+fn start(config: MockConfig) -> (MockServer, String) {
+    let server = MockServer::start(&collection(), config).unwrap();
+    let base = format!("http://127.0.0.1:{}", server.port);
+    (server, base)
+}
+
+fn get(url: &str, headers: &[(&str, &str)]) -> (u16, String) {
+    let mut request = reqwest::blocking::Client::new().get(url);
+    for (key, value) in headers {
+        request = request.header(*key, *value);
+    }
+    let response = request.send().unwrap();
+    (response.status().as_u16(), response.text().unwrap())
+}
+
+#[test]
+fn routes_pick_the_best_example() {
+    let (server, base) = start(MockConfig::default());
+
+    // Literal segments outrank `:param`, and 2xx examples are preferred.
+    assert_eq!(get(&format!("{}/users/me", base), &[]), (200, "me".to_string()));
+    assert_eq!(get(&format!("{}/users/7", base), &[]), (200, "a user".to_string()));
+    assert_eq!(get(&format!("{}/users/7", base), &[("x-mock-response-code", "404")]), (404, "no such user".to_string()));
+    assert_eq!(get(&format!("{}/users/7", base), &[("X-Mock-Response-Name", "Missing")]), (404, "no such user".to_string()));
+
+    // `+` is literal in a path, and queries are ignored unless matched.
+    assert_eq!(get(&format!("{}/search/c++", base), &[]), (200, "results".to_string()));
+    assert_eq!(get(&format!("{}/search/c%2B%2B?q=go", base), &[]), (200, "results".to_string()));
+
+    let response = reqwest::blocking::get(format!("{}/users/7", base)).unwrap();
+    assert_eq!(response.headers()["x-example"], "Found");
+    assert!(response.headers().get("content-encoding").is_none());
+    assert!(response.headers().get("x-off").is_none());
+
+    let (status, body) = get(&format!("{}/users/7/orders", base), &[]);
+    assert_eq!(status, 404);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({ "error": "No matching example", "method": "GET", "path": "/users/7/orders" })
+    );
+    server.stop();
+}
+
+#[test]
+fn queries_headers_and_bodies_can_be_matched() {
+    let config = MockConfig {
+        match_query: true,
+        match_headers: true,
+        match_body: true,
+        ..MockConfig::default()
+    };
+    let (server, base) = start(config);
+
+    // Query values are form-decoded; `{{variables}}` match anything.
+    assert_eq!(get(&format!("{}/search/c++?q=rust+lang&page=3", base), &[]).0, 200);
+    assert_eq!(get(&format!("{}/search/c++?page=3&q=rust%20lang", base), &[]).0, 200);
+    assert_eq!(get(&format!("{}/search/c++?q=rust", base), &[]).0, 404);
+
+    let client = reqwest::blocking::Client::new();
+    let post = |tenant: &str, body: &str| {
+        let response = client
+            .post(format!("{}/users", base))
+            .header("X-Tenant", tenant)
+            .body(body.to_string())
+            .send()
+            .unwrap();
+        response.status().as_u16()
+    };
+    // JSON bodies are compared as values, so spacing and key order do not matter.
+    assert_eq!(post("acme", r#"{"admin":false,"name":"Ada"}"#), 201);
+    assert_eq!(post("acme", r#"{"name":"Bob","admin":false}"#), 404);
+    assert_eq!(post("other", r#"{"name":"Ada","admin":false}"#), 404);
+    server.stop();
+}
+
+#[test]
+fn servers_are_started_and_stopped_across_the_ffi() {
+    let collection = CString::new(serde_json::to_string(&collection()).unwrap()).unwrap();
+    let config = CString::new(json!({ "latency_ms": 50 }).to_string()).unwrap();
+    let started = envelope(mock_server_start(collection.as_ptr(), config.as_ptr()));
+    let id = started["ok"]["id"].as_u64().unwrap() as u32;
+    let port = started["ok"]["port"].as_u64().unwrap();
+
+    let start = std::time::Instant::now();
+    assert_eq!(get(&format!("http://127.0.0.1:{}/users/me", port), &[]).1, "me");
+    assert!(start.elapsed().as_millis() >= 50);
+
+    assert_eq!(envelope(mock_server_stop(id)), json!({ "ok": true }));
+    let again = envelope(mock_server_stop(id));
+    assert_eq!(again["error"]["code"], "mock_server_not_found");
+
+    let invalid = CString::new(json!({ "port": port, "host": "256.0.0.1" }).to_string()).unwrap();
+    let failed = envelope(mock_server_start(collection.as_ptr(), invalid.as_ptr()));
+    assert_eq!(failed["error"]["code"], "bind_failed");
+    assert_eq!(failed["error"]["details"]["address"], format!("256.0.0.1:{}", port));
+}
@@ -0,0 +1,231 @@
+//! OpenAPI import and export: `$ref`s, tags, security, servers and path
+//! templates, and documents that survive a trip through a collection.
+
+use ababil_core::models::collection::{Collection, CollectionItem};
+use ababil_core::openapi::{collection_to_openapi, openapi_to_collection, parse_spec};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+fn spec() -> Value {
+    let text = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/openapi/store.yaml")).unwrap();
+    parse_spec(&text).unwrap()
+}
+
+/// `(folder/name, method, raw URL, auth type)` for each request, in order.
+fn requests(collection: &Collection) -> Vec<(String, String, String, Option<String>)> {
+    fn walk(items: &[CollectionItem], prefix: &str, out: &mut Vec<(String, String, String, Option<String>)>) {
+        for item in items {
+            if let Some(children) = &item.item {
+                walk(children, &format!("{}{}/", prefix, item.name), out);
+            } else if let Some(request) = &item.request {
+                out.push((
+                    format!("{}{}", prefix, item.name),
+                    request.method.clone().unwrap_or_default(),
+                    request.url.as_ref().and_then(|u| u.raw.clone()).unwrap_or_default(),
+                    request.auth.as_ref().and_then(|a| a.auth_type.clone()),
+                ));
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(&collection.item, "", &mut out);
+    out
+}
+
+fn find<'a>(items: &'a [CollectionItem], name: &str) -> &'a CollectionItem {
+    fn search<'a>(items: &'a [CollectionItem], name: &str) -> Option<&'a CollectionItem> {
+        items.iter().find_map(|item| match &item.item {
+            _ if item.name == name => Some(item),
+            Some(children) => search(children, name),
+            None => None,
+        })
+    }
+    search(items, name).unwrap()
+}
+
+fn value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap()
+}
+
+fn row(name: &str, method: &str, url: &str, auth: Option<&str>) -> (String, String, String, Option<String>) {
+    (name.to_string(), method.to_string(), url.to_string(), auth.map(str::to_string))
+}
+
+#[test]
+fn documents_are_imported() {
+    let collection = openapi_to_collection(&spec());
+    assert_eq!(collection.info.name, "Store API");
+    // Folders follow the declared tags, then first use; unused tags are
+    // dropped and untagged operations stay at the root.
+    assert_eq!(
+        requests(&collection),
+        [
+            row("Orders/List orders", "GET", "{{baseUrl}}/orders?page=1&status=open", None),
+            row("Orders/Create order", "POST", "{{baseUrl}}/orders", Some("basic")),
+            row("Orders/getOrder", "GET", "{{baseUrl}}/orders/:id", Some("noauth")),
+            row("Admin/Delete order", "DELETE", "{{baseUrl}}/orders/:id?key={{apiKey}}", Some("noauth")),
+            row("Download file", "GET", "{{baseUrl}}/files/{{name}}.{{ext}}", Some("noauth")),
+            row("Upload", "POST", "{{baseUrl}}/upload", Some("oauth2")),
+        ]
+    );
+    assert_eq!(value(&collection.item[0].description), json!("Order management"));
+
+    // The first server, with its variables.
+    let variables: Vec<(&str, &str)> = collection
+        .variable
+        .iter()
+        .flatten()
+        .map(|v| (v.key.as_str(), v.value.as_str()))
+        .collect();
+    assert_eq!(
+        variables,
+        [
+            ("baseUrl", "https://{{region}}.store.test/{{version}}"),
+            ("region", "eu"),
+            ("version", "v2"),
+            ("bearerToken", ""),
+            ("username", ""),
+            ("password", ""),
+            ("apiKey", ""),
+            ("accessToken", ""),
+        ]
+    );
+    assert_eq!(value(&collection.auth)["bearer"][0]["value"], "{{bearerToken}}");
+
+    // Parameters from `$ref`s, path items and operations, which override them.
+    let get = value(&find(&collection.item, "getOrder").request);
+    assert_eq!(get["url"]["variable"], json!([{ "key": "id", "value": "42", "type": "string" }]));
+    let delete = value(&find(&collection.item, "Delete order").request);
+    assert_eq!(delete["url"]["variable"][0]["value"], "7");
+    let list = value(&find(&collection.item, "List orders").request);
+    assert_eq!(list["header"], json!([{ "key": "X-Trace", "value": "abc" }]));
+
+    // Recursive schemas are expanded once.
+    let create = value(&find(&collection.item, "Create order").request);
+    assert_eq!(
+        serde_json::from_str::<Value>(create["body"]["raw"].as_str().unwrap()).unwrap(),
+        json!({ "item": "string", "quantity": 0, "placed": "2024-01-01T00:00:00Z" })
+    );
+
+    // Templates within a segment become item variables.
+    let download = find(&collection.item, "Download file");
+    assert_eq!(
+        value(&download.variable),
+        json!([
+            { "key": "name", "value": "report", "type": "string" },
+            { "key": "ext", "value": "pdf", "type": "string" }
+        ])
+    );
+    assert!(download.request.as_ref().unwrap().url.as_ref().unwrap().variable.is_none());
+    assert_eq!(value(&download.request)["header"], json!([{ "key": "X-Api-Key", "value": "{{apiKey}}" }]));
+
+    let upload = value(&find(&collection.item, "Upload").request);
+    assert_eq!(
+        upload["body"]["formdata"],
+        json!([
+            { "key": "note", "value": "string", "type": "text", "description": "A note" },
+            { "key": "file", "value": "", "type": "file" }
+        ])
+    );
+    assert_eq!(
+        upload["auth"]["oauth2"],
+        json!([
+            { "key": "accessToken", "value": "{{accessToken}}", "type": "string" },
+            { "key": "addTokenTo", "value": "header", "type": "string" },
+            { "key": "grant_type", "value": "client_credentials", "type": "string" },
+            { "key": "accessTokenUrl", "value": "https://auth.store.test/token", "type": "string" },
+            { "key": "scope", "value": "write", "type": "string" }
+        ])
+    );
+}
+
+#[test]
+fn collections_are_exported() {
+    let document = collection_to_openapi(&openapi_to_collection(&spec()));
+    assert_eq!(document["openapi"], "3.1.0");
+    assert_eq!(
+        document["servers"],
+        json!([{
+            "url": "https://{region}.store.test/{version}",
+            "variables": { "region": { "default": "eu" }, "version": { "default": "v2" } }
+        }])
+    );
+    assert_eq!(document["tags"], json!([{ "name": "Orders", "description": "Order management" }, { "name": "Admin" }]));
+    assert_eq!(document["security"], json!([{ "bearerAuth": [] }]));
+
+    let paths: Vec<&String> = document["paths"].as_object().unwrap().keys().collect();
+    assert_eq!(paths, ["/orders", "/orders/{id}", "/files/{name}.{ext}", "/upload"]);
+    let download = &document["paths"]["/files/{name}.{ext}"]["get"];
+    assert_eq!(
+        download["parameters"].as_array().unwrap()[..2],
+        [
+            json!({ "name": "name", "in": "path", "required": true, "schema": { "type": "string" }, "example": "report" }),
+            json!({ "name": "ext", "in": "path", "required": true, "schema": { "type": "string" }, "example": "pdf" }),
+        ]
+    );
+    let orders = &document["paths"]["/orders"];
+    assert!(orders["get"].get("security").is_none());
+    assert_eq!(orders["post"]["security"], json!([{ "basicAuth": [] }]));
+    assert_eq!(document["paths"]["/orders/{id}"]["get"]["security"], json!([]));
+    assert_eq!(
+        document["components"]["securitySchemes"]["basicAuth"],
+        json!({ "type": "http", "scheme": "basic" })
+    );
+    assert_eq!(
+        document["components"]["securitySchemes"]["oauth2"],
+        json!({
+            "type": "oauth2",
+            "flows": { "clientCredentials": { "tokenUrl": "https://auth.store.test/token", "scopes": { "write": "" } } }
+        })
+    );
+}
+
+#[test]
+fn documents_survive_a_trip_through_a_collection() {
+    let original = spec();
+    let collection = openapi_to_collection(&original);
+    let exported = collection_to_openapi(&collection);
+
+    // Every operation keeps its path, method and first tag, and the same
+    // path parameters.
+    for (path, path_item) in original["paths"].as_object().unwrap() {
+        for (method, operation) in path_item.as_object().unwrap() {
+            if method == "parameters" {
+                continue;
+            }
+            let copy = &exported["paths"][path][method];
+            assert!(copy.is_object(), "{} {}", method, path);
+            assert_eq!(copy.get("tags").map(|t| &t[0]), operation.get("tags").map(|t| &t[0]), "{} {}", method, path);
+            let path_parameters = |operation: &Value| -> Vec<String> {
+                let mut names: Vec<String> = operation["parameters"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .chain(path_item["parameters"].as_array().into_iter().flatten())
+                    .filter(|p| p["in"] == "path")
+                    .map(|p| p["name"].as_str().unwrap().to_string())
+                    .collect();
+                names.sort();
+                names.dedup();
+                names
+            };
+            assert_eq!(path_parameters(copy), path_parameters(operation), "{} {}", method, path);
+        }
+    }
+
+    // Importing the export gives the same requests.
+    assert_eq!(requests(&openapi_to_collection(&exported)), requests(&collection));
+}
+
+#[test]
+fn other_documents_are_rejected() {
+    let cases = [
+        ("swagger: '2.0'", "Swagger 2.0 documents are not supported"),
+        ("openapi: 4.0.0", "Unsupported OpenAPI version '4.0.0'"),
+        ("{}", "Not an OpenAPI 3 document: missing 'openapi' field"),
+    ];
+    for (text, error) in cases {
+        assert_eq!(parse_spec(text).unwrap_err(), error);
+    }
+}
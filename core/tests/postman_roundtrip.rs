@@ -0,0 +1,107 @@
+//! Every Postman export under `tests/fixtures/postman` must survive
+//! import followed by export without losing anything.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ababil_core::models::collection::Collection;
+use ababil_core::models::environment::Environment;
+use serde_json::Value;
+
+fn fixtures(suffix: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/postman");
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(suffix))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no {} fixtures", suffix);
+    paths
+}
+
+/// Exports omit empty optional fields, so `"x": null` and a missing `x`
+/// count as the same document.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+        other => other,
+    }
+}
+
+fn parse(text: &str) -> Value {
+    without_nulls(serde_json::from_str(text).unwrap())
+}
+
+#[test]
+fn collections_round_trip() {
+    for path in fixtures(".postman_collection.json") {
+        let original = fs::read_to_string(&path).unwrap();
+
+        let collection = Collection::from_json(&original).unwrap();
+        let exported = collection.to_json().unwrap();
+        assert_eq!(parse(&exported), parse(&original), "{}", path.display());
+
+        // The FFI hands the parsed collection back as JSON, so a second pass
+        // must not change it either.
+        let reparsed: Collection = serde_json::from_str(&exported).unwrap();
+        assert_eq!(reparsed, collection, "{}", path.display());
+        assert_eq!(reparsed.to_json().unwrap(), exported, "{}", path.display());
+    }
+}
+
+#[test]
+fn unknown_fields_keep_their_order() {
+    let original = r#"{
+        "info": {"name": "Order", "zeta": 1, "alpha": 2, "mid": 3},
+        "item": []
+    }"#;
+    let exported = Collection::from_json(original).unwrap().to_json().unwrap();
+    let zeta = exported.find("zeta").unwrap();
+    let alpha = exported.find("alpha").unwrap();
+    let mid = exported.find("mid").unwrap();
+    assert!(zeta < alpha && alpha < mid, "{}", exported);
+}
+
+#[test]
+fn environments_round_trip() {
+    for path in fixtures(".postman_environment.json") {
+        let original = fs::read_to_string(&path).unwrap();
+
+        let environment: Environment = serde_json::from_str(&original).unwrap();
+        let exported = serde_json::to_string_pretty(&environment).unwrap();
+        assert_eq!(parse(&exported), parse(&original), "{}", path.display());
+    }
+}
+
+#[test]
+fn edited_values_lose_their_original_shape() {
+    let mut collection = Collection::from_json(
+        r#"{
+            "info": {"name": "Edited", "description": {"content": "Old", "type": "text/markdown"}},
+            "item": [],
+            "variable": [{"key": "a", "value": 1}, {"key": "b", "value": 2}, {"key": "c"}]
+        }"#,
+    )
+    .unwrap();
+    let variables = collection.variable.as_mut().unwrap();
+    variables[0].value = "one".to_string();
+    variables[2].value = "set".to_string();
+
+    let exported: Value = serde_json::from_str(&collection.to_json().unwrap()).unwrap();
+    assert_eq!(
+        exported["variable"],
+        serde_json::json!([{"key": "a", "value": "one"}, {"key": "b", "value": 2}, {"key": "c", "value": "set"}])
+    );
+    assert_eq!(
+        exported["info"]["description"],
+        serde_json::json!({"content": "Old", "type": "text/markdown"})
+    );
+}
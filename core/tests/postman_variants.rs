@@ -0,0 +1,76 @@
+//! The Postman schema allows several shapes for some fields; all of them
+//! must load into the same model.
+
+use ababil_core::models::collection::Collection;
+
+#[test]
+fn polymorphic_fields_are_normalized() {
+    let collection = Collection::from_json(
+        r##"{
+            "info": {
+                "name": "Variants",
+                "description": {"content": "# Notes", "type": "text/markdown"},
+                "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+            },
+            "item": [
+                {"name": "String request", "request": "https://api.example.com/ping"},
+                {
+                    "name": "String URL and headers",
+                    "request": {
+                        "method": "POST",
+                        "url": "https://api.example.com/users?page=2",
+                        "header": "Accept: application/json\nX-Id: 7",
+                        "description": {"content": "Creates a user"}
+                    }
+                },
+                {
+                    "name": "String host and path",
+                    "event": [{"listen": "test", "script": {"exec": "a();\nb();"}}],
+                    "request": {
+                        "method": "GET",
+                        "url": {
+                            "raw": "https://api.example.com/v1/users",
+                            "host": "api.example.com",
+                            "path": ["v1", {"type": "string", "value": "users"}]
+                        },
+                        "header": [{"key": "X-Empty"}, "X-Line: yes"]
+                    },
+                    "response": [{"name": "ok", "code": 200, "responseTime": 41, "header": "Content-Type: text/plain"}]
+                }
+            ],
+            "variable": [{"key": "retries", "value": 3}, {"key": "verbose", "value": true}, {"key": "unset"}]
+        }"##,
+    )
+    .unwrap();
+
+    assert_eq!(collection.info.description.as_deref(), Some("# Notes"));
+
+    let ping = collection.item[0].request.as_ref().unwrap();
+    assert_eq!(ping.method.as_deref(), Some("GET"));
+    assert_eq!(ping.url.as_ref().unwrap().path, Some(vec!["ping".to_string()]));
+
+    let create = collection.item[1].request.as_ref().unwrap();
+    let url = create.url.as_ref().unwrap();
+    assert_eq!(url.host, Some(vec!["api".to_string(), "example".to_string(), "com".to_string()]));
+    assert_eq!(url.query.as_ref().unwrap()[0].value.as_deref(), Some("2"));
+    let headers = create.header.as_ref().unwrap();
+    assert_eq!((headers[1].key.as_str(), headers[1].value.as_str()), ("X-Id", "7"));
+    assert_eq!(create.description.as_deref(), Some("Creates a user"));
+
+    let item = &collection.item[2];
+    let request = item.request.as_ref().unwrap();
+    let url = request.url.as_ref().unwrap();
+    assert_eq!(url.host.as_ref().unwrap().len(), 3);
+    assert_eq!(url.path, Some(vec!["v1".to_string(), "users".to_string()]));
+    let headers = request.header.as_ref().unwrap();
+    assert_eq!(headers[0].value, "");
+    assert_eq!(headers[1].key, "X-Line");
+    let exec = item.event.as_ref().unwrap()[0].script.as_ref().unwrap().exec.clone();
+    assert_eq!(exec, Some(vec!["a();".to_string(), "b();".to_string()]));
+    let response = &item.response.as_ref().unwrap()[0];
+    assert_eq!(response.response_time.as_deref(), Some("41"));
+    assert_eq!(response.header.as_ref().unwrap()[0].value, "text/plain");
+
+    let values: Vec<&str> = collection.variable.as_ref().unwrap().iter().map(|v| v.value.as_str()).collect();
+    assert_eq!(values, ["3", "true", ""]);
+}
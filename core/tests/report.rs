@@ -0,0 +1,194 @@
+//! Run reports: JUnit XML and HTML escaping, JSON statistics and timestamps.
+
+use ababil_core::report::{escape_xml, format_timestamp, html_report, json_report, junit_report, ReportFormat};
+use ababil_core::runner::RunSummary;
+use serde_json::{json, Value};
+
+fn assertion(name: &str, passed: bool, skipped: bool, error: Option<&str>) -> Value {
+    json!({ "name": name, "passed": passed, "skipped": skipped, "error": error })
+}
+
+fn execution(folders: &[&str], name: &str, url: &str, response: Option<Value>, error: Option<&str>, assertions: Vec<Value>) -> Value {
+    json!({
+        "iteration": 0,
+        "folder_path": folders,
+        "name": name,
+        "request": {
+            "method": "post",
+            "url": { "raw": url },
+            "header": [{ "key": "X-Note", "value": "<b>&</b>" }],
+            "body": { "mode": "raw", "raw": "{\"q\":\"</pre><script>alert(1)</script>\"}" }
+        },
+        "response": response,
+        "error": error,
+        "assertions": assertions,
+        "started_at_ms": 1_700_000_000_123u64
+    })
+}
+
+fn summary() -> RunSummary {
+    let response = |status: u16, duration: u64, body: &str| {
+        json!({
+            "status_code": status,
+            "headers": [["Content-Type", "text/html"]],
+            "body": body,
+            "duration_ms": duration
+        })
+    };
+    serde_json::from_value(json!({
+        "collection_name": "Shop \"API\" & <friends>",
+        "environment_name": "stage's",
+        "iterations": 1,
+        "started_at_ms": 1_700_000_000_000u64,
+        "duration_ms": 1_250,
+        "executions": [
+            execution(
+                &["Users", "A&B"],
+                "Create <user>",
+                "https://api.test/users?a=1&b=2",
+                Some(response(201, 100, "<h1>ok</h1>")),
+                None,
+                vec![
+                    assertion("status is 201", true, false, None),
+                    assertion("name has \"quotes\"", false, false, Some("expected 'a' < 'b'")),
+                    assertion("uses pm.sendRequest", false, true, Some("unsupported statement")),
+                ],
+            ),
+            execution(&[], "Ping", "https://api.test/ping", Some(response(200, 300, "")), None, vec![]),
+            execution(&[], "Down", "https://down.test/", None, Some("connection refused: <down>\u{1}"), vec![]),
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn junit_reports_escape_names_and_messages() {
+    assert_eq!(
+        junit_report(&summary()),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="Shop &quot;API&quot; &amp; &lt;friends&gt;" tests="5" failures="2" time="1.250">
+  <testsuite name="Users / A&amp;B / Create &lt;user&gt;" id="0" timestamp="2023-11-14T22:13:20.123Z" tests="3" failures="1" skipped="1" time="0.100">
+    <testcase name="status is 201" classname="Users.A&amp;B" time="0.100"/>
+    <testcase name="name has &quot;quotes&quot;" classname="Users.A&amp;B" time="0.100">
+      <failure type="AssertionError" message="expected &apos;a&apos; &lt; &apos;b&apos;"/>
+    </testcase>
+    <testcase name="uses pm.sendRequest" classname="Users.A&amp;B" time="0.100">
+      <skipped message="unsupported statement"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="Ping" id="1" timestamp="2023-11-14T22:13:20.123Z" tests="1" failures="0" skipped="0" time="0.300">
+    <testcase name="Ping" classname="" time="0.300"/>
+  </testsuite>
+  <testsuite name="Down" id="2" timestamp="2023-11-14T22:13:20.123Z" tests="1" failures="1" skipped="0" time="0.000">
+    <testcase name="Down" classname="" time="0.000">
+      <failure type="RequestError" message="connection refused: &lt;down&gt;"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+    );
+}
+
+#[test]
+fn html_reports_escape_everything_they_show() {
+    let html = html_report(&summary());
+    for text in [
+        "<title>Shop &quot;API&quot; &amp; &lt;friends&gt; - Ababil run report</title>",
+        "Environment stage&apos;s &middot; Started 2023-11-14T22:13:20.000Z &middot; 1250 ms",
+        r#"<strong class="fail">failed</strong>"#,
+        r#"<span class="method">POST</span>Users / A&amp;B / Create &lt;user&gt;<span class="status">201 Created &middot; 100 ms</span>"#,
+        "<p><code>https://api.test/users?a=1&amp;b=2</code></p>",
+        r#"<li class="fail">&#10007; name has &quot;quotes&quot; &mdash; expected &apos;a&apos; &lt; &apos;b&apos;</li>"#,
+        r#"<li class="skip">&#8211; uses pm.sendRequest &mdash; unsupported statement</li>"#,
+        "X-Note: &lt;b&gt;&amp;&lt;/b&gt;",
+        "<pre>{&quot;q&quot;:&quot;&lt;/pre&gt;&lt;script&gt;alert(1)&lt;/script&gt;&quot;}</pre>",
+        "<pre>&lt;h1&gt;ok&lt;/h1&gt;</pre>",
+        r#"<p class="fail">connection refused: &lt;down&gt;</p>"#,
+        "<td>average response time</td><td>200 ms</td>",
+    ] {
+        assert!(html.contains(text), "missing {}", text);
+    }
+    assert!(!html.contains("<script>"));
+    assert!(!html.contains('\u{1}'));
+}
+
+#[test]
+fn json_reports_count_requests_and_assertions() {
+    let report: Value = serde_json::from_str(&json_report(&summary()).unwrap()).unwrap();
+    assert_eq!(report["started_at"], "2023-11-14T22:13:20.000Z");
+    assert_eq!(report["success"], false);
+    assert_eq!(
+        report["stats"],
+        json!({
+            "iterations": 1,
+            "requests": 3,
+            "failed_requests": 1,
+            "assertions": 3,
+            "failed_assertions": 1,
+            "skipped_assertions": 1,
+            "average_response_time_ms": 200
+        })
+    );
+    assert_eq!(report["executions"][0]["request"]["header"][0]["value"], "<b>&</b>");
+}
+
+#[test]
+fn timestamps_and_escapes() {
+    let timestamps = [
+        (0, "1970-01-01T00:00:00.000Z"),
+        (951_782_400_000, "2000-02-29T00:00:00.000Z"),
+        (951_868_799_999, "2000-02-29T23:59:59.999Z"),
+        (1_709_251_199_001, "2024-02-29T23:59:59.001Z"),
+        (4_102_444_800_000, "2100-01-01T00:00:00.000Z"),
+        (253_402_300_799_999, "9999-12-31T23:59:59.999Z"),
+    ];
+    for (ms, text) in timestamps {
+        assert_eq!(format_timestamp(ms), text, "{}", ms);
+    }
+
+    assert_eq!(escape_xml("a\tb\nc\r\u{0}\u{1f}d é"), "a\tb\nc\rd é");
+    assert_eq!(ReportFormat::from_name("XML"), Some(ReportFormat::JUnit));
+    assert_eq!(ReportFormat::from_name("pdf"), None);
+}
+
+#[test]
+fn junit_counts_match_the_testcases_written() {
+    // The pre-request script's assertions ran, then the request failed.
+    let summary: RunSummary = serde_json::from_value(json!({
+        "collection_name": "Shop",
+        "environment_name": null,
+        "iterations": 1,
+        "started_at_ms": 0,
+        "duration_ms": 0,
+        "executions": [execution(
+            &[],
+            "Login",
+            "https://down.test/",
+            None,
+            Some("connection refused"),
+            vec![
+                assertion("token is set", false, false, Some("expected undefined to be a string")),
+                assertion("uses pm.sendRequest", false, true, Some("unsupported statement")),
+            ],
+        )]
+    }))
+    .unwrap();
+    assert_eq!(
+        junit_report(&summary),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="Shop" tests="3" failures="2" time="0.000">
+  <testsuite name="Login" id="0" timestamp="2023-11-14T22:13:20.123Z" tests="3" failures="2" skipped="1" time="0.000">
+    <testcase name="token is set" classname="" time="0.000">
+      <failure type="AssertionError" message="expected undefined to be a string"/>
+    </testcase>
+    <testcase name="uses pm.sendRequest" classname="" time="0.000">
+      <skipped message="unsupported statement"/>
+    </testcase>
+    <testcase name="Login" classname="" time="0.000">
+      <failure type="RequestError" message="connection refused"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+    );
+}
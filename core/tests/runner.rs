@@ -0,0 +1,302 @@
+//! Collection runs against a local server that echoes each request back as
+//! JSON, so tests can see what the runner actually sent.
+
+use ababil_core::models::collection::Collection;
+use ababil_core::runner::{parse_iteration_data, run_collection, RunOptions, RunSummary};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Replies to every request with `{"method", "url", "authorization", "json"}`,
+/// where `json` is the request body parsed as JSON.
+fn echo_server() -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://127.0.0.1:{}", server.server_addr().to_ip().unwrap().port());
+    std::thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.to_string());
+            let echo = json!({
+                "method": request.method().as_str(),
+                "url": request.url(),
+                "authorization": authorization,
+                "json": serde_json::from_str::<Value>(&body).ok(),
+            });
+            let header = tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap();
+            let response = tiny_http::Response::from_string(echo.to_string()).with_header(header);
+            request.respond(response).unwrap();
+        }
+    });
+    base
+}
+
+fn bearer(token: &str) -> Value {
+    json!({ "type": "bearer", "bearer": [{ "key": "token", "value": token, "type": "string" }] })
+}
+
+fn get(name: &str, path: &str) -> Value {
+    json!({ "name": name, "request": { "method": "GET", "url": format!("{{{{baseUrl}}}}{}", path) } })
+}
+
+fn collection(base: &str, items: Value) -> Collection {
+    Collection::from_json(
+        &json!({
+            "info": {
+                "name": "Runner",
+                "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+            },
+            "auth": bearer("collection"),
+            "variable": [{ "key": "baseUrl", "value": base }, { "key": "scope", "value": "collection" }],
+            "item": items
+        })
+        .to_string(),
+    )
+    .unwrap()
+}
+
+/// `(name, authorization, url)` as seen by the server, in run order.
+fn sent(summary: &RunSummary) -> Vec<(String, Value, Value)> {
+    summary
+        .executions
+        .iter()
+        .map(|e| {
+            let echo: Value = serde_json::from_str(&e.response.as_ref().unwrap().body).unwrap();
+            (e.name.clone(), echo["authorization"].clone(), echo["url"].clone())
+        })
+        .collect()
+}
+
+#[test]
+fn folders_pass_auth_and_variables_down() {
+    let base = echo_server();
+    let collection = collection(
+        &base,
+        json!([
+            get("top", "/top?scope={{scope}}"),
+            {
+                "name": "Admin",
+                "auth": bearer("admin"),
+                "variable": [{ "key": "scope", "value": "admin" }],
+                "item": [
+                    get("inherits", "/admin?scope={{scope}}"),
+                    {
+                        "name": "own auth",
+                        "request": {
+                            "method": "GET",
+                            "url": "{{baseUrl}}/own",
+                            "auth": { "type": "noauth" }
+                        }
+                    },
+                    {
+                        "name": "Nested",
+                        "variable": [{ "key": "scope", "value": "nested" }],
+                        "item": [get("deep", "/deep?scope={{scope}}")]
+                    }
+                ]
+            }
+        ]),
+    );
+
+    let summary = run_collection(&collection, &RunOptions::default()).unwrap();
+    assert_eq!(
+        sent(&summary),
+        [
+            ("top".into(), json!("Bearer collection"), json!("/top?scope=collection")),
+            ("inherits".into(), json!("Bearer admin"), json!("/admin?scope=admin")),
+            ("own auth".into(), Value::Null, json!("/own")),
+            ("deep".into(), json!("Bearer admin"), json!("/deep?scope=nested")),
+        ]
+    );
+}
+
+#[test]
+fn inherited_auth_is_found_through_every_folder() {
+    let base = echo_server();
+    let inherit = json!({ "type": "inherit" });
+    let collection = collection(
+        &base,
+        json!([{
+            "name": "Outer",
+            "auth": inherit,
+            "item": [{
+                "name": "Middle",
+                "item": [{
+                    "name": "Inner",
+                    "auth": inherit,
+                    "item": [
+                        get("no auth", "/none"),
+                        {
+                            "name": "inherit",
+                            "request": { "method": "GET", "url": "{{baseUrl}}/inherit", "auth": inherit }
+                        }
+                    ]
+                }]
+            }]
+        }]),
+    );
+
+    let summary = run_collection(&collection, &RunOptions::default()).unwrap();
+    assert_eq!(
+        sent(&summary),
+        [
+            ("no auth".into(), json!("Bearer collection"), json!("/none")),
+            ("inherit".into(), json!("Bearer collection"), json!("/inherit")),
+        ]
+    );
+}
+
+fn script(listen: &str, source: &str) -> Value {
+    json!([{ "listen": listen, "script": { "exec": source.lines().collect::<Vec<_>>() } }])
+}
+
+#[test]
+fn scripts_chain_variables_between_requests() {
+    let base = echo_server();
+    let collection = collection(
+        &base,
+        json!([
+            {
+                "name": "login",
+                "event": script("test", r#"
+                    pm.environment.set("token", pm.response.json().json.token);
+                    pm.variables.set("step", "2");
+                "#),
+                "request": {
+                    "method": "POST",
+                    "url": "{{baseUrl}}/login",
+                    "body": { "mode": "raw", "raw": "{\"token\": \"t-{{user}}\"}" }
+                }
+            },
+            {
+                "name": "profile",
+                "event": script("prerequest", "pm.variables.set(\"page\", pm.variables.get(\"step\"));"),
+                "request": {
+                    "method": "GET",
+                    "url": "{{baseUrl}}/profile?page={{page}}",
+                    "auth": bearer("{{token}}")
+                }
+            }
+        ]),
+    );
+    let options = RunOptions {
+        iteration_data: vec![
+            HashMap::from([("user".to_string(), "ada".to_string())]),
+            HashMap::from([("user".to_string(), "bob".to_string())]),
+        ],
+        ..Default::default()
+    };
+
+    let summary = run_collection(&collection, &options).unwrap();
+    assert!(summary.is_success());
+    assert_eq!(summary.skipped_assertions(), 0);
+    let sent: Vec<_> = sent(&summary).into_iter().map(|(_, auth, url)| (auth, url)).collect();
+    assert_eq!(
+        sent,
+        [
+            (json!("Bearer collection"), json!("/login")),
+            (json!("Bearer t-ada"), json!("/profile?page=2")),
+            (json!("Bearer collection"), json!("/login")),
+            (json!("Bearer t-bob"), json!("/profile?page=2")),
+        ]
+    );
+}
+
+#[test]
+fn unsupported_scripts_are_reported_not_passed() {
+    let base = echo_server();
+    let mut item = get("signed", "/signed");
+    item["event"] = script("prerequest", "const signature = CryptoJS.HmacSHA256(\"x\", \"key\");");
+    let summary = run_collection(&collection(&base, json!([item])), &RunOptions::default()).unwrap();
+
+    let assertions = &summary.executions[0].assertions;
+    assert_eq!(assertions.len(), 1);
+    assert_eq!(assertions[0].name, "pre-request script");
+    assert!(assertions[0].skipped && !assertions[0].passed);
+    assert_eq!(summary.skipped_assertions(), 1);
+}
+
+#[test]
+fn folders_can_be_selected() {
+    let base = echo_server();
+    let collection = collection(
+        &base,
+        json!([
+            get("root", "/root"),
+            { "name": "Users", "item": [get("list users", "/users"), { "name": "Admin", "item": [get("audit", "/audit")] }] },
+            { "name": "Orders", "item": [get("list orders", "/orders")] },
+            { "name": "Admin", "item": [get("settings", "/settings")] }
+        ]),
+    );
+    let names = |folders: &[&str]| {
+        let options = RunOptions {
+            folders: folders.iter().map(|f| f.to_string()).collect(),
+            ..Default::default()
+        };
+        let summary = run_collection(&collection, &options).unwrap();
+        summary
+            .executions
+            .iter()
+            .map(|e| format!("{}/{}", e.folder_path.join("/"), e.name))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(&["Orders"]), ["Orders/list orders"]);
+    assert_eq!(names(&["Users"]), ["Users/list users", "Users/Admin/audit"]);
+    // Every folder with the name is selected, at any depth.
+    assert_eq!(
+        names(&["Admin", "Orders"]),
+        ["Users/Admin/audit", "Orders/list orders", "Admin/settings"]
+    );
+
+    let options = RunOptions {
+        folders: vec!["Missing".to_string()],
+        ..Default::default()
+    };
+    let error = run_collection(&collection, &options).unwrap_err();
+    assert_eq!(error.to_string(), "Folder 'Missing' not found in collection");
+}
+
+#[test]
+fn iteration_data_is_read_from_csv_or_json() {
+    let rows = |contents: &str| {
+        parse_iteration_data(contents)
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                let mut row: Vec<_> = row.into_iter().collect();
+                row.sort();
+                row
+            })
+            .collect::<Vec<_>>()
+    };
+    let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+
+    let csv = "user,note\r\nada,\"likes \"\"tea\"\", a lot\"\r\n,\r\nbob,\"two\nlines\"";
+    assert_eq!(
+        rows(csv),
+        [
+            vec![pair("note", "likes \"tea\", a lot"), pair("user", "ada")],
+            vec![pair("note", "two\nlines"), pair("user", "bob")],
+        ]
+    );
+    assert_eq!(rows("user\n"), Vec::<Vec<(String, String)>>::new());
+
+    let json = r#" [{"user": "ada", "age": 36, "admin": true, "tags": ["a"]}, {}] "#;
+    assert_eq!(
+        rows(json),
+        [
+            vec![
+                pair("admin", "true"),
+                pair("age", "36"),
+                pair("tags", "[\"a\"]"),
+                pair("user", "ada")
+            ],
+            vec![],
+        ]
+    );
+    assert!(parse_iteration_data("[1, 2]").is_err());
+}
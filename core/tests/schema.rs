@@ -0,0 +1,173 @@
+//! v1 and v2.0 exports are migrated to the v2.1 model, and v2.1 collections
+//! can be written as v2.0 and read back without changes.
+
+use std::fs;
+use std::path::Path;
+
+use ababil_core::models::collection::{Collection, CollectionItem};
+use ababil_core::models::schema::{detect, SchemaVersion};
+use serde_json::{json, Value};
+
+fn fixture(name: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/postman").join(name)).unwrap()
+}
+
+/// Item names as `folder/child` paths, in order.
+fn tree(items: &[CollectionItem], prefix: &str, out: &mut Vec<String>) {
+    for item in items {
+        let path = format!("{}{}", prefix, item.name);
+        out.push(path.clone());
+        if let Some(children) = &item.item {
+            tree(children, &format!("{}/", path), out);
+        }
+    }
+}
+
+fn paths(collection: &Collection) -> Vec<String> {
+    let mut out = Vec::new();
+    tree(&collection.item, "", &mut out);
+    out
+}
+
+fn find<'a>(items: &'a [CollectionItem], name: &str) -> Option<&'a CollectionItem> {
+    items.iter().find_map(|item| {
+        if item.name == name {
+            Some(item)
+        } else {
+            find(item.item.as_deref()?, name)
+        }
+    })
+}
+
+fn value(collection: &Collection, name: &str) -> Value {
+    serde_json::to_value(find(&collection.item, name).unwrap()).unwrap()
+}
+
+#[test]
+fn v1_exports_are_migrated() {
+    let text = fixture("legacy/v1.postman_collection.json");
+    assert_eq!(detect(&serde_json::from_str(&text).unwrap()), SchemaVersion::V1);
+    let collection = Collection::from_json(&text).unwrap();
+
+    assert_eq!(collection.info.name, "Legacy API");
+    assert_eq!(collection.info.schema.as_deref(), Some(SchemaVersion::V21.schema_url()));
+    // Subfolders before requests; a request missing from every order list
+    // goes to its `folder`, or to the root.
+    assert_eq!(
+        paths(&collection),
+        [
+            "Users",
+            "Users/Sessions",
+            "Users/Sessions/Log in",
+            "Users/List users",
+            "Users/Get user",
+            "Admin",
+            "Admin/Update settings",
+            "Health",
+            "Version",
+        ]
+    );
+
+    let health = value(&collection, "Health");
+    assert_eq!(
+        health["request"]["header"],
+        json!([
+            { "key": "Accept", "value": "application/json" },
+            { "key": "X-Debug", "value": "1", "disabled": true }
+        ])
+    );
+    assert_eq!(
+        health["request"]["auth"],
+        json!({ "type": "bearer", "bearer": [{ "key": "token", "value": "{{token}}", "type": "string" }] })
+    );
+    assert_eq!(health["event"][0]["listen"], "test");
+
+    let list = value(&collection, "List users");
+    assert_eq!(list["request"]["method"], "GET");
+    assert_eq!(list["request"]["header"][1]["disabled"], true);
+    assert_eq!(list["request"]["url"]["query"][0], json!({ "key": "page", "value": "2", "description": "" }));
+    assert_eq!(list["request"]["url"]["query"][1]["disabled"], true);
+    assert!(list["request"].get("body").is_none());
+
+    // `pathVariables` is the older map form of `pathVariableData`.
+    let get = value(&collection, "Get user");
+    assert_eq!(get["request"]["url"]["variable"], json!([{ "key": "id", "value": "7" }]));
+    let response = &get["response"][0];
+    assert_eq!((response["code"].clone(), response["status"].clone()), (json!(200), json!("OK")));
+    assert_eq!(response["responseTime"], "12");
+    assert_eq!(response["originalRequest"]["url"]["variable"], get["request"]["url"]["variable"]);
+
+    let log_in = value(&collection, "Log in");
+    assert_eq!(log_in["request"]["body"]["mode"], "urlencoded");
+    assert_eq!(log_in["request"]["body"]["urlencoded"][1]["disabled"], true);
+    assert_eq!(log_in["request"]["auth"]["basic"][1], json!({ "key": "password", "value": "secret", "type": "string" }));
+
+    let settings = value(&collection, "Update settings");
+    assert_eq!(settings["request"]["body"], json!({ "mode": "raw", "raw": "{\"theme\": \"dark\"}" }));
+    assert_eq!(settings["request"]["url"]["variable"], json!([{ "key": "unused", "value": "x" }]));
+    assert_eq!(
+        settings["request"]["auth"]["apikey"],
+        json!([
+            { "key": "key", "value": "X-Admin-Key", "type": "string" },
+            { "key": "value", "value": "{{adminKey}}", "type": "string" },
+            { "key": "in", "value": "header", "type": "string" }
+        ])
+    );
+}
+
+#[test]
+fn v20_exports_are_migrated_and_written_back() {
+    let text = fixture("legacy/v2.0.postman_collection.json");
+    let original: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(detect(&original), SchemaVersion::V20);
+    let collection = Collection::from_json(&text).unwrap();
+
+    assert_eq!(
+        paths(&collection),
+        ["Orders", "Orders/Get order", "Orders/Create order", "Ping"]
+    );
+    let orders = find(&collection.item, "Orders").unwrap();
+    assert_eq!(orders.auth.as_ref().unwrap().auth_type.as_deref(), Some("apikey"));
+    let ping = value(&collection, "Ping");
+    assert_eq!(ping["request"]["url"]["path"], json!(["ping"]));
+
+    // Parameters keep their JSON type, so they can be written back exactly.
+    let create = value(&collection, "Create order");
+    assert_eq!(
+        create["request"]["auth"]["oauth2"][3],
+        json!({ "key": "useBrowser", "value": false, "type": "boolean" })
+    );
+    assert_eq!(
+        create["request"]["auth"]["oauth2"][4],
+        json!({ "key": "expiresIn", "value": 3600, "type": "number" })
+    );
+    let get = value(&collection, "Get order");
+    assert_eq!(get["request"]["url"]["variable"], original["item"][0]["item"][0]["request"]["url"]["variable"]);
+
+    let written: Value = serde_json::from_str(&collection.to_json_version(SchemaVersion::V20).unwrap()).unwrap();
+    assert_eq!(written["info"]["schema"], SchemaVersion::V20.schema_url());
+    assert_eq!(written["auth"], original["auth"]);
+    assert_eq!(written["item"][0]["auth"], original["item"][0]["auth"]);
+    assert_eq!(written["item"][0]["item"][1]["request"]["auth"], original["item"][0]["item"][1]["request"]["auth"]);
+    assert_eq!(written["item"][0]["item"][0]["request"]["url"], original["item"][0]["item"][0]["request"]["url"]);
+}
+
+#[test]
+fn v21_collections_survive_a_trip_through_v20() {
+    for name in [
+        "petstore.postman_collection.json",
+        "shapes.postman_collection.json",
+        "bodies.postman_collection.json",
+        "legacy/v2.0.postman_collection.json",
+    ] {
+        let collection = Collection::from_json(&fixture(name)).unwrap();
+        let v20 = collection.to_json_version(SchemaVersion::V20).unwrap();
+        let document: Value = serde_json::from_str(&v20).unwrap();
+        assert_eq!(detect(&document), SchemaVersion::V20, "{}", name);
+
+        let mut back = Collection::from_json(&v20).unwrap();
+        back.info.schema = collection.info.schema.clone();
+        assert_eq!(back, collection, "{}", name);
+        assert_eq!(paths(&back), paths(&collection), "{}", name);
+    }
+}
@@ -0,0 +1,218 @@
+//! `pm.test` blocks are evaluated without a JavaScript engine; anything we
+//! cannot understand must be reported as not evaluated, never as passed.
+
+use ababil_core::models::request::Event;
+use ababil_core::scripts::{evaluate_tests, run_scripts, AssertionResult, VariableChange, VariableTarget};
+use ababil_core::variables::VariableScope;
+use ababil_core::HttpResponse;
+use serde_json::json;
+
+fn response() -> HttpResponse {
+    HttpResponse {
+        status_code: 201,
+        headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+        body: r#"{"id": 7, "name": "Ada"}"#.to_string(),
+        duration_ms: 40,
+        encoded_size: 24,
+        decoded_size: 24,
+        body_base64: None,
+        warning: None,
+    }
+}
+
+fn event(listen: &str, script: &str) -> Event {
+    serde_json::from_value(json!({
+        "listen": listen,
+        "script": { "type": "text/javascript", "exec": script.lines().collect::<Vec<_>>() }
+    }))
+    .unwrap()
+}
+
+fn run(script: &str) -> Vec<AssertionResult> {
+    evaluate_tests(&[event("test", script)], &response())
+}
+
+fn change(target: VariableTarget, key: &str, value: Option<&str>) -> VariableChange {
+    VariableChange {
+        target,
+        key: key.to_string(),
+        value: value.map(str::to_string),
+    }
+}
+
+fn outcome_of(result: &AssertionResult) -> (&str, bool, bool, Option<&str>) {
+    (
+        result.name.as_str(),
+        result.passed,
+        result.skipped,
+        result.error.as_deref(),
+    )
+}
+
+#[test]
+fn recognised_assertions_pass_and_fail() {
+    let results = run(r#"
+        pm.test("created", function () {
+            // Status first, then the rest.
+            pm.response.to.have.status(201);
+            pm.response.to.have.status("Created");
+            pm.response.to.have.header("content-type", "application/json");
+            pm.expect(pm.response.responseTime)
+                /* chained on the next line */
+                .to.be.below(200);
+        });
+        pm.test("ok", () => { pm.response.to.be.ok; });
+        pm.test("body", () => pm.expect(pm.response.text()).to.include("Ada"));
+    "#);
+    assert_eq!(
+        results.iter().map(outcome_of).collect::<Vec<_>>(),
+        [
+            ("created", true, false, None),
+            ("ok", false, false, Some("pm.response.to.be.ok failed for status 201")),
+            ("body", true, false, None),
+        ]
+    );
+}
+
+#[test]
+fn blocks_with_unsupported_statements_are_not_evaluated() {
+    let results = run(r#"
+        pm.test("mixed", function () {
+            pm.response.to.have.status(201);
+            if (pm.response.code === 201) { throw new Error("nope"); }
+        });
+        pm.test("unsupported only", function () {
+            const body = xml2Json(responseBody);
+            pm.expect(body.items).to.have.lengthOf(3);
+        });
+        pm.test("mixed with a failure", function () {
+            pm.sendRequest("https://example.com/audit");
+            pm.response.to.have.status(404);
+        });
+        pm.test("empty", function () {});
+    "#);
+    assert_eq!(
+        results.iter().map(outcome_of).collect::<Vec<_>>(),
+        [
+            (
+                "mixed",
+                false,
+                true,
+                Some("unsupported statement: if (pm.response.code === 201) { throw new Error(\"nope\"); }")
+            ),
+            (
+                "unsupported only",
+                false,
+                true,
+                Some("unsupported statement: const body = xml2Json(responseBody)")
+            ),
+            (
+                "mixed with a failure",
+                false,
+                false,
+                Some("expected status code 404 but got 201")
+            ),
+            ("empty", false, true, Some("no assertions")),
+        ]
+    );
+}
+
+#[test]
+fn json_bodies_and_variables_can_be_asserted() {
+    let results = run(r#"
+        const json = pm.response.json();
+        pm.test("fields", () => {
+            pm.expect(json.id).to.eql(7);
+            pm.expect(json["name"], "name").to.be.a("string");
+            pm.expect(json).to.have.property("name", "Ada");
+            pm.expect(pm.response.headers.get("Content-Type")).to.include("json");
+            pm.expect(json.tags).to.not.exist;
+        });
+        pm.test("wrong type", () => pm.expect(json.name).to.be.below(3));
+        pm.test("missing parent", () => pm.expect(json.owner.id).to.eql(1));
+    "#);
+    assert_eq!(
+        results.iter().map(outcome_of).collect::<Vec<_>>(),
+        [
+            ("fields", true, false, None),
+            ("wrong type", false, false, Some("expected 'Ada' to be a number")),
+            (
+                "missing parent",
+                false,
+                false,
+                Some("Cannot read properties of undefined (reading 'id')")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn scripts_set_and_read_variables() {
+    let mut scope = VariableScope::new();
+    scope.set("prefix", "user");
+    let script = r#"
+        pm.environment.set("userId", pm.response.json().id);
+        pm.collectionVariables.set("label", pm.variables.get("prefix") + "-7");
+        pm.variables.set("key", pm.environment.get("userId"));
+        pm.globals.unset("stale");
+        pm.test("saved", function () {
+            pm.expect(pm.variables.get("key")).to.eql("7");
+        });
+    "#;
+    let outcome = run_scripts("test", &[event("test", script)], Some(&response()), &scope);
+    assert_eq!(
+        outcome.changes,
+        [
+            change(VariableTarget::Environment, "userId", Some("7")),
+            change(VariableTarget::Variables, "key", Some("7")),
+            change(VariableTarget::Globals, "stale", None),
+        ]
+    );
+    // The concatenation is not understood, so the whole script says so.
+    assert_eq!(
+        outcome.assertions.iter().map(outcome_of).collect::<Vec<_>>(),
+        [
+            ("saved", true, false, None),
+            (
+                "test script",
+                false,
+                true,
+                Some("unsupported statement: pm.collectionVariables.set(\"label\", pm.variables.get(\"prefix\") + \"-7\")")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn pre_request_scripts_cannot_see_a_response() {
+    let events = [
+        event(
+            "prerequest",
+            "pm.variables.set(\"nonce\", \"abc\");\npm.request.headers.add({ key: \"X-Nonce\", value: \"abc\" });",
+        ),
+        event("prerequest", "pm.test(\"too early\", () => pm.response.to.be.ok);"),
+        event("test", "pm.test(\"later\", () => pm.response.to.be.ok);"),
+    ];
+    let outcome = run_scripts("prerequest", &events, None, &VariableScope::new());
+    assert_eq!(
+        outcome.changes,
+        [change(VariableTarget::Variables, "nonce", Some("abc"))]
+    );
+    assert_eq!(
+        outcome.assertions.iter().map(outcome_of).collect::<Vec<_>>(),
+        [
+            (
+                "pre-request script",
+                false,
+                true,
+                Some("unsupported statement: pm.request.headers.add({ key: \"X-Nonce\", value: \"abc\" })")
+            ),
+            (
+                "too early",
+                false,
+                true,
+                Some("unsupported statement: pm.response.to.be.ok")
+            ),
+        ]
+    );
+}
@@ -0,0 +1,147 @@
+//! WSDL documents import as collections of SOAP requests with sample
+//! envelopes, and SOAP responses are checked for faults.
+
+use ababil_core::free_string;
+use ababil_core::soap::{parse_soap_response, parse_soap_response_json, wsdl_to_collection_json};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+
+const WSDL: &str = include_str!("fixtures/soap/calculator.wsdl");
+
+fn take(result: *mut std::os::raw::c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    serde_json::from_str(&text).unwrap()
+}
+
+fn call(f: extern "C" fn(*const std::os::raw::c_char) -> *mut std::os::raw::c_char, text: &str) -> Value {
+    let text = CString::new(text).unwrap();
+    take(f(text.as_ptr()))
+}
+
+#[test]
+fn wsdl_imports_one_folder_per_soap_port() {
+    let collection = call(wsdl_to_collection_json, WSDL)["ok"].take();
+    assert_eq!(collection["info"]["name"], "Calculator");
+    assert_eq!(collection["info"]["description"], "Adds numbers and describes items.");
+    let folders = collection["item"].as_array().unwrap();
+    let names: Vec<&str> = folders.iter().map(|f| f["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["CalculatorSoap", "CalculatorSoap12", "EchoRpc"]);
+
+    let add = &folders[0]["item"][0];
+    assert_eq!(add["name"], "Add");
+    assert_eq!(add["description"], "Adds two integers.");
+    let request = &add["request"];
+    assert_eq!(request["method"], "POST");
+    assert_eq!(request["url"]["raw"], "http://example.com/calculator.asmx");
+    assert_eq!(
+        request["header"],
+        json!([
+            { "key": "Content-Type", "value": "text/xml; charset=utf-8" },
+            { "key": "SOAPAction", "value": "\"http://example.com/calculator/Add\"" }
+        ])
+    );
+    assert_eq!(request["body"]["options"], json!({ "raw": { "language": "xml" } }));
+    assert_eq!(
+        request["body"]["raw"],
+        r#"<soapenv:Envelope xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/" xmlns:tns="http://example.com/calculator">
+  <soapenv:Header>
+    <tns:Auth realm="string">string</tns:Auth>
+  </soapenv:Header>
+  <soapenv:Body>
+    <tns:Add>
+      <tns:intA>0</tns:intA>
+      <tns:intB>0</tns:intB>
+      <!--Optional:-->
+      <tns:rounding>UP</tns:rounding>
+    </tns:Add>
+  </soapenv:Body>
+</soapenv:Envelope>"#
+    );
+
+    // Extensions include their base type's elements; recursive types stop.
+    let describe = folders[0]["item"][1]["request"]["body"]["raw"].as_str().unwrap();
+    assert!(describe.contains(
+        "      <!--Zero or more repetitions:-->\n      <tns:item kind=\"UP\">\n        <tns:id>0</tns:id>\n        <tns:name>string</tns:name>\n        <!--Optional:-->\n        <tns:children/>\n      </tns:item>\n"
+    ));
+    assert!(describe.contains("<!--You have a CHOICE of the next 2 items at this level-->"));
+
+    let add12 = &folders[1]["item"][0]["request"];
+    assert_eq!(
+        add12["header"],
+        json!([{ "key": "Content-Type", "value": "application/soap+xml; charset=utf-8; action=\"http://example.com/calculator/Add\"" }])
+    );
+    assert!(add12["body"]["raw"].as_str().unwrap().starts_with(
+        "<soap:Envelope xmlns:soap=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tns=\"http://example.com/calculator\">\n  <soap:Header/>"
+    ));
+
+    let echo = folders[2]["item"][0]["request"]["body"]["raw"].as_str().unwrap();
+    assert!(echo.contains(
+        "xmlns:ns1=\"urn:echo\">\n  <soapenv:Header/>\n  <soapenv:Body>\n    <ns1:Echo>\n      <text>string</text>\n      <times>0</times>\n    </ns1:Echo>"
+    ));
+
+    let invalid = call(wsdl_to_collection_json, "<definitions/>");
+    assert_eq!(invalid["error"]["code"], "invalid_input");
+}
+
+#[test]
+fn faults_are_extracted_from_responses() {
+    let fault11 = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
+  <s:Body>
+    <s:Fault>
+      <faultcode>s:Client</faultcode>
+      <faultstring>Invalid intA</faultstring>
+      <detail><e:Error xmlns:e="urn:errors">42</e:Error></detail>
+    </s:Fault>
+  </s:Body>
+</s:Envelope>"#;
+    let response = call(parse_soap_response_json, fault11)["ok"].take();
+    assert_eq!(response["version"], "1.1");
+    assert_eq!(
+        response["fault"],
+        json!({
+            "code": "s:Client",
+            "subcodes": [],
+            "reason": "Invalid intA",
+            "role": null,
+            "node": null,
+            "detail": "<e:Error xmlns:e=\"urn:errors\">42</e:Error>"
+        })
+    );
+
+    let fault12 = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+  <env:Body>
+    <env:Fault>
+      <env:Code>
+        <env:Value>env:Sender</env:Value>
+        <env:Subcode><env:Value>m:BadInput</env:Value></env:Subcode>
+      </env:Code>
+      <env:Reason><env:Text xml:lang="en">Bad input</env:Text></env:Reason>
+      <env:Role>http://example.com/gateway</env:Role>
+    </env:Fault>
+  </env:Body>
+</env:Envelope>"#;
+    let fault = parse_soap_response(fault12).unwrap().fault.unwrap();
+    assert_eq!(
+        (fault.code.as_str(), fault.subcodes),
+        ("env:Sender", vec!["m:BadInput".to_string()])
+    );
+    assert_eq!(
+        (fault.reason.as_str(), fault.role.as_deref()),
+        ("Bad input", Some("http://example.com/gateway"))
+    );
+
+    let ok = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><AddResponse xmlns="http://example.com/calculator"><AddResult>3</AddResult></AddResponse></soap:Body></soap:Envelope>"#;
+    let response = call(parse_soap_response_json, ok)["ok"].take();
+    assert_eq!(response["fault"], Value::Null);
+    assert_eq!(
+        response["body"],
+        r#"<AddResponse xmlns="http://example.com/calculator"><AddResult>3</AddResult></AddResponse>"#
+    );
+
+    assert_eq!(
+        call(parse_soap_response_json, "<html/>")["error"]["code"],
+        "invalid_input"
+    );
+}
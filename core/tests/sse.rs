@@ -0,0 +1,126 @@
+//! Events are delivered as they arrive, and a dropped stream is reopened
+//! with `Last-Event-ID` after the delay the server asked for.
+
+use ababil_core::free_string;
+use ababil_core::sse::{sse_close, sse_connect, sse_poll, SseParser};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::io::Read;
+use std::os::raw::c_char;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+fn take(result: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    let mut envelope: Value = serde_json::from_str(&text).unwrap();
+    envelope["ok"].take()
+}
+
+#[test]
+fn parser_follows_the_event_stream_format() {
+    let mut parser = SseParser::new(String::new());
+    let mut events = parser.feed(b"\xef\xbb\xbfdata: one\r");
+    events.extend(parser.feed(b"\n\r\n: comment\nevent: add\ndata:two\ndata\nid: 7\n\nretry: 10\nid\n\n"));
+    events.extend(parser.feed(b"data: three\n\ndata: partial"));
+
+    let summary: Vec<(&str, &str, &str)> =
+        events.iter().map(|e| (e.event.as_str(), e.data.as_str(), e.id.as_str())).collect();
+    assert_eq!(summary, [("message", "one", ""), ("add", "two\n", "7"), ("message", "three", "")]);
+    assert_eq!(parser.reconnection_ms, Some(10));
+}
+
+#[test]
+fn streams_reconnect_with_last_event_id() {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    let (seen_tx, seen_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let bodies = ["retry: 50\n\nid: 1\ndata: a\n\nevent: update\ndata: b\ndata: c\r\n\r\n", "id: 2\ndata: d\n\n"];
+        for (index, request) in server.incoming_requests().enumerate() {
+            let last_event_id = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Last-Event-ID"))
+                .map(|h| h.value.to_string());
+            seen_tx.send(last_event_id).unwrap();
+            let response = match bodies.get(index) {
+                Some(body) => tiny_http::Response::from_data(body.as_bytes().to_vec())
+                    .with_header(tiny_http::Header::from_bytes("Content-Type", "text/event-stream").unwrap())
+                    .boxed(),
+                None => tiny_http::Response::empty(204).boxed(),
+            };
+            request.respond(response).unwrap();
+        }
+    });
+
+    let request = CString::new(json!({ "method": "GET", "url": format!("http://127.0.0.1:{}/events", port) }).to_string()).unwrap();
+    let options = CString::new(json!({ "last_event_id": "0" }).to_string()).unwrap();
+    let id = take(sse_connect(request.as_ptr(), options.as_ptr(), None))["id"].as_u64().unwrap() as u32;
+
+    let mut messages = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let poll = take(sse_poll(id));
+        messages.extend(poll["messages"].as_array().unwrap().iter().cloned());
+        if poll["state"] == "closed" && messages.last().is_some_and(|m| m["type"] == "closed") {
+            break;
+        }
+        assert!(Instant::now() < deadline, "stream did not finish: {:?}", messages);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(take(sse_close(id)), true);
+
+    let types: Vec<&str> = messages.iter().map(|m| m["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["open", "event", "event", "reconnecting", "open", "event", "reconnecting", "closed"]);
+    assert_eq!(messages[1], json!({ "type": "event", "event": "message", "data": "a", "id": "1" }));
+    assert_eq!(messages[2]["data"], "b\nc");
+    assert_eq!(messages[2]["event"], "update");
+    assert_eq!(messages[3], json!({ "type": "reconnecting", "delay_ms": 50, "last_event_id": "1" }));
+    assert_eq!(messages[5]["id"], "2");
+
+    let seen: Vec<Option<String>> = seen_rx.try_iter().collect();
+    assert_eq!(seen, [Some("0".to_string()), Some("1".to_string()), Some("2".to_string())]);
+}
+
+static CLOSED_FROM_CALLBACK: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+extern "C" fn close_on_first_event(id: u32, message: *const c_char) {
+    let message: Value = serde_json::from_str(unsafe { CStr::from_ptr(message) }.to_str().unwrap()).unwrap();
+    let mut closed = CLOSED_FROM_CALLBACK.lock().unwrap();
+    if message["type"] == "event" && closed.is_empty() {
+        closed.push(take(sse_close(id)));
+        closed.push(take(sse_poll(id)));
+    }
+}
+
+#[test]
+fn callbacks_can_close_their_own_stream() {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            // Keep the stream open after the first event.
+            let body = (&b"data: first\n\n"[..]).chain(std::io::repeat(b'\n').take(1 << 30));
+            let response = tiny_http::Response::new(
+                200.into(),
+                vec![tiny_http::Header::from_bytes("Content-Type", "text/event-stream").unwrap()],
+                body,
+                None,
+                None,
+            );
+            let _ = request.respond(response);
+        }
+    });
+
+    let request = CString::new(json!({ "method": "GET", "url": format!("http://127.0.0.1:{}/", port) }).to_string()).unwrap();
+    let id = take(sse_connect(request.as_ptr(), std::ptr::null(), Some(close_on_first_event)))["id"].as_u64().unwrap() as u32;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while CLOSED_FROM_CALLBACK.lock().unwrap().is_empty() {
+        assert!(Instant::now() < deadline, "the callback never ran");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(*CLOSED_FROM_CALLBACK.lock().unwrap(), [json!(true), Value::Null]);
+    assert_eq!(take(sse_poll(id)), Value::Null);
+}
@@ -0,0 +1,184 @@
+//! Schema checks and lint rules, and the JSON pointers findings carry.
+
+use ababil_core::models::environment::Environment;
+use ababil_core::models::schema::migrate_to_v21;
+use ababil_core::validate::{validate_collection, Severity, ValidationReport};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+const SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+fn collection(items: Value) -> Value {
+    json!({ "info": { "name": "Lint", "schema": SCHEMA }, "item": items })
+}
+
+fn validate(document: &Value) -> ValidationReport {
+    validate_collection(&document.to_string(), None)
+}
+
+/// `(rule, pointer)` for each finding.
+fn findings(report: &ValidationReport) -> Vec<(&str, &str)> {
+    report.findings.iter().map(|f| (f.rule.as_str(), f.pointer.as_str())).collect()
+}
+
+#[test]
+fn lint_rules() {
+    let document = collection(json!([
+        { "name": "Users", "item": [
+            { "name": "Get", "request": { "method": "GET", "url": "{{baseUrl}}/users/{{id}}" } },
+            { "name": "Get", "request": { "method": "GET", "url": { "raw": "", "host": [] } } }
+        ] },
+        { "name": "Empty", "request": "" },
+        { "name": "Auth", "request": {
+            "url": "{{baseUrl}}/a",
+            "auth": { "type": "magic" },
+            "header": [
+                { "key": "Authorization", "value": "Bearer abc123" },
+                { "key": "X-Api-Key", "value": "{{apiKey}}" },
+                { "key": "Cookie", "value": "sid=1", "disabled": true }
+            ]
+        } },
+        { "name": "Token", "request": {
+            "url": "{{baseUrl}}/b",
+            "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "literal" }] },
+            "body": { "mode": "raw", "raw": "{\"at\": \"{{$timestamp}}\", \"by\": \"{{user}}\"}" }
+        } }
+    ]));
+    let report = validate(&document);
+    assert!(report.valid);
+    assert!(report.migrated_from.is_none());
+    assert!(report.findings.iter().all(|f| f.severity == Severity::Warning));
+    assert_eq!(
+        findings(&report),
+        [
+            ("undefined-variable", "/item/0/item/0/request/url"),
+            ("undefined-variable", "/item/0/item/0/request/url"),
+            ("duplicate-name", "/item/0/item/1/name"),
+            ("missing-url", "/item/0/item/1/request"),
+            ("missing-url", "/item/1/request"),
+            ("undefined-variable", "/item/2/request/url"),
+            ("hardcoded-secret", "/item/2/request/header/0/value"),
+            ("undefined-variable", "/item/2/request/header/1/value"),
+            ("unknown-auth", "/item/2/request/auth/type"),
+            ("undefined-variable", "/item/3/request/url"),
+            ("undefined-variable", "/item/3/request/body/raw"),
+            ("hardcoded-secret", "/item/3/request/auth/bearer/0/value"),
+        ]
+    );
+    assert_eq!(
+        report.findings[0].message,
+        "Variable '{{baseUrl}}' is not defined in the collection, environment or scripts"
+    );
+
+    // Every pointer resolves in the document.
+    for finding in &report.findings {
+        assert!(document.pointer(&finding.pointer).is_some(), "{}", finding.pointer);
+    }
+}
+
+#[test]
+fn variables_are_defined_by_the_collection_environment_and_scripts() {
+    let mut document = collection(json!([
+        { "name": "Login", "request": { "url": "{{baseUrl}}/login" }, "event": [{
+            "listen": "test",
+            "script": { "exec": ["pm.environment.set('token', pm.response.json().token);"] }
+        }] },
+        { "name": "Folder", "variable": [{ "key": "id", "value": "1" }], "item": [
+            { "name": "Me", "request": {
+                "url": "{{baseUrl}}/users/{{id}}",
+                "header": [{ "key": "Authorization", "value": "Bearer {{token}}" }]
+            } }
+        ] }
+    ]));
+    let report = validate(&document);
+    assert_eq!(
+        findings(&report),
+        [("undefined-variable", "/item/0/request/url"), ("undefined-variable", "/item/1/item/0/request/url")]
+    );
+
+    let environment: Environment =
+        serde_json::from_value(json!({ "name": "local", "values": [{ "key": "baseUrl", "value": "http://localhost" }] }))
+            .unwrap();
+    assert!(validate_collection(&document.to_string(), Some(&environment)).findings.is_empty());
+
+    document["variable"] = json!([{ "key": "baseUrl", "value": "http://localhost" }]);
+    assert!(validate(&document).findings.is_empty());
+}
+
+#[test]
+fn schema_errors_make_a_collection_invalid() {
+    let cases = [
+        (json!({ "item": [] }), vec![("schema", "")]),
+        (json!({ "info": { "name": 1, "schema": SCHEMA }, "item": [] }), vec![("schema", "/info/name")]),
+        (
+            collection(json!([{ "name": "Neither" }, { "name": "Bad", "item": {} }])),
+            vec![("schema", "/item/0"), ("schema", "/item/1/item")],
+        ),
+        (
+            collection(json!([{ "name": "Body", "request": {
+                "url": "https://a.test",
+                "body": { "mode": "stream", "urlencoded": [{ "value": "x" }] },
+                "header": [{ "key": "A" }]
+            } }])),
+            vec![
+                ("schema", "/item/0/request/header/0"),
+                ("schema", "/item/0/request/body/mode"),
+                ("schema", "/item/0/request/body/urlencoded/0"),
+            ],
+        ),
+        (
+            json!({ "info": { "name": "~/", "schema": SCHEMA }, "item": [], "variable": [{ "value": 1 }] }),
+            vec![("schema", "/variable/0")],
+        ),
+    ];
+    for (document, expected) in cases {
+        let report = validate(&document);
+        assert!(!report.valid, "{}", document);
+        assert_eq!(findings(&report), expected, "{}", document);
+    }
+
+    let report = validate_collection("{\"info\":", None);
+    assert!(!report.valid);
+    assert_eq!(findings(&report), [("json", "")]);
+}
+
+#[test]
+fn keys_are_escaped_in_pointers() {
+    let document = collection(json!([{ "name": "Escaped", "request": {
+        "url": "https://a.test",
+        "auth": { "type": "apikey", "apikey": [{ "key": "value", "value": "k3y" }] },
+        "body": { "mode": "urlencoded", "urlencoded": [{ "key": "a", "value": "{{x}}" }], "a/b~c": "{{y}}" }
+    } }]));
+    let report = validate(&document);
+    assert_eq!(
+        findings(&report),
+        [
+            ("undefined-variable", "/item/0/request/body/urlencoded/0/value"),
+            ("undefined-variable", "/item/0/request/body/a~1b~0c"),
+            ("hardcoded-secret", "/item/0/request/auth/apikey/0/value"),
+        ]
+    );
+    for finding in &report.findings {
+        assert!(document.pointer(&finding.pointer).is_some(), "{}", finding.pointer);
+    }
+}
+
+#[test]
+fn older_formats_are_checked_after_migration() {
+    let text = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/postman/legacy/v1.postman_collection.json"),
+    )
+    .unwrap();
+    let report = validate_collection(&text, None);
+    assert!(report.valid);
+    assert_eq!(report.migrated_from.as_deref(), Some("v1"));
+    assert_eq!(findings(&report)[0], ("schema-version", ""));
+
+    // Pointers refer to the migrated document.
+    let migrated = migrate_to_v21(serde_json::from_str(&text).unwrap());
+    assert!(report.findings.len() > 1);
+    for finding in &report.findings {
+        assert!(migrated.pointer(&finding.pointer).is_some(), "{}", finding.pointer);
+    }
+}
@@ -0,0 +1,145 @@
+//! WebSocket sessions handshake with the request's headers, auth and
+//! subprotocols, exchange frames with a local echo server and log them.
+
+use ababil_core::free_string;
+use ababil_core::websocket::{ws_close, ws_connect, ws_log, ws_poll, ws_send};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+fn take(result: *mut c_char) -> Value {
+    let text = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_string();
+    free_string(result);
+    serde_json::from_str(&text).unwrap()
+}
+
+/// Echoes text and binary frames back. The handshake must carry the bearer
+/// token and custom header, and picks the `echo.v1` subprotocol.
+// The handshake callback's error type is tungstenite's, not ours.
+#[allow(clippy::result_large_err)]
+fn echo_server() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            listener.set_nonblocking(true).unwrap();
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
+            let check = |request: &Request, mut response: Response| {
+                let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+                assert_eq!(header("authorization"), "Bearer secret");
+                assert_eq!(header("x-client"), "ababil");
+                assert_eq!(header("sec-websocket-protocol"), "chat, echo.v1");
+                response.headers_mut().insert("Sec-WebSocket-Protocol", HeaderValue::from_static("echo.v1"));
+                Ok(response)
+            };
+            let mut socket = tokio_tungstenite::accept_hdr_async(stream, check).await.unwrap();
+            while let Some(Ok(message)) = socket.next().await {
+                if message.is_text() || message.is_binary() {
+                    socket.send(message).await.unwrap();
+                }
+            }
+        });
+    });
+    port
+}
+
+/// A request for `echo_server` that passes its handshake checks.
+fn echo_request(port: u16) -> CString {
+    let request = json!({
+        "method": "GET",
+        "url": format!("http://127.0.0.1:{}/socket", port),
+        "header": [{ "key": "X-Client", "value": "ababil" }],
+        "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "secret" }] }
+    });
+    CString::new(request.to_string()).unwrap()
+}
+
+#[test]
+fn frames_are_echoed_and_logged() {
+    let request = echo_request(echo_server());
+    let options = CString::new(json!({ "protocols": ["chat", "echo.v1"] }).to_string()).unwrap();
+    let id = take(ws_connect(request.as_ptr(), options.as_ptr(), None))["ok"]["id"].as_u64().unwrap() as u32;
+
+    let poll_until = |count: usize| {
+        let mut messages = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while messages.len() < count && Instant::now() < deadline {
+            let polled = take(ws_poll(id));
+            messages.extend(polled["ok"]["messages"].as_array().unwrap().iter().cloned());
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        messages
+    };
+
+    let open = poll_until(1);
+    assert_eq!(open[0]["type"], "open");
+    assert_eq!(open[0]["status_code"], 101);
+    assert_eq!(open[0]["protocol"], "echo.v1");
+
+    for frame in [
+        json!({ "type": "text", "data": "hello" }),
+        json!({ "type": "binary", "data_base64": "AAEC" }),
+        json!({ "type": "ping", "data_base64": "cGluZw==" }),
+    ] {
+        let frame = CString::new(frame.to_string()).unwrap();
+        assert_eq!(take(ws_send(id, frame.as_ptr()))["ok"], true);
+    }
+
+    let echoes = poll_until(3);
+    assert!(echoes.iter().all(|m| m["direction"] == "received"));
+    let received: Vec<(String, String)> = echoes
+        .iter()
+        .map(|m| (m["type"].as_str().unwrap().to_string(), m["data"].as_str().or(m["data_base64"].as_str()).unwrap().to_string()))
+        .collect();
+    let expected = [("text", "hello"), ("binary", "AAEC"), ("pong", "cGluZw==")];
+    assert_eq!(received, expected.map(|(t, d)| (t.to_string(), d.to_string())));
+
+    let log = take(ws_log(id))["ok"].take();
+    assert_eq!(log["protocol"], "echo.v1");
+    assert_eq!(log["entries"].as_array().unwrap().len(), 6);
+
+    let reason = CString::new("done").unwrap();
+    let closed = take(ws_close(id, 1000, reason.as_ptr()))["ok"].take();
+    assert_eq!((closed["code"].clone(), closed["reason"].clone()), (json!(1000), json!("done")));
+    let entries = closed["log"]["entries"].as_array().unwrap();
+    let last = entries.last().unwrap();
+    assert_eq!((&last["type"], &last["direction"]), (&json!("close"), &json!("received")));
+
+    assert_eq!(take(ws_poll(id))["error"]["code"], "websocket_not_found");
+}
+
+static CLOSED_FROM_CALLBACK: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+extern "C" fn close_when_open(id: u32, message: *const c_char) {
+    let message: Value = serde_json::from_str(unsafe { CStr::from_ptr(message) }.to_str().unwrap()).unwrap();
+    if message["type"] == "open" {
+        let reason = CString::new("bye").unwrap();
+        let mut closed = CLOSED_FROM_CALLBACK.lock().unwrap();
+        closed.push(take(ws_close(id, 1000, reason.as_ptr())));
+        closed.push(take(ws_poll(id)));
+    }
+}
+
+#[test]
+fn callbacks_can_close_their_own_session() {
+    let request = echo_request(echo_server());
+    let options = CString::new(json!({ "protocols": ["chat", "echo.v1"] }).to_string()).unwrap();
+    let id = take(ws_connect(request.as_ptr(), options.as_ptr(), Some(close_when_open)))["ok"]["id"].as_u64().unwrap() as u32;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while CLOSED_FROM_CALLBACK.lock().unwrap().is_empty() {
+        assert!(Instant::now() < deadline, "the callback never ran");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let closed = CLOSED_FROM_CALLBACK.lock().unwrap();
+    assert!(closed[0]["ok"]["log"].is_object(), "{}", closed[0]);
+    assert_eq!(closed[1]["error"]["code"], "websocket_not_found");
+    assert_eq!(take(ws_poll(id))["error"]["code"], "websocket_not_found");
+}
@@ -98,6 +98,13 @@ async function initializeNativeLibrary() {
     }
 }
 
+// Error envelope in the same shape the native library returns
+function nativeError(message) {
+    return JSON.stringify({
+        error: { code: 'native_unavailable', category: 'internal', message },
+    });
+}
+
 // IPC Handlers - only setup once
 function setupIpcHandlers() {
     if (ipcHandlersSetup) {
@@ -107,32 +114,17 @@ function setupIpcHandlers() {
     // Handle HTTP request via native library
     ipcMain.handle('native:makeHttpRequest', async (event, requestJson) => {
         if (!nativeLib || !nativeLib.initialized) {
-            return JSON.stringify({
-                status_code: 0,
-                headers: [],
-                body: 'Error: Native library not initialized',
-                duration_ms: 0,
-            });
+            return nativeError('Native library not initialized');
         }
 
         try {
             const result = nativeLib.makeHttpRequest(requestJson);
             return (
                 result ||
-                JSON.stringify({
-                    status_code: 0,
-                    headers: [],
-                    body: 'Error: Null response from native library',
-                    duration_ms: 0,
-                })
+                nativeError('Null response from native library')
             );
         } catch (error) {
-            return JSON.stringify({
-                status_code: 0,
-                headers: [],
-                body: `Error: ${error.message}`,
-                duration_ms: 0,
-            });
+            return nativeError(error.message);
         }
     });
 
@@ -156,21 +148,17 @@ function setupIpcHandlers() {
                 !nativeLib.initialized ||
                 !nativeLib.parsePostmanCollection
             ) {
-                return JSON.stringify({
-                    error: 'Native library not initialized',
-                });
+                return nativeError('Native library not initialized');
             }
 
             try {
                 const result = nativeLib.parsePostmanCollection(jsonString);
                 return (
                     result ||
-                    JSON.stringify({
-                        error: 'Null response from native library',
-                    })
+                    nativeError('Null response from native library')
                 );
             } catch (error) {
-                return JSON.stringify({ error: error.message });
+                return nativeError(error.message);
             }
         }
     );
@@ -184,21 +172,17 @@ function setupIpcHandlers() {
                 !nativeLib.initialized ||
                 !nativeLib.parsePostmanEnvironment
             ) {
-                return JSON.stringify({
-                    error: 'Native library not initialized',
-                });
+                return nativeError('Native library not initialized');
             }
 
             try {
                 const result = nativeLib.parsePostmanEnvironment(jsonString);
                 return (
                     result ||
-                    JSON.stringify({
-                        error: 'Null response from native library',
-                    })
+                    nativeError('Null response from native library')
                 );
             } catch (error) {
-                return JSON.stringify({ error: error.message });
+                return nativeError(error.message);
             }
         }
     );
@@ -5,7 +5,12 @@
  * via the native Rust library through Electron IPC.
  */
 
-import { HttpRequest, HttpResponse, createSimpleRequest } from '../types/http';
+import {
+    HttpRequest,
+    HttpResponse,
+    NativeResult,
+    createSimpleRequest,
+} from '../types/http';
 import '../types/electron.d.ts';
 
 // Type assertion for window.ababilAPI
@@ -68,8 +73,17 @@ export async function makeHttpRequest(
         const requestJson = JSON.stringify(request);
         const responseJson =
             await window.ababilAPI.makeHttpRequest(requestJson);
-        const response = JSON.parse(responseJson) as HttpResponse;
-        return response;
+        const result = JSON.parse(responseJson) as NativeResult<HttpResponse>;
+        if ('error' in result) {
+            return {
+                status_code: 0,
+                headers: [],
+                body: `Error: ${result.error.message}`,
+                duration_ms: 0,
+                error: result.error,
+            };
+        }
+        return result.ok;
     } catch (error: unknown) {
         const errorMessage =
             error instanceof Error ? error.message : 'Unknown error';
@@ -6,6 +6,7 @@ import {
     getCollection,
 } from './storage';
 import { httpRequestToSavedRequest } from '../types/collection';
+import { NativeResult } from '../types/http';
 
 // Postman Collection types (matching Rust structs)
 interface PostmanCollectionInfo {
@@ -74,13 +75,15 @@ export async function parsePostmanCollection(
         const resultJson = await window.ababilAPI.parsePostmanCollection(
             jsonString
         );
-        const result = JSON.parse(resultJson);
+        const result = JSON.parse(resultJson) as NativeResult<
+            PostmanCollection
+        >;
 
-        if (result.error) {
-            throw new Error(result.error);
+        if ('error' in result) {
+            throw new Error(result.error.message);
         }
 
-        return result as PostmanCollection;
+        return result.ok;
     } catch (error: unknown) {
         const errorMessage =
             error instanceof Error ? error.message : 'Unknown error';
@@ -337,13 +340,15 @@ export async function parsePostmanEnvironmentJson(
         const resultJson = await window.ababilAPI.parsePostmanEnvironment(
             jsonString
         );
-        const result = JSON.parse(resultJson);
+        const result = JSON.parse(resultJson) as NativeResult<
+            PostmanEnvironment
+        >;
 
-        if (result.error) {
-            throw new Error(result.error);
+        if ('error' in result) {
+            throw new Error(result.error.message);
         }
 
-        return result as PostmanEnvironment;
+        return result.ok;
     } catch (error: unknown) {
         const errorMessage =
             error instanceof Error ? error.message : 'Unknown error';
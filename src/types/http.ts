@@ -1,17 +1,26 @@
 // HTTP Request Types - matching Rust struct format
 
+// Plain text, or an object such as { content, type: 'text/markdown' }
+export type Description =
+    | string
+    | {
+          content: string;
+          type?: string;
+          version?: unknown;
+      };
+
 export interface QueryParam {
     key: string;
     value?: string;
     disabled?: boolean;
-    description?: string;
+    description?: Description;
 }
 
 export interface RequestHeader {
     key: string;
     value: string;
     disabled?: boolean;
-    description?: string;
+    description?: Description;
 }
 
 export interface FormData {
@@ -19,7 +28,7 @@ export interface FormData {
     value?: string;
     type?: string;
     disabled?: boolean;
-    description?: string;
+    description?: Description;
 }
 
 export interface GraphQLBody {
@@ -68,8 +77,17 @@ export interface HttpRequest {
     header?: RequestHeader[];
     body?: RequestBody;
     url?: RequestUrl;
-    description?: string;
+    description?: Description;
     auth?: RequestAuth;
+    // Settings of this app that Postman collections have no place for
+    ababilOptions?: {
+        // false keeps the response body as received, without decompressing it
+        decompress?: boolean;
+        // Environment the request was resolved against, recorded in history
+        environment?: string;
+        // false sends a GraphQL query without checking it against the cached schema
+        validateGraphql?: boolean;
+    };
     testScript?: string;
 }
 
@@ -78,8 +96,42 @@ export interface HttpResponse {
     headers: [string, string][];
     body: string;
     duration_ms: number;
+    // Body size in bytes as received, and after decompression
+    encoded_size?: number;
+    decoded_size?: number;
+    // Raw body bytes when they are not valid text (binary or still compressed)
+    body_base64?: string;
+    // Something about the response could not be handled, e.g. a body that
+    // failed to decompress and is returned as received
+    warning?: string;
+    // Set when the request could not be completed (status_code is then 0)
+    error?: NativeError;
+}
+
+// Result envelope returned by every native library function
+export type NativeErrorCategory =
+    | 'argument'
+    | 'parse'
+    | 'auth'
+    | 'dns'
+    | 'connect'
+    | 'tls'
+    | 'timeout'
+    | 'redirect'
+    | 'network'
+    | 'io'
+    | 'unsupported'
+    | 'internal';
+
+export interface NativeError {
+    code: string;
+    category: NativeErrorCategory;
+    message: string;
+    details?: Record<string, unknown>;
 }
 
+export type NativeResult<T> = { ok: T } | { error: NativeError };
+
 // Helper to create a simple request
 export function createSimpleRequest(
     method: string,